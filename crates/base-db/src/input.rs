@@ -85,6 +85,8 @@ impl SourceRoot {
 #[derive(Clone, Default)]
 pub struct CrateGraph {
     arena: Arena<CrateData>,
+    /// Maps cfg variant crates to the crate they are a variant of.
+    cfg_variants: FxHashMap<CrateId, CrateId>,
 }
 
 impl fmt::Debug for CrateGraph {
@@ -490,6 +492,45 @@ impl CrateGraph {
         }
     }
 
+    /// Adds a cfg variant for each of the given crates: a copy of the crate that is analyzed with
+    /// the given cfg options instead of its own. Dependencies among the given crates are redirected
+    /// to their variants, so that a variant sees the items of the same configuration.
+    ///
+    /// Nothing outside the returned variants depends on them, they only exist so that code which
+    /// is cfg'd out in the original crate can be analyzed.
+    ///
+    /// Returns a map from the given crates to their new variants.
+    pub fn add_cfg_variants(
+        &mut self,
+        crates: impl IntoIterator<Item = (CrateId, Arc<CfgOptions>)>,
+    ) -> FxHashMap<CrateId, CrateId> {
+        let mut id_map = FxHashMap::default();
+        for (of, cfg_options) in crates {
+            let data = CrateData { cfg_options, ..self.arena[of].clone() };
+            let variant = self.arena.alloc(data);
+            self.cfg_variants.insert(variant, of);
+            id_map.insert(of, variant);
+        }
+        for &variant in id_map.values() {
+            self.arena[variant].dependencies.iter_mut().for_each(|dep| {
+                if let Some(&to) = id_map.get(&dep.crate_id) {
+                    dep.crate_id = to;
+                }
+            });
+        }
+        id_map
+    }
+
+    /// Returns the crate `krate` is a cfg variant of, if it is one.
+    pub fn cfg_variant_of(&self, krate: CrateId) -> Option<CrateId> {
+        self.cfg_variants.get(&krate).copied()
+    }
+
+    /// Returns the cfg variants of `krate`.
+    pub fn cfg_variants(&self, krate: CrateId) -> impl Iterator<Item = CrateId> + '_ {
+        self.cfg_variants.iter().filter(move |&(_, &of)| of == krate).map(|(&variant, _)| variant)
+    }
+
     /// Extends this crate graph by adding a complete second crate
     /// graph and adjust the ids in the [`ProcMacroPaths`] accordingly.
     ///
@@ -522,6 +563,9 @@ impl CrateGraph {
             id_map.insert(topo, new_id);
         }
 
+        self.cfg_variants
+            .extend(other.cfg_variants.iter().map(|(variant, of)| (id_map[variant], id_map[of])));
+
         *proc_macros =
             mem::take(proc_macros).into_iter().map(|(id, macros)| (id_map[&id], macros)).collect();
         id_map
@@ -572,11 +616,17 @@ impl CrateGraph {
                     id_map[dep.crate_id.into_raw().into_u32() as usize].expect("crate was filtered")
             });
         }
+        let map_id = |id: CrateId| id_map[id.into_raw().into_u32() as usize];
+        self.cfg_variants = mem::take(&mut self.cfg_variants)
+            .into_iter()
+            .filter_map(|(variant, of)| Some((map_id(variant)?, map_id(of)?)))
+            .collect();
         id_map
     }
 
     pub fn shrink_to_fit(&mut self) {
         self.arena.shrink_to_fit();
+        self.cfg_variants.shrink_to_fit();
     }
 }
 
@@ -685,7 +735,7 @@ impl fmt::Display for CyclicDependenciesError {
 mod tests {
     use crate::CrateOrigin;
 
-    use super::{CrateGraph, CrateName, Dependency, Edition::Edition2018, Env, FileId};
+    use super::{CrateGraph, CrateId, CrateName, Dependency, Edition::Edition2018, Env, FileId};
 
     #[test]
     fn detect_cyclic_dependency_indirect() {
@@ -847,4 +897,60 @@ mod tests {
             vec![Dependency::new(CrateName::new("crate_name_with_dashes").unwrap(), crate2,)]
         );
     }
+
+    #[test]
+    fn cfg_variants_depend_on_variants() {
+        let mut graph = CrateGraph::default();
+        let crate1 = graph.add_crate_root(
+            FileId::from_raw(1u32),
+            Edition2018,
+            None,
+            None,
+            Default::default(),
+            Default::default(),
+            Env::default(),
+            false,
+            CrateOrigin::Local { repo: None, name: None },
+        );
+        let crate2 = graph.add_crate_root(
+            FileId::from_raw(2u32),
+            Edition2018,
+            None,
+            None,
+            Default::default(),
+            Default::default(),
+            Env::default(),
+            false,
+            CrateOrigin::Local { repo: None, name: None },
+        );
+        let crate3 = graph.add_crate_root(
+            FileId::from_raw(3u32),
+            Edition2018,
+            None,
+            None,
+            Default::default(),
+            Default::default(),
+            Env::default(),
+            false,
+            CrateOrigin::Local { repo: None, name: None },
+        );
+        let dep2 = Dependency::new(CrateName::new("crate2").unwrap(), crate2);
+        let dep3 = Dependency::new(CrateName::new("crate3").unwrap(), crate3);
+        assert!(graph.add_dep(crate1, dep2).is_ok());
+        assert!(graph.add_dep(crate1, dep3.clone()).is_ok());
+
+        let variants = graph.add_cfg_variants([crate1, crate2].map(|it| (it, Default::default())));
+        let (variant1, variant2) = (variants[&crate1], variants[&crate2]);
+        assert_eq!(graph.cfg_variant_of(variant1), Some(crate1));
+        assert_eq!(graph.cfg_variant_of(crate1), None);
+        assert_eq!(graph.cfg_variants(crate2).collect::<Vec<_>>(), vec![variant2]);
+        assert_eq!(
+            graph[variant1].dependencies,
+            vec![Dependency::new(CrateName::new("crate2").unwrap(), variant2), dep3]
+        );
+
+        let id_map = graph.remove_crates_except(&[crate2, variant2]);
+        let new_id = |id: CrateId| id_map[id.into_raw().into_u32() as usize];
+        assert_eq!(graph.cfg_variant_of(new_id(variant2).unwrap()), new_id(crate2));
+    }
 }
@@ -21,6 +21,7 @@ use crate::{
     item_tree::{AttrOwner, ItemTree, ItemTreeSourceMaps},
     lang_item::{self, LangItem, LangItemTarget, LangItems},
     nameres::{diagnostics::DefDiagnostics, DefMap},
+    persist::{PersistKeys, PersistedCaches},
     tt,
     type_ref::TypesSourceMap,
     visibility::{self, Visibility},
@@ -86,6 +87,14 @@ pub trait DefDatabase: InternDatabase + ExpandDatabase + Upcast<dyn ExpandDataba
     #[ra_salsa::input]
     fn expand_proc_attr_macros(&self) -> bool;
 
+    /// Item trees, def maps and symbols of libraries that were persisted by an earlier session.
+    #[ra_salsa::input]
+    fn persisted_caches(&self) -> Arc<PersistedCaches>;
+
+    /// The keys of the crates and files of the current crate graph in [`PersistedCaches`].
+    #[ra_salsa::input]
+    fn persist_keys(&self) -> Arc<PersistKeys>;
+
     /// Computes an [`ItemTree`] for the given file or macro expansion.
     #[ra_salsa::invoke(ItemTree::file_item_tree_query)]
     fn file_item_tree(&self, file_id: HirFileId) -> Arc<ItemTree>;
@@ -203,6 +203,30 @@ pub enum WherePredicateTypeTarget {
 }
 
 impl GenericParams {
+    fn empty() -> Arc<GenericParams> {
+        static EMPTY: LazyLock<Arc<GenericParams>> = LazyLock::new(|| {
+            Arc::new(GenericParams {
+                lifetimes: Arena::new(),
+                type_or_consts: Arena::new(),
+                where_predicates: Box::default(),
+                types_map: TypesMap::default(),
+            })
+        });
+        Arc::clone(&EMPTY)
+    }
+
+    pub(crate) fn from_parts(
+        type_or_consts: Arena<TypeOrConstParamData>,
+        lifetimes: Arena<LifetimeParamData>,
+        where_predicates: Box<[WherePredicate]>,
+        types_map: TypesMap,
+    ) -> Arc<GenericParams> {
+        if lifetimes.is_empty() && type_or_consts.is_empty() && where_predicates.is_empty() {
+            return GenericParams::empty();
+        }
+        Arc::new(GenericParams { type_or_consts, lifetimes, where_predicates, types_map })
+    }
+
     /// Number of Generic parameters (type_or_consts + lifetimes)
     #[inline]
     pub fn len(&self) -> usize {
@@ -706,15 +730,7 @@ impl GenericParamsCollector {
         let Self { mut lifetimes, mut type_or_consts, mut where_predicates } = self;
 
         if lifetimes.is_empty() && type_or_consts.is_empty() && where_predicates.is_empty() {
-            static EMPTY: LazyLock<Arc<GenericParams>> = LazyLock::new(|| {
-                Arc::new(GenericParams {
-                    lifetimes: Arena::new(),
-                    type_or_consts: Arena::new(),
-                    where_predicates: Box::default(),
-                    types_map: TypesMap::default(),
-                })
-            });
-            return Arc::clone(&EMPTY);
+            return GenericParams::empty();
         }
 
         lifetimes.shrink_to_fit();
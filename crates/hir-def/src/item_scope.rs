@@ -17,6 +17,7 @@ use syntax::ast;
 use crate::{
     db::DefDatabase,
     per_ns::{Item, MacrosItem, PerNs, TypesItem, ValuesItem},
+    persist::{persist_enum, persist_struct},
     visibility::{Visibility, VisibilityExplicitness},
     AdtId, BuiltinType, ConstId, ExternBlockId, ExternCrateId, FxIndexMap, HasModule, ImplId,
     LocalModuleId, Lookup, MacroId, ModuleDefId, ModuleId, TraitId, UseId,
@@ -930,3 +931,35 @@ impl ItemInNs {
         }
     }
 }
+
+persist_struct! {
+    ItemScope {
+        types,
+        values,
+        macros,
+        unresolved,
+        declarations,
+        impls,
+        extern_blocks,
+        unnamed_consts,
+        unnamed_trait_imports,
+        use_imports_types,
+        use_imports_values,
+        use_imports_macros,
+        use_decls,
+        extern_crate_decls,
+        legacy_macros,
+        attr_macros,
+        macro_invocations,
+        derive_macros,
+    }
+    DeriveMacroInvocation { attr_id, attr_call_id, derive_call_ids }
+    ImportId { use_, idx }
+    GlobId { use_, idx }
+}
+
+persist_enum! {
+    ImportOrExternCrate { Glob(it), Import(it), ExternCrate(it) }
+    ImportOrGlob { Glob(it), Import(it) }
+    ImportOrDef { Import(it), Glob(it), ExternCrate(it), Def(it) }
+}
@@ -32,6 +32,7 @@
 #![allow(unexpected_cfgs)]
 
 mod lower;
+mod persist;
 mod pretty;
 #[cfg(test)]
 mod tests;
@@ -100,6 +101,14 @@ pub struct ItemTree {
 
 impl ItemTree {
     pub(crate) fn file_item_tree_query(db: &dyn DefDatabase, file_id: HirFileId) -> Arc<ItemTree> {
+        if let Some(editioned_file_id) = file_id.file_id() {
+            let persisted = db.persisted_caches();
+            if !persisted.is_empty() {
+                if let Some(item_tree) = persisted.item_tree(db, editioned_file_id) {
+                    return Arc::new(item_tree);
+                }
+            }
+        }
         db.file_item_tree_with_source_map(file_id).0
     }
 
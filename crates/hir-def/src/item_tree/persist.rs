@@ -0,0 +1,395 @@
+//! [`Persist`] implementations of the [`ItemTree`] and the types it consists of.
+//!
+//! An item tree is encoded without a database, so whatever is interned in one, like syntax
+//! contexts of macro expansions or `$crate` paths, can't be encoded; files containing such things
+//! are not persisted. Spans and ast ids are stored without their file and are attached to the file
+//! the tree is loaded for.
+
+use base_db::CrateId;
+use hir_expand::{
+    attrs::{Attr, AttrInput, RawAttrs},
+    name::Name,
+};
+use intern::Interned;
+use la_arena::Idx;
+use triomphe::Arc;
+
+use crate::{
+    generics::{
+        ConstParamData, GenericParams, LifetimeParamData, TypeOrConstParamData, TypeParamData,
+        TypeParamProvenance, WherePredicate, WherePredicateTypeTarget,
+    },
+    item_tree::{
+        AssocItem, AttrOwner, Const, Enum, ExternBlock, ExternCrate, Field, FieldParent,
+        FieldsShape, FileItemTreeId, FnFlags, Function, GenericModItem, Impl, ItemTree,
+        ItemTreeData, ItemTreeId, ItemVisibilities, Macro2, MacroCall, MacroRules, Mod, ModItem,
+        ModKind, Param, RawVisibilityId, Static, Struct, Trait, TraitAlias, TreeId, TypeAlias,
+        Union, Use, UseTree, UseTreeKind, Variant,
+    },
+    path::{
+        AssociatedTypeBinding, GenericArg, GenericArgs, ImportAlias, ModPath, NormalPath, Path,
+        PathKind,
+    },
+    persist::{persist_enum, persist_struct, Persist, Reader, Writer},
+    type_ref::{
+        ArrayType, ConstRef, FnType, LifetimeRef, LiteralConstRef, Mutability, PathId, RefType,
+        TraitBoundModifier, TraitRef, TypeBound, TypeRef, TypesMap, UseArgRef,
+    },
+    visibility::RawVisibility,
+};
+
+impl Persist for TreeId {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.file.write(w);
+        self.block.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(TreeId { file: Persist::read(r)?, block: Persist::read(r)? })
+    }
+}
+
+impl<N: 'static> Persist for ItemTreeId<N> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.tree.write(w);
+        self.value.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(ItemTreeId { tree: Persist::read(r)?, value: Persist::read(r)? })
+    }
+}
+
+impl<N: 'static> Persist for FileItemTreeId<N> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.0.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Idx::read(r).map(FileItemTreeId)
+    }
+}
+
+impl Persist for PathKind {
+    fn write(&self, w: &mut Writer<'_>) {
+        match *self {
+            PathKind::Plain => 0u8.write(w),
+            PathKind::Super(degree) => {
+                1u8.write(w);
+                degree.write(w);
+            }
+            PathKind::Crate => 2u8.write(w),
+            PathKind::Abs => 3u8.write(w),
+            PathKind::DollarCrate(krate) => {
+                4u8.write(w);
+                krate.write(w);
+            }
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(match u8::read(r)? {
+            0 => PathKind::Plain,
+            1 => PathKind::Super(u8::read(r)?),
+            2 => PathKind::Crate,
+            3 => PathKind::Abs,
+            4 => PathKind::DollarCrate(CrateId::read(r)?),
+            _ => return None,
+        })
+    }
+}
+
+impl Persist for ModPath {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.kind.write(w);
+        w.slice(self.segments());
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let kind = PathKind::read(r)?;
+        Some(ModPath::from_segments(kind, Vec::<Name>::read(r)?))
+    }
+}
+
+impl Persist for Interned<ModPath> {
+    fn write(&self, w: &mut Writer<'_>) {
+        (**self).write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        ModPath::read(r).map(Interned::new)
+    }
+}
+
+impl Persist for Path {
+    fn write(&self, w: &mut Writer<'_>) {
+        match self {
+            Path::BarePath(path) => {
+                0u8.write(w);
+                path.write(w);
+            }
+            Path::Normal(path) => {
+                1u8.write(w);
+                path.type_anchor().write(w);
+                path.mod_path().write(w);
+                w.slice(path.generic_args());
+            }
+            Path::LangItem(..) => w.unsupported(),
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(match u8::read(r)? {
+            0 => Path::BarePath(Persist::read(r)?),
+            1 => {
+                let type_anchor = Persist::read(r)?;
+                let mod_path = Persist::read(r)?;
+                let generic_args: Vec<_> = Persist::read(r)?;
+                Path::Normal(NormalPath::new(type_anchor, mod_path, generic_args))
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl Persist for FnType {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.is_varargs().write(w);
+        self.is_unsafe().write(w);
+        self.abi().write(w);
+        w.slice(self.params());
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let is_varargs = Persist::read(r)?;
+        let is_unsafe = Persist::read(r)?;
+        let abi = Persist::read(r)?;
+        let params: Vec<_> = Persist::read(r)?;
+        Some(FnType::new(is_varargs, is_unsafe, abi, params))
+    }
+}
+
+impl Persist for PathId {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.type_ref().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Idx::read(r).map(PathId::from_type_ref_unchecked)
+    }
+}
+
+impl Persist for Arc<GenericParams> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.iter_type_or_consts().count().write(w);
+        self.iter_type_or_consts().for_each(|(_, it)| it.write(w));
+        self.iter_lt().count().write(w);
+        self.iter_lt().for_each(|(_, it)| it.write(w));
+        w.slice(self.where_predicates().as_slice());
+        self.types_map.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let type_or_consts = Persist::read(r)?;
+        let lifetimes = Persist::read(r)?;
+        let where_predicates = Persist::read(r)?;
+        let types_map = Persist::read(r)?;
+        Some(GenericParams::from_parts(type_or_consts, lifetimes, where_predicates, types_map))
+    }
+}
+
+impl Persist for FnFlags {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.bits().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        u16::read(r).map(FnFlags::from_bits_retain)
+    }
+}
+
+impl Persist for RawVisibilityId {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.0.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        u32::read(r).map(RawVisibilityId)
+    }
+}
+
+impl Persist for RawAttrs {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.slice(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Vec::read(r).map(RawAttrs::from_attrs)
+    }
+}
+
+persist_struct! {
+    ItemTree { top_level, attrs, data }
+    ItemTreeData {
+        uses,
+        extern_crates,
+        extern_blocks,
+        functions,
+        structs,
+        unions,
+        enums,
+        variants,
+        consts,
+        statics,
+        traits,
+        trait_aliases,
+        impls,
+        type_aliases,
+        mods,
+        macro_calls,
+        macro_rules,
+        macro_defs,
+        vis,
+    }
+    ItemVisibilities { arena }
+    Use { visibility, ast_id, use_tree }
+    UseTree { index, kind }
+    ExternCrate { name, alias, visibility, ast_id }
+    ExternBlock { abi, ast_id, children }
+    Function {
+        name,
+        visibility,
+        explicit_generic_params,
+        abi,
+        params,
+        ret_type,
+        ast_id,
+        types_map,
+        flags,
+    }
+    Param { type_ref }
+    Struct { name, visibility, generic_params, fields, shape, ast_id, types_map }
+    Union { name, visibility, generic_params, fields, ast_id, types_map }
+    Enum { name, visibility, generic_params, variants, ast_id }
+    Variant { name, fields, shape, ast_id, types_map }
+    Field { name, type_ref, visibility }
+    Const { name, visibility, type_ref, ast_id, has_body, types_map }
+    Static { name, visibility, mutable, has_safe_kw, has_unsafe_kw, type_ref, ast_id, types_map }
+    Trait { name, visibility, generic_params, is_auto, is_unsafe, items, ast_id }
+    TraitAlias { name, visibility, generic_params, ast_id }
+    Impl {
+        generic_params,
+        target_trait,
+        self_ty,
+        is_negative,
+        is_unsafe,
+        items,
+        ast_id,
+        types_map,
+    }
+    TypeAlias { name, visibility, bounds, generic_params, type_ref, ast_id, types_map }
+    Mod { name, visibility, kind, ast_id }
+    MacroCall { path, ast_id, expand_to, ctxt }
+    MacroRules { name, ast_id }
+    Macro2 { name, visibility, ast_id }
+
+    TypesMap { types }
+    RefType { ty, lifetime, mutability }
+    ArrayType { ty, len }
+    LifetimeRef { name }
+    TraitRef { path }
+    GenericArgs { args, has_self_type, bindings, desugared_from_fn }
+    AssociatedTypeBinding { name, args, type_ref, bounds }
+    TypeParamData { name, default, provenance }
+    LifetimeParamData { name }
+    ConstParamData { name, ty, default }
+
+    Attr { id, path, input, ctxt }
+}
+
+persist_enum! {
+    ModItem {
+        Use(it),
+        ExternCrate(it),
+        ExternBlock(it),
+        Function(it),
+        Struct(it),
+        Union(it),
+        Enum(it),
+        Const(it),
+        Static(it),
+        Trait(it),
+        TraitAlias(it),
+        Impl(it),
+        TypeAlias(it),
+        Mod(it),
+        MacroCall(it),
+        MacroRules(it),
+        Macro2(it),
+    }
+    GenericModItem {
+        Function(it),
+        Struct(it),
+        Union(it),
+        Enum(it),
+        Trait(it),
+        TraitAlias(it),
+        Impl(it),
+        TypeAlias(it),
+    }
+    AssocItem { Function(it), TypeAlias(it), Const(it), MacroCall(it) }
+    AttrOwner {
+        ModItem(it),
+        TopLevel,
+        Variant(it),
+        Field(parent, field),
+        Param(func, param),
+        TypeOrConstParamData(item, param),
+        LifetimeParamData(item, param),
+    }
+    FieldParent { Struct(it), Union(it), Variant(it) }
+    FieldsShape { Record, Tuple, Unit }
+    ModKind { Inline { items }, Outline }
+    UseTreeKind { Single { path, alias }, Glob { path }, Prefixed { prefix, list } }
+    ImportAlias { Underscore, Alias(name) }
+    RawVisibility { Module(path, explicitness), Public }
+
+    TypeRef {
+        Never,
+        Placeholder,
+        Tuple(types),
+        Path(path),
+        RawPtr(ty, mutability),
+        Reference(ref_),
+        Array(array),
+        Slice(ty),
+        Fn(fn_),
+        ImplTrait(bounds),
+        DynTrait(bounds),
+        Macro(ast_id),
+        Error,
+    }
+    Mutability { Shared, Mut }
+    ConstRef { Scalar(lit), Path(name), Complex(ast_id) }
+    LiteralConstRef { Int(it), UInt(it), Bool(it), Char(it), Unknown }
+    TypeBound {
+        Path(path, modifier),
+        ForLifetime(lifetimes, path),
+        Lifetime(lifetime),
+        Use(args),
+        Error,
+    }
+    UseArgRef { Name(name), Lifetime(lifetime) }
+    TraitBoundModifier { None, Maybe }
+    GenericArg { Type(ty), Lifetime(lifetime), Const(konst) }
+    TypeOrConstParamData { TypeParamData(it), ConstParamData(it) }
+    TypeParamProvenance { TypeParamList, TraitSelf, ArgumentImplTrait }
+    WherePredicate {
+        TypeBound { target, bound },
+        Lifetime { target, bound },
+        ForLifetime { lifetimes, target, bound },
+    }
+    WherePredicateTypeTarget { TypeRef(ty), TypeOrConstParam(param) }
+
+    AttrInput { Literal(lit), TokenTree(tt) }
+}
@@ -2,13 +2,30 @@ use expect_test::{expect, Expect};
 use span::Edition;
 use test_fixture::WithFixture;
 
-use crate::{db::DefDatabase, test_db::TestDB};
+use crate::{db::DefDatabase, persist::PersistedCaches, test_db::TestDB};
 
 fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str, expect: Expect) {
     let (db, file_id) = TestDB::with_single_file(ra_fixture);
     let item_tree = db.file_item_tree(file_id.into());
     let pretty = item_tree.pretty_print(&db, Edition::CURRENT);
     expect.assert_eq(&pretty);
+
+    let mut persisted = PersistedCaches::default();
+    assert!(persisted.insert_item_tree(&db, file_id));
+    assert_eq!(persisted.item_tree(&db, file_id).as_ref(), Some(&*item_tree));
+}
+
+#[test]
+fn persisted_tree_of_another_text_is_not_used() {
+    let (db, file_id) = TestDB::with_single_file("struct S;");
+    let mut persisted = PersistedCaches::default();
+    assert!(persisted.insert_item_tree(&db, file_id));
+    let persisted = PersistedCaches::from_bytes(&persisted.to_bytes()).unwrap();
+    assert!(persisted.item_tree(&db, file_id).is_some());
+
+    // A text of the same length is told apart by its hash.
+    let (db, file_id) = TestDB::with_single_file("struct T;");
+    assert_eq!(persisted.item_tree(&db, file_id), None);
 }
 
 #[test]
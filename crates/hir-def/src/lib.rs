@@ -46,6 +46,7 @@ pub mod expr_store;
 pub mod resolver;
 
 pub mod nameres;
+pub mod persist;
 
 pub mod src;
 
@@ -234,3 +234,14 @@ use proc_macros::disallow_cfg;
         expect![[r#""#]],
     );
 }
+
+#[test]
+fn proc_macro_timeout() {
+    check_errors(
+        r#"
+//- proc_macros: timeout
+proc_macros::timeout!(struct S;);
+    "#,
+        expect!["0..33: proc macro timed out after 5s"],
+    );
+}
@@ -52,6 +52,7 @@ mod collector;
 pub mod diagnostics;
 mod mod_resolution;
 mod path_resolution;
+mod persist;
 pub mod proc_macro;
 
 #[cfg(test)]
@@ -340,6 +341,13 @@ impl DefMap {
         let name = krate.display_name.as_deref().map(Symbol::as_str).unwrap_or_default();
         let _p = tracing::info_span!("crate_def_map_query", ?name).entered();
 
+        let persisted = db.persisted_caches();
+        if !persisted.is_empty() {
+            if let Some(def_map) = persisted.def_map(db, &db.persist_keys(), crate_id) {
+                return Arc::new(def_map);
+            }
+        }
+
         let module_data = ModuleData::new(
             ModuleOrigin::CrateRoot { definition: krate.root_file_id() },
             Visibility::Public,
@@ -0,0 +1,105 @@
+//! [`Persist`] implementations of the [`DefMap`] of a crate and the types it consists of.
+//!
+//! Block def maps are not persisted, they are cheap to compute from the persisted item trees.
+
+use cfg::{CfgAtom, CfgExpr, CfgOptions};
+
+use crate::{
+    nameres::{
+        diagnostics::{DefDiagnostic, DefDiagnosticKind},
+        DefMap, DefMapCrateData, ModuleData, ModuleOrigin,
+    },
+    persist::{persist_enum, persist_struct, Persist, Reader, Writer},
+};
+
+impl Persist for DefMap {
+    fn write(&self, w: &mut Writer<'_>) {
+        let DefMap {
+            krate,
+            block,
+            modules,
+            prelude,
+            macro_use_prelude,
+            enum_definitions,
+            derive_helpers_in_scope,
+            diagnostics,
+            data,
+        } = self;
+        if block.is_some() {
+            w.unsupported();
+        }
+        krate.write(w);
+        modules.write(w);
+        prelude.write(w);
+        macro_use_prelude.write(w);
+        enum_definitions.write(w);
+        derive_helpers_in_scope.write(w);
+        diagnostics.write(w);
+        data.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(DefMap {
+            krate: Persist::read(r)?,
+            block: None,
+            modules: Persist::read(r)?,
+            prelude: Persist::read(r)?,
+            macro_use_prelude: Persist::read(r)?,
+            enum_definitions: Persist::read(r)?,
+            derive_helpers_in_scope: Persist::read(r)?,
+            diagnostics: Persist::read(r)?,
+            data: Persist::read(r)?,
+        })
+    }
+}
+
+impl Persist for CfgOptions {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.into_iter().cloned().collect::<Vec<_>>().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Vec::<CfgAtom>::read(r).map(CfgOptions::from_iter)
+    }
+}
+
+persist_struct! {
+    DefMapCrateData {
+        extern_prelude,
+        exported_derives,
+        fn_proc_macro_mapping,
+        registered_attrs,
+        registered_tools,
+        unstable_features,
+        rustc_coherence_is_core,
+        no_core,
+        no_std,
+        edition,
+        recursion_limit,
+    }
+    ModuleData { origin, visibility, parent, children, scope }
+    DefDiagnostic { in_module, kind }
+}
+
+persist_enum! {
+    ModuleOrigin {
+        CrateRoot { definition },
+        File { is_mod_rs, declaration, declaration_tree_id, definition },
+        Inline { definition_tree_id, definition },
+        BlockExpr { id, block },
+    }
+    DefDiagnosticKind {
+        UnresolvedModule { ast, candidates },
+        UnresolvedExternCrate { ast },
+        UnresolvedImport { id, index },
+        UnconfiguredCode { tree, item, cfg, opts },
+        UnresolvedMacroCall { ast, path },
+        UnimplementedBuiltinMacro { ast },
+        InvalidDeriveTarget { ast, id },
+        MalformedDerive { ast, id },
+        MacroDefError { ast, message },
+        MacroError { ast, path, err },
+    }
+    CfgExpr { Invalid, Atom(it), All(it), Any(it), Not(it) }
+    CfgAtom { Flag(it), KeyValue { key, value } }
+}
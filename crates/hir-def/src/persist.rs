@@ -0,0 +1,1533 @@
+//! A compact binary encoding of the results of name resolution, used to reuse them for library
+//! crates across restarts.
+//!
+//! [`PersistedCaches`] holds three kinds of entries:
+//!
+//! * item trees, keyed by a 128-bit hash and the length of their file's text,
+//! * crate def maps, keyed by the fingerprint of their crate,
+//! * symbol indices of library source roots, which are encoded by `hir` and keyed by the
+//!   fingerprints of the crates of the source root.
+//!
+//! Item trees only depend on the text of their file, so they are encoded without referring to
+//! anything else; spans and ast ids are attached to the file the tree is loaded for. Def maps and
+//! symbols consist of ids that are interned in the database that built them. These ids are
+//! encoded by their locations instead: the first occurrence of an id is written out in full and
+//! later ones refer back to it, and decoding interns the locations again. Crates and files are
+//! referred to by the keys of [`PersistKeys`], a fingerprint of everything that goes into the def
+//! map of a crate and the path of a file. The keys are computed by `ide-db`, which knows about
+//! source roots.
+//!
+//! Bump [`PersistedCaches::VERSION`] whenever one of the encoded types changes.
+
+use std::{any::TypeId, fmt, hash::Hash, ops::Range, time::Duration};
+
+use base_db::{
+    ra_salsa::{InternId, InternKey},
+    CrateData, CrateGraph, CrateId,
+};
+use hir_expand::{
+    attrs::AttrId,
+    builtin::{
+        find_builtin_attr, find_builtin_derive, find_builtin_macro, BuiltinAttrExpander,
+        BuiltinDeriveExpander, BuiltinFnLikeExpander, EagerExpander,
+    },
+    hygiene::intern_syntax_context,
+    name::{AsName, Name},
+    proc_macro::{CustomProcMacroExpander, ProcMacroKind},
+    tt::{
+        Delimiter, DelimiterKind, Ident, IdentIsRaw, Leaf, LitKind, Literal, Punct, Spacing,
+        Subtree, TokenTree, TopSubtree,
+    },
+    EagerCallInfo, ExpandError, ExpandErrorKind, ExpandTo, HirFileId, InFile, MacroCallId,
+    MacroCallKind, MacroCallLoc, MacroDefId, MacroDefKind, MacroFileId,
+};
+use intern::Symbol;
+use la_arena::{Arena, Idx, RawIdx};
+use mbe::ExpandErrorKind as MbeExpandErrorKind;
+use rustc_hash::{FxHashMap, FxHashSet};
+use smallvec::SmallVec;
+use span::{
+    AstIdNode, Edition, EditionedFileId, ErasedFileAstId, FileAstId, FileId, HirFileIdRepr, Span,
+    SpanAnchor, SyntaxContextId, TextRange, TextSize, Transparency,
+};
+use stdx::thin_vec::{EmptyOptimizedThinVec, ThinVec};
+use syntax::SmolStr;
+use triomphe::Arc;
+
+use crate::{
+    builtin_type::BuiltinType,
+    db::DefDatabase,
+    item_tree::{ItemTree, ItemTreeNode},
+    nameres::DefMap,
+    per_ns::Item,
+    visibility::{Visibility, VisibilityExplicitness},
+    AdtId, AssocItemLoc, BlockId, BlockLoc, ConstId, CrateRootModuleId, EnumId, EnumVariantId,
+    EnumVariantLoc, ExternBlockId, ExternCrateId, FunctionId, FxIndexMap, ImplId, Intern,
+    ItemContainerId, ItemLoc, Lookup, Macro2Id, Macro2Loc, MacroExpander, MacroId, MacroRulesId,
+    MacroRulesLoc, MacroRulesLocFlags, ModuleDefId, ModuleId, ProcMacroId, ProcMacroLoc, StaticId,
+    StructId, TraitAliasId, TraitId, TypeAliasId, UnionId, UseId,
+};
+
+/// Encoded item trees, def maps and symbol indices of library crates.
+#[derive(Default, PartialEq, Eq)]
+pub struct PersistedCaches {
+    item_trees: FxHashMap<TextKey, Box<[u8]>>,
+    def_maps: FxHashMap<u128, Box<[u8]>>,
+    symbols: FxHashMap<u128, Box<[u8]>>,
+}
+
+/// Identifies the text of a file by its hash and length. The hash is strong enough that a tree
+/// found for a key can be used without comparing the texts.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct TextKey {
+    hash: u128,
+    len: u32,
+    edition: Edition,
+}
+
+impl TextKey {
+    fn new(text: &str, edition: Edition) -> TextKey {
+        TextKey { hash: content_hash(text.as_bytes()), len: text.len() as u32, edition }
+    }
+}
+
+/// A 128-bit hash of `data`, used to tell contents apart without comparing them.
+pub fn content_hash(data: &[u8]) -> u128 {
+    let digest = tenthash::hash(data);
+    u128::from_le_bytes(digest[..16].try_into().unwrap())
+}
+
+impl fmt::Debug for PersistedCaches {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistedCaches")
+            .field("item_trees", &self.item_trees.len())
+            .field("def_maps", &self.def_maps.len())
+            .field("symbols", &self.symbols.len())
+            .finish()
+    }
+}
+
+impl PersistedCaches {
+    const MAGIC: &'static [u8] = b"rust-analyzer caches\n";
+    const VERSION: u32 = 3;
+
+    /// Decodes the output of [`PersistedCaches::to_bytes`], returns `None` if the data is
+    /// malformed or was written by an incompatible version.
+    pub fn from_bytes(bytes: &[u8]) -> Option<PersistedCaches> {
+        let mut r = Reader::new(bytes.strip_prefix(Self::MAGIC)?, None, None, None);
+        if u32::read(&mut r)? != Self::VERSION {
+            return None;
+        }
+        let len = usize::read(&mut r)?;
+        let mut item_trees = FxHashMap::default();
+        for _ in 0..len {
+            let key = TextKey {
+                hash: Persist::read(&mut r)?,
+                len: Persist::read(&mut r)?,
+                edition: Persist::read(&mut r)?,
+            };
+            item_trees.insert(key, r.blob()?);
+        }
+        let def_maps = r.blobs()?;
+        let symbols = r.blobs()?;
+        r.buf.is_empty().then_some(PersistedCaches { item_trees, def_maps, symbols })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new(None, None, None);
+        w.buf.extend_from_slice(Self::MAGIC);
+        Self::VERSION.write(&mut w);
+        self.item_trees.len().write(&mut w);
+        for (&TextKey { hash, len, edition }, tree) in &self.item_trees {
+            hash.write(&mut w);
+            len.write(&mut w);
+            edition.write(&mut w);
+            w.blob(tree);
+        }
+        w.blobs(&self.def_maps);
+        w.blobs(&self.symbols);
+        w.buf
+    }
+
+    /// The number of persisted item trees, def maps and symbol indices.
+    pub fn len(&self) -> usize {
+        self.item_trees.len() + self.def_maps.len() + self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Encodes the item tree of `file_id`. Returns `false` if the tree refers to things that can't
+    /// be persisted.
+    pub fn insert_item_tree(&mut self, db: &dyn DefDatabase, file_id: EditionedFileId) -> bool {
+        let key = TextKey::new(&db.file_text(file_id.file_id()), file_id.edition());
+        if self.item_trees.contains_key(&key) {
+            return true;
+        }
+        let item_tree = db.file_item_tree(file_id.into());
+        let Some(tree) = encode(None, None, Some(file_id), &*item_tree) else { return false };
+        self.item_trees.insert(key, tree);
+        true
+    }
+
+    pub(crate) fn item_tree(
+        &self,
+        db: &dyn DefDatabase,
+        file_id: EditionedFileId,
+    ) -> Option<ItemTree> {
+        let key = TextKey::new(&db.file_text(file_id.file_id()), file_id.edition());
+        let tree = self.item_trees.get(&key)?;
+        let item_tree = decode(None, None, Some(file_id), tree);
+        if item_tree.is_none() {
+            tracing::warn!(?file_id, "failed to decode persisted item tree");
+        }
+        item_tree
+    }
+
+    /// Encodes the def map of `krate`. Returns `false` if the crate has no fingerprint or its def
+    /// map refers to things that can't be persisted.
+    pub fn insert_def_map(
+        &mut self,
+        db: &dyn DefDatabase,
+        keys: &PersistKeys,
+        krate: CrateId,
+    ) -> bool {
+        let Some(fingerprint) = keys.crate_fingerprint(krate) else { return false };
+        if self.def_maps.contains_key(&fingerprint) {
+            return true;
+        }
+        let def_map = db.crate_def_map(krate);
+        let Some(def_map) = encode(Some(db), Some(keys), None, &*def_map) else { return false };
+        self.def_maps.insert(fingerprint, def_map);
+        true
+    }
+
+    pub(crate) fn def_map(
+        &self,
+        db: &dyn DefDatabase,
+        keys: &PersistKeys,
+        krate: CrateId,
+    ) -> Option<DefMap> {
+        if !keys.is_current(db) {
+            return None;
+        }
+        let def_map = self.def_maps.get(&keys.crate_fingerprint(krate)?)?;
+        let def_map: Option<DefMap> = decode(Some(db), Some(keys), None, def_map);
+        match def_map {
+            Some(def_map) if def_map.krate() == krate => Some(def_map),
+            _ => {
+                tracing::warn!(?krate, "failed to decode persisted def map");
+                None
+            }
+        }
+    }
+
+    /// Encodes the symbols of a source root, identified by `fingerprint`. Returns `false` if the
+    /// symbols refer to things that can't be persisted.
+    pub fn insert_symbols<T: Persist>(
+        &mut self,
+        db: &dyn DefDatabase,
+        keys: &PersistKeys,
+        fingerprint: u128,
+        symbols: &T,
+    ) -> bool {
+        let Some(symbols) = encode(Some(db), Some(keys), None, symbols) else { return false };
+        self.symbols.insert(fingerprint, symbols);
+        true
+    }
+
+    pub fn symbols<T: Persist>(
+        &self,
+        db: &dyn DefDatabase,
+        keys: &PersistKeys,
+        fingerprint: u128,
+    ) -> Option<T> {
+        if !keys.is_current(db) {
+            return None;
+        }
+        let symbols = decode(Some(db), Some(keys), None, self.symbols.get(&fingerprint)?);
+        if symbols.is_none() {
+            tracing::warn!("failed to decode persisted symbols");
+        }
+        symbols
+    }
+}
+
+/// Stable keys for the crates and files of a crate graph, used to refer to them in persisted def
+/// maps and symbols.
+///
+/// A crate is keyed by a fingerprint of everything its def map depends on, including the
+/// fingerprints of its dependencies, so that a persisted def map is only used for an identical
+/// crate. A file is keyed by its path. Only library crates and files have keys.
+#[derive(Default)]
+pub struct PersistKeys {
+    /// The crate graph the keys were computed for, keys of other crate graphs are never used.
+    crate_graph: Option<Arc<CrateGraph>>,
+    crates: FxHashMap<CrateId, u128>,
+    crates_by_fingerprint: FxHashMap<u128, CrateId>,
+    files: FxHashMap<FileId, Box<str>>,
+    files_by_path: FxHashMap<Box<str>, FileId>,
+}
+
+impl PersistKeys {
+    pub fn new(crate_graph: Arc<CrateGraph>) -> PersistKeys {
+        PersistKeys { crate_graph: Some(crate_graph), ..PersistKeys::default() }
+    }
+
+    pub fn insert_crate(&mut self, krate: CrateId, fingerprint: u128) {
+        self.crates.insert(krate, fingerprint);
+        self.crates_by_fingerprint.insert(fingerprint, krate);
+    }
+
+    pub fn insert_file(&mut self, file_id: FileId, path: Box<str>) {
+        self.files.insert(file_id, path.clone());
+        self.files_by_path.insert(path, file_id);
+    }
+
+    pub fn crate_fingerprint(&self, krate: CrateId) -> Option<u128> {
+        self.crates.get(&krate).copied()
+    }
+
+    /// Whether the keys were computed for the current crate graph of `db`.
+    pub fn is_current(&self, db: &dyn DefDatabase) -> bool {
+        self.crate_graph.as_ref().is_some_and(|it| Arc::ptr_eq(it, &db.crate_graph()))
+    }
+}
+
+impl PartialEq for PersistKeys {
+    fn eq(&self, other: &Self) -> bool {
+        // Crate graphs can't be compared, keys are only equal if they were computed for the same
+        // graph.
+        let same_graph = match (&self.crate_graph, &other.crate_graph) {
+            (Some(it), Some(other)) => Arc::ptr_eq(it, other),
+            (None, None) => true,
+            _ => false,
+        };
+        same_graph && self.crates == other.crates && self.files == other.files
+    }
+}
+
+impl Eq for PersistKeys {}
+
+impl fmt::Debug for PersistKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistKeys")
+            .field("crates", &self.crates.len())
+            .field("files", &self.files.len())
+            .finish()
+    }
+}
+
+/// Encodes `value`, returns `None` if it refers to things that can't be persisted.
+fn encode<T: Persist>(
+    db: Option<&dyn DefDatabase>,
+    keys: Option<&PersistKeys>,
+    file_id: Option<EditionedFileId>,
+    value: &T,
+) -> Option<Box<[u8]>> {
+    let mut w = Writer::new(db, keys, file_id);
+    value.write(&mut w);
+    (!w.unsupported).then(|| w.buf.into_boxed_slice())
+}
+
+fn decode<T: Persist>(
+    db: Option<&dyn DefDatabase>,
+    keys: Option<&PersistKeys>,
+    file_id: Option<EditionedFileId>,
+    bytes: &[u8],
+) -> Option<T> {
+    let mut r = Reader::new(bytes, db, keys, file_id);
+    T::read(&mut r).filter(|_| r.buf.is_empty())
+}
+
+pub struct Writer<'a> {
+    buf: Vec<u8>,
+    /// The database the written ids are interned in.
+    db: Option<&'a dyn DefDatabase>,
+    keys: Option<&'a PersistKeys>,
+    /// The file whose item tree is written, which is referred to without a key.
+    file_id: Option<EditionedFileId>,
+    /// The index of every value written by [`Writer::memoized`], by its type and raw value.
+    memoized: FxHashMap<(TypeId, u32), u32>,
+    memoized_len: FxHashMap<TypeId, u32>,
+    /// Set when something that can't be persisted was encountered.
+    unsupported: bool,
+}
+
+impl<'a> Writer<'a> {
+    fn new(
+        db: Option<&'a dyn DefDatabase>,
+        keys: Option<&'a PersistKeys>,
+        file_id: Option<EditionedFileId>,
+    ) -> Writer<'a> {
+        Writer {
+            buf: Vec::new(),
+            db,
+            keys,
+            file_id,
+            memoized: FxHashMap::default(),
+            memoized_len: FxHashMap::default(),
+            unsupported: false,
+        }
+    }
+
+    fn leb128(&mut self, mut it: u64) {
+        while it >= 0x80 {
+            self.buf.push(it as u8 | 0x80);
+            it >>= 7;
+        }
+        self.buf.push(it as u8);
+    }
+
+    fn str(&mut self, it: &str) {
+        it.len().write(self);
+        self.buf.extend_from_slice(it.as_bytes());
+    }
+
+    fn blob(&mut self, it: &[u8]) {
+        it.len().write(self);
+        self.buf.extend_from_slice(it);
+    }
+
+    fn blobs(&mut self, blobs: &FxHashMap<u128, Box<[u8]>>) {
+        blobs.len().write(self);
+        for (key, blob) in blobs {
+            key.write(self);
+            self.blob(blob);
+        }
+    }
+
+    pub(crate) fn slice<T: Persist>(&mut self, items: &[T]) {
+        items.len().write(self);
+        items.iter().for_each(|it| it.write(self));
+    }
+
+    /// Marks the written value as one that can't be persisted.
+    pub(crate) fn unsupported(&mut self) {
+        self.unsupported = true;
+    }
+
+    /// Writes a value of type `T` with the raw representation `raw`. The first time the value is
+    /// encountered, it is written in full by `write`, afterwards only its index is written.
+    fn memoized<T: 'static>(&mut self, raw: u32, write: impl FnOnce(&mut Self)) {
+        let key = (TypeId::of::<T>(), raw);
+        if let Some(&idx) = self.memoized.get(&key) {
+            (idx + 1).write(self);
+            return;
+        }
+        0u32.write(self);
+        write(self);
+        let len = self.memoized_len.entry(key.0).or_default();
+        self.memoized.insert(key, *len);
+        *len += 1;
+    }
+
+    /// Writes an interned id by its location.
+    fn interned<Id: InternKey + 'static, Loc: Persist>(
+        &mut self,
+        id: Id,
+        lookup: impl FnOnce(&dyn DefDatabase) -> Loc,
+    ) {
+        let Some(db) = self.db else {
+            self.unsupported = true;
+            return;
+        };
+        self.memoized::<Id>(id.as_intern_id().as_u32(), |w| lookup(db).write(w));
+    }
+}
+
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    /// The database read ids are interned in.
+    db: Option<&'a dyn DefDatabase>,
+    keys: Option<&'a PersistKeys>,
+    /// The file whose item tree is read.
+    file_id: Option<EditionedFileId>,
+    /// The values read by [`Reader::memoized`] so far, by their type.
+    memoized: FxHashMap<TypeId, Vec<u32>>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(
+        buf: &'a [u8],
+        db: Option<&'a dyn DefDatabase>,
+        keys: Option<&'a PersistKeys>,
+        file_id: Option<EditionedFileId>,
+    ) -> Reader<'a> {
+        Reader { buf, db, keys, file_id, memoized: FxHashMap::default() }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.buf.len() {
+            return None;
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Some(bytes)
+    }
+
+    fn leb128(&mut self) -> Option<u64> {
+        let mut result = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.bytes(1)?[0];
+            result |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    fn str(&mut self) -> Option<&'a str> {
+        let len = usize::read(self)?;
+        std::str::from_utf8(self.bytes(len)?).ok()
+    }
+
+    fn blob(&mut self) -> Option<Box<[u8]>> {
+        let len = usize::read(self)?;
+        self.bytes(len).map(Box::from)
+    }
+
+    fn blobs(&mut self) -> Option<FxHashMap<u128, Box<[u8]>>> {
+        let len = usize::read(self)?;
+        let mut blobs = FxHashMap::default();
+        for _ in 0..len {
+            blobs.insert(u128::read(self)?, self.blob()?);
+        }
+        Some(blobs)
+    }
+
+    /// Capacity for a collection of `len` elements, bounded by the remaining input so that
+    /// malformed lengths can't trigger huge allocations.
+    fn capacity(&self, len: usize) -> usize {
+        len.min(self.buf.len())
+    }
+
+    /// Reads the output of [`Writer::memoized`], `read` reads a value written in full and returns
+    /// its raw representation.
+    fn memoized<T: 'static>(&mut self, read: impl FnOnce(&mut Self) -> Option<u32>) -> Option<u32> {
+        match u32::read(self)? {
+            0 => {
+                let raw = read(self)?;
+                self.memoized.entry(TypeId::of::<T>()).or_default().push(raw);
+                Some(raw)
+            }
+            idx => self.memoized.get(&TypeId::of::<T>())?.get(idx as usize - 1).copied(),
+        }
+    }
+
+    /// Reads an interned id by interning its location.
+    fn interned<Id: InternKey + 'static, Loc: Persist>(
+        &mut self,
+        intern: impl FnOnce(&dyn DefDatabase, Loc) -> Id,
+    ) -> Option<Id> {
+        let db = self.db?;
+        let raw = self.memoized::<Id>(|r| {
+            let loc = Loc::read(r)?;
+            Some(intern(db, loc).as_intern_id().as_u32())
+        })?;
+        Some(Id::from_intern_id(InternId::from(raw)))
+    }
+}
+
+/// A type that can be persisted.
+pub trait Persist: Sized {
+    fn write(&self, w: &mut Writer<'_>);
+    fn read(r: &mut Reader<'_>) -> Option<Self>;
+}
+
+/// Implements [`Persist`] for structs by encoding their fields in order.
+macro_rules! persist_struct {
+    ($($ty:ident { $($field:ident),* $(,)? })*) => {$(
+        impl $crate::persist::Persist for $ty {
+            fn write(&self, w: &mut $crate::persist::Writer<'_>) {
+                let Self { $($field),* } = self;
+                $($crate::persist::Persist::write($field, w);)*
+            }
+
+            fn read(r: &mut $crate::persist::Reader<'_>) -> Option<Self> {
+                Some(Self { $($field: $crate::persist::Persist::read(r)?),* })
+            }
+        }
+    )*};
+}
+pub(crate) use persist_struct;
+
+/// Implements [`Persist`] for enums by encoding the index of the variant followed by its fields.
+macro_rules! persist_enum {
+    ($($ty:ident {
+        $($variant:ident $(($($tuple:ident),+))? $({ $($field:ident),+ })?),* $(,)?
+    })*) => {$(
+        impl $crate::persist::Persist for $ty {
+            fn write(&self, w: &mut $crate::persist::Writer<'_>) {
+                enum Tag { $($variant),* }
+                match self {
+                    $(Self::$variant $(($($tuple),+))? $({ $($field),+ })? => {
+                        $crate::persist::Persist::write(&(Tag::$variant as u8), w);
+                        $($($crate::persist::Persist::write($tuple, w);)+)?
+                        $($($crate::persist::Persist::write($field, w);)+)?
+                    })*
+                }
+            }
+
+            fn read(r: &mut $crate::persist::Reader<'_>) -> Option<Self> {
+                enum Tag { $($variant),* }
+                let tag: u8 = $crate::persist::Persist::read(r)?;
+                $(
+                    if tag == Tag::$variant as u8 {
+                        return Some(Self::$variant
+                            $(($({
+                                let $tuple = $crate::persist::Persist::read(r)?;
+                                $tuple
+                            }),+))?
+                            $({ $($field: $crate::persist::Persist::read(r)?),+ })?
+                        );
+                    }
+                )*
+                None
+            }
+        }
+    )*};
+}
+pub(crate) use persist_enum;
+
+/// Implements [`Persist`] for interned ids by encoding their locations.
+macro_rules! persist_interned {
+    ($($id:ident),* $(,)?) => {$(
+        impl Persist for $id {
+            fn write(&self, w: &mut Writer<'_>) {
+                w.interned(*self, |db| self.lookup(db));
+            }
+
+            fn read(r: &mut Reader<'_>) -> Option<Self> {
+                r.interned(|db, loc: <$id as Lookup>::Data| loc.intern(db))
+            }
+        }
+    )*};
+}
+
+macro_rules! persist_int {
+    ($($ty:ty),*) => {$(
+        impl Persist for $ty {
+            fn write(&self, w: &mut Writer<'_>) {
+                w.leb128(*self as u64);
+            }
+
+            fn read(r: &mut Reader<'_>) -> Option<Self> {
+                r.leb128()?.try_into().ok()
+            }
+        }
+    )*};
+}
+
+persist_int!(u8, u16, u32, u64, usize);
+
+impl Persist for () {
+    fn write(&self, _: &mut Writer<'_>) {}
+
+    fn read(_: &mut Reader<'_>) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl Persist for bool {
+    fn write(&self, w: &mut Writer<'_>) {
+        u8::from(*self).write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        match u8::read(r)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Persist for char {
+    fn write(&self, w: &mut Writer<'_>) {
+        u32::from(*self).write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        char::from_u32(u32::read(r)?)
+    }
+}
+
+impl Persist for u128 {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.buf.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(u128::from_le_bytes(r.bytes(16)?.try_into().ok()?))
+    }
+}
+
+impl Persist for i128 {
+    fn write(&self, w: &mut Writer<'_>) {
+        (*self as u128).write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(u128::read(r)? as i128)
+    }
+}
+
+impl Persist for Duration {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.as_secs().write(w);
+        self.subsec_nanos().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(Duration::new(u64::read(r)?, u32::read(r)?))
+    }
+}
+
+impl Persist for Box<str> {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.str(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        r.str().map(Box::from)
+    }
+}
+
+impl Persist for String {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.str(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        r.str().map(String::from)
+    }
+}
+
+impl Persist for SmolStr {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.str(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        r.str().map(SmolStr::new)
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.is_some().write(w);
+        if let Some(it) = self {
+            it.write(w);
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(if bool::read(r)? { Some(T::read(r)?) } else { None })
+    }
+}
+
+impl<A: Persist, B: Persist> Persist for (A, B) {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.0.write(w);
+        self.1.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some((A::read(r)?, B::read(r)?))
+    }
+}
+
+impl<A: Persist, B: Persist, C: Persist> Persist for (A, B, C) {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.0.write(w);
+        self.1.write(w);
+        self.2.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some((A::read(r)?, B::read(r)?, C::read(r)?))
+    }
+}
+
+impl<T: Persist> Persist for Range<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.start.write(w);
+        self.end.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(T::read(r)?..T::read(r)?)
+    }
+}
+
+impl<T: Persist> Persist for Box<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        (**self).write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        T::read(r).map(Box::new)
+    }
+}
+
+impl<T: Persist> Persist for Arc<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        (**self).write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        T::read(r).map(Arc::new)
+    }
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.slice(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let len = usize::read(r)?;
+        let mut vec = Vec::with_capacity(r.capacity(len));
+        for _ in 0..len {
+            vec.push(T::read(r)?);
+        }
+        Some(vec)
+    }
+}
+
+impl<T: Persist> Persist for Box<[T]> {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.slice(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Vec::read(r).map(Vec::into_boxed_slice)
+    }
+}
+
+impl<T: Persist> Persist for ThinVec<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.slice(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Vec::read(r).map(ThinVec::from_iter)
+    }
+}
+
+impl<T: Persist> Persist for EmptyOptimizedThinVec<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.slice(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Vec::read(r).map(EmptyOptimizedThinVec::from_iter)
+    }
+}
+
+impl<A: smallvec::Array> Persist for SmallVec<A>
+where
+    A::Item: Persist,
+{
+    fn write(&self, w: &mut Writer<'_>) {
+        w.slice(self);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Vec::read(r).map(SmallVec::from_vec)
+    }
+}
+
+impl<K: Persist + Eq + Hash, V: Persist> Persist for FxHashMap<K, V> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.len().write(w);
+        self.iter().for_each(|it| {
+            it.0.write(w);
+            it.1.write(w);
+        });
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let len = usize::read(r)?;
+        let mut map = FxHashMap::default();
+        map.reserve(r.capacity(len));
+        for _ in 0..len {
+            map.insert(K::read(r)?, V::read(r)?);
+        }
+        Some(map)
+    }
+}
+
+impl<K: Persist + Eq + Hash, V: Persist> Persist for FxIndexMap<K, V> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.len().write(w);
+        self.iter().for_each(|it| {
+            it.0.write(w);
+            it.1.write(w);
+        });
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let len = usize::read(r)?;
+        let mut map = FxIndexMap::default();
+        map.reserve(r.capacity(len));
+        for _ in 0..len {
+            map.insert(K::read(r)?, V::read(r)?);
+        }
+        Some(map)
+    }
+}
+
+impl<T: Persist + Eq + Hash> Persist for FxHashSet<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.len().write(w);
+        self.iter().for_each(|it| it.write(w));
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let len = usize::read(r)?;
+        let mut set = FxHashSet::default();
+        set.reserve(r.capacity(len));
+        for _ in 0..len {
+            set.insert(T::read(r)?);
+        }
+        Some(set)
+    }
+}
+
+impl<T: Persist> Persist for Arena<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.len().write(w);
+        self.values().for_each(|it| it.write(w));
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let len = usize::read(r)?;
+        let mut arena = Arena::with_capacity(r.capacity(len));
+        for _ in 0..len {
+            arena.alloc(T::read(r)?);
+        }
+        Some(arena)
+    }
+}
+
+impl<T: 'static> Persist for Idx<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        // `CrateId`s are indices into the crate graph, which are not stable across sessions.
+        if TypeId::of::<T>() == TypeId::of::<CrateData>() {
+            write_crate(CrateId::from_raw(self.into_raw()), w);
+        } else {
+            self.into_raw().into_u32().write(w);
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        if TypeId::of::<T>() == TypeId::of::<CrateData>() {
+            read_crate(r).map(|krate| Idx::from_raw(krate.into_raw()))
+        } else {
+            Some(Idx::from_raw(RawIdx::from_u32(u32::read(r)?)))
+        }
+    }
+}
+
+impl Persist for Edition {
+    fn write(&self, w: &mut Writer<'_>) {
+        (*self as u8).write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Edition::iter().nth(usize::read(r)?)
+    }
+}
+
+impl Persist for Symbol {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.str(self.as_str());
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        r.str().map(Symbol::intern)
+    }
+}
+
+impl Persist for Name {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.symbol().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Symbol::read(r).map(Name::new_symbol_root)
+    }
+}
+
+impl Persist for TextRange {
+    fn write(&self, w: &mut Writer<'_>) {
+        u32::from(self.start()).write(w);
+        u32::from(self.len()).write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let start = TextSize::from(u32::read(r)?);
+        Some(TextRange::at(start, TextSize::from(u32::read(r)?)))
+    }
+}
+
+/// Writes a crate by its fingerprint.
+fn write_crate(krate: CrateId, w: &mut Writer<'_>) {
+    let fingerprint = w.keys.and_then(|keys| keys.crate_fingerprint(krate));
+    w.memoized::<CrateId>(krate.into_raw().into_u32(), |w| match fingerprint {
+        Some(fingerprint) => fingerprint.write(w),
+        None => w.unsupported = true,
+    });
+}
+
+fn read_crate(r: &mut Reader<'_>) -> Option<CrateId> {
+    let raw = r.memoized::<CrateId>(|r| {
+        let fingerprint = u128::read(r)?;
+        let krate = r.keys?.crates_by_fingerprint.get(&fingerprint)?;
+        Some(krate.into_raw().into_u32())
+    })?;
+    Some(CrateId::from_raw(RawIdx::from_u32(raw)))
+}
+
+impl Persist for EditionedFileId {
+    fn write(&self, w: &mut Writer<'_>) {
+        if w.file_id == Some(*self) {
+            0u8.write(w);
+            return;
+        }
+        1u8.write(w);
+        let path = w.keys.and_then(|keys| keys.files.get(&self.file_id()));
+        let path = path.map(|it| &**it);
+        w.memoized::<EditionedFileId>(self.as_u32(), |w| match path {
+            Some(path) => {
+                w.str(path);
+                self.edition().write(w);
+            }
+            None => w.unsupported = true,
+        });
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        if u8::read(r)? == 0 {
+            return r.file_id;
+        }
+        let raw = r.memoized::<EditionedFileId>(|r| {
+            let path = r.str()?;
+            let file_id = *r.keys?.files_by_path.get(path)?;
+            Some(EditionedFileId::new(file_id, Edition::read(r)?).as_u32())
+        })?;
+        Some(EditionedFileId::from_raw(raw))
+    }
+}
+
+impl Persist for HirFileId {
+    fn write(&self, w: &mut Writer<'_>) {
+        match self.repr() {
+            HirFileIdRepr::FileId(file_id) => {
+                0u8.write(w);
+                file_id.write(w);
+            }
+            HirFileIdRepr::MacroFile(MacroFileId { macro_call_id }) => {
+                1u8.write(w);
+                macro_call_id.write(w);
+            }
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(match u8::read(r)? {
+            0 => EditionedFileId::read(r)?.into(),
+            1 => MacroCallId::read(r)?.as_file(),
+            _ => return None,
+        })
+    }
+}
+
+impl<T: Persist> Persist for InFile<T> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.file_id.write(w);
+        self.value.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(InFile::new(HirFileId::read(r)?, T::read(r)?))
+    }
+}
+
+impl<N: AstIdNode> Persist for FileAstId<N> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.erase().into_raw().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(FileAstId::from_erased_unchecked(ErasedFileAstId::from_raw(u32::read(r)?)))
+    }
+}
+
+impl Persist for SyntaxContextId {
+    fn write(&self, w: &mut Writer<'_>) {
+        // The root contexts are the same in every database, the others are interned.
+        if self.is_root() {
+            0u8.write(w);
+            self.into_u32().write(w);
+            return;
+        }
+        1u8.write(w);
+        w.interned(*self, |db| {
+            let data = db.lookup_intern_syntax_context(*self);
+            (data.parent, data.outer_expn, data.outer_transparency, data.edition)
+        });
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        if u8::read(r)? == 0 {
+            let ctx = SyntaxContextId::from_u32(u32::read(r)?);
+            return ctx.is_root().then_some(ctx);
+        }
+        r.interned(|db, (parent, outer_expn, transparency, edition): SyntaxContextKey| {
+            let outer_expn: Option<MacroCallId> = outer_expn;
+            // Only root contexts have no outer expansion, they are not encoded this way.
+            let outer_expn = outer_expn.expect("non-root context without outer expansion");
+            intern_syntax_context(db.upcast(), parent, outer_expn, transparency, edition)
+        })
+    }
+}
+
+type SyntaxContextKey = (SyntaxContextId, Option<MacroCallId>, Transparency, Edition);
+
+impl<A: Persist, B: Persist, C: Persist, D: Persist> Persist for (A, B, C, D) {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.0.write(w);
+        self.1.write(w);
+        self.2.write(w);
+        self.3.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some((A::read(r)?, B::read(r)?, C::read(r)?, D::read(r)?))
+    }
+}
+
+impl Persist for Span {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.range.write(w);
+        self.anchor.file_id.write(w);
+        self.anchor.ast_id.into_raw().write(w);
+        self.ctx.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(Span {
+            range: TextRange::read(r)?,
+            anchor: SpanAnchor {
+                file_id: EditionedFileId::read(r)?,
+                ast_id: ErasedFileAstId::from_raw(u32::read(r)?),
+            },
+            ctx: SyntaxContextId::read(r)?,
+        })
+    }
+}
+
+impl Persist for AttrId {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.into_u32().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        u32::read(r).map(AttrId::from_u32)
+    }
+}
+
+impl Persist for TopSubtree {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.0.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Persist::read(r).map(::tt::TopSubtree)
+    }
+}
+
+impl Persist for MacroCallId {
+    fn write(&self, w: &mut Writer<'_>) {
+        w.interned(*self, |db| db.lookup_intern_macro_call(*self));
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        r.interned(|db, loc| db.intern_macro_call(loc))
+    }
+}
+
+impl Persist for EagerCallInfo {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.arg().write(w);
+        self.arg_id().write(w);
+        self.error().cloned().write(w);
+        self.span().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let arg = Persist::read(r)?;
+        let arg_id = Persist::read(r)?;
+        let error = Persist::read(r)?;
+        Some(EagerCallInfo::new(arg, arg_id, error, Span::read(r)?))
+    }
+}
+
+impl Persist for ExpandError {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.kind().write(w);
+        self.span().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        let kind = ExpandErrorKind::read(r)?;
+        Some(ExpandError::new(Span::read(r)?, kind))
+    }
+}
+
+impl Persist for CustomProcMacroExpander {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.into_u32().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        u32::read(r).map(CustomProcMacroExpander::from_u32)
+    }
+}
+
+/// Implements [`Persist`] for builtin macro expanders by encoding their names.
+macro_rules! persist_builtin_expander {
+    ($($ty:ident => $find:expr),* $(,)?) => {$(
+        impl Persist for $ty {
+            fn write(&self, w: &mut Writer<'_>) {
+                self.name().write(w);
+            }
+
+            fn read(r: &mut Reader<'_>) -> Option<Self> {
+                $find(&Name::new_symbol_root(Symbol::read(r)?))
+            }
+        }
+    )*};
+}
+
+persist_builtin_expander! {
+    BuiltinFnLikeExpander => |name| find_builtin_macro(name)?.left(),
+    EagerExpander => |name| find_builtin_macro(name)?.right(),
+    BuiltinAttrExpander => find_builtin_attr,
+    BuiltinDeriveExpander => find_builtin_derive,
+}
+
+impl Persist for BuiltinType {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.as_name().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        BuiltinType::by_name(&Name::read(r)?)
+    }
+}
+
+impl Persist for MacroRulesLocFlags {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.bits().write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        u8::read(r).map(MacroRulesLocFlags::from_bits_retain)
+    }
+}
+
+impl<N: ItemTreeNode + 'static> Persist for ItemLoc<N> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.container.write(w);
+        self.id.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(ItemLoc { container: Persist::read(r)?, id: Persist::read(r)? })
+    }
+}
+
+impl<N: ItemTreeNode + 'static> Persist for AssocItemLoc<N> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.container.write(w);
+        self.id.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(AssocItemLoc { container: Persist::read(r)?, id: Persist::read(r)? })
+    }
+}
+
+impl<Def: Persist, Import: Persist> Persist for Item<Def, Import> {
+    fn write(&self, w: &mut Writer<'_>) {
+        self.def.write(w);
+        self.vis.write(w);
+        self.import.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(Item { def: Persist::read(r)?, vis: Persist::read(r)?, import: Persist::read(r)? })
+    }
+}
+
+persist_interned! {
+    FunctionId,
+    StructId,
+    UnionId,
+    EnumId,
+    ConstId,
+    StaticId,
+    TraitId,
+    TraitAliasId,
+    TypeAliasId,
+    ImplId,
+    UseId,
+    ExternCrateId,
+    ExternBlockId,
+    EnumVariantId,
+    Macro2Id,
+    MacroRulesId,
+    ProcMacroId,
+    BlockId,
+}
+
+persist_struct! {
+    ModuleId { krate, block, local_id }
+    CrateRootModuleId { krate }
+    EnumVariantLoc { id, parent, index }
+    Macro2Loc { container, id, expander, allow_internal_unsafe, edition }
+    MacroRulesLoc { container, id, expander, flags, edition }
+    ProcMacroLoc { container, id, expander, kind, edition }
+    BlockLoc { ast_id, module }
+
+    MacroCallLoc { def, krate, kind, ctxt }
+    MacroDefId { krate, edition, kind, local_inner, allow_internal_unsafe }
+
+    Literal { symbol, span, kind, suffix }
+    Punct { char, spacing, span }
+    Ident { sym, span, is_raw }
+    Subtree { delimiter, len }
+    Delimiter { open, close, kind }
+}
+
+persist_enum! {
+    ModuleDefId {
+        ModuleId(it),
+        FunctionId(it),
+        AdtId(it),
+        EnumVariantId(it),
+        ConstId(it),
+        StaticId(it),
+        TraitId(it),
+        TraitAliasId(it),
+        TypeAliasId(it),
+        BuiltinType(it),
+        MacroId(it),
+    }
+    AdtId { StructId(it), UnionId(it), EnumId(it) }
+    MacroId { Macro2Id(it), MacroRulesId(it), ProcMacroId(it) }
+    ItemContainerId { ExternBlockId(it), ModuleId(it), ImplId(it), TraitId(it) }
+    MacroExpander {
+        Declarative,
+        BuiltIn(it),
+        BuiltInAttr(it),
+        BuiltInDerive(it),
+        BuiltInEager(it),
+    }
+    Visibility { Module(module, explicitness), Public }
+    VisibilityExplicitness { Explicit, Implicit }
+
+    MacroDefKind {
+        Declarative(ast_id),
+        BuiltIn(ast_id, expander),
+        BuiltInAttr(ast_id, expander),
+        BuiltInDerive(ast_id, expander),
+        BuiltInEager(ast_id, expander),
+        ProcMacro(ast_id, expander, kind),
+    }
+    MacroCallKind {
+        FnLike { ast_id, expand_to, eager },
+        Derive { ast_id, derive_attr_index, derive_index, derive_macro_id },
+        Attr { ast_id, attr_args, invoc_attr_index },
+    }
+    ExpandTo { Statements, Items, Pattern, Type, Expr }
+    ProcMacroKind { CustomDerive, Bang, Attr }
+    ExpandErrorKind {
+        ProcMacroAttrExpansionDisabled,
+        MissingProcMacroExpander(krate),
+        MacroDisabled,
+        MacroDefinition,
+        Mbe(it),
+        RecursionOverflow,
+        Other(it),
+        ProcMacroPanic(it),
+        ProcMacroTimedOut(it),
+    }
+    MbeExpandErrorKind {
+        BindingError(it),
+        UnresolvedBinding(it),
+        LeftoverTokens,
+        LimitExceeded,
+        NoMatchingRule,
+        UnexpectedToken,
+    }
+    Transparency { Transparent, SemiTransparent, Opaque }
+
+    TokenTree { Leaf(leaf), Subtree(subtree) }
+    Leaf { Literal(it), Punct(it), Ident(it) }
+    LitKind {
+        Byte,
+        Char,
+        Integer,
+        Float,
+        Str,
+        StrRaw(hashes),
+        ByteStr,
+        ByteStrRaw(hashes),
+        CStr,
+        CStrRaw(hashes),
+        Err(it),
+    }
+    Spacing { Alone, Joint, JointHidden }
+    IdentIsRaw { No, Yes }
+    DelimiterKind { Parenthesis, Brace, Bracket, Invisible }
+}
+
+#[cfg(test)]
+mod tests {
+    use base_db::SourceDatabase;
+    use test_fixture::WithFixture;
+    use triomphe::Arc;
+
+    use super::{PersistKeys, PersistedCaches};
+    use crate::{db::DefDatabase, test_db::TestDB};
+
+    const FIXTURE: &str = r#"
+//- /main.rs crate:main deps:lib
+#[macro_use]
+extern crate lib;
+use lib::{S, E::*};
+mod m;
+make_struct!(Made);
+#[derive(lib::Trait)]
+#[cfg(test)]
+struct Unconfigured;
+use unresolved::Import;
+//- /m.rs
+pub(crate) fn f() {}
+macro_rules! local { () => { pub struct Local; } }
+local!();
+//- /lib.rs crate:lib
+pub struct S;
+pub enum E { A, B }
+#[macro_export]
+macro_rules! make_struct { ($name:ident) => { pub struct $name($crate::S); } }
+pub trait Trait {}
+"#;
+
+    /// Keys that are stable for databases with the same fixture, in place of the ones computed
+    /// by `ide-db`.
+    fn persist_keys(db: &TestDB) -> PersistKeys {
+        let crate_graph = db.crate_graph();
+        let mut keys = PersistKeys::new(crate_graph.clone());
+        for krate in crate_graph.iter() {
+            keys.insert_crate(krate, u128::from(krate.into_raw().into_u32()) + 1);
+            let file_id = crate_graph[krate].root_file_id;
+            keys.insert_file(file_id, file_id.index().to_string().into());
+        }
+        for file_id in 0..4 {
+            let file_id = span::FileId::from_raw(file_id);
+            keys.insert_file(file_id, file_id.index().to_string().into());
+        }
+        keys
+    }
+
+    fn persisted_def_maps(db: &TestDB, keys: &PersistKeys) -> PersistedCaches {
+        let mut persisted = PersistedCaches::default();
+        for krate in db.crate_graph().iter() {
+            assert!(persisted.insert_def_map(db, keys, krate));
+        }
+        PersistedCaches::from_bytes(&persisted.to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn persisted_def_map_round_trips() {
+        let db = TestDB::with_files(FIXTURE);
+        let keys = persist_keys(&db);
+        let persisted = persisted_def_maps(&db, &keys);
+        assert_eq!(persisted.len(), 2);
+
+        for krate in db.crate_graph().iter() {
+            let def_map = persisted.def_map(&db, &keys, krate).unwrap();
+            assert_eq!(def_map, *db.crate_def_map(krate));
+        }
+    }
+
+    #[test]
+    fn persisted_def_map_is_used() {
+        let db = TestDB::with_files(FIXTURE);
+        let keys = persist_keys(&db);
+        let persisted = persisted_def_maps(&db, &keys);
+        let krate = db.fetch_test_crate();
+        let dump = db.crate_def_map(krate).dump(&db);
+
+        let mut db = TestDB::with_files(FIXTURE);
+        let keys = persist_keys(&db);
+        db.set_persisted_caches(Arc::new(persisted));
+        db.set_persist_keys(Arc::new(keys));
+        let krate = db.fetch_test_crate();
+        let events = db.log_executed(|| {
+            db.crate_def_map(krate);
+        });
+        assert!(events.iter().any(|it| it.contains("CrateDefMapQuery")), "{events:#?}");
+        assert!(!events.iter().any(|it| it.contains("FileItemTreeQuery")), "{events:#?}");
+        assert_eq!(db.crate_def_map(krate).dump(&db), dump);
+    }
+
+    #[test]
+    fn persisted_def_map_of_another_crate_graph_is_not_used() {
+        let db = TestDB::with_files(FIXTURE);
+        let stale_keys = persist_keys(&db);
+        let persisted = persisted_def_maps(&db, &stale_keys);
+
+        let mut db = TestDB::with_files(FIXTURE);
+        db.set_persisted_caches(Arc::new(persisted));
+        db.set_persist_keys(Arc::new(stale_keys));
+        let krate = db.fetch_test_crate();
+        let events = db.log_executed(|| {
+            db.crate_def_map(krate);
+        });
+        assert!(events.iter().any(|it| it.contains("FileItemTreeQuery")), "{events:#?}");
+    }
+
+    #[test]
+    fn persisted_item_tree_is_used() {
+        let (mut db, file_id) = TestDB::with_single_file(
+            r#"
+#![doc = "crate docs"]
+pub struct S<T: Clone, const N: usize = 3>(T, [u8; N]) where T: Copy;
+pub fn f<'a>(x: &'a dyn Fn(u32) -> u32) -> impl Iterator<Item = u32> + 'a { loop {} }
+#[derive(Debug)]
+enum E { A { field: <S<u8> as Tr>::Assoc }, B = 1 }
+"#,
+        );
+        let mut persisted = PersistedCaches::default();
+        assert!(persisted.insert_item_tree(&db, file_id));
+        let persisted = PersistedCaches::from_bytes(&persisted.to_bytes()).unwrap();
+        assert_eq!(persisted.len(), 1);
+
+        let item_tree = db.file_item_tree(file_id.into());
+        db.set_persisted_caches(Arc::new(persisted));
+        let events = db.log_executed(|| {
+            assert_eq!(db.file_item_tree(file_id.into()), item_tree);
+        });
+        assert!(events.iter().any(|it| it.contains("FileItemTreeQuery")), "{events:#?}");
+        assert!(!events.iter().any(|it| it.contains("with_source_map")), "{events:#?}");
+    }
+}
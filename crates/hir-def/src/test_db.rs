@@ -35,6 +35,8 @@ impl Default for TestDB {
         let mut this = Self { storage: Default::default(), events: Default::default() };
         this.setup_syntax_context_root();
         this.set_expand_proc_attr_macros_with_durability(true, Durability::HIGH);
+        this.set_persisted_caches_with_durability(Default::default(), Durability::HIGH);
+        this.set_persist_keys_with_durability(Default::default(), Durability::HIGH);
         this
     }
 }
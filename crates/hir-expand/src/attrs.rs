@@ -76,10 +76,14 @@ impl RawAttrs {
             })
             .collect();
 
-        let entries = if entries.is_empty() {
+        Self::from_attrs(entries)
+    }
+
+    pub fn from_attrs(attrs: Vec<Attr>) -> Self {
+        let entries = if attrs.is_empty() {
             None
         } else {
-            Some(ThinArc::from_header_and_iter((), entries.into_iter()))
+            Some(ThinArc::from_header_and_iter((), attrs.into_iter()))
         };
 
         RawAttrs { entries }
@@ -204,6 +208,16 @@ impl AttrId {
     pub fn with_cfg_attr(self, idx: usize) -> AttrId {
         AttrId { id: self.id | ((idx as u32) << Self::AST_INDEX_BITS) | Self::CFG_ATTR_SET_BITS }
     }
+
+    /// Deconstructs an `AttrId` into a raw `u32`, for serialization purposes.
+    pub fn into_u32(self) -> u32 {
+        self.id
+    }
+
+    /// Constructs an `AttrId` from a raw `u32`, for deserialization purposes.
+    pub fn from_u32(id: u32) -> Self {
+        AttrId { id }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
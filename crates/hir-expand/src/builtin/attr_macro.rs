@@ -1,5 +1,5 @@
 //! Builtin attributes.
-use intern::sym;
+use intern::{sym, Symbol};
 use span::{MacroCallId, Span};
 
 use crate::{db::ExpandDatabase, name, tt, ExpandResult, MacroCallKind};
@@ -14,6 +14,12 @@ macro_rules! register_builtin {
         }
 
         impl BuiltinAttrExpander {
+            pub fn name(&self) -> &'static Symbol {
+                match *self {
+                    $( BuiltinAttrExpander::$variant => &sym::$name, )*
+                }
+            }
+
             pub fn expander(&self) -> fn (&dyn ExpandDatabase, MacroCallId, &tt::TopSubtree, Span) -> ExpandResult<tt::TopSubtree>  {
                 match *self {
                     $( BuiltinAttrExpander::$variant => $expand, )*
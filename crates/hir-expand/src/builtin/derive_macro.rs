@@ -1,6 +1,6 @@
 //! Builtin derives.
 
-use intern::sym;
+use intern::{sym, Symbol};
 use itertools::{izip, Itertools};
 use parser::SyntaxKind;
 use rustc_hash::FxHashSet;
@@ -33,6 +33,12 @@ macro_rules! register_builtin {
         }
 
         impl BuiltinDeriveExpander {
+            pub fn name(&self) -> &'static Symbol {
+                match *self {
+                    $( BuiltinDeriveExpander::$trait => &sym::$trait, )*
+                }
+            }
+
             pub fn expander(&self) -> fn(&dyn ExpandDatabase, Span, &tt::TopSubtree) -> ExpandResult<tt::TopSubtree>  {
                 match *self {
                     $( BuiltinDeriveExpander::$trait => $expand, )*
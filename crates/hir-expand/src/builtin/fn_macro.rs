@@ -36,6 +36,12 @@ macro_rules! register_builtin {
         }
 
         impl BuiltinFnLikeExpander {
+            pub fn name(&self) -> &'static Symbol {
+                match *self {
+                    $( BuiltinFnLikeExpander::$kind => &sym::$name, )*
+                }
+            }
+
             fn expander(&self) -> fn (&dyn ExpandDatabase, MacroCallId, &tt::TopSubtree, Span) -> ExpandResult<tt::TopSubtree>  {
                 match *self {
                     $( BuiltinFnLikeExpander::$kind => $expand, )*
@@ -44,6 +50,12 @@ macro_rules! register_builtin {
         }
 
         impl EagerExpander {
+            pub fn name(&self) -> &'static Symbol {
+                match *self {
+                    $( EagerExpander::$e_kind => &sym::$e_name, )*
+                }
+            }
+
             fn expander(&self) -> fn (&dyn ExpandDatabase, MacroCallId, &tt::TopSubtree, Span) -> ExpandResult<tt::TopSubtree>  {
                 match *self {
                     $( EagerExpander::$e_kind => $e_expand, )*
@@ -49,6 +49,19 @@ impl DeclarativeMacroExpander {
         }
     }
 
+    /// Returns for every rule of this macro why it does not match `tt`, see
+    /// [`mbe::DeclarativeMacro::match_rules`].
+    pub fn match_rules(
+        &self,
+        tt: &tt::TopSubtree,
+        def_site_edition: Edition,
+    ) -> Vec<Option<mbe::ExpandError>> {
+        match self.mac.err() {
+            Some(_) => Vec::new(),
+            None => self.mac.match_rules(tt, def_site_edition),
+        }
+    }
+
     pub fn expand_unhygienic(
         &self,
         tt: tt::TopSubtree,
@@ -107,6 +107,19 @@ pub(super) fn apply_mark(
     apply_mark_internal(db, call_site_ctxt, call_id, transparency, edition)
 }
 
+/// Interns the context with the given parent and outer mark. Unlike [`apply_mark`], this does not
+/// adjust the parent to the call site, so it recreates exactly the context whose
+/// [`SyntaxContextData`] has these `parent`, `outer_expn`, `outer_transparency` and `edition`.
+pub fn intern_syntax_context(
+    db: &dyn ExpandDatabase,
+    parent: SyntaxContextId,
+    outer_expn: MacroCallId,
+    outer_transparency: Transparency,
+    edition: Edition,
+) -> SyntaxContextId {
+    apply_mark_internal(db, parent, outer_expn, outer_transparency, edition)
+}
+
 fn apply_mark_internal(
     db: &dyn ExpandDatabase,
     ctxt: SyntaxContextId,
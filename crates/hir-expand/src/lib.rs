@@ -29,7 +29,7 @@ use stdx::TupleExt;
 use triomphe::Arc;
 
 use core::fmt;
-use std::hash::Hash;
+use std::{hash::Hash, time::Duration};
 
 use base_db::{ra_salsa::InternValueTrivial, CrateId};
 use either::Either;
@@ -60,7 +60,7 @@ pub use crate::{
     prettify_macro_expansion_::prettify_macro_expansion,
 };
 
-pub use mbe::{DeclarativeMacro, ValueResult};
+pub use mbe::{DeclarativeMacro, DefinitionLint, DefinitionLintKind, ValueResult};
 pub use span::{HirFileId, MacroCallId, MacroFileId};
 
 pub mod tt {
@@ -172,6 +172,8 @@ pub enum ExpandErrorKind {
     RecursionOverflow,
     Other(Box<str>),
     ProcMacroPanic(Box<str>),
+    /// The proc-macro server killed the expansion after the configured timeout.
+    ProcMacroTimedOut(Duration),
 }
 
 pub struct RenderedExpandError {
@@ -244,6 +246,11 @@ impl ExpandErrorKind {
                 error: true,
                 kind: RenderedExpandError::GENERAL_KIND,
             },
+            ExpandErrorKind::ProcMacroTimedOut(timeout) => RenderedExpandError {
+                message: format!("proc macro timed out after {timeout:?}"),
+                error: true,
+                kind: RenderedExpandError::GENERAL_KIND,
+            },
         }
     }
 }
@@ -299,6 +306,33 @@ pub struct EagerCallInfo {
     span: Span,
 }
 
+impl EagerCallInfo {
+    pub fn new(
+        arg: Arc<tt::TopSubtree>,
+        arg_id: MacroCallId,
+        error: Option<ExpandError>,
+        span: Span,
+    ) -> Self {
+        EagerCallInfo { arg, arg_id, error, span }
+    }
+
+    pub fn arg(&self) -> &Arc<tt::TopSubtree> {
+        &self.arg
+    }
+
+    pub fn arg_id(&self) -> MacroCallId {
+        self.arg_id
+    }
+
+    pub fn error(&self) -> Option<&ExpandError> {
+        self.error.as_ref()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MacroCallKind {
     FnLike {
@@ -1,7 +1,7 @@
 //! Proc Macro Expander stuff
 
 use core::fmt;
-use std::{panic::RefUnwindSafe, sync};
+use std::{panic::RefUnwindSafe, sync, time::Duration};
 
 use base_db::{CrateId, Env};
 use intern::Symbol;
@@ -39,6 +39,8 @@ pub enum ProcMacroExpansionError {
     Panic(String),
     /// The server itself errored out.
     System(String),
+    /// The expansion took too long and got aborted.
+    TimedOut(Duration),
 }
 
 pub type ProcMacroLoadResult = Result<Vec<ProcMacro>, (String, bool)>;
@@ -155,6 +157,17 @@ impl CustomProcMacroExpander {
         Self { proc_macro_id }
     }
 
+    /// Deconstructs the handle into a raw `u32`, including the special values of missing and
+    /// disabled expanders.
+    pub fn into_u32(self) -> u32 {
+        self.proc_macro_id
+    }
+
+    /// Constructs a handle from the output of [`Self::into_u32`].
+    pub fn from_u32(proc_macro_id: u32) -> Self {
+        Self { proc_macro_id }
+    }
+
     /// An expander that always errors due to the actual proc-macro expander missing.
     pub const fn missing_expander() -> Self {
         Self { proc_macro_id: Self::MISSING_EXPANDER }
@@ -261,6 +274,27 @@ impl CustomProcMacroExpander {
                                 err: Some(ExpandError::other(call_site, text)),
                             }
                         }
+                        ProcMacroExpansionError::TimedOut(timeout)
+                            if proc_macro.kind == ProcMacroKind::Attr =>
+                        {
+                            ExpandResult {
+                                value: tt.clone(),
+                                err: Some(ExpandError::new(
+                                    call_site,
+                                    ExpandErrorKind::ProcMacroTimedOut(timeout),
+                                )),
+                            }
+                        }
+                        ProcMacroExpansionError::TimedOut(timeout) => ExpandResult::new(
+                            tt::TopSubtree::empty(tt::DelimSpan {
+                                open: call_site,
+                                close: call_site,
+                            }),
+                            ExpandError::new(
+                                call_site,
+                                ExpandErrorKind::ProcMacroTimedOut(timeout),
+                            ),
+                        ),
                         ProcMacroExpansionError::System(text)
                         | ProcMacroExpansionError::Panic(text) => ExpandResult::new(
                             tt::TopSubtree::empty(tt::DelimSpan {
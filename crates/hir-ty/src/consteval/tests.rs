@@ -664,6 +664,79 @@ const GOAL: i32 = outer().a();
     );
 }
 
+#[test]
+fn generic_trait_method() {
+    check_number(
+        r#"
+    //- minicore: add, copy, builtin_impls
+    trait Hash {
+        const LEN: usize;
+        fn rounds() -> usize;
+        fn block(&self) -> usize {
+            Self::LEN * 2
+        }
+    }
+
+    struct Sha;
+
+    impl Hash for Sha {
+        const LEN: usize = 32;
+        fn rounds() -> usize {
+            64
+        }
+    }
+
+    const fn total<H: Hash>(h: &H) -> usize {
+        H::LEN * <H as Hash>::rounds() + h.block()
+    }
+
+    const GOAL: usize = total(&Sha);
+    "#,
+        2112,
+    );
+    check_number(
+        r#"
+    //- minicore: default, add, copy, builtin_impls
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    impl Zero for u32 {
+        fn zero() -> u32 {
+            0
+        }
+    }
+
+    trait Sum<T> {
+        fn sum(&self) -> T;
+    }
+
+    struct Pair<T>(T, T);
+
+    impl<T: Zero + core::ops::Add<Output = T> + Copy> Sum<T> for Pair<T> {
+        fn sum(&self) -> T {
+            T::zero() + self.0 + self.1
+        }
+    }
+
+    trait Width {
+        fn width<const N: usize>(&self) -> usize {
+            N
+        }
+    }
+
+    impl<T> Width for T {}
+
+    const fn go<S: Sum<u32>>(s: S) -> u32 {
+        s.sum()
+    }
+
+    const GOAL: u32 = go(Pair(3u32, 4)) + <u32 as Default>::default() + 5u8.width::<3>() as u32;
+    "#,
+        10,
+    );
+}
+
 #[test]
 fn generic_fn() {
     check_number(
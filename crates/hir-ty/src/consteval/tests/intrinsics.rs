@@ -706,6 +706,198 @@ fn rotate() {
     );
 }
 
+#[test]
+fn bswap_and_bitreverse() {
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub fn bswap<T: Copy>(x: T) -> T;
+
+        const GOAL: u32 = bswap(0x12345678u32);
+        "#,
+        0x78563412,
+    );
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub fn bitreverse<T: Copy>(x: T) -> T;
+
+        const GOAL: u16 = bitreverse(0b0000_0000_0000_0110u16);
+        "#,
+        0b0110_0000_0000_0000,
+    );
+}
+
+#[test]
+fn black_box() {
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub fn black_box<T>(dummy: T) -> T;
+
+        const GOAL: i32 = black_box(5) + 2;
+        "#,
+        7,
+    );
+}
+
+#[test]
+fn type_id() {
+    check_number(
+        r#"
+        //- minicore: option
+        #[rustc_intrinsic]
+        pub fn type_id<T: ?Sized + 'static>() -> u128;
+
+        struct X;
+        mod m {
+            pub struct X;
+        }
+        const OF_X: u128 = type_id::<X>();
+        const GOAL: bool = type_id::<X>() == type_id::<X>()
+            && OF_X == type_id::<X>()
+            && type_id::<X>() != type_id::<m::X>()
+            && type_id::<X>() != type_id::<i32>()
+            && type_id::<&'static str>() != type_id::<str>()
+            && type_id::<Option<X>>() != type_id::<Option<m::X>>();
+        "#,
+        1,
+    );
+}
+
+#[test]
+fn variant_count() {
+    check_number(
+        r#"
+        //- minicore: option
+        #[rustc_intrinsic]
+        pub fn variant_count<T>() -> usize;
+
+        enum E { A, B(i32), C { x: u8 } }
+        const GOAL: usize = variant_count::<E>() * 10 + variant_count::<Option<E>>();
+        "#,
+        32,
+    );
+}
+
+#[test]
+fn const_allocate() {
+    check_number(
+        r#"
+        //- minicore: add, builtin_impls
+        #[rustc_intrinsic]
+        pub const fn const_allocate(size: usize, align: usize) -> *mut u8;
+        #[rustc_intrinsic]
+        pub const fn const_make_global(ptr: *mut u8) -> *const u8;
+        #[rustc_intrinsic]
+        pub const fn const_deallocate(ptr: *mut u8, size: usize, align: usize);
+
+        const fn table() -> &'static [u32; 4] {
+            unsafe {
+                let ptr = const_allocate(16, 4) as *mut u32;
+                let mut i = 0;
+                while i < 4 {
+                    *((ptr as usize + i * 4) as *mut u32) = (i as u32 + 1) * 3;
+                    i += 1;
+                }
+                let scratch = const_allocate(8, 8);
+                const_deallocate(scratch, 8, 8);
+                &*(const_make_global(ptr as *mut u8) as *const [u32; 4])
+            }
+        }
+
+        const GOAL: u32 = {
+            let [first, _, _, last] = *table();
+            first + last
+        };
+        "#,
+        15,
+    );
+}
+
+#[test]
+fn raw_eq_and_compare_bytes() {
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub const fn raw_eq<T>(a: &T, b: &T) -> bool;
+
+        const GOAL: bool = raw_eq(&[1u8, 2, 3], &[1u8, 2, 3]) && !raw_eq(&(1u16, 2u16), &(1u16, 3u16));
+        "#,
+        1,
+    );
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub const fn compare_bytes(left: *const u8, right: *const u8, bytes: usize) -> i32;
+
+        const GOAL: i32 = unsafe {
+            let a = [1u8, 2, 3];
+            let b = [1u8, 2, 4];
+            compare_bytes(&a as *const [u8; 3] as *const u8, &b as *const [u8; 3] as *const u8, 3) * 10
+                + compare_bytes(&a as *const [u8; 3] as *const u8, &b as *const [u8; 3] as *const u8, 2)
+        };
+        "#,
+        -10,
+    );
+}
+
+#[test]
+fn ptr_mask() {
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub fn ptr_mask<T>(ptr: *const T, mask: usize) -> *const T;
+
+        const GOAL: usize = ptr_mask(0x1234 as *const u8, !0xff) as usize;
+        "#,
+        0x1200,
+    );
+}
+
+#[test]
+fn volatile_store() {
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub unsafe fn volatile_store<T>(dst: *mut T, val: T);
+        #[rustc_intrinsic]
+        pub unsafe fn unaligned_volatile_load<T>(src: *const T) -> T;
+
+        const GOAL: u64 = unsafe {
+            let mut x = 2u64;
+            volatile_store(&mut x as *mut u64, 40);
+            unaligned_volatile_load(&x as *const u64) + 2
+        };
+        "#,
+        42,
+    );
+}
+
+#[test]
+fn fast_float_arithmetic() {
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub unsafe fn fmul_fast<T: Copy>(a: T, b: T) -> T;
+        #[rustc_intrinsic]
+        pub fn fadd_algebraic<T: Copy>(a: T, b: T) -> T;
+
+        const GOAL: i32 = unsafe { fadd_algebraic(fmul_fast(2.5f64, 4.0), 0.5f64) * 2.0 } as i32;
+        "#,
+        21,
+    );
+    check_number(
+        r#"
+        #[rustc_intrinsic]
+        pub fn frem_algebraic<T: Copy>(a: T, b: T) -> T;
+
+        const GOAL: i32 = frem_algebraic(7.5f32, 2.0) as i32;
+        "#,
+        1,
+    );
+}
+
 #[test]
 fn simd() {
     check_number(
@@ -10,7 +10,8 @@ use either::Either;
 use hir_def::{
     lang_item::LangItem,
     resolver::{HasResolver, ValueNs},
-    AdtId, AssocItemId, DefWithBodyId, HasModule, ItemContainerId, Lookup,
+    AdtId, AssocItemId, AttrDefId, DefWithBodyId, HasModule, ItemContainerId, Lookup, ModuleDefId,
+    TraitId,
 };
 use intern::sym;
 use itertools::Itertools;
@@ -26,13 +27,15 @@ use triomphe::Arc;
 use typed_arena::Arena;
 
 use crate::{
+    consteval::try_const_usize,
     db::HirDatabase,
     diagnostics::match_check::{
         self,
         pat_analysis::{self, DeconstructedPat, MatchCheckCtx, WitnessPat},
     },
     display::HirDisplay,
-    Adjust, InferenceResult, Interner, Ty, TyExt, TyKind,
+    Adjust, AliasTy, CallableDefId, InferenceResult, Interner, TraitRefExt, Ty, TyExt, TyKind,
+    WhereClause,
 };
 
 pub(crate) use hir_def::{
@@ -64,6 +67,26 @@ pub enum BodyValidationDiagnostic {
     RemoveUnnecessaryElse {
         if_expr: ExprId,
     },
+    UnusedMustUse {
+        expr: ExprId,
+        path: MustUsePath,
+        reason: Option<String>,
+    },
+}
+
+/// How the unused value of an expression leads to a `#[must_use]` item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MustUsePath {
+    /// A `#[must_use]` function that is called or a `#[must_use]` type of the value.
+    Def(ModuleDefId),
+    /// An opaque type implementing a `#[must_use]` trait.
+    Opaque(TraitId),
+    /// A trait object of a `#[must_use]` trait.
+    TraitObject(TraitId),
+    Boxed(Box<MustUsePath>),
+    Pinned(Box<MustUsePath>),
+    TupleElement(usize, Box<MustUsePath>),
+    Array(Box<MustUsePath>),
 }
 
 impl BodyValidationDiagnostic {
@@ -126,6 +149,7 @@ impl ExprValidator {
                 }
                 Expr::Block { .. } | Expr::Async { .. } | Expr::Unsafe { .. } => {
                     self.validate_block(db, expr);
+                    self.check_for_unused_must_use(db, expr);
                 }
                 _ => {}
             }
@@ -367,6 +391,50 @@ impl ExprValidator {
         }
     }
 
+    fn check_for_unused_must_use(&mut self, db: &dyn HirDatabase, expr: &Expr) {
+        let (Expr::Block { statements, .. }
+        | Expr::Async { statements, .. }
+        | Expr::Unsafe { statements, .. }) = expr
+        else {
+            return;
+        };
+        for stmt in &**statements {
+            let &Statement::Expr { expr, has_semi: true } = stmt else { continue };
+            for (path, reason) in self.must_use(db, expr) {
+                self.diagnostics.push(BodyValidationDiagnostic::UnusedMustUse {
+                    expr,
+                    path,
+                    reason,
+                });
+            }
+        }
+    }
+
+    /// Returns the `#[must_use]` items that make the value of `expr` must be used, with the
+    /// reasons given in their attributes. Like rustc, both a `#[must_use]` type of the value and a
+    /// `#[must_use]` function that is called are reported.
+    fn must_use(&self, db: &dyn HirDatabase, expr: ExprId) -> Vec<(MustUsePath, Option<String>)> {
+        let ty = &self.infer[expr];
+        if ty.contains_unknown() {
+            return Vec::new();
+        }
+        let mut res = must_use_ty(db, ty);
+        let callee = match &self.body[expr] {
+            Expr::Call { callee, .. } => match self.infer[*callee].callable_def(db) {
+                Some(CallableDefId::FunctionId(func)) => Some(func),
+                _ => None,
+            },
+            Expr::MethodCall { .. } => self.infer.method_resolution(expr).map(|(func, _)| func),
+            _ => None,
+        };
+        if let Some(func) = callee {
+            if let Some(reason) = must_use_attr(db, func.into()) {
+                res.push((MustUsePath::Def(func.into()), reason));
+            }
+        }
+        res
+    }
+
     fn lower_pattern<'p>(
         &self,
         cx: &MatchCheckCtx<'p>,
@@ -603,6 +671,74 @@ pub fn record_pattern_missing_fields(
     Some((variant_def, missed_fields, exhaustive))
 }
 
+/// The `#[must_use]` items the type of an unused value leads to, looking through boxes, pins,
+/// tuples and arrays like rustc.
+fn must_use_ty(db: &dyn HirDatabase, ty: &Ty) -> Vec<(MustUsePath, Option<String>)> {
+    let wrap = |ty: &Ty, wrapper: fn(Box<MustUsePath>) -> MustUsePath| {
+        must_use_ty(db, ty)
+            .into_iter()
+            .map(|(path, reason)| (wrapper(Box::new(path)), reason))
+            .collect()
+    };
+    match ty.kind(Interner) {
+        TyKind::Adt(chalk_ir::AdtId(adt), subst) => match db.lang_attr((*adt).into()) {
+            Some(LangItem::OwnedBox) => subst
+                .type_parameters(Interner)
+                .next()
+                .map_or_else(Vec::new, |ty| wrap(&ty, MustUsePath::Boxed)),
+            Some(LangItem::Pin) => subst
+                .type_parameters(Interner)
+                .next()
+                .map_or_else(Vec::new, |ty| wrap(&ty, MustUsePath::Pinned)),
+            _ => must_use_attr(db, (*adt).into())
+                .map(|reason| (MustUsePath::Def((*adt).into()), reason))
+                .into_iter()
+                .collect(),
+        },
+        TyKind::Tuple(_, subst) => subst
+            .type_parameters(Interner)
+            .enumerate()
+            .flat_map(|(idx, ty)| {
+                must_use_ty(db, &ty).into_iter().map(move |(path, reason)| {
+                    (MustUsePath::TupleElement(idx, Box::new(path)), reason)
+                })
+            })
+            .collect(),
+        TyKind::Array(ty, len) => match try_const_usize(db, len) {
+            Some(0) | None => Vec::new(),
+            Some(_) => wrap(ty, MustUsePath::Array),
+        },
+        TyKind::Dyn(_) => ty
+            .dyn_trait()
+            .and_then(|trait_| {
+                Some((MustUsePath::TraitObject(trait_), must_use_attr(db, trait_.into())?))
+            })
+            .into_iter()
+            .collect(),
+        TyKind::OpaqueType(..) | TyKind::Alias(AliasTy::Opaque(_)) => ty
+            .impl_trait_bounds(db)
+            .into_iter()
+            .flatten()
+            .find_map(|bound| match bound.skip_binders() {
+                WhereClause::Implemented(trait_ref) => {
+                    let trait_ = trait_ref.hir_trait_id();
+                    Some((MustUsePath::Opaque(trait_), must_use_attr(db, trait_.into())?))
+                }
+                _ => None,
+            })
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The reason given in the `#[must_use]` attribute of `def`, if it has one.
+fn must_use_attr(db: &dyn HirDatabase, def: AttrDefId) -> Option<Option<String>> {
+    let attrs = db.attrs(def);
+    let must_use = attrs.by_key(&sym::must_use);
+    must_use.exists().then(|| must_use.string_value().map(|it| it.to_string()))
+}
+
 fn types_of_subpatterns_do_match(pat: PatId, body: &Body, infer: &InferenceResult) -> bool {
     fn walk(pat: PatId, body: &Body, infer: &InferenceResult, has_type_mismatches: &mut bool) {
         match infer.type_mismatch_for_pat(pat) {
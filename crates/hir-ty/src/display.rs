@@ -85,6 +85,8 @@ pub struct HirFormatter<'a> {
     pub entity_limit: Option<usize>,
     /// When rendering functions, whether to show the constraint from the container
     show_container_bounds: bool,
+    /// Whether to show the lifetimes of references that are known, i.e. named or `'static`.
+    show_lifetimes: bool,
     omit_verbose_types: bool,
     closure_style: ClosureStyle,
     display_target: DisplayTarget,
@@ -183,6 +185,7 @@ pub trait HirDisplay {
             display_target,
             closure_style,
             show_container_bounds,
+            show_lifetimes: false,
         }
     }
 
@@ -205,6 +208,7 @@ pub trait HirDisplay {
             closure_style: ClosureStyle::ImplFn,
             display_target: DisplayTarget::Diagnostics { edition },
             show_container_bounds: false,
+            show_lifetimes: false,
         }
     }
 
@@ -228,6 +232,7 @@ pub trait HirDisplay {
             closure_style: ClosureStyle::ImplFn,
             display_target: DisplayTarget::Diagnostics { edition },
             show_container_bounds: false,
+            show_lifetimes: false,
         }
     }
 
@@ -251,6 +256,7 @@ pub trait HirDisplay {
             closure_style: ClosureStyle::ImplFn,
             display_target: DisplayTarget::Diagnostics { edition },
             show_container_bounds: false,
+            show_lifetimes: false,
         }
     }
 
@@ -274,6 +280,7 @@ pub trait HirDisplay {
             closure_style: ClosureStyle::ImplFn,
             display_target: DisplayTarget::SourceCode { module_id, allow_opaque },
             show_container_bounds: false,
+            show_lifetimes: false,
             bounds_formatting_ctx: Default::default(),
         }) {
             Ok(()) => {}
@@ -297,6 +304,7 @@ pub trait HirDisplay {
             closure_style: ClosureStyle::ImplFn,
             display_target: DisplayTarget::Test,
             show_container_bounds: false,
+            show_lifetimes: false,
         }
     }
 
@@ -320,6 +328,7 @@ pub trait HirDisplay {
             closure_style: ClosureStyle::ImplFn,
             display_target: DisplayTarget::Diagnostics { edition },
             show_container_bounds,
+            show_lifetimes: false,
         }
     }
 }
@@ -452,6 +461,7 @@ pub struct HirDisplayWrapper<'a, T> {
     closure_style: ClosureStyle,
     display_target: DisplayTarget,
     show_container_bounds: bool,
+    show_lifetimes: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -482,6 +492,7 @@ impl<T: HirDisplay> HirDisplayWrapper<'_, T> {
             display_target: self.display_target,
             closure_style: self.closure_style,
             show_container_bounds: self.show_container_bounds,
+            show_lifetimes: self.show_lifetimes,
             bounds_formatting_ctx: Default::default(),
         })
     }
@@ -490,6 +501,12 @@ impl<T: HirDisplay> HirDisplayWrapper<'_, T> {
         self.closure_style = c;
         self
     }
+
+    /// Shows the lifetimes of references that are named or `'static`.
+    pub fn with_lifetimes(mut self, show_lifetimes: bool) -> Self {
+        self.show_lifetimes = show_lifetimes;
+        self
+    }
 }
 
 impl<T> fmt::Display for HirDisplayWrapper<'_, T>
@@ -998,6 +1015,14 @@ impl HirDisplay for Ty {
                         // hints) so we gate it to testing only for the time being
                         l.hir_fmt(f)?;
                         f.write_char(' ')?;
+                    } else if f.show_lifetimes
+                        && matches!(
+                            l.data(Interner),
+                            LifetimeData::Placeholder(_) | LifetimeData::Static
+                        )
+                    {
+                        l.hir_fmt(f)?;
+                        f.write_char(' ')?;
                     }
                     match m {
                         Mutability::Not => (),
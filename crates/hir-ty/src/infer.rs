@@ -43,8 +43,8 @@ use hir_def::{
     path::{ModPath, Path},
     resolver::{HasResolver, ResolveValueResult, Resolver, TypeNs, ValueNs},
     type_ref::{LifetimeRef, TypeRefId, TypesMap},
-    AdtId, AssocItemId, DefWithBodyId, FieldId, FunctionId, ImplId, ItemContainerId, Lookup,
-    TraitId, TupleFieldId, TupleId, TypeAliasId, VariantId,
+    AdtId, AssocItemId, CallableDefId, DefWithBodyId, FieldId, FunctionId, ImplId, ItemContainerId,
+    Lookup, TraitId, TupleFieldId, TupleId, TypeAliasId, VariantId,
 };
 use hir_expand::name::Name;
 use indexmap::IndexSet;
@@ -67,11 +67,12 @@ use crate::{
     lower::{diagnostics::TyLoweringDiagnostic, ImplTraitLoweringMode},
     mir::MirSpan,
     to_assoc_type_id,
-    traits::FnTrait,
+    traits::{unsatisfied_bound_causes, FnTrait},
     utils::{InTypeConstIdMetadata, UnevaluatedConstEvaluatorFolder},
     AliasEq, AliasTy, Binders, ClosureId, Const, DomainGoal, GenericArg, Goal, ImplTraitId,
     ImplTraitIdx, InEnvironment, Interner, Lifetime, OpaqueTyId, ParamLoweringMode,
-    PathLoweringDiagnostic, ProjectionTy, Substitution, TraitEnvironment, Ty, TyBuilder, TyExt,
+    PathLoweringDiagnostic, ProjectionTy, Substitution, TraitEnvironment, TraitRef, Ty, TyBuilder,
+    TyExt,
 };
 
 // This lint has a false positive here. See the link below for details.
@@ -286,6 +287,22 @@ pub enum InferenceDiagnostic {
         node: ExprOrPatId,
         diag: PathLoweringDiagnostic,
     },
+    UnsatisfiedTraitBound {
+        expr: ExprId,
+        bound: TraitRef,
+        /// The unsatisfied where clauses of the impls that would otherwise implement `bound`,
+        /// innermost last.
+        causes: Vec<TraitRef>,
+        required_by: CallableDefId,
+    },
+}
+
+/// A trait bound of an item called by `expr`.
+#[derive(Clone, Debug)]
+struct BoundCheck {
+    expr: ExprId,
+    trait_ref: TraitRef,
+    required_by: CallableDefId,
 }
 
 /// A mismatch between an expected and an inferred type.
@@ -622,6 +639,8 @@ pub(crate) struct InferenceContext<'a> {
     inside_assignment: bool,
 
     deferred_cast_checks: Vec<CastCheck>,
+    /// The trait bounds of called items, checked once all types are known.
+    deferred_bound_checks: Vec<BoundCheck>,
 
     // fields related to closure capture
     current_captures: Vec<CapturedItemWithoutTy>,
@@ -713,6 +732,7 @@ impl<'a> InferenceContext<'a> {
             diverges: Diverges::Maybe,
             breakables: Vec::new(),
             deferred_cast_checks: Vec::new(),
+            deferred_bound_checks: Vec::new(),
             current_captures: Vec::new(),
             current_capture_span_stack: Vec::new(),
             current_closure: None,
@@ -742,6 +762,7 @@ impl<'a> InferenceContext<'a> {
             mut table,
             mut result,
             mut deferred_cast_checks,
+            deferred_bound_checks,
             tuple_field_accesses_rev,
             diagnostics,
             ..
@@ -796,6 +817,25 @@ impl<'a> InferenceContext<'a> {
         // FIXME resolve obligations as well (use Guidance if necessary)
         table.resolve_obligations_as_possible();
 
+        for BoundCheck { expr, trait_ref, required_by } in deferred_bound_checks {
+            let trait_ref = table.resolve_completely(trait_ref);
+            // Bounds that still mention unknown types can't be decided, and `!` coerces to
+            // whatever is needed.
+            let undecidable = trait_ref.substitution.iter(Interner).any(|arg| {
+                arg.ty(Interner).is_some_and(|ty| ty.contains_unknown() || ty.is_never())
+            });
+            if undecidable || !table.has_failed(&trait_ref.clone().cast(Interner)) {
+                continue;
+            }
+            let causes = unsatisfied_bound_causes(&mut table, &trait_ref);
+            diagnostics.push(InferenceDiagnostic::UnsatisfiedTraitBound {
+                expr,
+                bound: trait_ref,
+                causes,
+                required_by,
+            });
+        }
+
         // make sure diverging type variables are marked as such
         table.propagate_diverging_flag();
         for ty in type_of_expr.values_mut() {
@@ -45,12 +45,12 @@ use crate::{
     traits::FnTrait,
     Adjust, Adjustment, AdtId, AutoBorrow, Binders, CallableDefId, CallableSig, DeclContext,
     DeclOrigin, FnAbi, FnPointer, FnSig, FnSubst, Interner, Rawness, Scalar, Substitution,
-    TraitEnvironment, TraitRef, Ty, TyBuilder, TyExt, TyKind,
+    TraitEnvironment, TraitRef, TraitRefExt, Ty, TyBuilder, TyExt, TyKind, WhereClause,
 };
 
 use super::{
-    cast::CastCheck, coerce::auto_deref_adjust_steps, find_breakable, BreakableContext, Diverges,
-    Expectation, InferenceContext, InferenceDiagnostic, TypeMismatch,
+    cast::CastCheck, coerce::auto_deref_adjust_steps, find_breakable, BoundCheck, BreakableContext,
+    Diverges, Expectation, InferenceContext, InferenceDiagnostic, TypeMismatch,
 };
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -1461,7 +1461,8 @@ impl InferenceContext<'_> {
         self.write_method_resolution(tgt_expr, func, subst.clone());
 
         let method_ty = self.db.value_ty(func.into()).unwrap().substitute(Interner, &subst);
-        self.register_obligations_for_call(&method_ty);
+        // Unsatisfied operator traits aren't reported as bounds of a call.
+        self.register_obligations_for_call(&method_ty, None);
 
         self.infer_expr_coerce(rhs, &Expectation::has_type(rhs_ty.clone()), ExprIsRead::Yes);
 
@@ -1883,7 +1884,13 @@ impl InferenceContext<'_> {
         is_varargs: bool,
         expected: &Expectation,
     ) -> Ty {
-        self.register_obligations_for_call(&callee_ty);
+        // Calls of lang items come from desugaring `for` loops, `?` and the like, whose bounds
+        // don't make sense to report as bounds of a call.
+        let is_desugared = matches!(
+            &self.body[tgt_expr],
+            Expr::Call { callee, .. } if matches!(self.body[*callee], Expr::Path(Path::LangItem(..)))
+        );
+        self.register_obligations_for_call(&callee_ty, (!is_desugared).then_some(tgt_expr));
 
         let expected_inputs = self.expected_inputs_for_expected_output(
             expected,
@@ -2010,7 +2017,7 @@ impl InferenceContext<'_> {
         expected: &Expectation,
     ) -> Ty {
         let method_ty = method_ty.substitute(Interner, &substs);
-        self.register_obligations_for_call(&method_ty);
+        self.register_obligations_for_call(&method_ty, Some(tgt_expr));
         let ((formal_receiver_ty, param_tys), ret_ty, is_varargs) =
             match method_ty.callable_sig(self.db) {
                 Some(sig) => (
@@ -2238,18 +2245,35 @@ impl InferenceContext<'_> {
         Substitution::from_iter(Interner, substs)
     }
 
-    fn register_obligations_for_call(&mut self, callable_ty: &Ty) {
+    /// Registers the obligations of calling `callable_ty`. If `call_expr` is given, the trait
+    /// bounds are also checked once inference is done, so unsatisfied ones can be reported.
+    fn register_obligations_for_call(&mut self, callable_ty: &Ty, call_expr: Option<ExprId>) {
         let callable_ty = self.resolve_ty_shallow(callable_ty);
         if let TyKind::FnDef(fn_def, parameters) = callable_ty.kind(Interner) {
             let def: CallableDefId = from_chalk(self.db, *fn_def);
             let generic_predicates =
                 self.db.generic_predicates(GenericDefId::from_callable(self.db.upcast(), def));
+            let sized_trait = self
+                .db
+                .lang_item(self.resolver.krate(), LangItem::Sized)
+                .and_then(|it| it.as_trait());
             for predicate in generic_predicates.iter() {
                 let (predicate, binders) = predicate
                     .clone()
                     .substitute(Interner, parameters)
                     .into_value_and_skipped_binders();
                 always!(binders.len(Interner) == 0); // quantified where clauses not yet handled
+                if let (Some(expr), WhereClause::Implemented(trait_ref)) = (call_expr, &predicate) {
+                    // `Sized` bounds are implied everywhere and mostly fail because of other
+                    // errors.
+                    if Some(trait_ref.hir_trait_id()) != sized_trait {
+                        self.deferred_bound_checks.push(BoundCheck {
+                            expr,
+                            trait_ref: trait_ref.clone(),
+                            required_by: def,
+                        });
+                    }
+                }
                 self.push_obligation(predicate.cast(Interner));
             }
             // add obligation for trait implementation, if this is a trait method
@@ -2265,10 +2289,16 @@ impl InferenceContext<'_> {
                             // function.
                             &parameters.as_slice(Interner)[params_len - trait_params_len..],
                         );
-                        self.push_obligation(
-                            TraitRef { trait_id: to_chalk_trait_id(trait_), substitution: substs }
-                                .cast(Interner),
-                        );
+                        let trait_ref =
+                            TraitRef { trait_id: to_chalk_trait_id(trait_), substitution: substs };
+                        if let Some(expr) = call_expr {
+                            self.deferred_bound_checks.push(BoundCheck {
+                                expr,
+                                trait_ref: trait_ref.clone(),
+                                required_by: def,
+                            });
+                        }
+                        self.push_obligation(trait_ref.cast(Interner));
                     }
                 }
                 CallableDefId::StructId(_) | CallableDefId::EnumVariantId(_) => {}
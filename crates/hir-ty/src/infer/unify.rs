@@ -232,12 +232,15 @@ pub(crate) struct InferenceTable<'a> {
     /// Double buffer used in [`Self::resolve_obligations_as_possible`] to cut down on
     /// temporary allocations.
     resolve_obligations_buffer: Vec<Canonicalized<InEnvironment<Goal>>>,
+    /// Goals that turned out to have no solution, with the inference variables they had then.
+    failed_obligations: Vec<Goal>,
 }
 
 pub(crate) struct InferenceTableSnapshot {
     var_table_snapshot: chalk_solve::infer::InferenceSnapshot<Interner>,
     type_variable_table: SmallVec<[TypeVariableFlags; 16]>,
     pending_obligations: Vec<Canonicalized<InEnvironment<Goal>>>,
+    failed_obligations: usize,
 }
 
 impl<'a> InferenceTable<'a> {
@@ -250,6 +253,7 @@ impl<'a> InferenceTable<'a> {
             type_variable_table: SmallVec::new(),
             pending_obligations: Vec::new(),
             resolve_obligations_buffer: Vec::new(),
+            failed_obligations: Vec::new(),
         }
     }
 
@@ -583,7 +587,12 @@ impl<'a> InferenceTable<'a> {
         let var_table_snapshot = self.var_unification_table.snapshot();
         let type_variable_table = self.type_variable_table.clone();
         let pending_obligations = self.pending_obligations.clone();
-        InferenceTableSnapshot { var_table_snapshot, pending_obligations, type_variable_table }
+        InferenceTableSnapshot {
+            var_table_snapshot,
+            pending_obligations,
+            type_variable_table,
+            failed_obligations: self.failed_obligations.len(),
+        }
     }
 
     #[tracing::instrument(skip_all)]
@@ -591,6 +600,7 @@ impl<'a> InferenceTable<'a> {
         self.var_unification_table.rollback_to(snapshot.var_table_snapshot);
         self.type_variable_table = snapshot.type_variable_table;
         self.pending_obligations = snapshot.pending_obligations;
+        self.failed_obligations.truncate(snapshot.failed_obligations);
     }
 
     #[tracing::instrument(skip_all)]
@@ -619,11 +629,31 @@ impl<'a> InferenceTable<'a> {
     fn register_obligation_in_env(&mut self, goal: InEnvironment<Goal>) {
         let canonicalized = self.canonicalize_with_free_vars(goal);
         let solution = self.try_resolve_obligation(&canonicalized);
-        if matches!(solution, Some(Solution::Ambig(_))) {
-            self.pending_obligations.push(canonicalized);
+        match solution {
+            Some(Solution::Ambig(_)) => self.pending_obligations.push(canonicalized),
+            // This is also reached for obligations that were ambiguous at first, when
+            // `resolve_obligations_as_possible()` registers them again.
+            None => {
+                let goal = chalk_ir::Substitute::apply(
+                    &canonicalized.free_vars,
+                    canonicalized.value.value.goal,
+                    Interner,
+                );
+                self.failed_obligations.push(goal);
+            }
+            Some(Solution::Unique(_)) => (),
         }
     }
 
+    /// Whether `goal` was registered as an obligation and known to have no solution, comparing
+    /// the failed obligations with the inference variables resolved as far as they are now.
+    pub(crate) fn has_failed(&mut self, goal: &Goal) -> bool {
+        let failed = mem::take(&mut self.failed_obligations);
+        let res = failed.iter().any(|it| self.resolve_completely(it.clone()) == *goal);
+        self.failed_obligations = failed;
+        res
+    }
+
     pub(crate) fn register_infer_ok<T>(&mut self, infer_ok: InferOk<T>) {
         infer_ok.goals.into_iter().for_each(|goal| self.register_obligation_in_env(goal));
     }
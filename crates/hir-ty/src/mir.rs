@@ -28,7 +28,7 @@ mod lower;
 mod monomorphization;
 mod pretty;
 
-pub use borrowck::{borrowck_query, BorrowckResult, MutabilityReason};
+pub use borrowck::{borrowck_query, BorrowckResult, MutabilityReason, UniversalRegion};
 pub use eval::{
     interpret_mir, pad16, render_const_using_debug_impl, Evaluator, MirEvalError, VTableMap,
 };
@@ -695,10 +695,10 @@ impl BorrowKind {
         }
     }
 
-    fn from_chalk(m: Mutability) -> Self {
+    fn from_autoref(m: Mutability) -> Self {
         match m {
             Mutability::Not => BorrowKind::Shared,
-            Mutability::Mut => BorrowKind::Mut { kind: MutBorrowKind::Default },
+            Mutability::Mut => BorrowKind::Mut { kind: MutBorrowKind::TwoPhasedBorrow },
         }
     }
 }
@@ -1062,6 +1062,21 @@ pub struct MirBody {
     /// This field stores the closures directly owned by this body. It is used
     /// in traversing every mir body.
     pub closures: Vec<ClosureId>,
+    /// Code that is never reached and so isn't lowered.
+    pub unreachable_code: Vec<UnreachableCode>,
+}
+
+/// Statements of a block that are never executed because a statement before them diverges.
+///
+/// The statements never include an item, so removing them keeps the items of the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnreachableCode {
+    /// The expression of the statement that diverges.
+    pub diverging: ExprId,
+    /// The first unreachable statement or the tail expression of the block.
+    pub first: MirSpan,
+    /// The last unreachable statement or the tail expression of the block.
+    pub last: MirSpan,
 }
 
 impl MirBody {
@@ -1170,6 +1185,7 @@ impl MirBody {
             param_locals,
             closures,
             projection_store,
+            unreachable_code,
         } = self;
         projection_store.shrink_to_fit();
         basic_blocks.shrink_to_fit();
@@ -1177,6 +1193,7 @@ impl MirBody {
         binding_locals.shrink_to_fit();
         param_locals.shrink_to_fit();
         closures.shrink_to_fit();
+        unreachable_code.shrink_to_fit();
         for (_, b) in basic_blocks.iter_mut() {
             let BasicBlock { statements, terminator: _, is_cleanup: _ } = b;
             statements.shrink_to_fit();
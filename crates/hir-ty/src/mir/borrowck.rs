@@ -1,13 +1,22 @@
 //! MIR borrow checker, which is used in diagnostics like `unused_mut`
 
-// Currently it is an ad-hoc implementation, only useful for mutability analysis. Feel free to remove all of these
-// if needed for implementing a proper borrow checker.
+// Currently it is an ad-hoc implementation, only useful for mutability analysis and a conservative
+// subset of move and borrow errors. Lifetime errors are found by the region inference in
+// `regions`. Feel free to remove all of these if needed for implementing a proper borrow checker.
 
-use std::iter;
+mod regions;
 
-use hir_def::{DefWithBodyId, HasModule};
+use std::{collections::hash_map::Entry, iter};
+
+use chalk_ir::GenericArgData;
+use hir_def::{
+    hir::{Expr, PatId},
+    lang_item::LangItem,
+    DefWithBodyId, HasModule, LifetimeParamId,
+};
 use la_arena::ArenaMap;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use smallvec::{smallvec, SmallVec};
 use stdx::never;
 use triomphe::Arc;
 
@@ -15,12 +24,12 @@ use crate::{
     db::{HirDatabase, InternedClosure},
     mir::Operand,
     utils::ClosureSubst,
-    ClosureId, Interner, Substitution, Ty, TyExt, TypeFlags,
+    ClosureId, Interner, Substitution, Ty, TyExt, TyKind, TypeFlags,
 };
 
 use super::{
-    BasicBlockId, BorrowKind, LocalId, MirBody, MirLowerError, MirSpan, MutBorrowKind, Place,
-    ProjectionElem, Rvalue, StatementKind, TerminatorKind,
+    return_slot, BasicBlock, BasicBlockId, BorrowKind, LocalId, MirBody, MirLowerError, MirSpan,
+    MutBorrowKind, Place, ProjectionElem, ProjectionStore, Rvalue, StatementKind, TerminatorKind,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -51,6 +60,62 @@ pub struct BorrowRegion {
     pub places: Vec<MirSpan>,
 }
 
+/// A use of a binding after it was moved out as a whole on some path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UseAfterMove {
+    pub local: LocalId,
+    pub span: MirSpan,
+    pub moved_at: MirSpan,
+}
+
+/// A borrow of a binding that overlaps with a borrow which is still live, where at least one of
+/// the two is mutable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictingBorrow {
+    pub local: LocalId,
+    pub kind: BorrowKind,
+    pub span: MirSpan,
+    pub previous_kind: BorrowKind,
+    pub previous_span: MirSpan,
+}
+
+/// A borrow of a binding that is still reachable through a live local after the binding went out
+/// of scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DanglingBorrow {
+    pub local: LocalId,
+    pub span: MirSpan,
+    pub dropped_at: MirSpan,
+}
+
+/// A borrow of a binding of the body that ends up in the return value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReturnedLocalBorrow {
+    pub local: LocalId,
+    pub span: MirSpan,
+}
+
+/// A lifetime of the signature of a function, which outlives its body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UniversalRegion {
+    Static,
+    Named(LifetimeParamId),
+    /// An elided lifetime of the parameters, numbered from 1 in order of appearance.
+    Anonymous(u32),
+}
+
+/// A lifetime of the signature that is required to outlive another one, without a bound saying
+/// so.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LifetimeMismatch {
+    pub expected: UniversalRegion,
+    /// The lifetime that has to outlive `expected`.
+    pub actual: UniversalRegion,
+    pub span: MirSpan,
+    /// Whether the requirement comes from returning the data.
+    pub returned: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BorrowckResult {
     pub mir_body: Arc<MirBody>,
@@ -58,21 +123,29 @@ pub struct BorrowckResult {
     pub moved_out_of_ref: Vec<MovedOutOfRef>,
     pub partially_moved: Vec<PartiallyMoved>,
     pub borrow_regions: Vec<BorrowRegion>,
+    pub use_after_move: Vec<UseAfterMove>,
+    pub conflicting_borrows: Vec<ConflictingBorrow>,
+    pub dangling_borrows: Vec<DanglingBorrow>,
+    pub returned_local_borrows: Vec<ReturnedLocalBorrow>,
+    pub lifetime_mismatches: Vec<LifetimeMismatch>,
+    /// The types of the bindings with the lifetimes of the signature their references are
+    /// inferred to have. Other lifetimes are elided.
+    pub inferred_local_tys: ArenaMap<LocalId, Ty>,
 }
 
 fn all_mir_bodies(
     db: &dyn HirDatabase,
     def: DefWithBodyId,
-    mut cb: impl FnMut(Arc<MirBody>),
+    mut cb: impl FnMut(Arc<MirBody>, Option<ClosureId>),
 ) -> Result<(), MirLowerError> {
     fn for_closure(
         db: &dyn HirDatabase,
         c: ClosureId,
-        cb: &mut impl FnMut(Arc<MirBody>),
+        cb: &mut impl FnMut(Arc<MirBody>, Option<ClosureId>),
     ) -> Result<(), MirLowerError> {
         match db.mir_body_for_closure(c) {
             Ok(body) => {
-                cb(body.clone());
+                cb(body.clone(), Some(c));
                 body.closures.iter().try_for_each(|&it| for_closure(db, it, cb))
             }
             Err(e) => Err(e),
@@ -80,7 +153,7 @@ fn all_mir_bodies(
     }
     match db.mir_body(def) {
         Ok(body) => {
-            cb(body.clone());
+            cb(body.clone(), None);
             body.closures.iter().try_for_each(|&it| for_closure(db, it, &mut cb))
         }
         Err(e) => Err(e),
@@ -93,12 +166,19 @@ pub fn borrowck_query(
 ) -> Result<Arc<[BorrowckResult]>, MirLowerError> {
     let _p = tracing::info_span!("borrowck_query").entered();
     let mut res = vec![];
-    all_mir_bodies(db, def, |body| {
+    all_mir_bodies(db, def, |body, closure| {
+        let regions = regions::infer_regions(db, &body, closure);
         res.push(BorrowckResult {
             mutability_of_locals: mutability_of_locals(db, &body),
             moved_out_of_ref: moved_out_of_ref(db, &body),
             partially_moved: partially_moved(db, &body),
             borrow_regions: borrow_regions(db, &body),
+            use_after_move: use_after_move(db, &body),
+            conflicting_borrows: conflicting_borrows(db, &body),
+            dangling_borrows: regions.dangling_borrows,
+            returned_local_borrows: regions.returned_local_borrows,
+            lifetime_mismatches: regions.lifetime_mismatches,
+            inferred_local_tys: regions.inferred_local_tys,
             mir_body: body,
         });
     })?;
@@ -619,3 +699,663 @@ fn mutability_of_locals(
     }
     result
 }
+
+fn successors(terminator: &TerminatorKind) -> SmallVec<[BasicBlockId; 2]> {
+    match terminator {
+        TerminatorKind::Goto { target } => smallvec![*target],
+        TerminatorKind::SwitchInt { targets, .. } => {
+            targets.all_targets().iter().copied().collect()
+        }
+        TerminatorKind::Call { target, cleanup, .. } => {
+            target.iter().chain(cleanup).copied().collect()
+        }
+        TerminatorKind::Drop { target, unwind, .. }
+        | TerminatorKind::DropAndReplace { target, unwind, .. } => {
+            iter::once(target).chain(unwind).copied().collect()
+        }
+        TerminatorKind::Assert { target, cleanup, .. } => {
+            iter::once(target).chain(cleanup).copied().collect()
+        }
+        TerminatorKind::Yield { resume, drop, .. } => {
+            iter::once(resume).chain(drop).copied().collect()
+        }
+        TerminatorKind::FalseEdge { real_target, imaginary_target } => {
+            smallvec![*real_target, *imaginary_target]
+        }
+        TerminatorKind::FalseUnwind { real_target, unwind } => {
+            iter::once(real_target).chain(unwind).copied().collect()
+        }
+        TerminatorKind::UnwindResume
+        | TerminatorKind::Abort
+        | TerminatorKind::Return
+        | TerminatorKind::Unreachable
+        | TerminatorKind::CoroutineDrop => smallvec![],
+    }
+}
+
+/// Calls `f` with every place read by `rvalue`. The flag is `true` if the place is read as an
+/// operand, i.e. the value is copied or moved out of it.
+fn places_in_rvalue(rvalue: &Rvalue, f: &mut impl FnMut(&Place, bool)) {
+    let mut for_operand = |op: &Operand| {
+        if let Operand::Copy(p) | Operand::Move(p) = op {
+            f(p, true);
+        }
+    };
+    match rvalue {
+        Rvalue::ShallowInitBox(o, _)
+        | Rvalue::UnaryOp(_, o)
+        | Rvalue::Cast(_, o, _)
+        | Rvalue::Repeat(o, _)
+        | Rvalue::Use(o) => for_operand(o),
+        Rvalue::CheckedBinaryOp(_, o1, o2) => {
+            for_operand(o1);
+            for_operand(o2);
+        }
+        Rvalue::Aggregate(_, ops) => ops.iter().for_each(for_operand),
+        Rvalue::CopyForDeref(p) | Rvalue::Discriminant(p) | Rvalue::Len(p) | Rvalue::Ref(_, p) => {
+            f(p, false)
+        }
+        Rvalue::ShallowInitBoxWithAlloc(_) => (),
+        Rvalue::ThreadLocalRef(n)
+        | Rvalue::AddressOf(n)
+        | Rvalue::BinaryOp(n)
+        | Rvalue::NullaryOp(n) => match *n {},
+    }
+}
+
+/// Same as [`places_in_rvalue`], but for the operands of a terminator.
+fn places_in_terminator(terminator: &TerminatorKind, f: &mut impl FnMut(&Place, bool)) {
+    let mut for_operand = |op: &Operand| {
+        if let Operand::Copy(p) | Operand::Move(p) = op {
+            f(p, true);
+        }
+    };
+    match terminator {
+        TerminatorKind::SwitchInt { discr: o, .. }
+        | TerminatorKind::DropAndReplace { value: o, .. }
+        | TerminatorKind::Assert { cond: o, .. }
+        | TerminatorKind::Yield { value: o, .. } => for_operand(o),
+        TerminatorKind::Call { func, args, .. } => {
+            for_operand(func);
+            args.iter().for_each(for_operand);
+        }
+        TerminatorKind::Goto { .. }
+        | TerminatorKind::UnwindResume
+        | TerminatorKind::Abort
+        | TerminatorKind::Return
+        | TerminatorKind::Unreachable
+        | TerminatorKind::Drop { .. }
+        | TerminatorKind::CoroutineDrop
+        | TerminatorKind::FalseEdge { .. }
+        | TerminatorKind::FalseUnwind { .. } => (),
+    }
+}
+
+/// Runs `transfer` on every reachable block, starting with `init` at the start block, until the
+/// per-block entry states stop growing. The states are sets that only grow, and `join` returns
+/// whether it added something.
+fn fixpoint<S: Clone>(
+    db: &dyn HirDatabase,
+    body: &MirBody,
+    init: S,
+    mut transfer: impl FnMut(BasicBlockId, &mut S),
+    mut join: impl FnMut(&mut S, &S) -> bool,
+) -> ArenaMap<BasicBlockId, S> {
+    let mut entry_states: ArenaMap<BasicBlockId, S> = ArenaMap::default();
+    entry_states.insert(body.start_block, init);
+    let mut worklist = vec![body.start_block];
+    while let Some(block_id) = worklist.pop() {
+        db.unwind_if_cancelled();
+        let mut state = entry_states[block_id].clone();
+        transfer(block_id, &mut state);
+        let Some(terminator) = &body.basic_blocks[block_id].terminator else {
+            continue;
+        };
+        for succ in successors(&terminator.kind) {
+            let changed = match entry_states.get_mut(succ) {
+                Some(entry) => join(entry, &state),
+                None => {
+                    entry_states.insert(succ, state.clone());
+                    true
+                }
+            };
+            if changed {
+                worklist.push(succ);
+            }
+        }
+    }
+    entry_states
+}
+
+/// Locals that might have been moved out as a whole, with the span of the move.
+type MovedLocals = FxHashMap<LocalId, MirSpan>;
+
+fn moves_in_block(
+    movable: &FxHashSet<LocalId>,
+    ignored_moves: &FxHashSet<PatId>,
+    block: &BasicBlock,
+    moved: &mut MovedLocals,
+    on_use_after_move: &mut impl FnMut(LocalId, MirSpan, MirSpan),
+) {
+    let mut access = |moved: &mut MovedLocals, place: &Place, is_operand: bool, span: MirSpan| {
+        if let Some(&moved_at) = moved.get(&place.local) {
+            on_use_after_move(place.local, span, moved_at);
+        }
+        let is_ignored = matches!(span, MirSpan::PatId(pat) if ignored_moves.contains(&pat));
+        if is_operand
+            && !is_ignored
+            && place.projection.is_empty()
+            && movable.contains(&place.local)
+        {
+            moved.insert(place.local, span);
+        }
+    };
+    for statement in &block.statements {
+        match &statement.kind {
+            StatementKind::Assign(place, rvalue) => {
+                places_in_rvalue(rvalue, &mut |p, is_operand| {
+                    access(moved, p, is_operand, statement.span)
+                });
+                if place.projection.is_empty() {
+                    moved.remove(&place.local);
+                }
+            }
+            StatementKind::StorageLive(l) | StatementKind::StorageDead(l) => {
+                moved.remove(l);
+            }
+            StatementKind::FakeRead(_) | StatementKind::Deinit(_) | StatementKind::Nop => (),
+        }
+    }
+    if let Some(terminator) = &block.terminator {
+        places_in_terminator(&terminator.kind, &mut |p, is_operand| {
+            access(moved, p, is_operand, terminator.span)
+        });
+        match &terminator.kind {
+            TerminatorKind::Call { destination: place, .. }
+            | TerminatorKind::DropAndReplace { place, .. }
+                if place.projection.is_empty() =>
+            {
+                moved.remove(&place.local);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// A forward "maybe moved" dataflow analysis. Only whole user bindings of non-`Copy` types are
+/// tracked, partial moves and temporaries are ignored, so this reports a subset of E0382.
+fn use_after_move(db: &dyn HirDatabase, body: &MirBody) -> Vec<UseAfterMove> {
+    // Without the `Copy` trait every type looks like it is moved on use.
+    if db.lang_item(body.owner.krate(db.upcast()), LangItem::Copy).is_none() {
+        return vec![];
+    }
+    let movable: FxHashSet<LocalId> = body
+        .binding_locals
+        .iter()
+        .map(|(_, &local)| local)
+        .filter(|&local| {
+            let ty = &body.locals[local].ty;
+            ty.as_reference().is_none()
+                && !matches!(ty.kind(Interner), TyKind::Closure(..))
+                && !ty.data(Interner).flags.intersects(TypeFlags::HAS_ERROR)
+                && !ty.clone().is_copy(db, body.owner)
+        })
+        .collect();
+    if movable.is_empty() {
+        return vec![];
+    }
+
+    // Bindings of a match arm with a guard are assigned before the guard runs, and the next arm
+    // is tried from there if the guard fails, which would look like a second move.
+    let hir_body = db.body(body.owner);
+    let mut ignored_moves = FxHashSet::default();
+    for (_, expr) in hir_body.exprs.iter() {
+        if let Expr::Match { arms, .. } = expr {
+            for arm in arms.iter().filter(|arm| arm.guard.is_some()) {
+                hir_body.walk_pats(arm.pat, &mut |pat| {
+                    ignored_moves.insert(pat);
+                });
+            }
+        }
+    }
+
+    let entry_states = fixpoint(
+        db,
+        body,
+        MovedLocals::default(),
+        |block_id, moved: &mut MovedLocals| {
+            let block = &body.basic_blocks[block_id];
+            moves_in_block(&movable, &ignored_moves, block, moved, &mut |_, _, _| ());
+        },
+        |entry, state| {
+            let mut changed = false;
+            for (&local, &span) in state {
+                if let Entry::Vacant(it) = entry.entry(local) {
+                    it.insert(span);
+                    changed = true;
+                }
+            }
+            changed
+        },
+    );
+
+    let mut result = vec![];
+    let mut reported = FxHashSet::default();
+    for (block_id, entry) in entry_states.iter() {
+        let mut moved = entry.clone();
+        let block = &body.basic_blocks[block_id];
+        moves_in_block(
+            &movable,
+            &ignored_moves,
+            block,
+            &mut moved,
+            &mut |local, span, moved_at| {
+                // Only report the first use, the following ones are just noise.
+                if reported.insert(local) {
+                    result.push(UseAfterMove { local, span, moved_at });
+                }
+            },
+        );
+    }
+    result
+}
+
+#[derive(Debug)]
+struct BorrowData {
+    place: Place,
+    kind: BorrowKind,
+    span: MirSpan,
+    /// The local the reference was first stored in.
+    holder: LocalId,
+}
+
+/// For every local, the borrows whose references might be stored in it.
+type HeldBorrows = FxHashMap<LocalId, FxHashSet<usize>>;
+
+/// Whether a value of this type can contain a reference to a local of the body.
+fn may_hold_borrow(ty: &Ty) -> bool {
+    match ty.kind(Interner) {
+        TyKind::Ref(..)
+        | TyKind::Closure(..)
+        | TyKind::Dyn(_)
+        | TyKind::OpaqueType(..)
+        | TyKind::Alias(_) => true,
+        TyKind::Adt(_, subst) | TyKind::Tuple(_, subst) => {
+            subst.iter(Interner).any(|arg| match arg.data(Interner) {
+                GenericArgData::Ty(ty) => may_hold_borrow(ty),
+                GenericArgData::Lifetime(_) => true,
+                GenericArgData::Const(_) => false,
+            })
+        }
+        TyKind::Array(ty, _) | TyKind::Slice(ty) => may_hold_borrow(ty),
+        _ => false,
+    }
+}
+
+/// Whether two places of the same local might refer to overlapping memory. Projections we can't
+/// compare are treated as disjoint.
+fn places_overlap(store: &ProjectionStore, a: &Place, b: &Place) -> bool {
+    if a.local != b.local {
+        return false;
+    }
+    let a = a.projection.lookup(store);
+    let b = b.projection.lookup(store);
+    a.iter().zip(b).all(|pair| match pair {
+        (ProjectionElem::Index(_), ProjectionElem::Index(_)) => true,
+        (x, y) => x == y,
+    })
+}
+
+fn is_mut_borrow(kind: BorrowKind) -> bool {
+    matches!(kind, BorrowKind::Mut { .. })
+}
+
+struct BorrowAnalysis<'a> {
+    body: &'a MirBody,
+    borrows: Vec<BorrowData>,
+    /// Maps `(block, statement index)` of a borrowing assignment to its borrow.
+    borrow_at: FxHashMap<(BasicBlockId, usize), usize>,
+}
+
+/// The borrows held by every local at the entry of every reachable block, and the locals that
+/// may be read later at the exit of every block.
+struct BorrowFacts {
+    entry_states: ArenaMap<BasicBlockId, HeldBorrows>,
+    live_at_exit: ArenaMap<BasicBlockId, FxHashSet<LocalId>>,
+}
+
+impl<'a> BorrowAnalysis<'a> {
+    /// Collects the borrows of the bindings of the body. Borrows through references are not
+    /// borrows of the binding itself, so they are skipped.
+    fn of_bindings(db: &dyn HirDatabase, body: &'a MirBody) -> Self {
+        let binding_of_local = body.local_to_binding_map();
+        let mut analysis =
+            BorrowAnalysis { body, borrows: vec![], borrow_at: FxHashMap::default() };
+        for (block_id, block) in body.basic_blocks.iter() {
+            for (index, statement) in block.statements.iter().enumerate() {
+                if let StatementKind::Assign(dest, Rvalue::Ref(kind, place)) = &statement.kind {
+                    if *kind == BorrowKind::Shallow
+                        || !binding_of_local.contains_idx(place.local)
+                        || place_case(db, body, place) == ProjectionCase::Indirect
+                    {
+                        continue;
+                    }
+                    analysis.borrow_at.insert((block_id, index), analysis.borrows.len());
+                    analysis.borrows.push(BorrowData {
+                        place: *place,
+                        kind: *kind,
+                        span: statement.span,
+                        holder: dest.local,
+                    });
+                }
+            }
+        }
+        analysis
+    }
+
+    /// Computes the borrows held by every local at the entry of every reachable block.
+    fn entry_states(
+        &self,
+        db: &dyn HirDatabase,
+        init: HeldBorrows,
+    ) -> ArenaMap<BasicBlockId, HeldBorrows> {
+        fixpoint(
+            db,
+            self.body,
+            init,
+            |block_id, held: &mut HeldBorrows| self.transfer_block(held, block_id),
+            |entry, state| {
+                let mut changed = false;
+                for (local, borrows) in state {
+                    let entry = entry.entry(*local).or_default();
+                    let len = entry.len();
+                    entry.extend(borrows.iter().copied());
+                    changed |= entry.len() != len;
+                }
+                changed
+            },
+        )
+    }
+
+    fn run(&self, db: &dyn HirDatabase, init: HeldBorrows) -> BorrowFacts {
+        let entry_states = self.entry_states(db, init);
+        let live_at_exit = live_holders_at_exit(db, self.body);
+        BorrowFacts { entry_states, live_at_exit }
+    }
+
+    fn carried_by_place(&self, held: &HeldBorrows, place: &Place) -> FxHashSet<usize> {
+        held.get(&place.local).cloned().unwrap_or_default()
+    }
+
+    fn carried_by_rvalue(
+        &self,
+        held: &HeldBorrows,
+        rvalue: &Rvalue,
+        new_borrow: Option<usize>,
+    ) -> FxHashSet<usize> {
+        let mut result: FxHashSet<usize> = new_borrow.into_iter().collect();
+        match rvalue {
+            Rvalue::Use(_)
+            | Rvalue::Cast(..)
+            | Rvalue::Repeat(..)
+            | Rvalue::Aggregate(..)
+            | Rvalue::Ref(..)
+            | Rvalue::CopyForDeref(_) => places_in_rvalue(rvalue, &mut |p, _| {
+                result.extend(self.carried_by_place(held, p));
+            }),
+            _ => (),
+        }
+        result
+    }
+
+    fn store(&self, held: &mut HeldBorrows, place: &Place, carried: FxHashSet<usize>) {
+        if !may_hold_borrow(&self.body.locals[place.local].ty) {
+            return;
+        }
+        if place.projection.is_empty() {
+            if carried.is_empty() {
+                held.remove(&place.local);
+            } else {
+                held.insert(place.local, carried);
+            }
+        } else if !carried.is_empty() {
+            held.entry(place.local).or_default().extend(carried);
+        }
+    }
+
+    /// Applies a statement to the held borrows, returning the borrow it creates, if any.
+    fn transfer_statement(
+        &self,
+        held: &mut HeldBorrows,
+        block_id: BasicBlockId,
+        index: usize,
+    ) -> Option<usize> {
+        let statement = &self.body.basic_blocks[block_id].statements[index];
+        match &statement.kind {
+            StatementKind::Assign(place, rvalue) => {
+                let new_borrow = self.borrow_at.get(&(block_id, index)).copied();
+                let carried = self.carried_by_rvalue(held, rvalue, new_borrow);
+                self.store(held, place, carried);
+                new_borrow
+            }
+            StatementKind::StorageDead(l) => {
+                held.remove(l);
+                None
+            }
+            StatementKind::FakeRead(_)
+            | StatementKind::Deinit(_)
+            | StatementKind::StorageLive(_)
+            | StatementKind::Nop => None,
+        }
+    }
+
+    fn transfer_terminator(&self, held: &mut HeldBorrows, block_id: BasicBlockId) {
+        let Some(terminator) = &self.body.basic_blocks[block_id].terminator else {
+            return;
+        };
+        if let TerminatorKind::Call { args, destination, .. } = &terminator.kind {
+            // We don't know which arguments the result borrows from, so only keep the borrows
+            // alive if there is a single candidate.
+            let mut carrying_args = args.iter().filter_map(|arg| match arg {
+                Operand::Copy(p) | Operand::Move(p) => {
+                    Some(self.carried_by_place(held, p)).filter(|it| !it.is_empty())
+                }
+                Operand::Constant(_) | Operand::Static(_) => None,
+            });
+            let carried = match (carrying_args.next(), carrying_args.next()) {
+                (Some(it), None) => it,
+                _ => FxHashSet::default(),
+            };
+            self.store(held, destination, carried);
+        }
+    }
+
+    fn transfer_block(&self, held: &mut HeldBorrows, block_id: BasicBlockId) {
+        for index in 0..self.body.basic_blocks[block_id].statements.len() {
+            self.transfer_statement(held, block_id, index);
+        }
+        self.transfer_terminator(held, block_id);
+    }
+}
+
+fn for_each_local_use(body: &MirBody, kind: &StatementKind, mut f: impl FnMut(LocalId)) {
+    match kind {
+        StatementKind::Assign(place, rvalue) => {
+            places_in_rvalue(rvalue, &mut |p, _| f(p.local));
+            // Writing through a reference uses the reference.
+            if place.projection.lookup(&body.projection_store).contains(&ProjectionElem::Deref) {
+                f(place.local);
+            }
+        }
+        StatementKind::FakeRead(_)
+        | StatementKind::Deinit(_)
+        | StatementKind::StorageLive(_)
+        | StatementKind::StorageDead(_)
+        | StatementKind::Nop => (),
+    }
+}
+
+/// Same as [`for_each_local_use`], for terminators. `Return` reads the return slot.
+fn for_each_terminator_use(terminator: &TerminatorKind, mut f: impl FnMut(LocalId)) {
+    if let TerminatorKind::Return = terminator {
+        f(return_slot());
+    }
+    places_in_terminator(terminator, &mut |p, _| f(p.local));
+}
+
+fn local_defined_by(kind: &StatementKind) -> Option<LocalId> {
+    match kind {
+        StatementKind::Assign(place, _) if place.projection.is_empty() => Some(place.local),
+        StatementKind::StorageDead(l) => Some(*l),
+        _ => None,
+    }
+}
+
+fn is_holder(body: &MirBody, local: LocalId) -> bool {
+    may_hold_borrow(&body.locals[local].ty)
+}
+
+/// Computes which locals holding borrows are live, i.e. may be read later, at the end of every
+/// block.
+fn live_holders_at_exit(
+    db: &dyn HirDatabase,
+    body: &MirBody,
+) -> ArenaMap<BasicBlockId, FxHashSet<LocalId>> {
+    let mut predecessors: ArenaMap<BasicBlockId, Vec<BasicBlockId>> = ArenaMap::default();
+    for (block_id, block) in body.basic_blocks.iter() {
+        if let Some(terminator) = &block.terminator {
+            for succ in successors(&terminator.kind) {
+                predecessors.entry(succ).or_default().push(block_id);
+            }
+        }
+    }
+    let mut live_at_exit: ArenaMap<BasicBlockId, FxHashSet<LocalId>> =
+        body.basic_blocks.iter().map(|(id, _)| (id, FxHashSet::default())).collect();
+    let mut worklist: Vec<BasicBlockId> = body.basic_blocks.iter().map(|(id, _)| id).collect();
+    while let Some(block_id) = worklist.pop() {
+        db.unwind_if_cancelled();
+        let mut live = live_at_exit[block_id].clone();
+        let block = &body.basic_blocks[block_id];
+        if let Some(terminator) = &block.terminator {
+            if let TerminatorKind::Call { destination, .. } = &terminator.kind {
+                if destination.projection.is_empty() {
+                    live.remove(&destination.local);
+                }
+            }
+            for_each_terminator_use(&terminator.kind, |local| {
+                if is_holder(body, local) {
+                    live.insert(local);
+                }
+            });
+        }
+        for statement in block.statements.iter().rev() {
+            if let Some(local) = local_defined_by(&statement.kind) {
+                live.remove(&local);
+            }
+            for_each_local_use(body, &statement.kind, |local| {
+                if is_holder(body, local) {
+                    live.insert(local);
+                }
+            });
+        }
+        for &pred in predecessors.get(block_id).into_iter().flatten() {
+            let pred_live = &mut live_at_exit[pred];
+            let len = pred_live.len();
+            pred_live.extend(live.iter().copied());
+            if pred_live.len() != len {
+                worklist.push(pred);
+            }
+        }
+    }
+    live_at_exit
+}
+
+impl BorrowFacts {
+    /// The live locals that may hold borrows after every statement of the block.
+    fn live_after_statements(
+        &self,
+        body: &MirBody,
+        block_id: BasicBlockId,
+    ) -> Vec<FxHashSet<LocalId>> {
+        let block = &body.basic_blocks[block_id];
+        let mut live_after = vec![FxHashSet::default(); block.statements.len()];
+        let mut live = self.live_at_exit[block_id].clone();
+        if let Some(terminator) = &block.terminator {
+            for_each_terminator_use(&terminator.kind, |local| {
+                if is_holder(body, local) {
+                    live.insert(local);
+                }
+            });
+        }
+        for (index, statement) in block.statements.iter().enumerate().rev() {
+            live_after[index] = live.clone();
+            if let Some(local) = local_defined_by(&statement.kind) {
+                live.remove(&local);
+            }
+            for_each_local_use(body, &statement.kind, |local| {
+                if is_holder(body, local) {
+                    live.insert(local);
+                }
+            });
+        }
+        live_after
+    }
+}
+
+/// Reports a borrow of a binding that is created while an overlapping borrow is still reachable
+/// through a live local, and at least one of the two is mutable (E0499 and E0502).
+///
+/// Borrows are followed through copies, aggregates and calls with a single borrowing argument.
+/// Borrows through references and borrows flowing anywhere else are dropped, so this only
+/// reports a subset of the real errors.
+fn conflicting_borrows(db: &dyn HirDatabase, body: &MirBody) -> Vec<ConflictingBorrow> {
+    let analysis = BorrowAnalysis::of_bindings(db, body);
+    if analysis.borrows.is_empty() {
+        return vec![];
+    }
+    let facts = analysis.run(db, HeldBorrows::default());
+
+    let mut result = vec![];
+    for (block_id, entry) in facts.entry_states.iter() {
+        let live_after = facts.live_after_statements(body, block_id);
+        let block = &body.basic_blocks[block_id];
+        let mut held = entry.clone();
+        for (index, statement) in block.statements.iter().enumerate() {
+            let before = held.clone();
+            let Some(new) = analysis.transfer_statement(&mut held, block_id, index) else {
+                continue;
+            };
+            let new_borrow = &analysis.borrows[new];
+            let overwritten = local_defined_by(&statement.kind);
+            let conflict = live_after[index]
+                .iter()
+                .filter(|&&holder| Some(holder) != overwritten)
+                .filter_map(|holder| before.get(holder).map(|borrows| (holder, borrows)))
+                .flat_map(|(holder, borrows)| borrows.iter().map(move |&b| (*holder, b)))
+                .map(|(holder, b)| (holder, &analysis.borrows[b]))
+                .find(|(holder, old)| {
+                    if !is_mut_borrow(old.kind) && !is_mut_borrow(new_borrow.kind) {
+                        return false;
+                    }
+                    // A two-phase borrow is only reserved until it leaves its temporary, shared
+                    // borrows may be created in the meantime (e.g. `v.push(v.len())`).
+                    let reserved = *holder == old.holder
+                        && old.kind == (BorrowKind::Mut { kind: MutBorrowKind::TwoPhasedBorrow });
+                    if reserved && !is_mut_borrow(new_borrow.kind) {
+                        return false;
+                    }
+                    places_overlap(&body.projection_store, &old.place, &new_borrow.place)
+                });
+            if let Some((_, old)) = conflict {
+                result.push(ConflictingBorrow {
+                    local: new_borrow.place.local,
+                    kind: new_borrow.kind,
+                    span: new_borrow.span,
+                    previous_kind: old.kind,
+                    previous_span: old.span,
+                });
+            }
+        }
+    }
+    result
+}
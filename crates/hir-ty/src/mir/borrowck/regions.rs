@@ -0,0 +1,1215 @@
+//! Region inference on MIR, which the lifetime diagnostics of the borrow checker and the inferred
+//! lifetimes shown in hover are based on.
+//!
+//! Type inference erases most lifetimes, so like rustc's borrow checker this replaces every
+//! lifetime in the types of the body with a region variable, and relates the variables along the
+//! assignments and calls of the body. Only the lifetimes of the signature keep their identity,
+//! as universal regions. A region is a set of points of the body, plus the ends of the universal
+//! regions it has to outlive. Liveness of locals adds the points where their references may be
+//! used, and an outlives constraint `'a: 'b` adds everything in `'b` to `'a`.
+//!
+//! Constraints are only generated where types line up structurally, and calls of anything but a
+//! known function don't relate their arguments to their result, so this may miss errors. Missing
+//! constraints never add errors.
+
+use chalk_ir::{BoundVar, DebruijnIndex, InferenceVar, Mutability};
+use hir_def::{
+    generics::WherePredicate, hir::BindingId, AdtId, GenericDefId, GenericParamId, HasModule,
+    LifetimeParamId,
+};
+use intern::sym;
+use la_arena::ArenaMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use triomphe::Arc;
+
+use base_db::CrateId;
+
+use crate::{
+    db::HirDatabase, error_lifetime, fold_generic_args, fold_tys, generics::generics,
+    lt_from_placeholder_idx, lt_to_placeholder_idx, static_lifetime, CallableDefId, CallableSig,
+    ClosureId, DynTy, GenericArg, GenericArgData, HasInterner, Interner, Lifetime, LifetimeData,
+    Ty, TyKind, TypeFoldable, Variance,
+};
+
+use super::{
+    fixpoint, is_holder, is_mut_borrow, live_holders_at_exit, make_fetch_closure_field, place_case,
+    DanglingBorrow, LifetimeMismatch, ProjectionCase, ReturnedLocalBorrow, UniversalRegion,
+};
+use crate::mir::{
+    return_slot, AggregateKind, BasicBlockId, BorrowKind, CastKind, LocalId, MirBody, MirSpan,
+    Operand, Place, ProjectionElem, Rvalue, StatementKind, TerminatorKind,
+};
+
+/// Above this number of bits in the region values, regions are not inferred.
+const MAX_REGION_BITS: usize = 1 << 27;
+
+/// Above this number of regions visited when naming a region for display, it is left elided.
+const MAX_NAMING_STEPS: usize = 1024;
+
+type RegionVid = u32;
+
+#[derive(Debug, Default)]
+pub(super) struct RegionResults {
+    pub(super) dangling_borrows: Vec<DanglingBorrow>,
+    pub(super) returned_local_borrows: Vec<ReturnedLocalBorrow>,
+    pub(super) lifetime_mismatches: Vec<LifetimeMismatch>,
+    pub(super) inferred_local_tys: ArenaMap<LocalId, Ty>,
+}
+
+/// Why a constraint was added.
+#[derive(Debug, Clone, Copy)]
+struct Cause {
+    span: MirSpan,
+    category: Category,
+}
+
+/// The kinds of constraints, from the least to the most interesting one to blame for an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Category {
+    Assignment,
+    Call,
+    /// A value stored in the return slot.
+    Return,
+}
+
+impl Cause {
+    fn new(span: MirSpan, dest: &Place, category: Category) -> Self {
+        let category = if dest.local == return_slot() { Category::Return } else { category };
+        Cause { span, category }
+    }
+}
+
+/// `sup: sub`
+#[derive(Debug, Clone, Copy)]
+struct Outlives {
+    sup: RegionVid,
+    sub: RegionVid,
+    cause: Cause,
+}
+
+/// A borrow of a binding of the body.
+#[derive(Debug)]
+struct Loan {
+    local: LocalId,
+    region: RegionVid,
+    span: MirSpan,
+    /// Whether the borrow is captured by a closure.
+    captured: bool,
+}
+
+struct RegionValues {
+    words: usize,
+    bits: Vec<u64>,
+}
+
+impl RegionValues {
+    fn new(regions: usize, len: usize) -> Self {
+        let words = len.div_ceil(64);
+        RegionValues { words, bits: vec![0; regions * words] }
+    }
+
+    fn insert(&mut self, region: RegionVid, index: usize) {
+        self.bits[region as usize * self.words + index / 64] |= 1 << (index % 64);
+    }
+
+    fn contains(&self, region: RegionVid, index: usize) -> bool {
+        self.bits[region as usize * self.words + index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Adds everything in `sub` to `sup`, returning whether `sup` changed.
+    fn union(&mut self, sup: RegionVid, sub: RegionVid) -> bool {
+        let mut changed = false;
+        for word in 0..self.words {
+            let from = self.bits[sub as usize * self.words + word];
+            let to = &mut self.bits[sup as usize * self.words + word];
+            if *to | from != *to {
+                *to |= from;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn region_lifetime(region: RegionVid) -> Lifetime {
+    LifetimeData::InferenceVar(InferenceVar::from(region)).intern(Interner)
+}
+
+fn region_of(lifetime: &Lifetime) -> Option<RegionVid> {
+    match lifetime.data(Interner) {
+        LifetimeData::InferenceVar(var) => Some(var.index()),
+        _ => None,
+    }
+}
+
+/// The region variables in `t`, in order of appearance.
+fn regions_in<T: HasInterner<Interner = Interner> + TypeFoldable<Interner> + Clone>(
+    t: &T,
+) -> Vec<RegionVid> {
+    let mut result = vec![];
+    fold_generic_args(
+        t.clone(),
+        |arg, _| {
+            if let GenericArgData::Lifetime(lifetime) = &arg {
+                result.extend(region_of(lifetime));
+            }
+            arg
+        },
+        DebruijnIndex::INNERMOST,
+    );
+    result
+}
+
+/// Replaces the lifetimes of trait objects that may be their default lifetime with `Erased`. The
+/// default lifetime isn't an elided lifetime, and we can't tell it from an explicit one.
+fn erase_dyn_default<T: HasInterner<Interner = Interner> + TypeFoldable<Interner>>(t: T) -> T {
+    fold_tys(
+        t,
+        |ty, _| match ty.kind(Interner) {
+            TyKind::Dyn(dyn_ty)
+                if matches!(
+                    dyn_ty.lifetime.data(Interner),
+                    LifetimeData::Static | LifetimeData::Error
+                ) =>
+            {
+                TyKind::Dyn(DynTy {
+                    bounds: dyn_ty.bounds.clone(),
+                    lifetime: LifetimeData::Erased.intern(Interner),
+                })
+                .intern(Interner)
+            }
+            _ => ty,
+        },
+        DebruijnIndex::INNERMOST,
+    )
+}
+
+/// Whether a value of this type may contain references whose regions don't appear in the type.
+fn may_hide_regions(ty: &Ty) -> bool {
+    match ty.kind(Interner) {
+        TyKind::Closure(..)
+        | TyKind::Coroutine(..)
+        | TyKind::Dyn(_)
+        | TyKind::OpaqueType(..)
+        | TyKind::Alias(_) => true,
+        TyKind::Ref(_, _, ty) | TyKind::Raw(_, ty) | TyKind::Array(ty, _) | TyKind::Slice(ty) => {
+            may_hide_regions(ty)
+        }
+        TyKind::Adt(_, subst) | TyKind::Tuple(_, subst) => {
+            subst.type_parameters(Interner).any(|ty| may_hide_regions(&ty))
+        }
+        _ => false,
+    }
+}
+
+struct RegionCtx<'a> {
+    db: &'a dyn HirDatabase,
+    body: &'a MirBody,
+    krate: CrateId,
+    /// The universal region of every region variable, if it is one.
+    universals: Vec<Option<UniversalRegion>>,
+    static_region: RegionVid,
+    named: FxHashMap<LifetimeParamId, RegionVid>,
+    local_tys: ArenaMap<LocalId, Ty>,
+    /// For locals whose values may contain references their type doesn't mention (like
+    /// closures), a region for those references.
+    hidden: ArenaMap<LocalId, RegionVid>,
+    /// The types of every prefix of a place, starting with its local.
+    place_tys: FxHashMap<Place, Arc<[Ty]>>,
+    constraints: Vec<Outlives>,
+    loans: Vec<Loan>,
+    /// The regions of borrows of locals of the body, including temporaries.
+    local_borrow_regions: FxHashSet<RegionVid>,
+}
+
+impl<'a> RegionCtx<'a> {
+    fn new(db: &'a dyn HirDatabase, body: &'a MirBody) -> Self {
+        let mut ctx = RegionCtx {
+            db,
+            body,
+            krate: body.owner.module(db.upcast()).krate(),
+            universals: vec![],
+            static_region: 0,
+            named: FxHashMap::default(),
+            local_tys: ArenaMap::default(),
+            hidden: ArenaMap::default(),
+            place_tys: FxHashMap::default(),
+            constraints: vec![],
+            loans: vec![],
+            local_borrow_regions: FxHashSet::default(),
+        };
+        ctx.static_region = ctx.new_region(Some(UniversalRegion::Static));
+        if let Some(def) = body.owner.as_generic_def_id(db.upcast()) {
+            for id in generics(db.upcast(), def).iter_id() {
+                if let GenericParamId::LifetimeParamId(id) = id {
+                    let region = ctx.new_region(Some(UniversalRegion::Named(id)));
+                    ctx.named.insert(id, region);
+                }
+            }
+        }
+        ctx
+    }
+
+    fn new_region(&mut self, universal: Option<UniversalRegion>) -> RegionVid {
+        self.universals.push(universal);
+        (self.universals.len() - 1) as RegionVid
+    }
+
+    fn new_existential(&mut self) -> RegionVid {
+        self.new_region(None)
+    }
+
+    /// Replaces every lifetime of `t` that isn't bound inside of it with a new region.
+    fn renumber_fresh<T: HasInterner<Interner = Interner> + TypeFoldable<Interner>>(
+        &mut self,
+        t: T,
+    ) -> T {
+        self.renumber_with(t, |this, _| this.new_existential())
+    }
+
+    /// Replaces the lifetimes of a type written in a signature with their regions. Named
+    /// lifetimes and `'static` get their universal regions, and elided ones `elided`.
+    fn renumber_declared<T: HasInterner<Interner = Interner> + TypeFoldable<Interner>>(
+        &mut self,
+        t: T,
+        mut elided: impl FnMut(&mut Self) -> RegionVid,
+    ) -> T {
+        let t = erase_dyn_default(t);
+        self.renumber_with(t, |this, lifetime| match lifetime {
+            LifetimeData::Static => this.static_region,
+            LifetimeData::Placeholder(idx) => {
+                let id = lt_from_placeholder_idx(this.db, *idx);
+                match this.named.get(&id) {
+                    Some(&region) => region,
+                    None => this.new_existential(),
+                }
+            }
+            LifetimeData::Error => elided(this),
+            _ => this.new_existential(),
+        })
+    }
+
+    fn renumber_with<T: HasInterner<Interner = Interner> + TypeFoldable<Interner>>(
+        &mut self,
+        t: T,
+        mut region_for: impl FnMut(&mut Self, &LifetimeData) -> RegionVid,
+    ) -> T {
+        fold_generic_args(
+            t,
+            |arg, _| match arg {
+                GenericArgData::Lifetime(lifetime) => match lifetime.data(Interner) {
+                    LifetimeData::Static
+                    | LifetimeData::Placeholder(_)
+                    | LifetimeData::Error
+                    | LifetimeData::Erased => GenericArgData::Lifetime(region_lifetime(
+                        region_for(self, lifetime.data(Interner)),
+                    )),
+                    _ => GenericArgData::Lifetime(lifetime),
+                },
+                arg => arg,
+            },
+            DebruijnIndex::INNERMOST,
+        )
+    }
+
+    /// Types the locals of the body. For functions, the parameters and the return slot get the
+    /// types of the signature, with an anonymous universal region for every elided lifetime of
+    /// the parameters.
+    fn type_locals(&mut self, is_fn: bool) {
+        let body = self.body;
+        let mut signature_locals = FxHashSet::default();
+        if is_fn {
+            let mut anonymous = 0;
+            let mut inputs = vec![];
+            for &param in &body.param_locals {
+                let ty = self.renumber_declared(body.locals[param].ty.clone(), |this| {
+                    anonymous += 1;
+                    this.new_region(Some(UniversalRegion::Anonymous(anonymous)))
+                });
+                for region in regions_in(&ty) {
+                    if self.universals[region as usize].is_some() && !inputs.contains(&region) {
+                        inputs.push(region);
+                    }
+                }
+                self.local_tys.insert(param, ty);
+                signature_locals.insert(param);
+            }
+            // Elided lifetimes of the output are the lifetime of `&self`, or the only lifetime of
+            // the inputs.
+            let has_self_param = match body.owner {
+                hir_def::DefWithBodyId::FunctionId(f) => self.db.function_data(f).has_self_param(),
+                _ => false,
+            };
+            let self_region = body.param_locals.first().filter(|_| has_self_param).and_then(|it| {
+                match self.local_tys[*it].kind(Interner) {
+                    TyKind::Ref(_, lifetime, _) => region_of(lifetime),
+                    _ => None,
+                }
+            });
+            let output = self_region.or(match *inputs {
+                [only] => Some(only),
+                _ => None,
+            });
+            let ty = self.renumber_declared(body.locals[return_slot()].ty.clone(), |this| {
+                output.unwrap_or_else(|| this.new_existential())
+            });
+            self.local_tys.insert(return_slot(), ty);
+            signature_locals.insert(return_slot());
+        }
+        for (local, data) in body.locals.iter() {
+            if !signature_locals.contains(&local) {
+                let ty = self.renumber_fresh(data.ty.clone());
+                self.local_tys.insert(local, ty);
+            }
+        }
+        for (local, ty) in self.local_tys.iter() {
+            if may_hide_regions(ty) {
+                self.universals.push(None);
+                self.hidden.insert(local, (self.universals.len() - 1) as RegionVid);
+            }
+        }
+    }
+
+    /// The outlives relations between the universal regions that hold in the body: the bounds
+    /// of the generics, and the bounds implied by the types of the signature being well-formed.
+    fn known_relations(&self, is_fn: bool) -> FxHashSet<(RegionVid, RegionVid)> {
+        let universal_regions = self.universal_regions();
+        let mut known: FxHashSet<_> =
+            universal_regions.iter().flat_map(|&u| [(u, u), (self.static_region, u)]).collect();
+        if !is_fn {
+            return known;
+        }
+        let mut by_name = FxHashMap::default();
+        by_name.insert(sym::tick_static.clone(), self.static_region);
+        for (&id, &region) in &self.named {
+            by_name.insert(
+                self.db.generic_params(id.parent)[id.local_id].name.symbol().clone(),
+                region,
+            );
+        }
+        let mut def = self.body.owner.as_generic_def_id(self.db.upcast());
+        while let Some(it) = def {
+            for pred in self.db.generic_params(it).where_predicates() {
+                if let WherePredicate::Lifetime { target, bound } = pred {
+                    if let (Some(&target), Some(&bound)) =
+                        (by_name.get(target.name.symbol()), by_name.get(bound.name.symbol()))
+                    {
+                        known.insert((target, bound));
+                    }
+                }
+            }
+            def = generics(self.db.upcast(), it).parent_generics().map(|it| it.def());
+        }
+        for &param in self.body.param_locals.iter().chain([&return_slot()]) {
+            self.implied_bounds(&self.local_tys[param], &mut known);
+        }
+        for &k in &universal_regions {
+            for &i in &universal_regions {
+                if !known.contains(&(i, k)) {
+                    continue;
+                }
+                for &j in &universal_regions {
+                    if known.contains(&(k, j)) {
+                        known.insert((i, j));
+                    }
+                }
+            }
+        }
+        known
+    }
+
+    fn implied_bounds(&self, ty: &Ty, known: &mut FxHashSet<(RegionVid, RegionVid)>) {
+        match ty.kind(Interner) {
+            TyKind::Ref(_, lifetime, inner) => {
+                if let Some(region) = region_of(lifetime) {
+                    known.extend(regions_in(inner).into_iter().map(|it| (it, region)));
+                }
+                self.implied_bounds(inner, known);
+            }
+            TyKind::Adt(adt, subst) => {
+                let lifetimes: Vec<_> = subst
+                    .iter(Interner)
+                    .filter_map(|it| region_of(it.lifetime(Interner)?))
+                    .collect();
+                for ty in subst.type_parameters(Interner) {
+                    for region in regions_in(&ty) {
+                        known.extend(lifetimes.iter().map(|&it| (region, it)));
+                    }
+                    self.implied_bounds(&ty, known);
+                }
+                self.adt_bounds(adt.0, subst, known);
+            }
+            TyKind::Raw(_, ty) | TyKind::Array(ty, _) | TyKind::Slice(ty) => {
+                self.implied_bounds(ty, known)
+            }
+            TyKind::Tuple(_, subst) => {
+                subst.type_parameters(Interner).for_each(|ty| self.implied_bounds(&ty, known))
+            }
+            _ => (),
+        }
+    }
+
+    /// The lifetime bounds declared on an ADT, for the arguments of `subst`.
+    fn adt_bounds(
+        &self,
+        adt: AdtId,
+        subst: &crate::Substitution,
+        known: &mut FxHashSet<(RegionVid, RegionVid)>,
+    ) {
+        let def = GenericDefId::AdtId(adt);
+        let params = self.db.generic_params(def);
+        let generics = generics(self.db.upcast(), def);
+        let region_named = |name: &hir_expand::name::Name| {
+            let (id, _) = params.iter_lt().find(|(_, it)| it.name == *name)?;
+            let idx = generics.lifetime_idx(LifetimeParamId { parent: def, local_id: id })?;
+            region_of(subst.at(Interner, idx).lifetime(Interner)?)
+        };
+        for pred in params.where_predicates() {
+            if let WherePredicate::Lifetime { target, bound } = pred {
+                if let (Some(target), Some(bound)) =
+                    (region_named(&target.name), region_named(&bound.name))
+                {
+                    known.insert((target, bound));
+                }
+            }
+        }
+    }
+
+    fn universal_regions(&self) -> Vec<RegionVid> {
+        (0..self.universals.len() as RegionVid)
+            .filter(|&it| self.universals[it as usize].is_some())
+            .collect()
+    }
+
+    fn outlives(&mut self, sup: RegionVid, sub: RegionVid, cause: Cause) {
+        if sup != sub {
+            self.constraints.push(Outlives { sup, sub, cause });
+        }
+    }
+
+    /// Adds the constraints for a value of type `a` to be used as a value of type `b`, where the
+    /// relation is `a <: b` for covariant, `b <: a` for contravariant and `a == b` for invariant.
+    fn relate_tys(&mut self, a: &Ty, b: &Ty, variance: Variance, cause: Cause) {
+        let nested = |m: &Mutability| match m {
+            Mutability::Mut => Variance::Invariant,
+            Mutability::Not => Variance::Covariant,
+        };
+        match (a.kind(Interner), b.kind(Interner)) {
+            (TyKind::Ref(m1, r1, t1), TyKind::Ref(m2, r2, t2)) if m1 == m2 => {
+                self.relate_lifetimes(r1, r2, variance, cause);
+                self.relate_tys(t1, t2, variance.xform(nested(m1)), cause);
+            }
+            // Casts between raw pointers that only differ in lifetimes are lowered as plain
+            // uses, so nothing is related through raw pointers.
+            (TyKind::Adt(id1, s1), TyKind::Adt(id2, s2)) if id1 == id2 => {
+                let Some(variances) = self.db.variances_of(id1.0.into()) else { return };
+                for ((a, b), v) in s1.iter(Interner).zip(s2.iter(Interner)).zip(variances.iter()) {
+                    self.relate_args(a, b, variance.xform(*v), cause);
+                }
+            }
+            (TyKind::Tuple(n1, s1), TyKind::Tuple(n2, s2)) if n1 == n2 => {
+                for (a, b) in s1.iter(Interner).zip(s2.iter(Interner)) {
+                    self.relate_args(a, b, variance, cause);
+                }
+            }
+            (
+                TyKind::Array(t1, _) | TyKind::Slice(t1),
+                TyKind::Array(t2, _) | TyKind::Slice(t2),
+            ) => self.relate_tys(t1, t2, variance, cause),
+            _ => (),
+        }
+    }
+
+    fn relate_args(&mut self, a: &GenericArg, b: &GenericArg, variance: Variance, cause: Cause) {
+        match (a.data(Interner), b.data(Interner)) {
+            (GenericArgData::Ty(a), GenericArgData::Ty(b)) => {
+                self.relate_tys(a, b, variance, cause)
+            }
+            (GenericArgData::Lifetime(a), GenericArgData::Lifetime(b)) => {
+                self.relate_lifetimes(a, b, variance, cause)
+            }
+            _ => (),
+        }
+    }
+
+    fn relate_lifetimes(&mut self, a: &Lifetime, b: &Lifetime, variance: Variance, cause: Cause) {
+        let (Some(a), Some(b)) = (region_of(a), region_of(b)) else { return };
+        match variance {
+            Variance::Covariant => self.outlives(a, b, cause),
+            Variance::Contravariant => self.outlives(b, a, cause),
+            Variance::Invariant => {
+                self.outlives(a, b, cause);
+                self.outlives(b, a, cause);
+            }
+            Variance::Bivariant => (),
+        }
+    }
+
+    /// The types of every prefix of `place`, starting with its local.
+    fn place_tys(&mut self, place: &Place) -> Arc<[Ty]> {
+        if let Some(tys) = self.place_tys.get(place) {
+            return tys.clone();
+        }
+        let mut tys = vec![self.local_tys[place.local].clone()];
+        for elem in place.projection.lookup(&self.body.projection_store) {
+            let base = tys.last().unwrap().clone();
+            let ty =
+                elem.projected_ty(base, self.db, make_fetch_closure_field(self.db), self.krate);
+            // Captures have the types inference gave to the captured places.
+            let ty = match elem {
+                ProjectionElem::ClosureField(_) => self.renumber_fresh(ty),
+                _ => self.renumber_declared(ty, Self::new_existential),
+            };
+            tys.push(ty);
+        }
+        let tys: Arc<[Ty]> = tys.into();
+        self.place_tys.insert(*place, tys.clone());
+        tys
+    }
+
+    fn place_ty(&mut self, place: &Place) -> Ty {
+        self.place_tys(place).last().unwrap().clone()
+    }
+
+    fn operand_ty(&mut self, operand: &Operand) -> Option<Ty> {
+        match operand {
+            Operand::Copy(p) | Operand::Move(p) => Some(self.place_ty(p)),
+            Operand::Constant(c) => {
+                Some(self.renumber_declared(c.data(Interner).ty.clone(), Self::new_existential))
+            }
+            Operand::Static(_) => None,
+        }
+    }
+
+    fn flow_hidden(&mut self, from: LocalId, to: LocalId, cause: Cause) {
+        if let (Some(&from), Some(&to)) = (self.hidden.get(from), self.hidden.get(to)) {
+            self.outlives(from, to, cause);
+        }
+    }
+
+    /// Makes the regions of `ty` outlive the hidden region of `local`.
+    fn flow_into_hidden(&mut self, ty: &Ty, local: LocalId, cause: Cause) {
+        if let Some(&hidden) = self.hidden.get(local) {
+            for region in regions_in(ty) {
+                self.outlives(region, hidden, cause);
+            }
+        }
+    }
+
+    fn add_statement_constraints(
+        &mut self,
+        place: &Place,
+        rvalue: &Rvalue,
+        cause: Cause,
+        binding_of_local: &ArenaMap<LocalId, BindingId>,
+        captured: &FxHashSet<LocalId>,
+    ) {
+        let dest = self.place_ty(place);
+        match rvalue {
+            Rvalue::Use(op) => {
+                if let Some(ty) = self.operand_ty(op) {
+                    self.relate_tys(&ty, &dest, Variance::Covariant, cause);
+                }
+            }
+            // Other casts are between raw pointers and values without lifetimes, and raw pointer
+            // casts may change lifetimes freely.
+            Rvalue::Cast(CastKind::PointerCoercion(_), op, _) => {
+                if let Some(ty) = self.operand_ty(op) {
+                    self.relate_tys(&ty, &dest, Variance::Covariant, cause);
+                    // Unsizing to a trait object hides the regions of the value.
+                    self.flow_into_hidden(&ty, place.local, cause);
+                }
+            }
+            Rvalue::CopyForDeref(p) => {
+                let ty = self.place_ty(p);
+                self.relate_tys(&ty, &dest, Variance::Covariant, cause);
+            }
+            Rvalue::Repeat(op, _) => {
+                if let (Some(ty), TyKind::Array(elem, _)) =
+                    (self.operand_ty(op), dest.kind(Interner))
+                {
+                    self.relate_tys(&ty, elem, Variance::Covariant, cause);
+                }
+            }
+            &Rvalue::Ref(kind, borrowed) => {
+                let Some(region) = self.add_borrow_constraints(kind, &borrowed, &dest, cause)
+                else {
+                    return;
+                };
+                let case = place_case(self.db, self.body, &borrowed);
+                if case == ProjectionCase::Indirect {
+                    return;
+                }
+                self.local_borrow_regions.insert(region);
+                if binding_of_local.contains_idx(borrowed.local) {
+                    self.loans.push(Loan {
+                        local: borrowed.local,
+                        region,
+                        span: cause.span,
+                        captured: captured.contains(&place.local),
+                    });
+                }
+            }
+            Rvalue::Aggregate(kind, ops) => {
+                let op_tys: Vec<_> = ops.iter().map(|op| self.operand_ty(op)).collect();
+                let field_tys: Vec<Ty> = match (kind, dest.kind(Interner)) {
+                    (AggregateKind::Array(_), TyKind::Array(elem, _)) => {
+                        vec![elem.clone(); ops.len()]
+                    }
+                    (AggregateKind::Tuple(_), TyKind::Tuple(_, subst)) => {
+                        subst.type_parameters(Interner).collect()
+                    }
+                    (AggregateKind::Adt(variant, _), TyKind::Adt(_, subst)) => self
+                        .db
+                        .field_types(*variant)
+                        .iter()
+                        .map(|(_, ty)| ty.clone().substitute(Interner, subst))
+                        .collect(),
+                    (AggregateKind::Union(_, field), TyKind::Adt(_, subst)) => {
+                        vec![self.db.field_types(field.parent)[field.local_id]
+                            .clone()
+                            .substitute(Interner, subst)]
+                    }
+                    (AggregateKind::Closure(_), _) => {
+                        for ty in op_tys.iter().flatten() {
+                            self.flow_into_hidden(ty, place.local, cause);
+                        }
+                        vec![]
+                    }
+                    _ => vec![],
+                };
+                if field_tys.len() == op_tys.len() {
+                    for (op_ty, field_ty) in op_tys.iter().zip(field_tys) {
+                        let Some(op_ty) = op_ty else { continue };
+                        let field_ty = self.renumber_declared(field_ty, Self::new_existential);
+                        self.relate_tys(op_ty, &field_ty, Variance::Covariant, cause);
+                    }
+                }
+            }
+            _ => (),
+        }
+        super::places_in_rvalue(rvalue, &mut |p, _| self.flow_hidden(p.local, place.local, cause));
+    }
+
+    /// Adds the constraints of borrowing `borrowed` as a reference of type `dest`, returning the
+    /// region of the borrow.
+    fn add_borrow_constraints(
+        &mut self,
+        kind: BorrowKind,
+        borrowed: &Place,
+        dest: &Ty,
+        cause: Cause,
+    ) -> Option<RegionVid> {
+        if kind == BorrowKind::Shallow {
+            return None;
+        }
+        let TyKind::Ref(_, dest_lifetime, dest_inner) = dest.kind(Interner) else { return None };
+        let dest_region = region_of(dest_lifetime)?;
+        let region = self.new_existential();
+        self.outlives(region, dest_region, cause);
+        let tys = self.place_tys(borrowed);
+        let variance = if is_mut_borrow(kind) { Variance::Invariant } else { Variance::Covariant };
+        self.relate_tys(tys.last().unwrap(), dest_inner, variance, cause);
+        // A reborrow through a reference can't outlive the reference. Going further than a
+        // shared reference isn't needed, as it can be copied out.
+        let projection = borrowed.projection.lookup(&self.body.projection_store);
+        for (i, elem) in projection.iter().enumerate().rev() {
+            if *elem != ProjectionElem::Deref {
+                continue;
+            }
+            match tys[i].kind(Interner) {
+                TyKind::Ref(m, lifetime, _) => {
+                    if let Some(lifetime) = region_of(lifetime) {
+                        self.outlives(lifetime, region, cause);
+                    }
+                    if *m == Mutability::Not {
+                        break;
+                    }
+                }
+                TyKind::Adt(..) => (),
+                _ => break,
+            }
+        }
+        Some(region)
+    }
+
+    /// The signature of the function called by `func`, with the lifetimes elided in it resolved
+    /// like in the declaration and all other lifetimes replaced by regions.
+    fn callee_sig(&mut self, func: &Operand) -> Option<CallableSig> {
+        let Operand::Constant(c) = func else { return None };
+        let TyKind::FnDef(def, subst) = c.data(Interner).ty.kind(Interner) else { return None };
+        let callable = self.db.lookup_intern_callable_def((*def).into());
+        let sig = self.db.callable_item_signature(callable);
+        if sig.binders.len(Interner) != subst.len(Interner) {
+            return None;
+        }
+        let subst = self.renumber_fresh(subst.clone());
+        let has_self_param = match callable {
+            CallableDefId::FunctionId(f) => self.db.function_data(f).has_self_param(),
+            _ => false,
+        };
+        let (sig, binders) = sig.into_value_and_skipped_binders();
+        let mut inputs: Vec<Lifetime> = vec![];
+        let params = sig.params().iter().map(|ty| {
+            fold_generic_args(
+                erase_dyn_default(ty.clone()),
+                |arg, outer_binder| {
+                    let GenericArgData::Lifetime(lifetime) = arg else { return arg };
+                    let (lifetime, input) = match lifetime.data(Interner) {
+                        LifetimeData::Error if outer_binder == DebruijnIndex::INNERMOST => {
+                            let lifetime = region_lifetime(self.new_existential());
+                            (lifetime.clone(), lifetime)
+                        }
+                        LifetimeData::BoundVar(bv) if bv.debruijn == outer_binder => {
+                            let input = BoundVar::new(DebruijnIndex::INNERMOST, bv.index)
+                                .to_lifetime(Interner);
+                            (lifetime, input)
+                        }
+                        LifetimeData::Static => (lifetime.clone(), lifetime),
+                        LifetimeData::Error | LifetimeData::Erased => {
+                            return GenericArgData::Lifetime(region_lifetime(
+                                self.new_existential(),
+                            ))
+                        }
+                        _ => return GenericArgData::Lifetime(lifetime),
+                    };
+                    if !inputs.contains(&input) {
+                        inputs.push(input);
+                    }
+                    GenericArgData::Lifetime(lifetime)
+                },
+                DebruijnIndex::INNERMOST,
+            )
+        });
+        let params: Vec<Ty> = params.collect();
+        let self_lifetime =
+            params.first().filter(|_| has_self_param).and_then(|ty| match ty.kind(Interner) {
+                TyKind::Ref(_, lifetime, _) => Some(lifetime.clone()),
+                _ => None,
+            });
+        let output = self_lifetime.or(match &*inputs {
+            [only] => Some(only.clone()),
+            _ => None,
+        });
+        let ret = erase_dyn_default(sig.ret().clone());
+        let ret = fold_generic_args(
+            ret,
+            |arg, outer_binder| match arg {
+                GenericArgData::Lifetime(lifetime) => {
+                    GenericArgData::Lifetime(match lifetime.data(Interner) {
+                        LifetimeData::Error if outer_binder == DebruijnIndex::INNERMOST => {
+                            match &output {
+                                Some(it) => it.clone(),
+                                None => region_lifetime(self.new_existential()),
+                            }
+                        }
+                        LifetimeData::Error | LifetimeData::Erased => {
+                            region_lifetime(self.new_existential())
+                        }
+                        _ => lifetime,
+                    })
+                }
+                arg => arg,
+            },
+            DebruijnIndex::INNERMOST,
+        );
+        let sig = CallableSig::from_params_and_return(
+            params.into_iter(),
+            ret,
+            sig.is_varargs,
+            sig.safety,
+            sig.abi(),
+        );
+        let sig = crate::Binders::new(binders, sig).substitute(Interner, &subst);
+        Some(self.renumber_declared(sig, Self::new_existential))
+    }
+
+    fn add_terminator_constraints(&mut self, kind: &TerminatorKind, span: MirSpan) {
+        let TerminatorKind::Call { func, args, destination, .. } = kind else { return };
+        let cause = Cause::new(span, destination, Category::Call);
+        let dest = self.place_ty(destination);
+        if let Some(sig) = self.callee_sig(func) {
+            for (arg, param) in args.iter().zip(sig.params()) {
+                if let Some(ty) = self.operand_ty(arg) {
+                    self.relate_tys(&ty, param, Variance::Covariant, cause);
+                }
+            }
+            self.relate_tys(sig.ret(), &dest, Variance::Covariant, cause);
+        }
+        for arg in args.iter() {
+            if let Operand::Copy(p) | Operand::Move(p) = arg {
+                self.flow_hidden(p.local, destination.local, cause);
+            }
+        }
+    }
+}
+
+/// Infers the regions of a body. Mismatches between universal regions are only reported for the
+/// body of a function, as closures don't have a signature of their own.
+pub(super) fn infer_regions(
+    db: &dyn HirDatabase,
+    body: &MirBody,
+    closure: Option<ClosureId>,
+) -> RegionResults {
+    let is_fn = closure.is_none() && matches!(body.owner, hir_def::DefWithBodyId::FunctionId(_));
+    let mut ctx = RegionCtx::new(db, body);
+    ctx.type_locals(is_fn);
+
+    let binding_of_local = body.local_to_binding_map();
+    let mut captured = FxHashSet::default();
+    for (_, block) in body.basic_blocks.iter() {
+        for statement in &block.statements {
+            if let StatementKind::Assign(_, Rvalue::Aggregate(AggregateKind::Closure(_), ops)) =
+                &statement.kind
+            {
+                captured.extend(ops.iter().filter_map(|op| match op {
+                    Operand::Copy(p) | Operand::Move(p) => Some(p.local),
+                    Operand::Constant(_) | Operand::Static(_) => None,
+                }));
+            }
+        }
+    }
+
+    // Every statement and terminator is a point.
+    let mut block_start: ArenaMap<BasicBlockId, usize> = ArenaMap::default();
+    let mut num_points = 0;
+    for (block_id, block) in body.basic_blocks.iter() {
+        block_start.insert(block_id, num_points);
+        num_points += block.statements.len() + 1;
+    }
+    let mut loan_at = FxHashMap::default();
+    for (block_id, block) in body.basic_blocks.iter() {
+        db.unwind_if_cancelled();
+        for (index, statement) in block.statements.iter().enumerate() {
+            let StatementKind::Assign(place, rvalue) = &statement.kind else { continue };
+            let cause = Cause::new(statement.span, place, Category::Assignment);
+            let loans = ctx.loans.len();
+            ctx.add_statement_constraints(place, rvalue, cause, &binding_of_local, &captured);
+            if ctx.loans.len() != loans {
+                loan_at.insert((block_id, index), loans);
+            }
+        }
+        if let Some(terminator) = &block.terminator {
+            ctx.add_terminator_constraints(&terminator.kind, terminator.span);
+        }
+    }
+
+    let universal_regions = ctx.universal_regions();
+    let end_of = |u: RegionVid| {
+        num_points + universal_regions.iter().position(|&it| it == u).unwrap_or_default()
+    };
+    let num_regions = ctx.universals.len();
+    let len = num_points + universal_regions.len();
+    let mut sups_of = vec![vec![]; num_regions];
+    let mut subs_of = vec![vec![]; num_regions];
+    for (idx, c) in ctx.constraints.iter().enumerate() {
+        sups_of[c.sub as usize].push(idx);
+        subs_of[c.sup as usize].push(idx);
+    }
+    let known = ctx.known_relations(is_fn);
+    if num_regions.saturating_mul(len) > MAX_REGION_BITS {
+        return RegionResults {
+            inferred_local_tys: ctx.inferred_local_tys(&sups_of, &known),
+            ..Default::default()
+        };
+    }
+    let mut values = RegionValues::new(num_regions, len);
+    for &u in &universal_regions {
+        for point in 0..num_points {
+            values.insert(u, point);
+        }
+        values.insert(u, end_of(u));
+    }
+
+    // A region contains the points where a local whose type mentions it may be used later.
+    let regions_of_local: ArenaMap<LocalId, Vec<RegionVid>> = ctx
+        .local_tys
+        .iter()
+        .map(|(local, ty)| {
+            let mut regions = regions_in(ty);
+            regions.extend(ctx.hidden.get(local).copied());
+            (local, regions)
+        })
+        .collect();
+    let live_at_exit = live_holders_at_exit(db, body);
+    for (block_id, block) in body.basic_blocks.iter() {
+        let mut live = live_at_exit.get(block_id).cloned().unwrap_or_default();
+        let mut mark_live = |live: &FxHashSet<LocalId>, point: usize| {
+            for &local in live {
+                for &region in regions_of_local.get(local).into_iter().flatten() {
+                    values.insert(region, point);
+                }
+            }
+        };
+        if let Some(terminator) = &block.terminator {
+            if let TerminatorKind::Call { destination, .. } = &terminator.kind {
+                if destination.projection.is_empty() {
+                    live.remove(&destination.local);
+                }
+            }
+            super::for_each_terminator_use(&terminator.kind, |local| {
+                if is_holder(body, local) {
+                    live.insert(local);
+                }
+            });
+        }
+        mark_live(&live, block_start[block_id] + block.statements.len());
+        for (index, statement) in block.statements.iter().enumerate().rev() {
+            if let Some(local) = super::local_defined_by(&statement.kind) {
+                live.remove(&local);
+            }
+            super::for_each_local_use(body, &statement.kind, |local| {
+                if is_holder(body, local) {
+                    live.insert(local);
+                }
+            });
+            mark_live(&live, block_start[block_id] + index);
+        }
+    }
+
+    // Propagate the values along the constraints until nothing changes.
+    let mut worklist: Vec<RegionVid> = (0..num_regions as RegionVid).collect();
+    let mut queued = vec![true; num_regions];
+    while let Some(sub) = worklist.pop() {
+        queued[sub as usize] = false;
+        for &c in &sups_of[sub as usize] {
+            let sup = ctx.constraints[c].sup;
+            if values.union(sup, sub) && !queued[sup as usize] {
+                queued[sup as usize] = true;
+                worklist.push(sup);
+            }
+        }
+    }
+
+    // The constraints leading from `from` to the first region `is_target` accepts.
+    let path_to = |from: RegionVid, is_target: &dyn Fn(RegionVid) -> bool| -> Vec<Outlives> {
+        let mut prev: FxHashMap<RegionVid, usize> = FxHashMap::default();
+        let mut queue = std::collections::VecDeque::from([from]);
+        let mut visited = FxHashSet::from_iter([from]);
+        while let Some(region) = queue.pop_front() {
+            if region != from && is_target(region) {
+                let mut path = vec![];
+                let mut current = region;
+                while let Some(&c) = prev.get(&current) {
+                    path.push(ctx.constraints[c]);
+                    current = ctx.constraints[c].sup;
+                }
+                path.reverse();
+                return path;
+            }
+            for &c in &subs_of[region as usize] {
+                let sub = ctx.constraints[c].sub;
+                if visited.insert(sub) {
+                    prev.insert(sub, c);
+                    queue.push_back(sub);
+                }
+            }
+        }
+        vec![]
+    };
+    // Points at the last of the most interesting constraints, and tells whether data is returned.
+    let blame = |path: &[Outlives]| {
+        let category = path.iter().map(|c| c.cause.category).max();
+        let span = path
+            .iter()
+            .rev()
+            .find(|c| Some(c.cause.category) == category)
+            .map_or(MirSpan::Unknown, |c| c.cause.span);
+        (span, category == Some(Category::Return))
+    };
+
+    let mut results = RegionResults::default();
+    if is_fn {
+        let mut reported_spans = vec![];
+        for &u in &universal_regions {
+            for &w in &universal_regions {
+                if u == w || !values.contains(u, end_of(w)) || known.contains(&(u, w)) {
+                    continue;
+                }
+                let (span, returned) = blame(&path_to(u, &|it| it == w));
+                let (Some(actual), Some(expected)) =
+                    (ctx.universals[u as usize], ctx.universals[w as usize])
+                else {
+                    continue;
+                };
+                if !reported_spans.contains(&span) {
+                    reported_spans.push(span);
+                    results.lifetime_mismatches.push(LifetimeMismatch {
+                        expected,
+                        actual,
+                        span,
+                        returned,
+                    });
+                }
+            }
+        }
+    }
+
+    // A borrow must not outlive the function, nor be live where the storage of its local ends.
+    let mut storage_dead_at = FxHashMap::default();
+    for (_, block) in body.basic_blocks.iter() {
+        for statement in &block.statements {
+            if let StatementKind::StorageDead(local) = statement.kind {
+                storage_dead_at.entry(local).or_insert(statement.span);
+            }
+        }
+    }
+    // Borrows captured by a closure that escapes are a different error (E0373), with a different
+    // fix, so they are skipped.
+    let mut reported: FxHashSet<usize> = match ctx.hidden.get(return_slot()) {
+        Some(&returned) => (0..ctx.loans.len())
+            .filter(|&idx| {
+                let loan = &ctx.loans[idx];
+                loan.captured && !path_to(loan.region, &|it| it == returned).is_empty()
+            })
+            .collect(),
+        None => FxHashSet::default(),
+    };
+    for (idx, loan) in ctx.loans.iter().enumerate() {
+        if reported.contains(&idx) {
+            continue;
+        }
+        if !universal_regions.iter().any(|&u| values.contains(loan.region, end_of(u))) {
+            continue;
+        }
+        reported.insert(idx);
+        if loan.captured {
+            continue;
+        }
+        let path = path_to(loan.region, &|it| ctx.universals[it as usize].is_some());
+        if blame(&path).1 {
+            results
+                .returned_local_borrows
+                .push(ReturnedLocalBorrow { local: loan.local, span: loan.span });
+        } else {
+            results.dangling_borrows.push(DanglingBorrow {
+                local: loan.local,
+                span: loan.span,
+                dropped_at: storage_dead_at.get(&loan.local).copied().unwrap_or(loan.span),
+            });
+        }
+    }
+    if ctx.loans.len() != reported.len() {
+        let transfer = |block_id: BasicBlockId, index: usize, in_scope: &mut FxHashSet<usize>| {
+            match &body.basic_blocks[block_id].statements[index].kind {
+                StatementKind::StorageDead(local) => {
+                    in_scope.retain(|&it| ctx.loans[it].local != *local)
+                }
+                StatementKind::Assign(place, _) if place.projection.is_empty() => {
+                    in_scope.retain(|&it| ctx.loans[it].local != place.local)
+                }
+                _ => (),
+            }
+            in_scope.extend(loan_at.get(&(block_id, index)).copied());
+        };
+        let entry_states = fixpoint(
+            db,
+            body,
+            FxHashSet::default(),
+            |block_id, in_scope| {
+                for index in 0..body.basic_blocks[block_id].statements.len() {
+                    transfer(block_id, index, in_scope);
+                }
+            },
+            |entry, state| {
+                let len = entry.len();
+                entry.extend(state.iter().copied());
+                entry.len() != len
+            },
+        );
+        for (block_id, entry) in entry_states.iter() {
+            let mut in_scope = entry.clone();
+            for (index, statement) in body.basic_blocks[block_id].statements.iter().enumerate() {
+                if let StatementKind::StorageDead(local) = statement.kind {
+                    let point = block_start[block_id] + index;
+                    for &idx in &in_scope {
+                        let loan = &ctx.loans[idx];
+                        if loan.local == local
+                            && values.contains(loan.region, point)
+                            && reported.insert(idx)
+                        {
+                            results.dangling_borrows.push(DanglingBorrow {
+                                local,
+                                span: loan.span,
+                                dropped_at: statement.span,
+                            });
+                        }
+                    }
+                }
+                transfer(block_id, index, &mut in_scope);
+            }
+        }
+    }
+
+    results.inferred_local_tys = ctx.inferred_local_tys(&sups_of, &known);
+    results
+}
+
+impl RegionCtx<'_> {
+    /// The types of the bindings, with the regions that can be named by a lifetime of the
+    /// signature replaced by it and the other ones elided.
+    fn inferred_local_tys(
+        &self,
+        sups_of: &[Vec<usize>],
+        known: &FxHashSet<(RegionVid, RegionVid)>,
+    ) -> ArenaMap<LocalId, Ty> {
+        let mut result = ArenaMap::default();
+        for (_, &local) in self.body.binding_locals.iter() {
+            let ty = fold_generic_args(
+                self.local_tys[local].clone(),
+                |arg, _| match arg {
+                    GenericArgData::Lifetime(lifetime) => match region_of(&lifetime) {
+                        Some(region) => {
+                            GenericArgData::Lifetime(match self.name_of(region, sups_of, known) {
+                                Some(UniversalRegion::Static) => static_lifetime(),
+                                Some(UniversalRegion::Named(id)) => {
+                                    LifetimeData::Placeholder(lt_to_placeholder_idx(self.db, id))
+                                        .intern(Interner)
+                                }
+                                Some(UniversalRegion::Anonymous(_)) | None => error_lifetime(),
+                            })
+                        }
+                        None => GenericArgData::Lifetime(lifetime),
+                    },
+                    arg => arg,
+                },
+                DebruijnIndex::INNERMOST,
+            );
+            result.insert(local, ty);
+        }
+        result
+    }
+
+    /// The universal region the data of `region` comes from. The data may come from several
+    /// universal regions, then it is named by the one the others are known to outlive.
+    fn name_of(
+        &self,
+        region: RegionVid,
+        sups_of: &[Vec<usize>],
+        known: &FxHashSet<(RegionVid, RegionVid)>,
+    ) -> Option<UniversalRegion> {
+        let mut origins = vec![];
+        let mut visited = FxHashSet::default();
+        let mut stack = vec![region];
+        while let Some(region) = stack.pop() {
+            if !visited.insert(region) {
+                continue;
+            }
+            if visited.len() > MAX_NAMING_STEPS {
+                return None;
+            }
+            if self.universals[region as usize].is_some() {
+                origins.push(region);
+                continue;
+            }
+            let sups = &sups_of[region as usize];
+            // Borrows of locals and regions we know nothing about can't be named.
+            if sups.is_empty() || self.local_borrow_regions.contains(&region) {
+                return None;
+            }
+            stack.extend(sups.iter().map(|&c| self.constraints[c].sup));
+        }
+        let &name = origins.iter().find(|&&m| origins.iter().all(|&o| known.contains(&(o, m))))?;
+        self.universals[name as usize]
+    }
+}
@@ -1,7 +1,10 @@
 //! Interpret intrinsics, lang items and `extern "C"` wellknown functions which their implementation
 //! is not available.
 //!
-use std::cmp::{self, Ordering};
+use std::{
+    cmp::{self, Ordering},
+    hash::{Hash, Hasher},
+};
 
 use chalk_ir::TyKind;
 use hir_def::{
@@ -11,6 +14,7 @@ use hir_def::{
 };
 use hir_expand::name::Name;
 use intern::{sym, Symbol};
+use rustc_hash::FxHasher;
 
 use crate::{
     error_lifetime,
@@ -762,7 +766,7 @@ impl Evaluator<'_> {
                 let size = self.size_of_sized(ty, locals, "size_of arg")?;
                 destination.write_from_bytes(self, &size.to_le_bytes()[0..destination.size])
             }
-            "min_align_of" | "pref_align_of" => {
+            "min_align_of" | "align_of" | "pref_align_of" => {
                 let Some(ty) =
                     generic_args.as_slice(Interner).first().and_then(|it| it.ty(Interner))
                 else {
@@ -845,6 +849,40 @@ impl Evaluator<'_> {
                     .slice(self.ptr_size()..2 * self.ptr_size())
                     .write_from_bytes(self, &len.to_le_bytes())
             }
+            "type_id" => {
+                let Some(ty) =
+                    generic_args.as_slice(Interner).first().and_then(|it| it.ty(Interner))
+                else {
+                    return Err(MirEvalError::InternalError(
+                        "type_id generic arg is not provided".into(),
+                    ));
+                };
+                // `Ty`'s `Hash` impl hashes the pointers of its interned parts, which differ between
+                // runs. The `Debug` output is structural and refers to items by their interned ids,
+                // so the same type gets the same id in every evaluation.
+                let rendered = format!("{ty:?}");
+                let hash = |salt: u8| {
+                    let mut hasher = FxHasher::default();
+                    (salt, &rendered).hash(&mut hasher);
+                    hasher.finish()
+                };
+                let id = u128::from(hash(0)) | (u128::from(hash(1)) << 64);
+                destination.write_from_bytes(self, &id.to_le_bytes()[0..destination.size])
+            }
+            "variant_count" => {
+                let Some(ty) =
+                    generic_args.as_slice(Interner).first().and_then(|it| it.ty(Interner))
+                else {
+                    return Err(MirEvalError::InternalError(
+                        "variant_count generic arg is not provided".into(),
+                    ));
+                };
+                let count = match ty.as_adt() {
+                    Some((AdtId::EnumId(e), _)) => self.db.enum_data(e).variants.len(),
+                    _ => 0,
+                };
+                destination.write_from_bytes(self, &count.to_le_bytes()[0..destination.size])
+            }
             "needs_drop" => {
                 let Some(ty) =
                     generic_args.as_slice(Interner).first().and_then(|it| it.ty(Interner))
@@ -1121,6 +1159,33 @@ impl Evaluator<'_> {
                 };
                 destination.write_from_interval(self, arg.interval)
             }
+            "black_box" => {
+                let [arg] = args else {
+                    return Err(MirEvalError::InternalError(
+                        "black_box arg is not provided".into(),
+                    ));
+                };
+                destination.write_from_interval(self, arg.interval)
+            }
+            "abort" => Err(MirEvalError::Panic("aborted".to_owned())),
+            "bswap" => {
+                let [arg] = args else {
+                    return Err(MirEvalError::InternalError("bswap arg is not provided".into()));
+                };
+                let mut result = arg.get(self)?.to_vec();
+                result.reverse();
+                destination.write_from_bytes(self, &result)
+            }
+            "bitreverse" => {
+                let [arg] = args else {
+                    return Err(MirEvalError::InternalError(
+                        "bitreverse arg is not provided".into(),
+                    ));
+                };
+                let result = u128::from_le_bytes(pad16(arg.get(self)?, false)).reverse_bits()
+                    >> (128 - arg.interval.size * 8);
+                destination.write_from_bytes(self, &result.to_le_bytes()[0..destination.size])
+            }
             "ctpop" => {
                 let [arg] = args else {
                     return Err(MirEvalError::InternalError("ctpop arg is not provided".into()));
@@ -1270,7 +1335,7 @@ impl Evaluator<'_> {
                 }
                 not_supported!("FnOnce was not available for executing const_eval_select");
             }
-            "read_via_copy" | "volatile_load" => {
+            "read_via_copy" | "volatile_load" | "unaligned_volatile_load" => {
                 let [arg] = args else {
                     return Err(MirEvalError::InternalError(
                         "read_via_copy args are not provided".into(),
@@ -1297,6 +1362,16 @@ impl Evaluator<'_> {
                 Interval { addr: dst, size }.write_from_interval(self, val.interval)?;
                 Ok(())
             }
+            "volatile_store" | "unaligned_volatile_store" | "nontemporal_store" => {
+                let [ptr, val] = args else {
+                    return Err(MirEvalError::InternalError(
+                        "volatile_store args are not provided".into(),
+                    ));
+                };
+                let dst = Address::from_bytes(ptr.get(self)?)?;
+                Interval { addr: dst, size: val.interval.size }
+                    .write_from_interval(self, val.interval)
+            }
             "write_bytes" => {
                 let [dst, val, count] = args else {
                     return Err(MirEvalError::InternalError(
@@ -1318,6 +1393,98 @@ impl Evaluator<'_> {
                 self.write_memory_using_ref(dst, size)?.fill(val);
                 Ok(())
             }
+            "const_allocate" => {
+                let [size, align] = args else {
+                    return Err(MirEvalError::InternalError(
+                        "const_allocate args are not provided".into(),
+                    ));
+                };
+                let size = from_bytes!(usize, size.get(self)?);
+                let align = from_bytes!(usize, align.get(self)?);
+                let result = self.heap_allocate(size, align)?;
+                destination.write_from_bytes(self, &result.to_bytes())
+            }
+            // Our heap lives as long as the evaluation, so there is nothing to free or intern.
+            "const_deallocate" => Ok(()),
+            "const_make_global" => {
+                let [ptr] = args else {
+                    return Err(MirEvalError::InternalError(
+                        "const_make_global args are not provided".into(),
+                    ));
+                };
+                destination.write_from_interval(self, ptr.interval)
+            }
+            "raw_eq" => {
+                let [lhs, rhs] = args else {
+                    return Err(MirEvalError::InternalError("raw_eq args are not provided".into()));
+                };
+                let Some(ty) =
+                    generic_args.as_slice(Interner).first().and_then(|it| it.ty(Interner))
+                else {
+                    return Err(MirEvalError::InternalError(
+                        "raw_eq generic arg is not provided".into(),
+                    ));
+                };
+                let size = self.size_of_sized(ty, locals, "raw_eq arg type")?;
+                let lhs = self.read_memory(Address::from_bytes(lhs.get(self)?)?, size)?.to_vec();
+                let rhs = self.read_memory(Address::from_bytes(rhs.get(self)?)?, size)?;
+                destination.write_from_bytes(self, &[u8::from(lhs == rhs)])
+            }
+            "compare_bytes" => {
+                let [lhs, rhs, len] = args else {
+                    return Err(MirEvalError::InternalError(
+                        "compare_bytes args are not provided".into(),
+                    ));
+                };
+                let len = from_bytes!(usize, len.get(self)?);
+                let lhs = self.read_memory(Address::from_bytes(lhs.get(self)?)?, len)?.to_vec();
+                let rhs = self.read_memory(Address::from_bytes(rhs.get(self)?)?, len)?;
+                let result: i32 = match lhs.as_slice().cmp(rhs) {
+                    Ordering::Less => -1,
+                    Ordering::Equal => 0,
+                    Ordering::Greater => 1,
+                };
+                destination.write_from_bytes(self, &result.to_le_bytes())
+            }
+            "ptr_mask" => {
+                let [ptr, mask] = args else {
+                    return Err(MirEvalError::InternalError(
+                        "ptr_mask args are not provided".into(),
+                    ));
+                };
+                let ptr = u128::from_le_bytes(pad16(ptr.get(self)?, false));
+                let mask = u128::from_le_bytes(pad16(mask.get(self)?, false));
+                destination.write_from_bytes(self, &(ptr & mask).to_le_bytes()[0..destination.size])
+            }
+            "fadd_fast" | "fsub_fast" | "fmul_fast" | "fdiv_fast" | "frem_fast"
+            | "fadd_algebraic" | "fsub_algebraic" | "fmul_algebraic" | "fdiv_algebraic"
+            | "frem_algebraic" => {
+                let [lhs, rhs] = args else {
+                    return Err(MirEvalError::InternalError(
+                        "float arithmetic args are not provided".into(),
+                    ));
+                };
+                let op = &name[..4];
+                macro_rules! float_op {
+                    ($ty:tt) => {{
+                        let lhs = from_bytes!($ty, lhs.get(self)?);
+                        let rhs = from_bytes!($ty, rhs.get(self)?);
+                        let result = match op {
+                            "fadd" => lhs + rhs,
+                            "fsub" => lhs - rhs,
+                            "fmul" => lhs * rhs,
+                            "fdiv" => lhs / rhs,
+                            _ => lhs % rhs,
+                        };
+                        destination.write_from_bytes(self, &result.to_le_bytes())
+                    }};
+                }
+                match destination.size {
+                    4 => float_op!(f32),
+                    8 => float_op!(f64),
+                    s => not_supported!("float arithmetic on a type with size {s}"),
+                }
+            }
             "ptr_metadata" => {
                 let [ptr] = args else {
                     return Err(MirEvalError::InternalError(
@@ -42,7 +42,7 @@ use crate::{
         Interner, Local, LocalId, MemoryMap, MirBody, MirSpan, Mutability, Operand, Place,
         PlaceElem, PointerCast, ProjectionElem, ProjectionStore, RawIdx, Rvalue, Statement,
         StatementKind, Substitution, SwitchTargets, Terminator, TerminatorKind, TupleFieldId, Ty,
-        UnOp, VariantId,
+        UnOp, UnreachableCode, VariantId,
     },
     static_lifetime,
     traits::FnTrait,
@@ -286,6 +286,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
             param_locals: vec![],
             owner,
             closures: vec![],
+            unreachable_code: vec![],
         };
         let resolver = owner.resolver(db.upcast());
 
@@ -358,7 +359,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
                     else {
                         return Ok(None);
                     };
-                    let bk = BorrowKind::from_chalk(*m);
+                    let bk = BorrowKind::from_autoref(*m);
                     self.push_assignment(current, place, Rvalue::Ref(bk, p), expr_id.into());
                     Ok(Some(current))
                 }
@@ -1733,7 +1734,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
         span: MirSpan,
     ) -> Result<Option<Idx<BasicBlock>>> {
         let scope = self.push_drop_scope();
-        for statement in statements.iter() {
+        for (idx, statement) in statements.iter().enumerate() {
             match statement {
                 hir_def::hir::Statement::Let { pat, initializer, else_branch, type_ref: _ } => {
                     if let Some(expr_id) = initializer {
@@ -1741,6 +1742,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
                         let Some((init_place, c)) =
                             self.lower_expr_as_place(current, *expr_id, true)?
                         else {
+                            self.record_unreachable_code(*expr_id, &statements[idx + 1..], tail);
                             scope.pop_assume_dropped(self);
                             return Ok(None);
                         };
@@ -1784,6 +1786,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
                 &hir_def::hir::Statement::Expr { expr, has_semi: _ } => {
                     let scope2 = self.push_drop_scope();
                     let Some((p, c)) = self.lower_expr_as_place(current, expr, true)? else {
+                        self.record_unreachable_code(expr, &statements[idx + 1..], tail);
                         scope2.pop_assume_dropped(self);
                         scope.pop_assume_dropped(self);
                         return Ok(None);
@@ -1805,6 +1808,46 @@ impl<'ctx> MirLowerCtx<'ctx> {
         Ok(Some(current))
     }
 
+    /// Records the statements and the tail of a block following a statement that diverges.
+    ///
+    /// Items aren't executed, so they aren't unreachable and split the recorded code into runs.
+    fn record_unreachable_code(
+        &mut self,
+        diverging: ExprId,
+        statements: &[hir_def::hir::Statement],
+        tail: Option<ExprId>,
+    ) {
+        // An empty `match` on an inhabited type is an error that is reported on its own.
+        if let Expr::Match { expr, arms } = &self.body[diverging] {
+            if arms.is_empty() && !self.is_uninhabited(*expr) {
+                return;
+            }
+        }
+        let statement_span = |statement: &hir_def::hir::Statement| match *statement {
+            hir_def::hir::Statement::Let { pat, .. } => Some(MirSpan::PatId(pat)),
+            hir_def::hir::Statement::Expr { expr, .. } => Some(MirSpan::ExprId(expr)),
+            hir_def::hir::Statement::Item(_) => None,
+        };
+        let spans =
+            statements.iter().map(statement_span).chain(tail.map(|it| Some(MirSpan::ExprId(it))));
+        let mut run: Option<(MirSpan, MirSpan)> = None;
+        for span in spans.chain(iter::once(None)) {
+            match (span, &mut run) {
+                (Some(span), Some((_, last))) => *last = span,
+                (Some(span), None) => run = Some((span, span)),
+                (None, _) => {
+                    if let Some((first, last)) = run.take() {
+                        self.result.unreachable_code.push(UnreachableCode {
+                            diverging,
+                            first,
+                            last,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
     fn lower_params_and_bindings(
         &mut self,
         params: impl Iterator<Item = (PatId, Ty)> + Clone,
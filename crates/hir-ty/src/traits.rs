@@ -3,7 +3,7 @@
 use core::fmt;
 use std::env::var;
 
-use chalk_ir::{fold::TypeFoldable, DebruijnIndex, GoalData};
+use chalk_ir::{cast::Cast, fold::TypeFoldable, DebruijnIndex, GoalData};
 use chalk_recursive::Cache;
 use chalk_solve::{logging_db::LoggingRustIrDatabase, rust_ir, Solver};
 
@@ -19,9 +19,10 @@ use stdx::{never, panic_context};
 use triomphe::Arc;
 
 use crate::{
-    db::HirDatabase, infer::unify::InferenceTable, utils::UnevaluatedConstEvaluatorFolder, AliasEq,
-    AliasTy, Canonical, DomainGoal, Goal, Guidance, InEnvironment, Interner, ProjectionTy,
-    ProjectionTyExt, Solution, TraitRefExt, Ty, TyKind, TypeFlags, WhereClause,
+    db::HirDatabase, infer::unify::InferenceTable, method_resolution::TyFingerprint,
+    utils::UnevaluatedConstEvaluatorFolder, AliasEq, AliasTy, Canonical, DomainGoal, Goal,
+    Guidance, InEnvironment, Interner, ProjectionTy, ProjectionTyExt, Solution, TraitRef,
+    TraitRefExt, Ty, TyBuilder, TyExt, TyKind, TypeFlags, WhereClause,
 };
 
 /// This controls how much 'time' we give the Chalk solver before giving up.
@@ -197,6 +198,70 @@ fn solve(
     }
 }
 
+/// Explains why `trait_ref`, which is known not to hold, doesn't hold: if an impl of the trait
+/// would apply except for one of its where clauses, that where clause is the cause, and it is
+/// explained in turn. Returns the causes innermost last.
+pub(crate) fn unsatisfied_bound_causes(
+    table: &mut InferenceTable<'_>,
+    trait_ref: &TraitRef,
+) -> Vec<TraitRef> {
+    // Impls whose where clauses require an impl of the same trait for ever larger types would
+    // otherwise make this loop forever.
+    const MAX_DEPTH: usize = 8;
+
+    let mut causes: Vec<TraitRef> = Vec::new();
+    while causes.len() < MAX_DEPTH {
+        let current = causes.last().unwrap_or(trait_ref);
+        match unsatisfied_impl_where_clause(table, current) {
+            Some(cause) => causes.push(cause),
+            None => break,
+        }
+    }
+    causes
+}
+
+fn unsatisfied_impl_where_clause(
+    table: &mut InferenceTable<'_>,
+    trait_ref: &TraitRef,
+) -> Option<TraitRef> {
+    let db = table.db;
+    let trait_ = trait_ref.hir_trait_id();
+    let self_ty_fp = TyFingerprint::for_trait_impl(&trait_ref.self_type_parameter(Interner))?;
+    let in_deps = db.trait_impls_in_deps(table.trait_env.krate);
+    let in_block = table.trait_env.block.and_then(|block| db.trait_impls_in_block(block));
+    let impls: Vec<_> = in_deps
+        .iter()
+        .chain(&in_block)
+        .flat_map(|impls| impls.for_trait_and_self_ty(trait_, self_ty_fp))
+        .filter(|&impl_| !db.impl_data(impl_).is_negative)
+        .collect();
+
+    impls.into_iter().find_map(|impl_| {
+        table.run_in_snapshot(|table| {
+            let impl_substs =
+                TyBuilder::subst_for_def(db, impl_, None).fill_with_inference_vars(table).build();
+            let impl_trait_ref = db.impl_trait(impl_)?.substitute(Interner, &impl_substs);
+            if !table.unify(&impl_trait_ref, trait_ref) {
+                return None;
+            }
+            db.generic_predicates(impl_.into()).iter().find_map(|predicate| {
+                let predicate = predicate.clone().substitute(Interner, &impl_substs);
+                let WhereClause::Implemented(where_clause) = predicate.skip_binders() else {
+                    return None;
+                };
+                let where_clause = table.resolve_completely(where_clause.clone());
+                let decidable = where_clause
+                    .substitution
+                    .iter(Interner)
+                    .all(|arg| arg.ty(Interner).is_none_or(|ty| !ty.contains_unknown()));
+                let unsatisfied = decidable
+                    && table.try_obligation(where_clause.clone().cast(Interner)).is_none();
+                unsatisfied.then_some(where_clause)
+            })
+        })
+    })
+}
+
 struct LoggingRustIrDatabaseLoggingOnDrop<'a>(LoggingRustIrDatabase<Interner, ChalkContext<'a>>);
 
 impl Drop for LoggingRustIrDatabaseLoggingOnDrop<'_> {
@@ -125,7 +125,7 @@ impl Variance {
     ///
     /// Source: Figure 1 of "Taming the Wildcards:
     /// Combining Definition- and Use-Site Variance" published in PLDI'11.
-    fn xform(self, v: Variance) -> Variance {
+    pub(crate) fn xform(self, v: Variance) -> Variance {
         match (self, v) {
             // Figure 1, column 1.
             (Variance::Covariant, Variance::Covariant) => Variance::Covariant,
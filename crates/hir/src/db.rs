@@ -18,9 +18,9 @@ pub use hir_def::db::{
     InternMacro2Query, InternMacroRulesQuery, InternProcMacroQuery, InternStaticQuery,
     InternStructQuery, InternTraitAliasQuery, InternTraitQuery, InternTypeAliasQuery,
     InternUnionQuery, InternUseQuery, LangItemQuery, Macro2DataQuery, MacroDefQuery,
-    MacroRulesDataQuery, NotableTraitsInDepsQuery, ProcMacroDataQuery, StaticDataQuery,
-    StructDataWithDiagnosticsQuery, TraitAliasDataQuery, TraitDataWithDiagnosticsQuery,
-    TypeAliasDataQuery, UnionDataWithDiagnosticsQuery,
+    MacroRulesDataQuery, NotableTraitsInDepsQuery, PersistKeysQuery, PersistedCachesQuery,
+    ProcMacroDataQuery, StaticDataQuery, StructDataWithDiagnosticsQuery, TraitAliasDataQuery,
+    TraitDataWithDiagnosticsQuery, TypeAliasDataQuery, UnionDataWithDiagnosticsQuery,
 };
 pub use hir_expand::db::{
     AstIdMapQuery, DeclMacroExpanderQuery, ExpandDatabase, ExpandDatabaseStorage,
@@ -9,8 +9,9 @@ use hir_def::{
     expr_store::ExprOrPatPtr,
     hir::ExprOrPatId,
     path::{hir_segment_to_ast_segment, ModPath},
+    resolver::HasResolver,
     type_ref::TypesSourceMap,
-    AssocItemId, DefWithBodyId, SyntheticSyntax,
+    AssocItemId, CallableDefId, DefWithBodyId, SyntheticSyntax,
 };
 use hir_expand::{name::Name, HirFileId, InFile};
 use hir_ty::{
@@ -25,7 +26,7 @@ use syntax::{
 };
 use triomphe::Arc;
 
-use crate::{AssocItem, Field, Local, Trait, Type};
+use crate::{Adt, AssocItem, Field, LifetimeParam, Local, ModuleDef, Trait, TraitRef, Type};
 
 pub use hir_def::VariantId;
 pub use hir_ty::{
@@ -68,12 +69,15 @@ diagnostics![
     AwaitOutsideOfAsync,
     BreakOutsideOfLoop,
     CastToUnsized,
+    ConflictingBorrow,
+    DoesNotLiveLongEnough,
     ExpectedFunction,
     InactiveCode,
     IncoherentImpl,
     IncorrectCase,
     InvalidCast,
     InvalidDeriveTarget,
+    LifetimeMayNotLiveLongEnough,
     MacroDefError,
     MacroError,
     MacroExpansionParseError,
@@ -92,6 +96,7 @@ diagnostics![
     RemoveTrailingReturn,
     RemoveUnnecessaryElse,
     ReplaceFilterMapNextWithFindMap,
+    ReturnsLocalReference,
     TraitImplIncorrectSafety,
     TraitImplMissingAssocItems,
     TraitImplOrphan,
@@ -100,6 +105,7 @@ diagnostics![
     TypeMismatch,
     UndeclaredLabel,
     UnimplementedBuiltinMacro,
+    UnreachableCode,
     UnreachableLabel,
     UnresolvedAssocItem,
     UnresolvedExternCrate,
@@ -109,8 +115,11 @@ diagnostics![
     UnresolvedMethodCall,
     UnresolvedModule,
     UnresolvedIdent,
+    UnsatisfiedTraitBound,
     UnusedMut,
+    UnusedMustUse,
     UnusedVariable,
+    UseAfterMove,
     GenericArgsProhibited,
 ];
 
@@ -195,7 +204,8 @@ pub struct MacroExpansionParseError {
 pub struct MacroDefError {
     pub node: InFile<AstPtr<ast::Macro>>,
     pub message: String,
-    pub name: Option<TextRange>,
+    pub precise_location: Option<TextRange>,
+    pub error: bool,
 }
 
 #[derive(Debug)]
@@ -338,6 +348,15 @@ pub struct UnusedMut {
     pub local: Local,
 }
 
+/// Code after a diverging expression, e.g. a `return` or a call of a function returning `!`.
+#[derive(Debug)]
+pub struct UnreachableCode {
+    /// The first unreachable statement or tail expression.
+    pub first: InFile<SyntaxNodePtr>,
+    /// The last unreachable statement or tail expression, which is `first` if there's only one.
+    pub last: InFile<SyntaxNodePtr>,
+}
+
 #[derive(Debug)]
 pub struct UnusedVariable {
     pub local: Local,
@@ -349,6 +368,51 @@ pub struct MovedOutOfRef {
     pub span: InFile<SyntaxNodePtr>,
 }
 
+#[derive(Debug)]
+pub struct UseAfterMove {
+    pub local: Local,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+#[derive(Debug)]
+pub struct ConflictingBorrow {
+    pub local: Local,
+    pub span: InFile<SyntaxNodePtr>,
+    pub is_mut: bool,
+    pub previous_is_mut: bool,
+}
+
+#[derive(Debug)]
+pub struct DoesNotLiveLongEnough {
+    pub local: Local,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+#[derive(Debug)]
+pub struct ReturnsLocalReference {
+    pub local: Local,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+#[derive(Debug)]
+pub struct LifetimeMayNotLiveLongEnough {
+    pub span: InFile<SyntaxNodePtr>,
+    pub expected: SignatureLifetime,
+    /// The lifetime that has to outlive `expected`.
+    pub actual: SignatureLifetime,
+    /// Whether the data is returned with the `expected` lifetime.
+    pub returned: bool,
+}
+
+/// A lifetime of the signature of a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureLifetime {
+    Static,
+    Param(LifetimeParam),
+    /// An elided lifetime of the parameters, numbered from 1 in order of appearance.
+    Elided(u32),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct IncoherentImpl {
     pub file_id: HirFileId,
@@ -394,12 +458,60 @@ pub struct RemoveUnnecessaryElse {
     pub if_expr: InFile<AstPtr<ast::IfExpr>>,
 }
 
+#[derive(Debug)]
+pub struct UnusedMustUse {
+    pub expr: InFile<AstPtr<ast::Expr>>,
+    pub path: MustUsePath,
+    pub reason: Option<String>,
+}
+
+/// How the unused value of an expression leads to a `#[must_use]` item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MustUsePath {
+    /// A `#[must_use]` function that is called or a `#[must_use]` type of the value.
+    Def(ModuleDef),
+    /// An opaque type implementing a `#[must_use]` trait.
+    Opaque(Trait),
+    /// A trait object of a `#[must_use]` trait.
+    TraitObject(Trait),
+    Boxed(Box<MustUsePath>),
+    Pinned(Box<MustUsePath>),
+    TupleElement(usize, Box<MustUsePath>),
+    Array(Box<MustUsePath>),
+}
+
+impl From<hir_ty::diagnostics::MustUsePath> for MustUsePath {
+    fn from(path: hir_ty::diagnostics::MustUsePath) -> Self {
+        use hir_ty::diagnostics::MustUsePath as P;
+        match path {
+            P::Def(it) => MustUsePath::Def(it.into()),
+            P::Opaque(it) => MustUsePath::Opaque(it.into()),
+            P::TraitObject(it) => MustUsePath::TraitObject(it.into()),
+            P::Boxed(it) => MustUsePath::Boxed(Box::new((*it).into())),
+            P::Pinned(it) => MustUsePath::Pinned(Box::new((*it).into())),
+            P::TupleElement(idx, it) => MustUsePath::TupleElement(idx, Box::new((*it).into())),
+            P::Array(it) => MustUsePath::Array(Box::new((*it).into())),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CastToUnsized {
     pub expr: InFile<ExprOrPatPtr>,
     pub cast_ty: Type,
 }
 
+#[derive(Debug)]
+pub struct UnsatisfiedTraitBound {
+    pub expr: InFile<ExprOrPatPtr>,
+    pub bound: TraitRef,
+    /// The unsatisfied where clauses of the impls that would otherwise implement `bound`,
+    /// innermost last.
+    pub causes: Vec<TraitRef>,
+    /// The function, struct or enum variant whose bound is not satisfied.
+    pub required_by: ModuleDef,
+}
+
 #[derive(Debug)]
 pub struct InvalidCast {
     pub expr: InFile<ExprOrPatPtr>,
@@ -545,6 +657,20 @@ impl AnyDiagnostic {
                     }
                 }
             }
+            BodyValidationDiagnostic::UnusedMustUse { expr, path, reason } => {
+                if let Ok(source_ptr) = source_map.expr_syntax(expr) {
+                    if let Some(ptr) = source_ptr.value.cast::<ast::Expr>() {
+                        return Some(
+                            UnusedMustUse {
+                                expr: InFile::new(source_ptr.file_id, ptr),
+                                path: path.into(),
+                                reason,
+                            }
+                            .into(),
+                        );
+                    }
+                }
+            }
         }
         None
     }
@@ -674,6 +800,24 @@ impl AnyDiagnostic {
                 let cast_ty = Type::new(db, def, cast_ty.clone());
                 InvalidCast { expr, error: *error, expr_ty, cast_ty }.into()
             }
+            InferenceDiagnostic::UnsatisfiedTraitBound { expr, bound, causes, required_by } => {
+                let expr = expr_syntax(*expr)?;
+                let resolver = def.resolver(db.upcast());
+                let trait_ref =
+                    |it: &hir_ty::TraitRef| TraitRef::new_with_resolver(db, &resolver, it.clone());
+                let required_by = match *required_by {
+                    CallableDefId::FunctionId(it) => ModuleDef::Function(it.into()),
+                    CallableDefId::StructId(it) => ModuleDef::Adt(Adt::Struct(it.into())),
+                    CallableDefId::EnumVariantId(it) => ModuleDef::Variant(it.into()),
+                };
+                UnsatisfiedTraitBound {
+                    expr,
+                    bound: trait_ref(bound),
+                    causes: causes.iter().map(trait_ref).collect(),
+                    required_by,
+                }
+                .into()
+            }
             InferenceDiagnostic::TyDiagnostic { source, diag } => {
                 let source_map = match source {
                     InferenceTyDiagnosticSource::Body => &source_map.types,
@@ -122,6 +122,7 @@ pub use {
         nameres::{DefMap, ModuleSource},
         path::{ModPath, PathKind},
         per_ns::Namespace,
+        persist::{content_hash, PersistKeys, PersistedCaches},
         type_ref::{Mutability, TypeRef},
         visibility::Visibility,
         ImportPathConfig,
@@ -227,6 +228,17 @@ impl Crate {
         Module { id: CrateRootModuleId::from(self.id).into() }
     }
 
+    /// Returns the crate this crate is a cfg variant of, if it is one.
+    ///
+    /// Cfg variants are copies of a crate analyzed under an alternate cfg configuration.
+    pub fn cfg_variant_of(self, db: &dyn HirDatabase) -> Option<Crate> {
+        db.crate_graph().cfg_variant_of(self.id).map(|id| Crate { id })
+    }
+
+    pub fn cfg_variants(self, db: &dyn HirDatabase) -> Vec<Crate> {
+        db.crate_graph().cfg_variants(self.id).map(|id| Crate { id }).collect()
+    }
+
     pub fn modules(self, db: &dyn HirDatabase) -> Vec<Module> {
         let def_map = db.crate_def_map(self.id);
         def_map.modules().map(|(id, _)| def_map.module_id(id).into()).collect()
@@ -944,6 +956,31 @@ fn emit_macro_def_diagnostics(db: &dyn HirDatabase, acc: &mut Vec<AnyDiagnostic>
                 &DefDiagnosticKind::MacroDefError { ast, message: e.to_string() },
                 edition,
             );
+            return;
+        }
+        let Some(ast) = id.ast_id().left() else { return };
+        let lints = expander.mac.lints();
+        if lints.is_empty() {
+            return;
+        }
+        let node = InFile::new(ast.file_id, AstPtr::new(&ast.to_node(db.upcast())));
+        for hir_expand::DefinitionLint { span, kind } in lints {
+            let precise_location = (span.anchor.file_id == ast.file_id).then(|| {
+                span.range
+                    + db.ast_id_map(span.anchor.file_id.into())
+                        .get_erased(span.anchor.ast_id)
+                        .text_range()
+                        .start()
+            });
+            acc.push(
+                MacroDefError {
+                    node,
+                    message: kind.to_string(),
+                    precise_location,
+                    error: kind.is_error(),
+                }
+                .into(),
+            );
         }
     }
 }
@@ -1147,8 +1184,9 @@ fn emit_def_diagnostic_(
             acc.push(
                 MacroDefError {
                     node: InFile::new(ast.file_id, AstPtr::new(&node)),
-                    name: node.name().map(|it| it.syntax().text_range()),
+                    precise_location: node.name().map(|it| it.syntax().text_range()),
                     message: message.clone(),
+                    error: true,
                 }
                 .into(),
             );
@@ -2014,39 +2052,141 @@ impl DefWithBody {
         }
 
         if let Ok(borrowck_results) = db.borrowck(self.into()) {
+            let span_syntax = |span: mir::MirSpan| -> Option<InFile<SyntaxNodePtr>> {
+                match span {
+                    mir::MirSpan::ExprId(e) => {
+                        source_map.expr_syntax(e).ok().map(|s| s.map(|it| it.into()))
+                    }
+                    mir::MirSpan::PatId(p) => {
+                        source_map.pat_syntax(p).ok().map(|s| s.map(|it| it.into()))
+                    }
+                    mir::MirSpan::SelfParam => {
+                        source_map.self_param_syntax().map(|s| s.map(|it| it.into()))
+                    }
+                    mir::MirSpan::BindingId(b) => source_map
+                        .patterns_for_binding(b)
+                        .iter()
+                        .find_map(|p| source_map.pat_syntax(*p).ok())
+                        .map(|s| s.map(|it| it.into())),
+                    mir::MirSpan::Unknown => None,
+                }
+            };
+            // Unreachable code isn't lowered, so the bindings in it look unused.
+            let unreachable_ranges = borrowck_results
+                .iter()
+                .flat_map(|it| &it.mir_body.unreachable_code)
+                .filter_map(|it| {
+                    let (first, last) = (span_syntax(it.first)?, span_syntax(it.last)?);
+                    Some(first.map(|it| it.text_range().cover(last.value.text_range())))
+                })
+                .collect::<Vec<_>>();
+            let is_unreachable = |binding_id| {
+                source_map.patterns_for_binding(binding_id).iter().any(|&pat| {
+                    source_map.pat_syntax(pat).is_ok_and(|pat| {
+                        unreachable_ranges.iter().any(|range| {
+                            range.file_id == pat.file_id
+                                && range.value.contains_range(pat.value.text_range())
+                        })
+                    })
+                })
+            };
             for borrowck_result in borrowck_results.iter() {
                 let mir_body = &borrowck_result.mir_body;
                 for moof in &borrowck_result.moved_out_of_ref {
-                    let span: InFile<SyntaxNodePtr> = match moof.span {
-                        mir::MirSpan::ExprId(e) => match source_map.expr_syntax(e) {
-                            Ok(s) => s.map(|it| it.into()),
-                            Err(_) => continue,
-                        },
-                        mir::MirSpan::PatId(p) => match source_map.pat_syntax(p) {
-                            Ok(s) => s.map(|it| it.into()),
-                            Err(_) => continue,
-                        },
-                        mir::MirSpan::SelfParam => match source_map.self_param_syntax() {
-                            Some(s) => s.map(|it| it.into()),
-                            None => continue,
-                        },
-                        mir::MirSpan::BindingId(b) => {
-                            match source_map
-                                .patterns_for_binding(b)
-                                .iter()
-                                .find_map(|p| source_map.pat_syntax(*p).ok())
-                            {
-                                Some(s) => s.map(|it| it.into()),
-                                None => continue,
-                            }
-                        }
-                        mir::MirSpan::Unknown => continue,
-                    };
+                    let Some(span) = span_syntax(moof.span) else { continue };
                     acc.push(
                         MovedOutOfRef { ty: Type::new_for_crate(krate, moof.ty.clone()), span }
                             .into(),
                     )
                 }
+                let local_to_binding = mir_body.local_to_binding_map();
+                for uam in &borrowck_result.use_after_move {
+                    let (Some(&binding_id), Some(span)) =
+                        (local_to_binding.get(uam.local), span_syntax(uam.span))
+                    else {
+                        continue;
+                    };
+                    acc.push(
+                        UseAfterMove { local: Local { parent: self.into(), binding_id }, span }
+                            .into(),
+                    )
+                }
+                for conflict in &borrowck_result.conflicting_borrows {
+                    let (Some(&binding_id), Some(span)) =
+                        (local_to_binding.get(conflict.local), span_syntax(conflict.span))
+                    else {
+                        continue;
+                    };
+                    let is_mut = |kind| matches!(kind, mir::BorrowKind::Mut { .. });
+                    acc.push(
+                        ConflictingBorrow {
+                            local: Local { parent: self.into(), binding_id },
+                            span,
+                            is_mut: is_mut(conflict.kind),
+                            previous_is_mut: is_mut(conflict.previous_kind),
+                        }
+                        .into(),
+                    )
+                }
+                for dangling in &borrowck_result.dangling_borrows {
+                    let (Some(&binding_id), Some(span)) =
+                        (local_to_binding.get(dangling.local), span_syntax(dangling.span))
+                    else {
+                        continue;
+                    };
+                    acc.push(
+                        DoesNotLiveLongEnough {
+                            local: Local { parent: self.into(), binding_id },
+                            span,
+                        }
+                        .into(),
+                    )
+                }
+                for returned in &borrowck_result.returned_local_borrows {
+                    let (Some(&binding_id), Some(span)) =
+                        (local_to_binding.get(returned.local), span_syntax(returned.span))
+                    else {
+                        continue;
+                    };
+                    acc.push(
+                        ReturnsLocalReference {
+                            local: Local { parent: self.into(), binding_id },
+                            span,
+                        }
+                        .into(),
+                    )
+                }
+                for mismatch in &borrowck_result.lifetime_mismatches {
+                    let Some(span) = span_syntax(mismatch.span) else { continue };
+                    let lifetime = |it| match it {
+                        mir::UniversalRegion::Static => SignatureLifetime::Static,
+                        mir::UniversalRegion::Named(id) => {
+                            SignatureLifetime::Param(LifetimeParam { id })
+                        }
+                        mir::UniversalRegion::Anonymous(n) => SignatureLifetime::Elided(n),
+                    };
+                    acc.push(
+                        LifetimeMayNotLiveLongEnough {
+                            span,
+                            expected: lifetime(mismatch.expected),
+                            actual: lifetime(mismatch.actual),
+                            returned: mismatch.returned,
+                        }
+                        .into(),
+                    )
+                }
+                for unreachable in &mir_body.unreachable_code {
+                    let (Some(first), Some(last)) =
+                        (span_syntax(unreachable.first), span_syntax(unreachable.last))
+                    else {
+                        continue;
+                    };
+                    // Code in macro expansions may be unreachable only for some inputs.
+                    if first.file_id.is_macro() || last.file_id.is_macro() {
+                        continue;
+                    }
+                    acc.push(UnreachableCode { first, last }.into())
+                }
                 let mol = &borrowck_result.mutability_of_locals;
                 for (binding_id, binding_data) in body.bindings.iter() {
                     if binding_data.problems.is_some() {
@@ -2064,6 +2204,9 @@ impl DefWithBody {
                         // Skip synthetic bindings
                         continue;
                     }
+                    if is_unreachable(binding_id) {
+                        continue;
+                    }
                     let mut need_mut = &mol[local];
                     if body[binding_id].name == sym::self_.clone()
                         && need_mut == &mir::MutabilityReason::Unused
@@ -2083,33 +2226,8 @@ impl DefWithBody {
                         (mir::MutabilityReason::Mut { .. }, true)
                         | (mir::MutabilityReason::Not, false) => (),
                         (mir::MutabilityReason::Mut { spans }, false) => {
-                            for span in spans {
-                                let span: InFile<SyntaxNodePtr> = match span {
-                                    mir::MirSpan::ExprId(e) => match source_map.expr_syntax(*e) {
-                                        Ok(s) => s.map(|it| it.into()),
-                                        Err(_) => continue,
-                                    },
-                                    mir::MirSpan::PatId(p) => match source_map.pat_syntax(*p) {
-                                        Ok(s) => s.map(|it| it.into()),
-                                        Err(_) => continue,
-                                    },
-                                    mir::MirSpan::BindingId(b) => {
-                                        match source_map
-                                            .patterns_for_binding(*b)
-                                            .iter()
-                                            .find_map(|p| source_map.pat_syntax(*p).ok())
-                                        {
-                                            Some(s) => s.map(|it| it.into()),
-                                            None => continue,
-                                        }
-                                    }
-                                    mir::MirSpan::SelfParam => match source_map.self_param_syntax()
-                                    {
-                                        Some(s) => s.map(|it| it.into()),
-                                        None => continue,
-                                    },
-                                    mir::MirSpan::Unknown => continue,
-                                };
+                            for &span in spans {
+                                let Some(span) = span_syntax(span) else { continue };
                                 acc.push(NeedMut { local, span }.into());
                             }
                         }
@@ -2462,6 +2580,82 @@ impl Function {
         }
         Ok(text)
     }
+
+    /// Runs this `#[test]` function with the MIR interpreter.
+    pub fn eval_test(
+        self,
+        db: &dyn HirDatabase,
+        span_formatter: impl Fn(FileId, TextRange) -> String,
+    ) -> TestEvalOutcome {
+        if self.is_ignore(db) {
+            return TestEvalOutcome::Ignored;
+        }
+        if !self.ret_type(db).is_unit() {
+            return TestEvalOutcome::Unsupported {
+                reason: "tests returning a value are not supported".to_owned(),
+            };
+        }
+        let krate = HasModule::krate(&self.id, db.upcast());
+        let edition = db.crate_graph()[krate].edition;
+        let unsupported = |e: ConstEvalError| {
+            let mut reason = String::new();
+            _ = e.pretty_print(&mut reason, db, &span_formatter, edition);
+            TestEvalOutcome::Unsupported { reason }
+        };
+        let body = match db.monomorphized_mir_body(
+            self.id.into(),
+            Substitution::empty(Interner),
+            db.trait_environment(self.id.into()),
+        ) {
+            Ok(body) => body,
+            Err(e) => return unsupported(e.into()),
+        };
+        let (result, output) = match interpret_mir(db, body, false, None) {
+            Ok(it) => it,
+            Err(e) => return unsupported(e.into()),
+        };
+        let attrs = db.attrs(self.id.into());
+        let should_panic = attrs.by_key(&sym::should_panic);
+        let panic = match result {
+            Ok(_) => None,
+            Err(e) => match e.is_panic() {
+                Some(message) => Some(message.to_owned()),
+                None => return unsupported(e.into()),
+            },
+        };
+        let mut message = match (panic, should_panic.exists()) {
+            (None, false) => return TestEvalOutcome::Passed,
+            (None, true) => "test did not panic as expected".to_owned(),
+            (Some(message), true) => match should_panic.find_string_value_in_tt(&sym::expected) {
+                Some(expected) if !message.contains(expected) => format!(
+                    "panic did not contain expected string\n      panic message: {message:?}\n expected substring: {expected:?}"
+                ),
+                _ => return TestEvalOutcome::Passed,
+            },
+            (Some(message), false) => format!("panicked:\n{message}"),
+        };
+        let stdout = output.stdout();
+        if !stdout.is_empty() {
+            message += "\n--------- stdout ---------\n";
+            message += &stdout;
+        }
+        TestEvalOutcome::Failed { message }
+    }
+}
+
+/// The outcome of running a test with [`Function::eval_test`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestEvalOutcome {
+    Passed,
+    Failed {
+        message: String,
+    },
+    Ignored,
+    /// The test needs something the interpreter can't do, like calling foreign functions, and has
+    /// to be compiled to run.
+    Unsupported {
+        reason: String,
+    },
 }
 
 // Note: logically, this belongs to `hir_ty`, but we are not using it there yet.
@@ -3102,6 +3296,38 @@ impl Macro {
         }
     }
 
+    /// Returns the ranges of the occurrences of the metavariable whose name is at `range`, within
+    /// the rule of this declarative macro containing it. The ranges are in the file of the macro
+    /// definition.
+    pub fn metavar_ranges(self, db: &dyn HirDatabase, range: TextRange) -> Vec<TextRange> {
+        let id = db.macro_def(self.id);
+        let hir_expand::db::TokenExpander::DeclarativeMacro(expander) = db.macro_expander(id)
+        else {
+            return Vec::new();
+        };
+        let Some(ast) = id.ast_id().left() else { return Vec::new() };
+        let ast_id_map = db.ast_id_map(ast.file_id);
+        let to_range = |span: span::Span| {
+            (span.anchor.file_id == ast.file_id).then(|| {
+                span.range + ast_id_map.get_erased(span.anchor.ast_id).text_range().start()
+            })
+        };
+        for metavars in expander.mac.metavar_spans() {
+            let metavars = metavars
+                .into_iter()
+                .filter_map(|(name, span)| Some((name, to_range(span)?)))
+                .collect::<Vec<_>>();
+            let Some((name, _)) = metavars.iter().find(|(_, it)| *it == range) else { continue };
+            return metavars
+                .iter()
+                .filter(|(it, _)| it == name)
+                .map(|&(_, range)| range)
+                .sorted_by_key(|range| range.start())
+                .collect();
+        }
+        Vec::new()
+    }
+
     pub fn is_builtin_derive(&self, db: &dyn HirDatabase) -> bool {
         match self.id {
             MacroId::Macro2Id(it) => {
@@ -3126,6 +3352,18 @@ impl Macro {
         }
     }
 
+    pub fn is_include_like(&self, db: &dyn HirDatabase) -> bool {
+        match self.id {
+            MacroId::Macro2Id(it) => {
+                matches!(it.lookup(db.upcast()).expander, MacroExpander::BuiltInEager(eager) if eager.is_include_like())
+            }
+            MacroId::MacroRulesId(it) => {
+                matches!(it.lookup(db.upcast()).expander, MacroExpander::BuiltInEager(eager) if eager.is_include_like())
+            }
+            MacroId::ProcMacroId(_) => false,
+        }
+    }
+
     pub fn is_asm_or_global_asm(&self, db: &dyn HirDatabase) -> bool {
         match self.id {
             MacroId::Macro2Id(it) => {
@@ -3819,6 +4057,21 @@ impl Local {
         Type::new(db, def, ty)
     }
 
+    /// The type of the local, with the lifetimes of the signature its references are inferred
+    /// to have by the borrow checker. Other lifetimes are left elided.
+    pub fn ty_with_inferred_lifetimes(self, db: &dyn HirDatabase) -> Type {
+        let inferred = db.borrowck(self.parent).ok().and_then(|results| {
+            results.iter().find_map(|it| {
+                let local = it.mir_body.binding_locals.get(self.binding_id)?;
+                it.inferred_local_tys.get(*local).cloned()
+            })
+        });
+        match inferred {
+            Some(ty) => Type::new(db, self.parent, ty),
+            None => self.ty(db),
+        }
+    }
+
     /// All definitions for this local. Example: `let (a$0, _) | (_, a$0) = it;`
     pub fn sources(self, db: &dyn HirDatabase) -> Vec<LocalSource> {
         let (body, source_map) = db.body_with_source_map(self.parent);
@@ -10,6 +10,7 @@ use std::{
     ops::{self, ControlFlow, Not},
 };
 
+use base_db::CrateId;
 use either::Either;
 use hir_def::{
     hir::{Expr, ExprOrPatId},
@@ -138,6 +139,8 @@ pub struct SemanticsImpl<'db> {
     s2d_cache: RefCell<SourceToDefCache>,
     /// MacroCall to its expansion's MacroFileId cache
     macro_call_cache: RefCell<FxHashMap<InFile<ast::MacroCall>, MacroFileId>>,
+    /// The crate whose modules take precedence for files belonging to several crates.
+    preferred_crate: Option<CrateId>,
 }
 
 impl<DB> fmt::Debug for Semantics<'_, DB> {
@@ -160,6 +163,14 @@ impl<DB: HirDatabase> Semantics<'_, DB> {
         Semantics { db, imp: impl_ }
     }
 
+    /// Like [`Semantics::new`], but files that belong to several crates are analyzed as part of
+    /// `krate` where possible. This is used to look at a file through one of the cfg variants of
+    /// its crate.
+    pub fn new_for_crate(db: &DB, krate: Crate) -> Semantics<'_, DB> {
+        let impl_ = SemanticsImpl { preferred_crate: Some(krate.id), ..SemanticsImpl::new(db) };
+        Semantics { db, imp: impl_ }
+    }
+
     pub fn hir_file_for(&self, syntax_node: &SyntaxNode) -> HirFileId {
         self.imp.find_file(syntax_node).file_id
     }
@@ -302,7 +313,12 @@ impl<DB: HirDatabase> Semantics<'_, DB> {
 
 impl<'db> SemanticsImpl<'db> {
     fn new(db: &'db dyn HirDatabase) -> Self {
-        SemanticsImpl { db, s2d_cache: Default::default(), macro_call_cache: Default::default() }
+        SemanticsImpl {
+            db,
+            s2d_cache: Default::default(),
+            macro_call_cache: Default::default(),
+            preferred_crate: None,
+        }
     }
 
     pub fn parse(&self, file_id: EditionedFileId) -> ast::SourceFile {
@@ -1554,6 +1570,22 @@ impl<'db> SemanticsImpl<'db> {
             .matched_arm
     }
 
+    /// For a call of a declarative macro, returns why each of its rules does or does not match
+    /// the call's input, `None` for the rules that match.
+    pub fn macro_call_rule_errors(
+        &self,
+        macro_call: &ast::MacroCall,
+    ) -> Option<Vec<Option<String>>> {
+        let sa = self.analyze(macro_call.syntax())?;
+        let file_id = sa.expand(self.db, self.wrap_node_infile(macro_call.clone()).as_ref())?;
+        let loc = self.db.lookup_intern_macro_call(file_id.macro_call_id);
+        let hir_expand::MacroDefKind::Declarative(id) = loc.def.kind else { return None };
+        let (arg, _, _) = self.db.macro_arg_considering_derives(file_id.macro_call_id, &loc.kind);
+        let errors =
+            self.db.decl_macro_expander(loc.def.krate, id).match_rules(&arg, loc.def.edition);
+        Some(errors.into_iter().map(|err| err.map(|err| err.to_string())).collect())
+    }
+
     pub fn is_unsafe_macro_call(&self, macro_call: &ast::MacroCall) -> bool {
         let Some(mac) = self.resolve_macro_call(macro_call) else { return false };
         if mac.is_asm_or_global_asm(self.db) {
@@ -1635,7 +1667,11 @@ impl<'db> SemanticsImpl<'db> {
     }
 
     fn with_ctx<F: FnOnce(&mut SourceToDefCtx<'_, '_>) -> T, T>(&self, f: F) -> T {
-        let mut ctx = SourceToDefCtx { db: self.db, cache: &mut self.s2d_cache.borrow_mut() };
+        let mut ctx = SourceToDefCtx {
+            db: self.db,
+            cache: &mut self.s2d_cache.borrow_mut(),
+            preferred_crate: self.preferred_crate,
+        };
         f(&mut ctx)
     }
 
@@ -2106,6 +2142,31 @@ impl SemanticsScope<'_> {
         )
     }
 
+    /// Resolve a type as-if it was written at the given scope. Like `speculative_resolve`, this
+    /// doesn't take hygiene into account.
+    pub fn speculative_resolve_type(&self, ast_ty: &ast::Type) -> Type {
+        let root = ast_ty.syntax().ancestors().last().unwrap();
+        let ast_id_map = Arc::new(AstIdMap::from_source(&root));
+        let (mut types_map, mut types_source_map) =
+            (TypesMap::default(), TypesSourceMap::default());
+        let mut ctx = LowerCtx::for_synthetic_ast(
+            self.db.upcast(),
+            ast_id_map,
+            &mut types_map,
+            &mut types_source_map,
+        );
+        let type_ref = crate::TypeRef::from_ast(&mut ctx, ast_ty.clone());
+        let ty = hir_ty::TyLoweringContext::new_maybe_unowned(
+            self.db,
+            &self.resolver,
+            &types_map,
+            None,
+            self.resolver.type_owner(),
+        )
+        .lower_ty(type_ref);
+        Type::new_with_resolver(self.db, &self.resolver, ty)
+    }
+
     pub fn resolve_mod_path(&self, path: &ModPath) -> impl Iterator<Item = ItemInNs> {
         let items = self.resolver.resolve_module_path_in_items(self.db.upcast(), path);
         items.iter_items().map(|(item, _)| item.into())
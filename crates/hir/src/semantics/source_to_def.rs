@@ -85,6 +85,7 @@
 //! active crate for a given position, and then provide an API to resolve all
 //! syntax nodes against this specific crate.
 
+use base_db::CrateId;
 use either::Either;
 use hir_def::{
     dyn_map::{
@@ -169,6 +170,8 @@ impl SourceToDefCache {
 pub(super) struct SourceToDefCtx<'db, 'cache> {
     pub(super) db: &'db dyn HirDatabase,
     pub(super) cache: &'cache mut SourceToDefCache,
+    /// The crate whose modules are listed first by [`SourceToDefCtx::file_to_def`].
+    pub(super) preferred_crate: Option<CrateId>,
 }
 
 impl SourceToDefCtx<'_, '_> {
@@ -211,6 +214,9 @@ impl SourceToDefCtx<'_, '_> {
             if mods.is_empty() {
                 // FIXME: detached file
             }
+            if let Some(preferred_crate) = self.preferred_crate {
+                mods.sort_by_key(|module: &ModuleId| module.krate() != preferred_crate);
+            }
             mods
         })
     }
@@ -5,6 +5,7 @@ use hir_def::{
     db::DefDatabase,
     item_scope::{ImportId, ImportOrExternCrate, ImportOrGlob},
     per_ns::Item,
+    persist::{Persist, Reader, Writer},
     src::{HasChildSource, HasSource},
     visibility::{Visibility, VisibilityExplicitness},
     AdtId, AssocItemId, DefWithBodyId, ExternCrateId, HasModule, ImplId, Lookup, MacroId,
@@ -18,9 +19,9 @@ use hir_ty::{
 use intern::Symbol;
 use rustc_hash::FxHashMap;
 use span::Edition;
-use syntax::{ast::HasName, AstNode, AstPtr, SmolStr, SyntaxNode, SyntaxNodePtr, ToSmolStr};
+use syntax::{ast::HasName, AstNode, SmolStr, TextRange, ToSmolStr};
 
-use crate::{Module, ModuleDef, Semantics};
+use crate::{Module, ModuleDef};
 
 pub type FxIndexSet<T> = indexmap::IndexSet<T, std::hash::BuildHasherDefault<rustc_hash::FxHasher>>;
 
@@ -39,18 +40,40 @@ pub struct FileSymbol {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct DeclarationLocation {
-    /// The file id for both the `ptr` and `name_ptr`.
+    /// The file id for both the `range` and `name_range`.
     pub hir_file_id: HirFileId,
-    /// This points to the whole syntax node of the declaration.
-    pub ptr: SyntaxNodePtr,
-    /// This points to the [`syntax::ast::Name`] identifier of the declaration.
-    pub name_ptr: AstPtr<Either<syntax::ast::Name, syntax::ast::NameRef>>,
+    /// The range of the whole syntax node of the declaration.
+    pub range: TextRange,
+    /// The range of the [`syntax::ast::Name`] identifier of the declaration.
+    pub name_range: TextRange,
 }
 
-impl DeclarationLocation {
-    pub fn syntax<DB: HirDatabase>(&self, sema: &Semantics<'_, DB>) -> SyntaxNode {
-        let root = sema.parse_or_expand(self.hir_file_id);
-        self.ptr.to_node(&root)
+impl Persist for FileSymbol {
+    fn write(&self, w: &mut Writer<'_>) {
+        let FileSymbol { name, def, loc, container_name, is_alias, is_assoc } = self;
+        name.write(w);
+        ModuleDefId::from(*def).write(w);
+        loc.hir_file_id.write(w);
+        loc.range.write(w);
+        loc.name_range.write(w);
+        container_name.write(w);
+        is_alias.write(w);
+        is_assoc.write(w);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Option<Self> {
+        Some(FileSymbol {
+            name: Persist::read(r)?,
+            def: ModuleDefId::read(r)?.into(),
+            loc: DeclarationLocation {
+                hir_file_id: Persist::read(r)?,
+                range: Persist::read(r)?,
+                name_range: Persist::read(r)?,
+            },
+            container_name: Persist::read(r)?,
+            is_alias: Persist::read(r)?,
+            is_assoc: Persist::read(r)?,
+        })
     }
 }
 
@@ -182,8 +205,8 @@ impl<'a> SymbolCollector<'a> {
             };
             let dec_loc = DeclarationLocation {
                 hir_file_id: source.file_id,
-                ptr: SyntaxNodePtr::new(use_tree_src.syntax()),
-                name_ptr: AstPtr::new(&name_syntax),
+                range: use_tree_src.syntax().text_range(),
+                name_range: name_syntax.syntax().text_range(),
             };
             this.symbols.insert(FileSymbol {
                 name: name.symbol().clone(),
@@ -211,8 +234,8 @@ impl<'a> SymbolCollector<'a> {
                 };
                 let dec_loc = DeclarationLocation {
                     hir_file_id: source.file_id,
-                    ptr: SyntaxNodePtr::new(source.value.syntax()),
-                    name_ptr: AstPtr::new(&name_syntax),
+                    range: source.value.syntax().text_range(),
+                    name_range: name_syntax.syntax().text_range(),
                 };
                 this.symbols.insert(FileSymbol {
                     name: name.symbol().clone(),
@@ -356,8 +379,8 @@ impl<'a> SymbolCollector<'a> {
         let def = ModuleDef::from(id.into());
         let dec_loc = DeclarationLocation {
             hir_file_id: source.file_id,
-            ptr: SyntaxNodePtr::new(source.value.syntax()),
-            name_ptr: AstPtr::new(&name_node).wrap_left(),
+            range: source.value.syntax().text_range(),
+            name_range: name_node.syntax().text_range(),
         };
 
         if let Some(attrs) = def.attrs(self.db) {
@@ -391,8 +414,8 @@ impl<'a> SymbolCollector<'a> {
         let Some(name_node) = module.name() else { return };
         let dec_loc = DeclarationLocation {
             hir_file_id: declaration.file_id,
-            ptr: SyntaxNodePtr::new(module.syntax()),
-            name_ptr: AstPtr::new(&name_node).wrap_left(),
+            range: module.syntax().text_range(),
+            name_range: name_node.syntax().text_range(),
         };
 
         let def = ModuleDef::Module(module_id.into());
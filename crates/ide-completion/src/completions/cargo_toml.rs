@@ -0,0 +1,253 @@
+//! Completes crate names and features in `Cargo.toml` manifests.
+use ide_db::{
+    base_db::{CrateId, SourceDatabase},
+    cargo_toml::{self, DependencyKind, Manifest, StrLit},
+    FilePosition, FxHashSet, RootDatabase, SymbolKind,
+};
+use syntax::{Edition, TextRange, TextSize};
+
+use crate::{completions::Completions, item::Builder, CompletionItem, CompletionItemKind};
+
+pub(crate) fn complete_cargo_toml(
+    acc: &mut Completions,
+    db: &RootDatabase,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<()> {
+    let (manifest, is_bare_key) = parse_manifest(&db.file_text(file_id), offset)?;
+    let crates = cargo_toml::package_crates(db, file_id);
+    let dependencies = manifest.dependencies();
+
+    // `features = ["$0"]` of a dependency
+    for dep in &dependencies {
+        let Some(lit) = dep.features.iter().find(|it| it.range.contains_inclusive(offset)) else {
+            continue;
+        };
+        let existing = dep.features.iter().filter(|&it| it != lit).map(|it| &*it.value).collect();
+        complete_dependency_features(acc, db, &crates, dep.package_name(), lit.range, &existing);
+        return Some(());
+    }
+
+    // `[features]`
+    let features = manifest.features();
+    for feature in &features {
+        let Some(lit) = feature.enables.iter().find(|it| it.range.contains_inclusive(offset))
+        else {
+            continue;
+        };
+        let existing: FxHashSet<_> =
+            feature.enables.iter().filter(|&it| it != lit).map(|it| &*it.value).collect();
+        let typed = &lit.value[..usize::from(offset - lit.range.start()).min(lit.value.len())];
+        if let Some(slash) = typed.find('/') {
+            let name = typed[..slash].trim_end_matches('?');
+            let dep = dependencies
+                .iter()
+                .find(|it| it.kind != DependencyKind::Workspace && it.key.name == name)?;
+            let range =
+                TextRange::new(lit.range.start() + TextSize::of(&typed[..=slash]), lit.range.end());
+            complete_dependency_features(
+                acc,
+                db,
+                &crates,
+                dep.package_name(),
+                range,
+                &FxHashSet::default(),
+            );
+            return Some(());
+        }
+        for other in &features {
+            let name = &*other.name.name;
+            if other != feature && !existing.contains(name) {
+                feature_item(lit, name).add_to(acc, db);
+            }
+        }
+        for dep in dependencies.iter().filter(|it| it.optional) {
+            let name = format!("dep:{}", dep.key.name);
+            if !existing.contains(&*name) {
+                feature_item(lit, &name).add_to(acc, db);
+            }
+        }
+        return Some(());
+    }
+
+    // the name of a dependency
+    let dep = dependencies
+        .iter()
+        .find(|it| it.kind != DependencyKind::Workspace && it.key.range.contains_inclusive(offset));
+    if let Some(dep) = dep {
+        let declared: FxHashSet<_> = dependencies.iter().map(|it| &*it.key.name).collect();
+        let own: FxHashSet<_> =
+            crates.iter().filter_map(|&it| cargo_toml::crate_package_name(db, it)).collect();
+        let crate_graph = db.crate_graph();
+        let mut seen = FxHashSet::default();
+        for krate in crate_graph.iter() {
+            let data = &crate_graph[krate];
+            if !data.origin.is_local() && !data.origin.is_lib() {
+                continue;
+            }
+            let Some(package) = cargo_toml::crate_package_name(db, krate) else { continue };
+            if own.contains(&package)
+                || declared.contains(&*package)
+                || !seen.insert(package.clone())
+            {
+                continue;
+            }
+            let mut item = CompletionItem::new(
+                SymbolKind::Module,
+                dep.key.range,
+                package.clone(),
+                Edition::CURRENT,
+            );
+            if let Some(version) = &data.version {
+                item.detail(version);
+                if is_bare_key {
+                    item.insert_text(format!("{package} = \"{version}\""));
+                }
+            }
+            item.add_to(acc, db);
+        }
+    }
+    Some(())
+}
+
+/// Parses the manifest. A dependency name that is being typed isn't followed by a value yet, so if
+/// the manifest isn't valid TOML and the line at `offset` is a bare key, it is parsed as if the key
+/// was set to an empty string. Returns whether that was the case.
+fn parse_manifest(text: &str, offset: TextSize) -> Option<(Manifest, bool)> {
+    if let Some(manifest) = Manifest::parse(text) {
+        return Some((manifest, false));
+    }
+    let offset = usize::from(offset);
+    let line_start = text[..offset].rfind('\n').map_or(0, |it| it + 1);
+    let line_end = text[offset..].find('\n').map_or(text.len(), |it| offset + it);
+    let line = text[line_start..line_end].trim();
+    if line.is_empty() || !line.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return None;
+    }
+    let patched = format!("{} = \"\"{}", &text[..line_end], &text[line_end..]);
+    Some((Manifest::parse(&patched)?, true))
+}
+
+fn complete_dependency_features(
+    acc: &mut Completions,
+    db: &RootDatabase,
+    crates: &[CrateId],
+    package: &str,
+    range: TextRange,
+    existing: &FxHashSet<&str>,
+) -> Option<()> {
+    let krate = cargo_toml::resolve_package(db, crates, package)?;
+    for feature in cargo_toml::declared_features(db, krate)? {
+        if !existing.contains(feature.as_str()) {
+            let mut item = CompletionItem::new(
+                CompletionItemKind::Feature,
+                range,
+                feature.as_str(),
+                Edition::CURRENT,
+            );
+            item.detail(format!("feature of {package}"));
+            item.add_to(acc, db);
+        }
+    }
+    Some(())
+}
+
+fn feature_item(lit: &StrLit, name: &str) -> Builder {
+    CompletionItem::new(CompletionItemKind::Feature, lit.range, name, Edition::CURRENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::tests::{check_edit, completion_list};
+
+    fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str, expect: Expect) {
+        let fixture = format!(
+            r#"
+//- /foo/src/lib.rs crate:foo deps:serde env:CARGO_MANIFEST_DIR=/foo
+//- /serde/src/lib.rs crate:serde@1.0.210,https://github.com/serde-rs/serde cfg:feature=derive,feature=rc,feature=std
+//- /regex/src/lib.rs crate:regex@1.10.0,https://github.com/rust-lang/regex
+//- /foo/Cargo.toml
+{ra_fixture}"#
+        );
+        expect.assert_eq(&completion_list(&fixture));
+    }
+
+    #[test]
+    fn dependency_features() {
+        check(
+            r#"
+[dependencies]
+serde = { version = "1", features = ["rc", "$0"] }
+"#,
+            expect![[r#"
+                ft derive feature of serde
+                ft std    feature of serde
+            "#]],
+        );
+    }
+
+    #[test]
+    fn features_table() {
+        check(
+            r#"
+[dependencies]
+serde = { version = "1", optional = true }
+
+[features]
+default = ["$0"]
+derive = ["serde/derive"]
+"#,
+            expect![[r#"
+                ft dep:serde
+                ft derive
+            "#]],
+        );
+        check(
+            r#"
+[dependencies]
+serde = { version = "1", optional = true }
+
+[features]
+default = ["serde?/s$0"]
+"#,
+            expect![[r#"
+                ft derive feature of serde
+                ft rc     feature of serde
+                ft std    feature of serde
+            "#]],
+        );
+    }
+
+    #[test]
+    fn dependency_names() {
+        check(
+            r#"
+[dependencies]
+serde = "1"
+re$0
+"#,
+            expect![[r#"
+                md regex 1.10.0
+            "#]],
+        );
+    }
+
+    #[test]
+    fn dependency_name_inserts_version() {
+        check_edit(
+            "regex",
+            r#"
+//- /foo/src/lib.rs crate:foo env:CARGO_MANIFEST_DIR=/foo
+//- /regex/src/lib.rs crate:regex@1.10.0,https://github.com/rust-lang/regex
+//- /foo/Cargo.toml
+[dependencies]
+re$0
+"#,
+            r#"
+[dependencies]
+regex = "1.10.0"
+"#,
+        );
+    }
+}
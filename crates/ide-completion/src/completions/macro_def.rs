@@ -0,0 +1,185 @@
+//! Completes fragment specifiers and metavariables in the rules of declarative macro definitions.
+use ide_db::{
+    syntax_helpers::macro_def::{metavar_binders, metavar_rule, FRAGMENT_SPECIFIERS},
+    FxHashSet,
+};
+use syntax::{algo::skip_trivia_token, AstNode, Direction, SmolStr, SyntaxKind, SyntaxToken, T};
+
+use crate::{
+    completions::Completions, context::CompletionContext, CompletionItem, CompletionItemKind,
+};
+
+pub(crate) fn complete_macro_def(
+    acc: &mut Completions,
+    ctx: &CompletionContext<'_>,
+    fake_ident_token: &SyntaxToken,
+) -> Option<()> {
+    let prev = prev_token(fake_ident_token)?;
+    match prev.kind() {
+        // `$name:$0`
+        T![:] => {
+            let name = prev_token(&prev)?;
+            if name.kind() != SyntaxKind::IDENT {
+                return None;
+            }
+            let rule = metavar_rule(&name)?;
+            if !rule.matcher.syntax().text_range().contains_range(name.text_range()) {
+                return None;
+            }
+            for &(fragment, description) in FRAGMENT_SPECIFIERS {
+                let mut item = CompletionItem::new(
+                    CompletionItemKind::Keyword,
+                    ctx.source_range(),
+                    SmolStr::new_static(fragment),
+                    ctx.edition,
+                );
+                item.detail(description);
+                item.add_to(acc, ctx.db);
+            }
+        }
+        // `$$0`
+        T![$] => {
+            let rule = metavar_rule(fake_ident_token)?;
+            let transcriber = rule.transcriber?;
+            if !transcriber.syntax().text_range().contains_range(fake_ident_token.text_range()) {
+                return None;
+            }
+            let mut seen = FxHashSet::default();
+            for binder in metavar_binders(&rule.matcher) {
+                let name = binder.name.text();
+                if !seen.insert(name.to_owned()) {
+                    continue;
+                }
+                let mut item = CompletionItem::new(
+                    CompletionItemKind::Binding,
+                    ctx.source_range(),
+                    SmolStr::new(name),
+                    ctx.edition,
+                );
+                if let Some(fragment) = binder.fragment {
+                    item.detail(fragment.text());
+                }
+                item.add_to(acc, ctx.db);
+            }
+            CompletionItem::new(
+                CompletionItemKind::Keyword,
+                ctx.source_range(),
+                SmolStr::new_static("crate"),
+                ctx.edition,
+            )
+            .add_to(acc, ctx.db);
+        }
+        _ => (),
+    }
+    Some(())
+}
+
+fn prev_token(token: &SyntaxToken) -> Option<SyntaxToken> {
+    skip_trivia_token(token.prev_token()?, Direction::Prev)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use crate::tests::{check, check_edit};
+
+    #[test]
+    fn completes_fragment_specifiers() {
+        check(
+            r#"
+macro_rules! foo {
+    ($name:$0) => {};
+}
+"#,
+            expect![[r#"
+                kw block                                  a block expression
+                kw expr                                        an expression
+                kw expr_2021 an expression, excluding `const` blocks and `_`
+                kw ident                            an identifier or keyword
+                kw item                                              an item
+                kw lifetime                                       a lifetime
+                kw literal             a literal, optionally prefixed by `-`
+                kw meta                         the contents of an attribute
+                kw pat                      a pattern, including or-patterns
+                kw pat_param         a pattern without top-level or-patterns
+                kw path                                    a type-style path
+                kw stmt           a statement without the trailing semicolon
+                kw tt                                    a single token tree
+                kw ty                                                 a type
+                kw vis                 a possibly empty visibility qualifier
+            "#]],
+        );
+        check_edit(
+            "expr",
+            r#"
+macro_rules! foo {
+    ($a:ident, $b:e$0) => {};
+}
+"#,
+            r#"
+macro_rules! foo {
+    ($a:ident, $b:expr) => {};
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_fragment_specifiers_in_transcriber() {
+        check(
+            r#"
+macro_rules! foo {
+    ($a:ident) => { $a:$0 };
+}
+"#,
+            expect![[r#""#]],
+        );
+    }
+
+    #[test]
+    fn completes_metavariables() {
+        check(
+            r#"
+macro_rules! foo {
+    ($a:ident, $($b:expr),*) => {};
+    ($first:tt $($rest:tt)*) => { foo!($($$0)*) };
+}
+"#,
+            expect![[r#"
+                bn first tt
+                bn rest  tt
+                kw crate
+            "#]],
+        );
+        check_edit(
+            "rest",
+            r#"
+macro_rules! foo {
+    ($first:tt $($rest:tt)*) => { foo!($($r$0)*) };
+}
+"#,
+            r#"
+macro_rules! foo {
+    ($first:tt $($rest:tt)*) => { foo!($($rest)*) };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn completes_metavariables_in_macro_2() {
+        check(
+            r#"
+macro foo($a:expr, $b:ty) {
+    let _: $$0 = $a;
+}
+"#,
+            expect![[r#"
+                bn a expr
+                bn b   ty
+                kw crate
+            "#]],
+        );
+    }
+}
@@ -11,8 +11,8 @@ use hir::{
     SemanticsScope, Symbol, Type, TypeInfo,
 };
 use ide_db::{
-    base_db::SourceDatabase, famous_defs::FamousDefs, helpers::is_editable_crate, FilePosition,
-    FxHashMap, FxHashSet, RootDatabase,
+    base_db::SourceDatabase, famous_defs::FamousDefs, helpers::is_editable_crate,
+    syntax_helpers::macro_def, FilePosition, FxHashMap, FxHashSet, RootDatabase,
 };
 use syntax::{
     ast::{self, AttrKind, NameOrNameRef},
@@ -380,6 +380,11 @@ pub(crate) enum CompletionAnalysis {
         fake_attribute_under_caret: Option<ast::Attr>,
         extern_crate: Option<ast::ExternCrate>,
     },
+    /// Set if we are currently completing in the rules of a `macro_rules!` or `macro` definition
+    MacroDefTT {
+        /// fake token
+        fake_ident_token: SyntaxToken,
+    },
 }
 
 /// Information about the field or method access we are completing.
@@ -728,17 +733,16 @@ impl<'a> CompletionContext<'a> {
 
             // only has a single colon
             if prev_token.kind() != T![:] {
-                return None;
-            }
-
-            // has 3 colon or 2 coloncolon in a row
-            // special casing this as per discussion in https://github.com/rust-lang/rust-analyzer/pull/13611#discussion_r1031845205
-            // and https://github.com/rust-lang/rust-analyzer/pull/13611#discussion_r1032812751
-            if prev_token
+                // which is fine for the fragment specifier of a metavariable in a macro definition
+                macro_def::metavar_rule(&prev_token)?;
+            } else if prev_token
                 .prev_token()
                 .map(|t| t.kind() == T![:] || t.kind() == T![::])
                 .unwrap_or(false)
             {
+                // has 3 colon or 2 coloncolon in a row
+                // special casing this as per discussion in https://github.com/rust-lang/rust-analyzer/pull/13611#discussion_r1031845205
+                // and https://github.com/rust-lang/rust-analyzer/pull/13611#discussion_r1032812751
                 return None;
             }
         }
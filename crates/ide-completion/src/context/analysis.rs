@@ -505,6 +505,10 @@ fn analyze(
                     colon_prefix,
                     extern_crate: p.ancestors().find_map(ast::ExternCrate::cast),
                 }
+            } else if p.kind() == SyntaxKind::TOKEN_TREE
+                && p.ancestors().any(|it| ast::Macro::can_cast(it.kind()))
+            {
+                CompletionAnalysis::MacroDefTT { fake_ident_token: fake_ident_token.clone() }
             } else {
                 return None;
             }
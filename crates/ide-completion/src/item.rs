@@ -365,6 +365,8 @@ pub enum CompletionItemKind {
     Snippet,
     UnresolvedReference,
     Expression,
+    /// A feature of a package in a `Cargo.toml` manifest.
+    Feature,
 }
 
 impl_from!(SymbolKind for CompletionItemKind);
@@ -411,6 +413,7 @@ impl CompletionItemKind {
             CompletionItemKind::Snippet => "sn",
             CompletionItemKind::UnresolvedReference => "??",
             CompletionItemKind::Expression => "ex",
+            CompletionItemKind::Feature => "ft",
         }
     }
 }
@@ -187,6 +187,12 @@ pub fn completions(
     position: FilePosition,
     trigger_character: Option<char>,
 ) -> Option<Vec<CompletionItem>> {
+    if ide_db::cargo_toml::is_cargo_toml(db, position.file_id) {
+        let mut completions = Completions::default();
+        completions::cargo_toml::complete_cargo_toml(&mut completions, db, position);
+        return Some(completions.into());
+    }
+
     let (ctx, analysis) = &CompletionContext::new(db, position, config)?;
     let mut completions = Completions::default();
 
@@ -257,6 +263,9 @@ pub fn completions(
                     extern_crate.as_ref(),
                 );
             }
+            CompletionAnalysis::MacroDefTT { fake_ident_token } => {
+                completions::macro_def::complete_macro_def(acc, ctx, fake_ident_token);
+            }
             CompletionAnalysis::UnexpandedAttrTT { .. } | CompletionAnalysis::String { .. } => (),
         }
     }
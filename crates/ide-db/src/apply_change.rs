@@ -194,6 +194,8 @@ impl RootDatabase {
             hir::db::MacroDefQuery
             hir::db::MacroRulesDataQuery
             hir::db::NotableTraitsInDepsQuery
+            hir::db::PersistKeysQuery
+            hir::db::PersistedCachesQuery
             hir::db::ProcMacroDataQuery
             hir::db::StaticDataQuery
             hir::db::StructDataWithDiagnosticsQuery
@@ -235,6 +237,10 @@ impl RootDatabase {
             // LineIndexDatabase
             crate::LineIndexQuery
 
+            // DeadCodeDatabase
+            crate::dead_code::CrateMacroDefIdentsQuery
+            crate::dead_code::FileMacroDefIdentsQuery
+
             // SourceDatabase
             base_db::ParseQuery
             base_db::ParseErrorsQuery
@@ -0,0 +1,424 @@
+//! A model of `Cargo.toml` manifests, used to provide completions, hovers and diagnostics for
+//! them.
+//!
+//! Manifests are parsed with `toml_edit`, the parser behind the `toml` crate, as it keeps the
+//! ranges of keys and values around. Only what is needed to find dependencies and features is
+//! modelled.
+
+use std::{iter, ops::Range};
+
+use base_db::{CrateId, CrateOrigin, SourceDatabase, SourceRootDatabase, VfsPath};
+use hir::Symbol;
+use syntax::{TextRange, TextSize};
+use toml_edit::{ImDocument, Item, TableLike, Value};
+
+use crate::{symbol_index::SymbolsDatabase, FileId, RootDatabase};
+
+/// Returns whether the file is a `Cargo.toml` manifest.
+pub fn is_cargo_toml(db: &RootDatabase, file_id: FileId) -> bool {
+    manifest_path(db, file_id)
+        .is_some_and(|path| path.name_and_extension() == Some(("Cargo", Some("toml"))))
+}
+
+fn manifest_path(db: &RootDatabase, file_id: FileId) -> Option<VfsPath> {
+    let source_root = db.source_root(db.file_source_root(file_id));
+    source_root.path_for_file(&file_id).cloned()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    pub name: String,
+    /// The range of the key, including the quotes of quoted keys.
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrLit {
+    pub value: String,
+    /// The range of the contents of the string, excluding the quotes.
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+    /// An entry of `[workspace.dependencies]`.
+    Workspace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub kind: DependencyKind,
+    /// The key the dependency is declared with, this is the package name unless `package` is set.
+    pub key: Key,
+    pub package: Option<StrLit>,
+    pub version: Option<StrLit>,
+    pub features: Vec<StrLit>,
+    pub optional: bool,
+    /// The `workspace` key if the dependency is inherited from the workspace.
+    pub workspace: Option<Key>,
+    /// All keys that are set on the dependency, e.g. `version` and `features`.
+    pub attributes: Vec<Key>,
+}
+
+impl Dependency {
+    pub fn package_name(&self) -> &str {
+        self.package.as_ref().map_or(&self.key.name, |it| &it.value)
+    }
+}
+
+/// An entry of the `[features]` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feature {
+    pub name: Key,
+    pub enables: Vec<StrLit>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    document: ImDocument<String>,
+}
+
+impl Manifest {
+    /// Parses a manifest, returning `None` if it is not valid TOML.
+    pub fn parse(text: &str) -> Option<Manifest> {
+        ImDocument::parse(text.to_owned()).ok().map(|document| Manifest { document })
+    }
+
+    pub fn is_workspace_root(&self) -> bool {
+        self.document.contains_key("workspace")
+    }
+
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        let mut res = Vec::new();
+        for (name, item) in self.document.iter() {
+            match (name, item.as_table_like()) {
+                ("workspace", Some(workspace)) => {
+                    if let Some(table) = workspace.get("dependencies").and_then(Item::as_table_like)
+                    {
+                        self.collect_dependencies(DependencyKind::Workspace, table, &mut res);
+                    }
+                }
+                // `[target.'cfg(unix)'.dependencies]`
+                ("target", Some(targets)) => {
+                    for (_, target) in targets.iter() {
+                        for (name, item) in
+                            target.as_table_like().into_iter().flat_map(|it| it.iter())
+                        {
+                            if let (Some(kind), Some(table)) =
+                                (dependency_kind(name), item.as_table_like())
+                            {
+                                self.collect_dependencies(kind, table, &mut res);
+                            }
+                        }
+                    }
+                }
+                (name, Some(table)) => {
+                    if let Some(kind) = dependency_kind(name) {
+                        self.collect_dependencies(kind, table, &mut res);
+                    }
+                }
+                (_, None) => (),
+            }
+        }
+        res
+    }
+
+    pub fn features(&self) -> Vec<Feature> {
+        let Some(table) = self.document.get("features").and_then(Item::as_table_like) else {
+            return Vec::new();
+        };
+        table
+            .iter()
+            .filter_map(|(name, item)| {
+                Some(Feature { name: self.key(table, name)?, enables: self.strings(item) })
+            })
+            .collect()
+    }
+
+    fn collect_dependencies(
+        &self,
+        kind: DependencyKind,
+        table: &dyn TableLike,
+        acc: &mut Vec<Dependency>,
+    ) {
+        for (name, item) in table.iter() {
+            let Some(key) = self.key(table, name) else { continue };
+            let mut dep = Dependency {
+                kind,
+                key,
+                package: None,
+                version: None,
+                features: Vec::new(),
+                optional: false,
+                workspace: None,
+                attributes: Vec::new(),
+            };
+            let Some(attrs) = item.as_table_like() else {
+                // `serde = "1.0"`
+                dep.version = self.str_lit(item);
+                acc.push(dep);
+                continue;
+            };
+            for (name, item) in attrs.iter() {
+                let Some(attr) = self.key(attrs, name) else { continue };
+                match name {
+                    "version" => dep.version = self.str_lit(item),
+                    "package" => dep.package = self.str_lit(item),
+                    "features" => dep.features = self.strings(item),
+                    "optional" => dep.optional = item.as_bool() == Some(true),
+                    "workspace" => dep.workspace = Some(attr.clone()),
+                    _ => (),
+                }
+                dep.attributes.push(attr);
+            }
+            acc.push(dep);
+        }
+    }
+
+    fn key(&self, table: &dyn TableLike, name: &str) -> Option<Key> {
+        let range = text_range(table.key(name)?.span()?);
+        Some(Key { name: name.to_owned(), range })
+    }
+
+    fn str_lit(&self, item: &Item) -> Option<StrLit> {
+        self.value_str_lit(item.as_value()?)
+    }
+
+    fn value_str_lit(&self, value: &Value) -> Option<StrLit> {
+        let span = value.span()?;
+        let repr = &self.document.raw()[span.clone()];
+        let quote_len = if repr.starts_with("\"\"\"") || repr.starts_with("'''") { 3 } else { 1 };
+        let range = text_range(span.start + quote_len..span.end - quote_len);
+        Some(StrLit { value: value.as_str()?.to_owned(), range })
+    }
+
+    /// The string items of an array value.
+    fn strings(&self, item: &Item) -> Vec<StrLit> {
+        let items = item.as_array().into_iter().flat_map(|it| it.iter());
+        items.filter_map(|it| self.value_str_lit(it)).collect()
+    }
+}
+
+fn text_range(span: Range<usize>) -> TextRange {
+    TextRange::new(TextSize::new(span.start as u32), TextSize::new(span.end as u32))
+}
+
+fn dependency_kind(table: &str) -> Option<DependencyKind> {
+    Some(match table {
+        "dependencies" => DependencyKind::Normal,
+        "dev-dependencies" | "dev_dependencies" => DependencyKind::Dev,
+        "build-dependencies" | "build_dependencies" => DependencyKind::Build,
+        _ => return None,
+    })
+}
+
+/// Returns the crates built from the package the manifest belongs to.
+pub fn package_crates(db: &RootDatabase, manifest: FileId) -> Vec<CrateId> {
+    let Some(dir) = manifest_path(db, manifest).and_then(|it| it.parent()) else {
+        return Vec::new();
+    };
+    let dir = dir.to_string();
+    let crate_graph = db.crate_graph();
+    crate_graph
+        .iter()
+        .filter(|&krate| {
+            crate_graph[krate].env.get("CARGO_MANIFEST_DIR").is_some_and(|it| it == dir)
+        })
+        .collect()
+}
+
+/// Returns the name of the package a crate was built from.
+pub fn crate_package_name(db: &RootDatabase, krate: CrateId) -> Option<String> {
+    let data = &db.crate_graph()[krate];
+    match &data.origin {
+        CrateOrigin::Local { name: Some(name), .. } | CrateOrigin::Library { name, .. } => {
+            Some(name.as_str().to_owned())
+        }
+        CrateOrigin::Local { name: None, .. }
+        | CrateOrigin::Rustc { .. }
+        | CrateOrigin::Lang(_) => {
+            data.display_name.as_ref().map(|it| it.canonical_name().as_str().to_owned())
+        }
+    }
+}
+
+/// Finds the crate a dependency on `package` resolves to, preferring the dependencies of
+/// `dependents` over the rest of the crate graph.
+pub fn resolve_package(
+    db: &RootDatabase,
+    dependents: &[CrateId],
+    package: &str,
+) -> Option<CrateId> {
+    let normalize = |name: &str| name.replace('-', "_");
+    let package = normalize(package);
+    let is_package = |krate: CrateId| {
+        !db.crate_graph()[krate].origin.is_lang()
+            && crate_package_name(db, krate).is_some_and(|it| normalize(&it) == package)
+    };
+    let crate_graph = db.crate_graph();
+    dependents
+        .iter()
+        .flat_map(|&krate| &crate_graph[krate].dependencies)
+        .map(|dep| dep.crate_id)
+        .find(|&krate| is_package(krate))
+        .or_else(|| crate_graph.iter().find(|&krate| is_package(krate)))
+}
+
+/// Returns all features a crate declares, or `None` if they are unknown.
+pub fn declared_features(db: &RootDatabase, krate: CrateId) -> Option<Vec<Symbol>> {
+    let data = &db.crate_graph()[krate];
+    match (&data.origin, &data.potential_cfg_options) {
+        (_, Some(cfg)) => Some(sorted_features(cfg.get_cfg_values("feature"))),
+        // Only packages coming from cargo are known to have no features.
+        (CrateOrigin::Local { name: Some(_), .. } | CrateOrigin::Library { .. }, None) => {
+            Some(Vec::new())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the features that are enabled for a crate.
+pub fn enabled_features(db: &RootDatabase, krate: CrateId) -> Vec<Symbol> {
+    sorted_features(db.crate_graph()[krate].cfg_options.get_cfg_values("feature"))
+}
+
+fn sorted_features<'a>(features: impl Iterator<Item = &'a Symbol>) -> Vec<Symbol> {
+    let mut res: Vec<_> = features.cloned().collect();
+    res.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    res.dedup();
+    res
+}
+
+/// Finds the manifest of the workspace the given manifest belongs to, which may be the manifest
+/// itself.
+pub fn workspace_manifest(db: &RootDatabase, manifest: FileId) -> Option<(FileId, Manifest)> {
+    let path = manifest_path(db, manifest)?;
+    let parsed = Manifest::parse(&db.file_text(manifest))?;
+    if parsed.is_workspace_root() {
+        return Some((manifest, parsed));
+    }
+    let mut dir = path.parent()?;
+    while let Some(parent) = dir.parent() {
+        dir = parent;
+        let candidate = dir.join("Cargo.toml")?;
+        let file_id = iter::once(db.file_source_root(manifest))
+            .chain(db.local_roots().iter().copied())
+            .find_map(|root| db.source_root(root).file_for_path(&candidate).copied());
+        let parsed = file_id.and_then(|it| Some((it, Manifest::parse(&db.file_text(it))?)));
+        if let Some((file_id, parsed)) = parsed.filter(|(_, it)| it.is_workspace_root()) {
+            return Some((file_id, parsed));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn check_dependencies(manifest: &str, expect: Expect) {
+        let manifest = Manifest::parse(manifest).unwrap();
+        let actual = manifest
+            .dependencies()
+            .iter()
+            .map(|dep| {
+                let mut buf = format!("{:?} {} {:?}", dep.kind, dep.key.name, dep.key.range);
+                if let Some(package) = &dep.package {
+                    buf += &format!(" package={}", package.value);
+                }
+                if let Some(version) = &dep.version {
+                    buf += &format!(" version={} {:?}", version.value, version.range);
+                }
+                if !dep.features.is_empty() {
+                    buf += &format!(
+                        " features=[{}]",
+                        dep.features.iter().map(|it| &it.value).join(",")
+                    );
+                }
+                if dep.optional {
+                    buf += " optional";
+                }
+                if dep.workspace.is_some() {
+                    buf += " workspace";
+                }
+                buf
+            })
+            .join("\n");
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn dependency_tables() {
+        check_dependencies(
+            r#"
+[package]
+name = "foo"
+
+[dependencies]
+serde = "1.0"
+tokio = { version = "1", features = ["rt", "macros"], optional = true }
+regex.version = "1.10"
+regex.default-features = false
+
+[dependencies.rand]
+version = "0.8"
+package = "rand_core"
+
+[target.'cfg(unix)'.dev-dependencies]
+libc = { workspace = true }
+
+[build-dependencies]
+cc = "1"
+"#,
+            expect![[r#"
+                Normal serde 40..45 version=1.0 49..52
+                Normal tokio 54..59 version=1 75..76 features=[rt,macros] optional
+                Normal regex 126..131 version=1.10 143..147
+                Normal rand 195..199 package=rand_core version=0.8 212..215
+                Dev libc 278..282 workspace
+                Build cc 328..330 version=1 334..335"#]],
+        );
+    }
+
+    #[test]
+    fn invalid_manifest() {
+        assert!(Manifest::parse("[dependencies]\nserde = { version = \"1\", features = [\"der\n")
+            .is_none());
+    }
+
+    #[test]
+    fn features_table() {
+        let manifest = Manifest::parse(
+            r#"
+[features]
+default = ["std"] # comment
+std = [
+    "serde?/std", # comment
+    "dep:libc",
+]
+"#,
+        )
+        .unwrap();
+        let features = manifest
+            .features()
+            .iter()
+            .map(|it| {
+                format!(
+                    "{} {:?}",
+                    it.name.name,
+                    it.enables.iter().map(|it| &it.value).collect_vec()
+                )
+            })
+            .join("\n");
+        expect![[r#"
+            default ["std"]
+            std ["serde?/std", "dep:libc"]"#]]
+        .assert_eq(&features);
+    }
+}
@@ -0,0 +1,203 @@
+//! Finds private items that are never used in the crate defining them.
+//!
+//! This is a per-item reference search, so unlike rustc it doesn't find items that are only used
+//! by other dead items.
+use std::fmt;
+
+use base_db::{ra_salsa, Upcast};
+use hir::{
+    db::HirDatabase, sym, AsAssocItem, Crate, HasAttrs, HasVisibility, Semantics, Visibility,
+};
+use syntax::{
+    ast::{self, HasName},
+    AstNode, NodeOrToken, SmolStr, SyntaxKind, SyntaxNode, TextRange,
+};
+use triomphe::Arc;
+
+use crate::{defs::Definition, EditionedFileId, FxHashSet, RootDatabase};
+
+#[ra_salsa::query_group(DeadCodeDatabaseStorage)]
+pub trait DeadCodeDatabase: HirDatabase + Upcast<dyn HirDatabase> {
+    /// The identifiers mentioned in the macro definitions of a crate.
+    ///
+    /// Macro definitions are only resolved where they are called, which a reference search can't
+    /// find, so any item named like one of these is treated as used.
+    fn crate_macro_def_idents(&self, krate: Crate) -> Arc<FxHashSet<SmolStr>>;
+
+    /// The identifiers mentioned in the macro definitions of a file.
+    fn file_macro_def_idents(&self, file_id: EditionedFileId) -> Arc<FxHashSet<SmolStr>>;
+}
+
+fn crate_macro_def_idents(db: &dyn DeadCodeDatabase, krate: Crate) -> Arc<FxHashSet<SmolStr>> {
+    let mut res = FxHashSet::default();
+    for file_id in
+        krate.modules(db.upcast()).into_iter().filter_map(|it| it.as_source_file_id(db.upcast()))
+    {
+        res.extend(db.file_macro_def_idents(file_id).iter().cloned());
+    }
+    Arc::new(res)
+}
+
+fn file_macro_def_idents(
+    db: &dyn DeadCodeDatabase,
+    file_id: EditionedFileId,
+) -> Arc<FxHashSet<SmolStr>> {
+    let mut res = FxHashSet::default();
+    for mac in db.parse(file_id).tree().syntax().descendants().filter_map(ast::Macro::cast) {
+        res.extend(
+            mac.syntax()
+                .descendants_with_tokens()
+                .filter_map(NodeOrToken::into_token)
+                .filter(|it| it.kind() == SyntaxKind::IDENT)
+                .map(|it| SmolStr::new(it.text())),
+        );
+    }
+    Arc::new(res)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadItemKind {
+    Function,
+    Const,
+    Static,
+    Field,
+    Variant,
+}
+
+impl fmt::Display for DeadItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeadItemKind::Function => "function",
+            DeadItemKind::Const => "constant",
+            DeadItemKind::Static => "static",
+            DeadItemKind::Field => "field",
+            DeadItemKind::Variant => "variant",
+        })
+    }
+}
+
+/// An item that isn't referenced anywhere but in its own definition.
+#[derive(Debug, Clone)]
+pub struct DeadItem {
+    pub kind: DeadItemKind,
+    pub def: Definition,
+    /// The node of the whole item, including its attributes.
+    pub node: SyntaxNode,
+    pub name: ast::Name,
+}
+
+/// Returns the items in `file_id` that are not `pub` and are never used.
+///
+/// `main`, tests, benches, exported symbols and items whose names start with an underscore are
+/// never reported. Neither are trait items and trait impl items, which are used through the trait.
+pub fn dead_items(sema: &Semantics<'_, RootDatabase>, file_id: EditionedFileId) -> Vec<DeadItem> {
+    let Some(krate) = sema.file_to_module_def(file_id).map(|it| it.krate()) else {
+        return Vec::new();
+    };
+    let source_file = sema.parse(file_id);
+    let candidates = source_file
+        .syntax()
+        .descendants()
+        .filter_map(|node| candidate(sema, node))
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    let macro_idents = sema.db.crate_macro_def_idents(krate);
+    candidates
+        .into_iter()
+        .filter(|item| !macro_idents.contains(item.name.text().as_str()))
+        .filter(|item| !is_used(sema, item.def, file_id, item.node.text_range()))
+        .collect()
+}
+
+fn candidate(sema: &Semantics<'_, RootDatabase>, node: SyntaxNode) -> Option<DeadItem> {
+    let db = sema.db;
+    let (kind, def, name) = if let Some(it) = ast::Fn::cast(node.clone()) {
+        if is_exported(sema, &it.clone().into()) {
+            return None;
+        }
+        let def = sema.to_def(&it)?;
+        if def.is_main(db) || def.is_test(db) || def.is_bench(db) {
+            return None;
+        }
+        (DeadItemKind::Function, Definition::Function(def), it.name()?)
+    } else if let Some(it) = ast::Const::cast(node.clone()) {
+        let def = sema.to_def(&it)?;
+        (DeadItemKind::Const, Definition::Const(def), it.name()?)
+    } else if let Some(it) = ast::Static::cast(node.clone()) {
+        if is_exported(sema, &it.clone().into()) {
+            return None;
+        }
+        let def = sema.to_def(&it)?;
+        (DeadItemKind::Static, Definition::Static(def), it.name()?)
+    } else if let Some(it) = ast::RecordField::cast(node.clone()) {
+        let def = sema.to_def(&it)?;
+        if matches!(def.parent_def(db), hir::VariantDef::Union(_)) {
+            return None;
+        }
+        (DeadItemKind::Field, Definition::Field(def), it.name()?)
+    } else if let Some(it) = ast::Variant::cast(node.clone()) {
+        let def = sema.to_def(&it)?;
+        (DeadItemKind::Variant, Definition::Variant(def), it.name()?)
+    } else {
+        return None;
+    };
+
+    if name.text().starts_with('_') {
+        return None;
+    }
+    if node.ancestors().any(|it| ast::ExternBlock::can_cast(it.kind())) {
+        return None;
+    }
+    // Trait items are used through the trait, and the names of trait impl items are dictated by
+    // the trait.
+    if let Some(assoc) = def.as_assoc_item(db) {
+        if assoc.container_trait(db).is_some() || assoc.implemented_trait(db).is_some() {
+            return None;
+        }
+    }
+    let visibility = match def {
+        Definition::Field(it) => it.visibility(db),
+        Definition::Variant(it) => it.parent_enum(db).visibility(db),
+        _ => def.visibility(db)?,
+    };
+    if visibility == Visibility::Public {
+        return None;
+    }
+    Some(DeadItem { kind, def, node, name })
+}
+
+/// Whether the item is exported under its symbol name, or is the input of an attribute macro that
+/// may do anything with it.
+fn is_exported(sema: &Semantics<'_, RootDatabase>, item: &ast::Item) -> bool {
+    if sema.is_attr_macro_call(item) {
+        return true;
+    }
+    let attrs = match item {
+        ast::Item::Fn(it) => sema.to_def(it).map(|it| it.attrs(sema.db)),
+        ast::Item::Static(it) => sema.to_def(it).map(|it| it.attrs(sema.db)),
+        _ => None,
+    };
+    attrs.is_some_and(|attrs| {
+        [&sym::no_mangle, &sym::export_name, &sym::used]
+            .iter()
+            .any(|key| attrs.by_key(key).exists())
+    })
+}
+
+/// Whether `def` is referenced anywhere outside of `own_range`, its own definition.
+fn is_used(
+    sema: &Semantics<'_, RootDatabase>,
+    def: Definition,
+    file_id: EditionedFileId,
+    own_range: TextRange,
+) -> bool {
+    let mut used = false;
+    def.usages(sema).search(&mut |ref_file_id, reference| {
+        used = ref_file_id != file_id || !own_range.contains_range(reference.range);
+        used
+    });
+    used
+}
@@ -306,8 +306,8 @@ impl Definition {
             Definition::BuiltinType(it) => it.name().display(db, edition).to_string(),
             Definition::BuiltinLifetime(it) => it.name().display(db, edition).to_string(),
             Definition::Local(it) => {
-                let ty = it.ty(db);
-                let ty_display = ty.display_truncated(db, None, edition);
+                let ty = it.ty_with_inferred_lifetimes(db);
+                let ty_display = ty.display_truncated(db, None, edition).with_lifetimes(true);
                 let is_mut = if it.is_mut(db) { "mut " } else { "" };
                 if it.is_self(db) {
                     format!("{is_mut}self: {ty_display}")
@@ -0,0 +1,232 @@
+//! Finds imports whose names are never used in the module that imports them.
+use std::collections::hash_map::Entry;
+
+use hir::{FileRange, HirFileIdExt, InFile, InRealFile, Module, ModuleSource, ScopeDef, Semantics};
+use syntax::{
+    ast::{self, HasName, Rename},
+    AstNode, NodeOrToken, SyntaxKind, TextRange,
+};
+
+use crate::{
+    defs::Definition,
+    search::{FileReference, ReferenceCategory, SearchScope},
+    FxHashMap, RootDatabase,
+};
+
+/// Returns the use trees of `use_` that import a single name or a glob, i.e. the ones that
+/// don't have a nested use tree list.
+pub fn leaf_use_trees(use_: &ast::Use) -> impl Iterator<Item = ast::UseTree> {
+    use_.syntax()
+        .descendants()
+        .filter_map(ast::UseTree::cast)
+        .filter(|it| it.use_tree_list().is_none())
+}
+
+/// Checks use trees for imports that are never used, caching the search scopes of the modules
+/// the use trees are in.
+pub struct UnusedImportsFinder<'a, 'db> {
+    sema: &'a Semantics<'db, RootDatabase>,
+    search_scopes: FxHashMap<Module, Vec<SearchScope>>,
+}
+
+impl<'a, 'db> UnusedImportsFinder<'a, 'db> {
+    pub fn new(sema: &'a Semantics<'db, RootDatabase>) -> Self {
+        UnusedImportsFinder { sema, search_scopes: FxHashMap::default() }
+    }
+
+    /// Returns whether none of the items imported by the leaf use tree `use_tree` are used in
+    /// the module that imports them. Use trees that can't be resolved are never unused.
+    pub fn is_unused(&mut self, use_tree: &ast::UseTree) -> bool {
+        self.is_unused_(use_tree).unwrap_or(false)
+    }
+
+    fn is_unused_(&mut self, u: &ast::UseTree) -> Option<bool> {
+        let sema = self.sema;
+        let use_module = sema.scope(u.syntax()).map(|s| s.module())?;
+        let scope = match self.search_scopes.entry(use_module) {
+            Entry::Occupied(o) => o.into_mut(),
+            Entry::Vacant(v) => v.insert(module_search_scope(sema.db, use_module)),
+        };
+
+        // Gets the path associated with this use tree. If there isn't one, then ignore this use tree.
+        let path = if let Some(path) = u.path() {
+            path
+        } else if u.star_token().is_some() {
+            // This case maps to the situation where the * token is braced.
+            // In this case, the parent use tree's path is the one we should use to resolve the glob.
+            match u.syntax().ancestors().skip(1).find_map(ast::UseTree::cast) {
+                Some(parent_u) if parent_u.path().is_some() => parent_u.path()?,
+                _ => return None,
+            }
+        } else {
+            return None;
+        };
+
+        // Get the actual definition associated with this use item.
+        let def = match sema.resolve_path(&path)? {
+            hir::PathResolution::Def(d) => Definition::from(d),
+            _ => return None,
+        };
+
+        if u.star_token().is_some() {
+            // Check if any of the children of this module are used
+            let def_mod = match def {
+                Definition::Module(module) => module,
+                _ => return None,
+            };
+
+            let used = def_mod
+                .scope(sema.db, Some(use_module))
+                .iter()
+                .filter_map(|(_, x)| match x {
+                    hir::ScopeDef::ModuleDef(d) => Some(Definition::from(*d)),
+                    _ => None,
+                })
+                .any(|d| used_once_in_scope(sema, d, u.rename(), scope));
+            return Some(!used);
+        }
+
+        // A name can be imported in several namespaces at once, e.g. a trait and the derive macro
+        // of the same name, so look at everything the name is visible as.
+        let mut defs = vec![def];
+        if let Some(name) = imported_name(u, &path) {
+            // Macro definitions are only resolved where they are called, so conservatively treat
+            // any mention in one as a use.
+            if mentioned_in_macro_def(u, &name) {
+                return Some(false);
+            }
+            sema.scope(u.syntax())?.process_all_names(&mut |it, scope_def| {
+                if let ScopeDef::ModuleDef(d) = scope_def {
+                    let d = Definition::from(d);
+                    if it.as_str() == name && !defs.contains(&d) {
+                        defs.push(d);
+                    }
+                }
+            });
+        }
+
+        let used = defs.into_iter().any(|def| {
+            if let Definition::Trait(t) = def {
+                // If the trait or any item is used.
+                std::iter::once((def, u.rename()))
+                    .chain(t.items(sema.db).into_iter().map(|item| (item.into(), None)))
+                    .any(|(d, rename)| used_once_in_scope(sema, d, rename, scope))
+            } else {
+                used_once_in_scope(sema, def, u.rename(), scope)
+            }
+        });
+        Some(!used)
+    }
+}
+
+/// The name `use_tree` brings into scope, unless it is `self` or `_`.
+fn imported_name(use_tree: &ast::UseTree, path: &ast::Path) -> Option<String> {
+    match use_tree.rename() {
+        Some(rename) => Some(rename.name()?.text().to_string()),
+        None => Some(path.segment()?.name_ref()?.text().to_string()),
+    }
+    .filter(|it| it != "self")
+}
+
+fn mentioned_in_macro_def(use_tree: &ast::UseTree, name: &str) -> bool {
+    let Some(root) = use_tree.syntax().ancestors().last() else { return false };
+    root.descendants().filter_map(ast::Macro::cast).any(|mac| {
+        mac.syntax()
+            .descendants_with_tokens()
+            .filter_map(NodeOrToken::into_token)
+            .any(|token| token.kind() == SyntaxKind::IDENT && token.text() == name)
+    })
+}
+
+fn used_once_in_scope(
+    sema: &Semantics<'_, RootDatabase>,
+    def: Definition,
+    rename: Option<Rename>,
+    scopes: &Vec<SearchScope>,
+) -> bool {
+    let mut found = false;
+
+    for scope in scopes {
+        let mut search_non_import = |_, r: FileReference| {
+            // The import itself is a use; we must skip that.
+            if !r.category.contains(ReferenceCategory::IMPORT) {
+                found = true;
+                true
+            } else {
+                false
+            }
+        };
+        def.usages(sema)
+            .in_scope(scope)
+            .with_rename(rename.as_ref())
+            .search(&mut search_non_import);
+        if found {
+            break;
+        }
+    }
+
+    found
+}
+
+/// Build a search scope spanning the given module but none of its submodules.
+fn module_search_scope(db: &RootDatabase, module: hir::Module) -> Vec<SearchScope> {
+    let (file_id, range) = {
+        let InFile { file_id, value } = module.definition_source(db);
+        if let Some(InRealFile { file_id, value: call_source }) = file_id.original_call_node(db) {
+            (file_id, Some(call_source.text_range()))
+        } else {
+            (
+                file_id.original_file(db),
+                match value {
+                    ModuleSource::SourceFile(_) => None,
+                    ModuleSource::Module(it) => Some(it.syntax().text_range()),
+                    ModuleSource::BlockExpr(it) => Some(it.syntax().text_range()),
+                },
+            )
+        }
+    };
+
+    fn split_at_subrange(first: TextRange, second: TextRange) -> (TextRange, Option<TextRange>) {
+        let intersect = first.intersect(second);
+        if let Some(intersect) = intersect {
+            let start_range = TextRange::new(first.start(), intersect.start());
+
+            if intersect.end() < first.end() {
+                (start_range, Some(TextRange::new(intersect.end(), first.end())))
+            } else {
+                (start_range, None)
+            }
+        } else {
+            (first, None)
+        }
+    }
+
+    let mut scopes = Vec::new();
+    if let Some(range) = range {
+        let mut ranges = vec![range];
+
+        for child in module.children(db) {
+            let rng = match child.definition_source(db).value {
+                ModuleSource::SourceFile(_) => continue,
+                ModuleSource::Module(it) => it.syntax().text_range(),
+                ModuleSource::BlockExpr(_) => continue,
+            };
+            let mut new_ranges = Vec::new();
+            for old_range in ranges.iter_mut() {
+                let split = split_at_subrange(*old_range, rng);
+                *old_range = split.0;
+                new_ranges.extend(split.1);
+            }
+
+            ranges.append(&mut new_ranges);
+        }
+
+        for range in ranges {
+            scopes.push(SearchScope::file_range(FileRange { file_id, range }));
+        }
+    } else {
+        scopes.push(SearchScope::single_file(file_id));
+    }
+
+    scopes
+}
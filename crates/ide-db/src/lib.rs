@@ -6,6 +6,8 @@ mod apply_change;
 
 pub mod active_parameter;
 pub mod assists;
+pub mod cargo_toml;
+pub mod dead_code;
 pub mod defs;
 pub mod documentation;
 pub mod famous_defs;
@@ -28,6 +30,7 @@ pub mod imports {
     pub mod import_assets;
     pub mod insert_use;
     pub mod merge_imports;
+    pub mod unused_imports;
 }
 
 pub mod generated {
@@ -37,6 +40,7 @@ pub mod generated {
 pub mod syntax_helpers {
     pub mod format_string;
     pub mod format_string_exprs;
+    pub mod macro_def;
     pub mod tree_diff;
     pub use hir::prettify_macro_expansion;
     pub mod node_ext;
@@ -45,7 +49,7 @@ pub mod syntax_helpers {
     pub use parser::LexedStr;
 }
 
-pub use hir::ChangeWithProcMacros;
+pub use hir::{ChangeWithProcMacros, PersistKeys, PersistedCaches};
 
 use std::{fmt, mem::ManuallyDrop};
 
@@ -84,7 +88,8 @@ pub type FileRange = FileRangeWrapper<FileId>;
     hir::db::HirDatabaseStorage,
     hir::db::InternDatabaseStorage,
     LineIndexDatabaseStorage,
-    symbol_index::SymbolsDatabaseStorage
+    symbol_index::SymbolsDatabaseStorage,
+    dead_code::DeadCodeDatabaseStorage
 )]
 pub struct RootDatabase {
     // We use `ManuallyDrop` here because every codegen unit that contains a
@@ -152,6 +157,8 @@ impl RootDatabase {
         db.set_local_roots_with_durability(Default::default(), Durability::HIGH);
         db.set_library_roots_with_durability(Default::default(), Durability::HIGH);
         db.set_expand_proc_attr_macros_with_durability(false, Durability::HIGH);
+        db.set_persisted_caches_with_durability(Default::default(), Durability::HIGH);
+        db.set_persist_keys_with_durability(Default::default(), Durability::HIGH);
         db.update_base_query_lru_capacities(lru_capacity);
         db.setup_syntax_context_root();
         db
@@ -161,6 +168,23 @@ impl RootDatabase {
         self.set_expand_proc_attr_macros_with_durability(true, Durability::HIGH);
     }
 
+    /// Reuses item trees, def maps and symbols that were persisted by an earlier session instead
+    /// of computing them again. Def maps and symbols are only used once matching
+    /// [`PersistKeys`] are bound.
+    pub fn use_persisted_caches(&mut self, caches: PersistedCaches) {
+        self.set_persisted_caches_with_durability(Arc::new(caches), Durability::HIGH);
+    }
+
+    /// Binds the keys of the current crate graph, computed by [`prime_caches::persist_keys`].
+    pub fn bind_persist_keys(&mut self, keys: PersistKeys) {
+        self.set_persist_keys_with_durability(Arc::new(keys), Durability::HIGH);
+    }
+
+    /// Whether the bound [`PersistKeys`] were computed for the current crate graph.
+    pub fn persist_keys_are_current(&self) -> bool {
+        self.persist_keys().is_current(self)
+    }
+
     pub fn update_base_query_lru_capacities(&mut self, lru_capacity: Option<u16>) {
         let lru_capacity = lru_capacity.unwrap_or(base_db::DEFAULT_PARSE_LRU_CAP);
         base_db::FileTextQuery.in_db_mut(self).set_lru_capacity(DEFAULT_FILE_TEXT_LRU_CAP);
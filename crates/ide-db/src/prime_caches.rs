@@ -6,16 +6,21 @@ mod topologic_sort;
 
 use std::time::Duration;
 
-use hir::{db::DefDatabase, Symbol};
+use hir::{
+    content_hash,
+    db::{DefDatabase, ExpandDatabase},
+    PersistKeys, PersistedCaches, Symbol,
+};
 use itertools::Itertools;
+use span::SyntaxContextId;
 
 use crate::{
     base_db::{
         ra_salsa::{Database, ParallelDatabase, Snapshot},
-        Cancelled, CrateId, SourceDatabase,
+        Cancelled, CrateId, SourceDatabase, SourceRootDatabase,
     },
-    symbol_index::SymbolsDatabase,
-    FxIndexMap, RootDatabase,
+    symbol_index::{collect_library_symbols, library_symbols_fingerprint, SymbolsDatabase},
+    FxHashMap, FxIndexMap, RootDatabase,
 };
 
 /// We're indexing many crates.
@@ -213,3 +218,107 @@ pub fn parallel_prime_caches(
         cb(progress);
     }
 }
+
+/// Computes the keys of the library crates and files of the current crate graph, which persisted
+/// def maps and symbols are looked up and encoded with.
+pub fn persist_keys(db: &RootDatabase) -> PersistKeys {
+    let _p = tracing::info_span!("persist_keys").entered();
+
+    let crate_graph = db.crate_graph();
+    let mut keys = PersistKeys::new(crate_graph.clone());
+
+    // Library files are keyed by their paths, their source roots by the hash of their paths and
+    // contents.
+    let mut root_hashes = FxHashMap::default();
+    for &root_id in db.library_roots().iter() {
+        let root = db.source_root(root_id);
+        let mut files = root
+            .iter()
+            .filter_map(|file_id| Some((root.path_for_file(&file_id)?.to_string(), file_id)))
+            .collect::<Vec<_>>();
+        files.sort_unstable();
+        let mut buf = Vec::new();
+        for (path, file_id) in files {
+            buf.extend_from_slice(path.as_bytes());
+            buf.push(0);
+            buf.extend_from_slice(&content_hash(db.file_text(file_id).as_bytes()).to_le_bytes());
+            keys.insert_file(file_id, path.into_boxed_str());
+        }
+        root_hashes.insert(root_id, content_hash(&buf));
+    }
+
+    // A crate's fingerprint covers everything its def map is computed from, so it is only keyed
+    // if its dependencies are.
+    let proc_macros = db.proc_macros();
+    let expand_proc_attr_macros = db.expand_proc_attr_macros();
+    for krate in crate_graph.crates_in_topological_order() {
+        let data = &crate_graph[krate];
+        let root_id = db.file_source_root(data.root_file_id);
+        let Some(root_hash) = root_hashes.get(&root_id) else { continue };
+        let root_path = db.source_root(root_id).path_for_file(&data.root_file_id).cloned();
+        let Some(deps) = data
+            .dependencies
+            .iter()
+            .map(|dep| {
+                let fingerprint = keys.crate_fingerprint(dep.crate_id)?;
+                Some((&dep.name, dep.is_prelude(), dep.is_sysroot(), fingerprint))
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let crate_proc_macros = proc_macros.for_crate(krate, SyntaxContextId::root(data.edition));
+        let description = format!(
+            "{:?}",
+            (
+                (data.edition, &data.version, &data.display_name, &data.origin, data.is_proc_macro),
+                (&data.cfg_options, &data.potential_cfg_options, &data.env),
+                (root_hash, root_path, deps),
+                (
+                    crate_proc_macros,
+                    proc_macros.get_error_for_crate(krate),
+                    expand_proc_attr_macros
+                ),
+            )
+        );
+        keys.insert_crate(krate, content_hash(description.as_bytes()));
+    }
+    keys
+}
+
+/// Collects the item trees of all library files that are part of a crate's module tree, the def
+/// maps of library crates and the symbols of library source roots, so that they can be persisted
+/// and reused by the next session. Def maps and symbols are only collected for the crates that
+/// the bound [`PersistKeys`] have a fingerprint for.
+pub fn library_caches(db: &RootDatabase) -> PersistedCaches {
+    let _p = tracing::info_span!("library_caches").entered();
+
+    let keys = db.persist_keys();
+    let keys = if keys.is_current(db) { &*keys } else { &PersistKeys::default() };
+    let mut caches = PersistedCaches::default();
+    let mut unsupported = 0;
+    for krate in db.crate_graph().iter() {
+        let def_map = db.crate_def_map(krate);
+        for (_, module) in def_map.modules() {
+            let Some(file_id) = module.origin.file_id() else { continue };
+            if !db.source_root(db.file_source_root(file_id.file_id())).is_library {
+                continue;
+            }
+            if !caches.insert_item_tree(db, file_id) {
+                unsupported += 1;
+            }
+        }
+        if keys.crate_fingerprint(krate).is_some() && !caches.insert_def_map(db, keys, krate) {
+            unsupported += 1;
+        }
+    }
+    for &root_id in db.library_roots().iter() {
+        let Some(fingerprint) = library_symbols_fingerprint(db, keys, root_id) else { continue };
+        let symbols = collect_library_symbols(db, root_id);
+        if !caches.insert_symbols(db, keys, fingerprint, &symbols) {
+            unsupported += 1;
+        }
+    }
+    tracing::debug!(persisted = caches.len(), unsupported, "collected library caches");
+    caches
+}
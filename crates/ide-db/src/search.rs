@@ -188,7 +188,7 @@ impl SearchScope {
     }
 
     /// Build a search scope spanning the given crate.
-    fn krate(db: &RootDatabase, of: hir::Crate) -> SearchScope {
+    pub fn krate(db: &RootDatabase, of: hir::Crate) -> SearchScope {
         let root_file = of.root_file(db);
         let source_root_id = db.file_source_root(root_file);
         let source_root = db.source_root(source_root_id);
@@ -367,7 +367,13 @@ impl Definition {
         if let Definition::Macro(macro_def) = self {
             return match macro_def.kind(db) {
                 hir::MacroKind::Declarative => {
-                    if macro_def.attrs(db).by_key(&sym::macro_export).exists() {
+                    // Unlike `macro_rules!`, `macro` definitions are scoped like other items.
+                    let is_macro_2 = macro_def.source(db).is_some_and(|src| {
+                        matches!(src.value, Either::Left(ast::Macro::MacroDef(_)))
+                    });
+                    if macro_def.attrs(db).by_key(&sym::macro_export).exists()
+                        || is_macro_2 && self.visibility(db) == Some(Visibility::Public)
+                    {
                         SearchScope::reverse_dependencies(db, module.krate())
                     } else {
                         SearchScope::krate(db, module.krate())
@@ -30,14 +30,15 @@ use std::{
 
 use base_db::{
     ra_salsa::{self, ParallelDatabase},
-    SourceRootDatabase, SourceRootId, Upcast,
+    SourceDatabase, SourceRootDatabase, SourceRootId, Upcast,
 };
 use fst::{raw::IndexedValue, Automaton, Streamer};
 use hir::{
+    content_hash,
     db::HirDatabase,
     import_map::{AssocSearchMode, SearchMode},
     symbols::{FileSymbol, SymbolCollector},
-    Crate, Module,
+    Crate, Module, PersistKeys,
 };
 use rayon::prelude::*;
 use rustc_hash::FxHashSet;
@@ -127,6 +128,24 @@ pub trait SymbolsDatabase: HirDatabase + SourceRootDatabase + Upcast<dyn HirData
 fn library_symbols(db: &dyn SymbolsDatabase, source_root_id: SourceRootId) -> Arc<SymbolIndex> {
     let _p = tracing::info_span!("library_symbols").entered();
 
+    let persisted = db.persisted_caches();
+    if !persisted.is_empty() {
+        let keys = db.persist_keys();
+        let hir_db: &dyn HirDatabase = db.upcast();
+        let symbols = library_symbols_fingerprint(db, &keys, source_root_id)
+            .and_then(|fingerprint| persisted.symbols(hir_db.upcast(), &keys, fingerprint));
+        if let Some(symbols) = symbols {
+            return Arc::new(SymbolIndex::new(symbols));
+        }
+    }
+
+    Arc::new(SymbolIndex::new(collect_library_symbols(db, source_root_id)))
+}
+
+pub(crate) fn collect_library_symbols(
+    db: &dyn SymbolsDatabase,
+    source_root_id: SourceRootId,
+) -> Box<[FileSymbol]> {
     let mut symbol_collector = SymbolCollector::new(db.upcast());
 
     db.source_root_crates(source_root_id)
@@ -137,7 +156,23 @@ fn library_symbols(db: &dyn SymbolsDatabase, source_root_id: SourceRootId) -> Ar
         // the module or crate indices for those in salsa unless we need to.
         .for_each(|module| symbol_collector.collect(module));
 
-    Arc::new(SymbolIndex::new(symbol_collector.finish()))
+    symbol_collector.finish()
+}
+
+/// Identifies the symbols of a library source root by the fingerprints of its crates.
+pub(crate) fn library_symbols_fingerprint(
+    db: &dyn SymbolsDatabase,
+    keys: &PersistKeys,
+    source_root_id: SourceRootId,
+) -> Option<u128> {
+    let mut fingerprints = db
+        .source_root_crates(source_root_id)
+        .iter()
+        .map(|&krate| keys.crate_fingerprint(krate))
+        .collect::<Option<Vec<_>>>()?;
+    fingerprints.sort_unstable();
+    let bytes = fingerprints.iter().flat_map(|it| it.to_le_bytes()).collect::<Vec<_>>();
+    Some(content_hash(&bytes))
 }
 
 fn module_symbols(db: &dyn SymbolsDatabase, module: Module) -> Arc<SymbolIndex> {
@@ -207,8 +242,15 @@ pub fn world_symbols(db: &RootDatabase, query: Query) -> Vec<FileSymbol> {
     } else {
         let mut crates = Vec::new();
 
+        let crate_graph = db.crate_graph();
         for &root in db.local_roots().iter() {
-            crates.extend(db.source_root_crates(root).iter().copied())
+            // Cfg variants would only duplicate the symbols of their crate
+            crates.extend(
+                db.source_root_crates(root)
+                    .iter()
+                    .copied()
+                    .filter(|&krate| crate_graph.cfg_variant_of(krate).is_none()),
+            )
         }
         let indices: Vec<_> = crates
             .into_par_iter()
@@ -404,10 +446,12 @@ impl Query {
 #[cfg(test)]
 mod tests {
 
+    use base_db::ra_salsa::Durability;
     use expect_test::expect_file;
     use test_fixture::WithFixture;
 
     use super::*;
+    use crate::PersistedCaches;
 
     #[test]
     fn test_symbol_index_collection() {
@@ -526,4 +570,55 @@ struct Duplicate;
 
         expect_file!["./test_data/test_doc_alias.txt"].assert_debug_eq(&symbols);
     }
+
+    #[test]
+    fn persisted_library_symbols_are_used() {
+        const FIXTURE: &str = r#"
+//- /main.rs crate:main deps:dep
+use dep::S;
+//- /dep/lib.rs crate:dep new_source_root:library
+macro_rules! define_struct {
+    ($name:ident) => { pub struct $name; }
+}
+define_struct!(S);
+pub mod m {
+    #[doc(alias = "g")]
+    pub fn f() {}
+}
+pub use m::f as reexported;
+"#;
+        fn symbols(db: &RootDatabase, source_root_id: SourceRootId) -> Vec<String> {
+            let mut symbols = db
+                .library_symbols(source_root_id)
+                .symbols
+                .iter()
+                .map(|it| format!("{} {:?} {:?}", it.name, it.loc.range, it.container_name))
+                .collect::<Vec<_>>();
+            symbols.sort();
+            symbols
+        }
+
+        let load = || {
+            let (mut db, files) = RootDatabase::with_many_files(FIXTURE);
+            let source_root_id = db.file_source_root(files[1].file_id());
+            db.set_library_roots_with_durability(
+                Arc::new(FxHashSet::from_iter([source_root_id])),
+                Durability::HIGH,
+            );
+            (db, source_root_id)
+        };
+
+        let (mut db, source_root_id) = load();
+        db.bind_persist_keys(crate::prime_caches::persist_keys(&db));
+        let caches = crate::prime_caches::library_caches(&db);
+        // The item tree and def map of `dep`, and the symbols of its source root.
+        assert_eq!(caches.len(), 3, "{caches:?}");
+        let expected = symbols(&db, source_root_id);
+        assert!(expected.iter().any(|it| it.starts_with("S ")), "{expected:?}");
+
+        let (mut db, _) = load();
+        db.use_persisted_caches(PersistedCaches::from_bytes(&caches.to_bytes()).unwrap());
+        db.bind_persist_keys(crate::prime_caches::persist_keys(&db));
+        assert_eq!(symbols(&db, source_root_id), expected);
+    }
 }
@@ -0,0 +1,123 @@
+//! Syntactic helpers for the rules of declarative macro definitions, which the parser leaves as
+//! plain token trees.
+use syntax::{
+    algo::skip_trivia_token, ast, AstNode, Direction, NodeOrToken, SyntaxKind, SyntaxToken, T,
+};
+
+/// The fragment specifiers a metavariable can be bound with, and what they match.
+pub const FRAGMENT_SPECIFIERS: &[(&str, &str)] = &[
+    ("block", "a block expression"),
+    ("expr", "an expression"),
+    ("expr_2021", "an expression, excluding `const` blocks and `_`"),
+    ("ident", "an identifier or keyword"),
+    ("item", "an item"),
+    ("lifetime", "a lifetime"),
+    ("literal", "a literal, optionally prefixed by `-`"),
+    ("meta", "the contents of an attribute"),
+    ("pat", "a pattern, including or-patterns"),
+    ("pat_param", "a pattern without top-level or-patterns"),
+    ("path", "a type-style path"),
+    ("stmt", "a statement without the trailing semicolon"),
+    ("tt", "a single token tree"),
+    ("ty", "a type"),
+    ("vis", "a possibly empty visibility qualifier"),
+];
+
+/// A `matcher => transcriber` rule of a declarative macro.
+#[derive(Debug, Clone)]
+pub struct MacroRule {
+    pub matcher: ast::TokenTree,
+    pub transcriber: Option<ast::TokenTree>,
+}
+
+/// A metavariable binder, `$name:fragment`, in the matcher of a rule.
+#[derive(Debug, Clone)]
+pub struct MetavarBinder {
+    pub name: SyntaxToken,
+    pub fragment: Option<SyntaxToken>,
+    /// The number of repetitions the binder is nested in.
+    pub depth: usize,
+}
+
+/// Splits the body of a `macro_rules!` or `macro` definition into its rules.
+pub fn macro_rules(mac: &ast::Macro) -> Vec<MacroRule> {
+    let rules = match mac {
+        ast::Macro::MacroRules(it) => it.token_tree(),
+        ast::Macro::MacroDef(it) => match it.args() {
+            Some(matcher) => return vec![MacroRule { matcher, transcriber: it.body() }],
+            None => it.body(),
+        },
+    };
+    let Some(rules) = rules else { return Vec::new() };
+    let mut trees = rules.token_trees_and_tokens().filter_map(NodeOrToken::into_node);
+    let mut res = Vec::new();
+    while let Some(matcher) = trees.next() {
+        res.push(MacroRule { matcher, transcriber: trees.next() });
+    }
+    res
+}
+
+/// Returns the metavariables bound by `matcher`, in source order.
+pub fn metavar_binders(matcher: &ast::TokenTree) -> Vec<MetavarBinder> {
+    let mut res = Vec::new();
+    collect_binders(matcher, 0, &mut res);
+    res
+}
+
+fn collect_binders(tt: &ast::TokenTree, depth: usize, acc: &mut Vec<MetavarBinder>) {
+    let mut elements = tt
+        .token_trees_and_tokens()
+        .filter(|it| it.as_token().is_none_or(|it| !it.kind().is_trivia()))
+        .peekable();
+    let mut after_dollar = false;
+    while let Some(element) = elements.next() {
+        match element {
+            NodeOrToken::Node(subtree) => {
+                collect_binders(&subtree, depth + usize::from(after_dollar), acc)
+            }
+            NodeOrToken::Token(token) if token.kind() == T![$] => {
+                after_dollar = true;
+                continue;
+            }
+            NodeOrToken::Token(name) if after_dollar && name.kind() == SyntaxKind::IDENT => {
+                let fragment = match elements.peek() {
+                    Some(NodeOrToken::Token(colon)) if colon.kind() == T![:] => {
+                        elements.next();
+                        match elements.peek() {
+                            Some(NodeOrToken::Token(it)) if it.kind() == SyntaxKind::IDENT => {
+                                let fragment = it.clone();
+                                elements.next();
+                                Some(fragment)
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                acc.push(MetavarBinder { name, fragment, depth });
+            }
+            NodeOrToken::Token(_) => (),
+        }
+        after_dollar = false;
+    }
+}
+
+/// If `token` is the name of a metavariable, `$name`, returns the rule it is part of.
+pub fn metavar_rule(token: &SyntaxToken) -> Option<MacroRule> {
+    if token.kind() != SyntaxKind::IDENT {
+        return None;
+    }
+    let prev = skip_trivia_token(token.prev_token()?, Direction::Prev)?;
+    if prev.kind() != T![$] {
+        return None;
+    }
+    let mac = token.parent_ancestors().find_map(ast::Macro::cast)?;
+    let range = token.text_range();
+    macro_rules(&mac).into_iter().find(|rule| {
+        rule.matcher.syntax().text_range().contains_range(range)
+            || rule
+                .transcriber
+                .as_ref()
+                .is_some_and(|it| it.syntax().text_range().contains_range(range))
+    })
+}
@@ -0,0 +1,201 @@
+//! Diagnostics for `Cargo.toml` manifests.
+
+use hir::Symbol;
+use ide_db::{
+    base_db::SourceDatabase,
+    cargo_toml::{self, Dependency, DependencyKind, Manifest, StrLit},
+    FileId, FileRange, RootDatabase,
+};
+
+use crate::{Diagnostic, DiagnosticCode, Severity};
+
+// Diagnostic: unresolved-cargo-feature
+//
+// This diagnostic is triggered if a `Cargo.toml` manifest enables a feature that the dependency
+// does not declare, or if the `[features]` table refers to an unknown feature or dependency.
+//
+// Dependencies are looked up in the crate graph, so features of dependencies that are not part of
+// it are not checked.
+//
+// Diagnostic: invalid-workspace-dependency
+//
+// This diagnostic is triggered if a dependency that is inherited from the workspace with
+// `workspace = true` specifies keys other than `features`, `optional` and `default-features`, or if
+// the workspace does not declare the dependency in `[workspace.dependencies]`.
+pub(crate) fn cargo_toml(db: &RootDatabase, acc: &mut Vec<Diagnostic>, file_id: FileId) {
+    // Syntax errors are left to cargo.
+    let Some(manifest) = Manifest::parse(&db.file_text(file_id)) else { return };
+    let dependencies = manifest.dependencies();
+    let crates = cargo_toml::package_crates(db, file_id);
+    let workspace_dependencies = cargo_toml::workspace_manifest(db, file_id).map(|(_, it)| {
+        it.dependencies()
+            .into_iter()
+            .filter(|it| it.kind == DependencyKind::Workspace)
+            .collect::<Vec<_>>()
+    });
+
+    let package_of = |dep: &Dependency| -> String {
+        let inherited = dep
+            .workspace
+            .as_ref()
+            .and(workspace_dependencies.as_ref())
+            .and_then(|it| it.iter().find(|it| it.key.name == dep.key.name));
+        inherited.unwrap_or(dep).package_name().to_owned()
+    };
+    let features_of = |package: &str| -> Option<Vec<Symbol>> {
+        cargo_toml::declared_features(db, cargo_toml::resolve_package(db, &crates, package)?)
+    };
+    let mut unresolved_feature = |message: String, lit: &StrLit| {
+        acc.push(Diagnostic::new(
+            DiagnosticCode::Ra("unresolved-cargo-feature", Severity::Error),
+            message,
+            FileRange { file_id, range: lit.range },
+        ))
+    };
+
+    for dep in &dependencies {
+        let package = package_of(dep);
+        let Some(features) = features_of(&package) else { continue };
+        for feature in &dep.features {
+            if !is_declared(&features, &feature.value) {
+                unresolved_feature(
+                    format!("unknown feature `{}` of `{package}`", feature.value),
+                    feature,
+                );
+            }
+        }
+    }
+
+    let declared = manifest.features();
+    let is_optional_dependency = |name: &str| {
+        dependencies
+            .iter()
+            .any(|it| it.kind == DependencyKind::Normal && it.optional && it.key.name == name)
+    };
+    for enabled in declared.iter().flat_map(|it| &it.enables) {
+        let value = &*enabled.value;
+        if let Some(name) = value.strip_prefix("dep:") {
+            if !is_optional_dependency(name) {
+                unresolved_feature(format!("`{name}` is not an optional dependency"), enabled);
+            }
+        } else if let Some((name, feature)) = value.split_once('/') {
+            let name = name.strip_suffix('?').unwrap_or(name);
+            let dep = dependencies
+                .iter()
+                .find(|it| it.kind != DependencyKind::Workspace && it.key.name == name);
+            let Some(dep) = dep else {
+                unresolved_feature(format!("`{name}` is not a dependency"), enabled);
+                continue;
+            };
+            let package = package_of(dep);
+            if features_of(&package).is_some_and(|features| !is_declared(&features, feature)) {
+                unresolved_feature(format!("unknown feature `{feature}` of `{package}`"), enabled);
+            }
+        } else if !declared.iter().any(|it| it.name.name == value) && !is_optional_dependency(value)
+        {
+            unresolved_feature(format!("unknown feature `{value}`"), enabled);
+        }
+    }
+
+    for dep in &dependencies {
+        let Some(workspace) = &dep.workspace else { continue };
+        if dep.kind == DependencyKind::Workspace {
+            continue;
+        }
+        for attr in &dep.attributes {
+            if !matches!(
+                &*attr.name,
+                "workspace" | "features" | "optional" | "default-features" | "default_features"
+            ) {
+                acc.push(Diagnostic::new(
+                    DiagnosticCode::Ra("invalid-workspace-dependency", Severity::Error),
+                    format!(
+                        "`{}` cannot be specified for a dependency inherited from the workspace",
+                        attr.name
+                    ),
+                    FileRange { file_id, range: attr.range },
+                ));
+            }
+        }
+        let Some(workspace_dependencies) = &workspace_dependencies else { continue };
+        if !workspace_dependencies.iter().any(|it| it.key.name == dep.key.name) {
+            acc.push(Diagnostic::new(
+                DiagnosticCode::Ra("invalid-workspace-dependency", Severity::Error),
+                format!("`{}` is not declared in `[workspace.dependencies]`", dep.key.name),
+                FileRange { file_id, range: workspace.range },
+            ));
+        }
+    }
+}
+
+fn is_declared(features: &[Symbol], feature: &str) -> bool {
+    feature == "default" || features.iter().any(|it| it.as_str() == feature)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn unknown_dependency_feature() {
+        check_diagnostics(
+            r#"
+//- /foo/src/lib.rs crate:foo deps:serde env:CARGO_MANIFEST_DIR=/foo
+//- /foo/Cargo.toml
+[package]
+name = "foo"
+
+[dependencies]
+serde = { version = "1", features = ["derive", "rc", "defualt"] }
+                                                   #//^^^^^^^ error: unknown feature `defualt` of `serde`
+[dev-dependencies]
+unknown = { version = "1", features = ["whatever"] }
+//- /serde/src/lib.rs crate:serde cfg:feature=derive,feature=rc,feature=std
+"#,
+        );
+    }
+
+    #[test]
+    fn features_table() {
+        check_diagnostics(
+            r#"
+//- /foo/src/lib.rs crate:foo deps:serde env:CARGO_MANIFEST_DIR=/foo
+//- /foo/Cargo.toml
+[dependencies]
+serde = { version = "1", optional = true }
+libc = "0.2"
+
+[features]
+default = ["std", "alloc"]
+                #//^^^^^ error: unknown feature `alloc`
+std = ["serde?/std", "serde/alloc", "dep:libc", "serde"]
+                   #//^^^^^^^^^^^ error: unknown feature `alloc` of `serde`
+                                  #//^^^^^^^^ error: `libc` is not an optional dependency
+derive = ["dep:serde", "serde_derive/std"]
+                     #//^^^^^^^^^^^^^^^^ error: `serde_derive` is not a dependency
+//- /serde/src/lib.rs crate:serde cfg:feature=derive,feature=std
+"#,
+        );
+    }
+
+    #[test]
+    fn workspace_inheritance() {
+        check_diagnostics(
+            r#"
+//- /foo/src/lib.rs crate:foo env:CARGO_MANIFEST_DIR=/foo
+//- /Cargo.toml
+[workspace]
+members = ["foo"]
+
+[workspace.dependencies]
+serde = { version = "1", features = ["derive"] }
+//- /foo/Cargo.toml
+[dependencies]
+serde = { workspace = true, version = "1.0", features = ["rc"], optional = true }
+                         #//^^^^^^^ error: `version` cannot be specified for a dependency inherited from the workspace
+regex.workspace = true
+   #//^^^^^^^^^ error: `regex` is not declared in `[workspace.dependencies]`
+"#,
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: conflicting-borrows
+//
+// This diagnostic is triggered when a local variable is borrowed while an incompatible borrow of
+// it is still alive.
+pub(crate) fn conflicting_borrows(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::ConflictingBorrow,
+) -> Diagnostic {
+    let name = d.local.name(ctx.sema.db);
+    let name = name.display(ctx.sema.db, ctx.edition);
+    let (code, message) = match (d.is_mut, d.previous_is_mut) {
+        (true, true) => {
+            ("E0499", format!("cannot borrow `{name}` as mutable more than once at a time"))
+        }
+        (true, false) => (
+            "E0502",
+            format!("cannot borrow `{name}` as mutable because it is also borrowed as immutable"),
+        ),
+        (false, _) => (
+            "E0502",
+            format!("cannot borrow `{name}` as immutable because it is also borrowed as mutable"),
+        ),
+    };
+    Diagnostic::new_with_syntax_node_ptr(ctx, DiagnosticCode::RustcHardError(code), message, d.span)
+        .experimental() // we don't model non-lexical lifetimes of borrows through calls precisely
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn two_mutable_borrows() {
+        check_diagnostics(
+            r#"
+fn main() {
+    let mut a = 5;
+    let r1 = &mut a;
+    let r2 = &mut a;
+           //^^^^^^ error: cannot borrow `a` as mutable more than once at a time
+    *r1 = 1;
+    *r2 = 2;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn mutable_while_shared() {
+        check_diagnostics(
+            r#"
+fn read(_: &i32) {}
+fn main() {
+    let mut a = 5;
+    let r1 = &a;
+    let r2 = &mut a;
+           //^^^^^^ error: cannot borrow `a` as mutable because it is also borrowed as immutable
+    *r2 = 2;
+    read(r1);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn shared_while_mutable() {
+        check_diagnostics(
+            r#"
+fn read(_: &i32) {}
+fn main() {
+    let mut a = 5;
+    let r1 = &mut a;
+    let r2 = &a;
+           //^^ error: cannot borrow `a` as immutable because it is also borrowed as mutable
+    *r1 = 2;
+    read(r2);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrow_no_longer_used() {
+        check_diagnostics(
+            r#"
+fn read(_: &i32) {}
+fn main() {
+    let mut a = 5;
+    let r1 = &mut a;
+    *r1 = 1;
+    let r2 = &mut a;
+    *r2 = 2;
+    let r3 = &a;
+    let r4 = &a;
+    read(r3);
+    read(r4);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn two_phase_borrow() {
+        check_diagnostics(
+            r#"
+struct V;
+impl V {
+    fn push(&mut self, _: usize) {}
+    fn len(&self) -> usize { 0 }
+}
+fn main() {
+    let mut v = V;
+    v.push(v.len());
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrow_returned_from_call() {
+        check_diagnostics(
+            r#"
+struct V;
+impl V {
+    fn first(&mut self) -> &mut i32 { loop {} }
+}
+fn main() {
+    let mut v = V;
+    let a = v.first();
+    let b = v.first();
+          //^ error: cannot borrow `v` as mutable more than once at a time
+    *a = 1;
+    *b = 2;
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use hir::InFile;
+use ide_db::{
+    dead_code::{dead_items, DeadItem},
+    source_change::SourceChange,
+    text_edit::TextEdit,
+    EditionedFileId, FileRange,
+};
+use syntax::{
+    algo::skip_trivia_token, AstNode, Direction, NodeOrToken, SyntaxKind, SyntaxNode,
+    SyntaxNodePtr, TextRange, T,
+};
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: dead-code
+//
+// This diagnostic is triggered when a private function, constant, static, field or enum variant
+// is never used in its crate.
+pub(crate) fn dead_code(
+    ctx: &DiagnosticsContext<'_>,
+    acc: &mut Vec<Diagnostic>,
+    file_id: EditionedFileId,
+) {
+    for DeadItem { kind, node, name, .. } in dead_items(&ctx.sema, file_id) {
+        let name_range = name.syntax().text_range();
+        let edit = TextEdit::delete(removal_range(&node));
+        acc.push(
+            Diagnostic::new(
+                DiagnosticCode::RustcLint("dead_code"),
+                format!("{kind} `{name}` is never used"),
+                FileRange { file_id: file_id.into(), range: name_range },
+            )
+            .with_main_node(InFile::new(file_id.into(), SyntaxNodePtr::new(&node)))
+            .with_fixes(Some(vec![fix(
+                "remove_dead_item",
+                &format!("Remove {kind} `{name}`"),
+                SourceChange::from_text_edit(file_id, edit),
+                name_range,
+            )]))
+            .with_unused(true)
+            .experimental(),
+        );
+    }
+}
+
+/// The range to delete to remove `node`, together with the whitespace before it and, for fields
+/// and variants, the comma after it.
+fn removal_range(node: &SyntaxNode) -> TextRange {
+    let mut range = node.text_range();
+    if let Some(comma) = node
+        .next_sibling_or_token()
+        .and_then(|it| skip_trivia_token(it.into_token()?, Direction::Next))
+        .filter(|it| it.kind() == T![,])
+    {
+        range = range.cover(comma.text_range());
+    }
+    match node.prev_sibling_or_token() {
+        Some(NodeOrToken::Token(ws)) if ws.kind() == SyntaxKind::WHITESPACE => {
+            range.cover(ws.text_range())
+        }
+        _ => match node.next_sibling_or_token() {
+            Some(NodeOrToken::Token(ws)) if ws.kind() == SyntaxKind::WHITESPACE => {
+                range.cover(ws.text_range())
+            }
+            _ => range,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{
+        check_diagnostics_with_config, check_fix_with_config, test_config_enabling,
+    };
+
+    #[track_caller]
+    fn check_diagnostics(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
+        check_diagnostics_with_config(test_config_enabling("dead_code"), ra_fixture);
+    }
+
+    #[track_caller]
+    fn check_fix(
+        #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
+        #[rust_analyzer::rust_fixture] ra_fixture_after: &str,
+    ) {
+        check_fix_with_config(
+            test_config_enabling("dead_code"),
+            ra_fixture_before,
+            ra_fixture_after,
+        );
+    }
+
+    #[test]
+    fn dead_items() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+fn main() {
+    used();
+    let _ = Used::A;
+    let _ = CONST;
+}
+
+fn read(s: &S) -> u32 {
+ //^^^^ 💡 warn: function `read` is never used
+    s.used
+}
+
+fn used() {}
+fn unused() {}
+ //^^^^^^ 💡 warn: function `unused` is never used
+fn recursive() {
+ //^^^^^^^^^ 💡 warn: function `recursive` is never used
+    recursive();
+}
+fn _underscore() {}
+pub fn public() {}
+pub(crate) fn crate_visible() {}
+            //^^^^^^^^^^^^^ 💡 warn: function `crate_visible` is never used
+#[allow(dead_code)]
+fn allowed() {}
+#[no_mangle]
+fn exported() {}
+
+const CONST: u32 = 0;
+const UNUSED: u32 = 0;
+    //^^^^^^ 💡 warn: constant `UNUSED` is never used
+static STATIC: u32 = 0;
+     //^^^^^^ 💡 warn: static `STATIC` is never used
+
+enum Used {
+    A,
+    B,
+  //^ 💡 warn: variant `B` is never used
+}
+
+struct S {
+    used: u32,
+    unused: u32,
+  //^^^^^^ 💡 warn: field `unused` is never used
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn assoc_items() {
+        check_diagnostics(
+            r#"
+struct S;
+
+impl S {
+    fn method(&self) {}
+    fn unused_method(&self) {}
+     //^^^^^^^^^^^^^ 💡 warn: function `unused_method` is never used
+    pub fn public_method(&self) {}
+    const UNUSED: u32 = 0;
+        //^^^^^^ 💡 warn: constant `UNUSED` is never used
+}
+
+trait Tr {
+    fn trait_method(&self);
+}
+
+impl Tr for S {
+    fn trait_method(&self) {}
+}
+
+fn main() {
+    S.method();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn used_in_macro_rules() {
+        check_diagnostics(
+            r#"
+macro_rules! m {
+    () => {
+        helper()
+    };
+}
+
+fn helper() {}
+
+fn main() {
+    m!();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn remove_dead_items() {
+        check_fix(
+            r#"
+fn main() {}
+
+fn unused$0() {}
+"#,
+            r#"
+fn main() {}
+"#,
+        );
+        check_fix(
+            r#"
+enum E {
+    A,
+    B$0,
+    C,
+}
+
+fn main() {
+    let _ = (E::A, E::C);
+}
+"#,
+            r#"
+enum E {
+    A,
+    C,
+}
+
+fn main() {
+    let _ = (E::A, E::C);
+}
+"#,
+        );
+        check_fix(
+            r#"
+//- minicore: copy
+struct S {
+    unused$0: u32,
+    used: u32,
+}
+
+fn main(s: &S) -> u32 {
+    s.used
+}
+"#,
+            r#"
+struct S {
+    used: u32,
+}
+
+fn main(s: &S) -> u32 {
+    s.used
+}
+"#,
+        );
+    }
+}
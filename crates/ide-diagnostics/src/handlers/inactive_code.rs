@@ -39,13 +39,11 @@ pub(crate) fn inactive_code(
 
 #[cfg(test)]
 mod tests {
-    use crate::{tests::check_diagnostics_with_config, DiagnosticsConfig};
+    use crate::tests::{check_diagnostics_with_config, test_config};
 
     pub(crate) fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
-        let config = DiagnosticsConfig {
-            disabled: std::iter::once("unlinked-file".to_owned()).collect(),
-            ..DiagnosticsConfig::test_sample()
-        };
+        let mut config = test_config();
+        config.disabled.insert("unlinked-file".to_owned());
         check_diagnostics_with_config(config, ra_fixture)
     }
 
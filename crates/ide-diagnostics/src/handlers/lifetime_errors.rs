@@ -0,0 +1,328 @@
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: does-not-live-long-enough
+//
+// This diagnostic is triggered when a reference to a local variable is used after the variable
+// went out of scope.
+pub(crate) fn does_not_live_long_enough(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::DoesNotLiveLongEnough,
+) -> Diagnostic {
+    let name = d.local.name(ctx.sema.db);
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0597"),
+        format!("`{}` does not live long enough", name.display(ctx.sema.db, ctx.edition)),
+        d.span,
+    )
+    .experimental() // calls of closures and function pointers don't relate lifetimes
+}
+
+// Diagnostic: returns-local-reference
+//
+// This diagnostic is triggered when a function returns a value referencing one of its local
+// variables or parameters.
+pub(crate) fn returns_local_reference(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::ReturnsLocalReference,
+) -> Diagnostic {
+    let name = d.local.name(ctx.sema.db);
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0515"),
+        format!(
+            "cannot return value referencing local variable `{}`",
+            name.display(ctx.sema.db, ctx.edition)
+        ),
+        d.span,
+    )
+    .experimental() // calls of closures and function pointers don't relate lifetimes
+}
+
+// Diagnostic: lifetime-may-not-live-long-enough
+//
+// This diagnostic is triggered when data with a lifetime of the function signature flows
+// somewhere that requires it to outlive another lifetime of the signature, without a bound saying
+// so.
+pub(crate) fn lifetime_may_not_live_long_enough(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::LifetimeMayNotLiveLongEnough,
+) -> Diagnostic {
+    let db = ctx.sema.db;
+    let lifetime = |it| match it {
+        hir::SignatureLifetime::Static => "'static".to_owned(),
+        hir::SignatureLifetime::Param(it) => it.name(db).display(db, ctx.edition).to_string(),
+        hir::SignatureLifetime::Elided(n) => format!("'{n}"),
+    };
+    let (expected, actual) = (lifetime(d.expected), lifetime(d.actual));
+    let message = if d.returned {
+        format!(
+            "lifetime may not live long enough: function was supposed to return data with \
+             lifetime `{expected}` but it is returning data with lifetime `{actual}`"
+        )
+    } else {
+        format!("lifetime may not live long enough: `{actual}` must outlive `{expected}`")
+    };
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::Ra("lifetime-may-not-live-long-enough", crate::Severity::Error),
+        message,
+        d.span,
+    )
+    .experimental() // calls of closures and function pointers don't relate lifetimes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn borrow_outlives_block() {
+        check_diagnostics(
+            r#"
+fn read(_: &i32) {}
+fn main() {
+    let r;
+    {
+        let x = 5;
+        r = &x;
+          //^^ error: `x` does not live long enough
+    }
+    read(r);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrow_dead_before_scope_end() {
+        check_diagnostics(
+            r#"
+fn read(_: &i32) {}
+fn main() {
+    let mut r = &0;
+    {
+        let x = 5;
+        r = &x;
+        read(r);
+    }
+    r = &1;
+    read(r);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn return_reference_to_local() {
+        check_diagnostics(
+            r#"
+fn f() -> &'static i32 {
+    let x = 5;
+    &x
+  //^^ error: cannot return value referencing local variable `x`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn return_reference_to_param() {
+        check_diagnostics(
+            r#"
+struct S { a: i32 }
+fn f(s: S, early: bool) -> &'static i32 {
+    if early {
+        return &s.a;
+             //^^^^ error: cannot return value referencing local variable `s`
+    }
+    loop {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn escaping_closure_is_not_a_returned_reference() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, fn
+fn f() -> impl Fn() -> i32 {
+    let x = 5;
+    || x
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn return_reference_through_param() {
+        check_diagnostics(
+            r#"
+struct S { a: i32 }
+fn f(s: &S) -> &i32 {
+    let r = &s.a;
+    r
+}
+fn g() -> &'static i32 {
+    &5
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn return_data_of_other_lifetime() {
+        check_diagnostics(
+            r#"
+fn pick<'a, 'b>(x: &'a str, y: &'b str, first: bool) -> &'a str {
+    if first {
+        x
+    } else {
+        y
+      //^ error: lifetime may not live long enough: function was supposed to return data with lifetime `'a` but it is returning data with lifetime `'b`
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn return_data_of_bounded_lifetime() {
+        check_diagnostics(
+            r#"
+struct S { a: i32 }
+fn f<'a, 'b: 'a>(x: &'a S, y: &'b S, first: bool) -> &'a i32 {
+    if first { &x.a } else { &y.a }
+}
+fn g<'a, 'b>(x: &'a S, y: &'b S) -> &'a i32 where 'b: 'a {
+    let _ = x;
+    &y.a
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn return_data_read_through_param() {
+        check_diagnostics(
+            r#"
+struct S { name: &'static str }
+fn f<'a, 'b>(_: &'a str, s: &'b S) -> &'a str {
+    s.name
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrow_pushed_into_param() {
+        check_diagnostics(
+            r#"
+struct V<T>(T);
+impl<T> V<T> {
+    fn push(&mut self, _: T) {}
+}
+fn f<'a>(v: &mut V<&'a i32>) {
+    let x = 5;
+    v.push(&x);
+         //^^ error: `x` does not live long enough
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrow_passed_as_static() {
+        check_diagnostics(
+            r#"
+fn keep(_: &'static i32) {}
+fn f(r: &i32) -> &i32 {
+    let x = 5;
+    keep(&x);
+       //^^ error: `x` does not live long enough
+    r
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrow_captured_by_closure_outlives_block() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, fn
+fn call<F: Fn() -> i32>(f: F) -> i32 {
+    f()
+}
+fn main() {
+    let c;
+    {
+        let x = 5;
+        c = || x;
+             //^ error: `x` does not live long enough
+    }
+    call(c);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn param_pushed_with_other_lifetime() {
+        check_diagnostics(
+            r#"
+struct V<T>(T);
+impl<T> V<T> {
+    fn push(&mut self, _: T) {}
+}
+fn f<'a, 'b>(v: &mut V<&'a str>, s: &'b str) {
+    v.push(s);
+  //^^^^^^^^^ error: lifetime may not live long enough: `'b` must outlive `'a`
+}
+fn g<'a, 'b: 'a>(v: &mut V<&'a str>, s: &'b str) {
+    v.push(s);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn return_data_of_elided_lifetime() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+fn f(x: &str, _: &str) -> &'static str {
+    x
+  //^ error: lifetime may not live long enough: function was supposed to return data with lifetime `'static` but it is returning data with lifetime `'1`
+}
+fn g(x: &str) -> &str {
+    x
+}
+struct S<'a> { name: &'a str }
+impl S<'_> {
+    fn name(&self, _: &str) -> &str {
+        self.name
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn return_data_with_implied_bound() {
+        check_diagnostics(
+            r#"
+fn f<'a, 'b>(x: &'a &'b str) -> &'a str {
+    *x
+}
+fn g<'a, 'b>(x: &'a str, _: &'b str) -> &'b str {
+    let y = x;
+    let z = y;
+    z
+  //^ error: lifetime may not live long enough: function was supposed to return data with lifetime `'b` but it is returning data with lifetime `'a`
+}
+"#,
+        );
+    }
+}
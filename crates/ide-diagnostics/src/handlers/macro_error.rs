@@ -23,13 +23,17 @@ pub(crate) fn macro_error(ctx: &DiagnosticsContext<'_>, d: &hir::MacroError) ->
 
 // Diagnostic: macro-def-error
 //
-// This diagnostic is shown for macro expansion errors.
+// This diagnostic is shown for errors in macro definitions, such as malformed rules, invalid
+// fragment specifiers or metavariables that are not bound by the matcher.
 pub(crate) fn macro_def_error(ctx: &DiagnosticsContext<'_>, d: &hir::MacroDefError) -> Diagnostic {
     // Use more accurate position if available.
     let display_range =
-        ctx.resolve_precise_location(&d.node.map(|it| it.syntax_node_ptr()), d.name);
+        ctx.resolve_precise_location(&d.node.map(|it| it.syntax_node_ptr()), d.precise_location);
     Diagnostic::new(
-        DiagnosticCode::Ra("macro-def-error", Severity::Error),
+        DiagnosticCode::Ra(
+            "macro-def-error",
+            if d.error { Severity::Error } else { Severity::Warning },
+        ),
         d.message.clone(),
         display_range,
     )
@@ -260,6 +264,37 @@ fn f() {
         )
     }
 
+    #[test]
+    fn def_lints() {
+        check_diagnostics(
+            r#"
+macro_rules! foo {
+    ($a:expr, $b:exp) => {};
+             //^ error: invalid fragment specifier for `$b`
+    ($($a:ident),*) => { $a $c };
+                        //^ error: variable `a` is still repeating at this depth
+                           //^ warn: unknown macro variable `c`
+    ($($a:ident),*) => { $(${ignore($a)} x)* };
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn def_lints_nested_macro_definition() {
+        check_diagnostics(
+            r#"
+macro_rules! outer {
+    ($name:ident) => {
+        macro_rules! $name {
+            ($inner:expr) => { $inner };
+        }
+    };
+}
+"#,
+        )
+    }
+
     #[test]
     fn expansion_syntax_diagnostic() {
         check_diagnostics(
@@ -427,12 +427,13 @@ fn main() {
         // special casing it is not trivial, we emit it.
 
         // Update: now MIR based `unused-variable` is taking over `unused-mut` for the same reason.
+        // Update: dead code is now reported as `unreachable-code`, and the variables in it aren't
+        // reported as unused.
         check_diagnostics(
             r#"
 fn main() {
     return;
     let mut x = 2;
-      //^^^^^ 💡 warn: unused variable
     &mut x;
 }
 "#,
@@ -442,7 +443,6 @@ fn main() {
 fn main() {
     loop {}
     let mut x = 2;
-      //^^^^^ 💡 warn: unused variable
     &mut x;
 }
 "#,
@@ -463,7 +463,6 @@ fn main(b: bool) {
         g();
     }
     let mut x = 2;
-      //^^^^^ 💡 warn: unused variable
     &mut x;
 }
 "#,
@@ -478,7 +477,6 @@ fn main(b: bool) {
         return;
     }
     let mut x = 2;
-      //^^^^^ 💡 warn: unused variable
     &mut x;
 }
 "#,
@@ -727,7 +725,7 @@ fn f() {
 
     #[test]
     fn overloaded_index() {
-        check_diagnostics(
+        check_diagnostics_with_disabled(
             r#"
 //- minicore: index, copy
 use core::ops::{Index, IndexMut};
@@ -776,12 +774,14 @@ fn f() {
     _ = y;
 }
 "#,
+            // The moves and borrows of `x` are not what this test is about.
+            &["E0382", "E0499", "E0502"],
         );
     }
 
     #[test]
     fn overloaded_deref() {
-        check_diagnostics(
+        check_diagnostics_with_disabled(
             r#"
 //- minicore: deref_mut, copy
 use core::ops::{Deref, DerefMut};
@@ -826,6 +826,7 @@ fn f() {
     }
 }
 "#,
+            &["E0382", "E0499", "E0502"],
         );
     }
 
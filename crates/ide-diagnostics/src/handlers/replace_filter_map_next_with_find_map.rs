@@ -55,14 +55,11 @@ fn fixes(
 
 #[cfg(test)]
 mod tests {
-    use crate::{
-        tests::{check_diagnostics_with_config, check_fix},
-        DiagnosticsConfig,
-    };
+    use crate::tests::{check_diagnostics_with_config, check_fix, test_config};
 
     #[track_caller]
     pub(crate) fn check_diagnostics(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
-        let mut config = DiagnosticsConfig::test_sample();
+        let mut config = test_config();
         config.disabled.insert("inactive-code".to_owned());
         config.disabled.insert("E0599".to_owned());
         check_diagnostics_with_config(config, ra_fixture)
@@ -0,0 +1,229 @@
+use hir::{db::ExpandDatabase, InFile};
+use ide_db::{source_change::SourceChange, text_edit::TextEdit, FileRange};
+use syntax::{ast, AstNode, SyntaxKind, SyntaxNode, SyntaxNodePtr, TextRange};
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unreachable-code
+//
+// This diagnostic is triggered when statements or the tail expression of a block come after an
+// expression that never completes, like a `return`, an endless `loop` or a call of a function
+// returning `!`.
+pub(crate) fn unreachable_code(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UnreachableCode,
+) -> Option<Diagnostic> {
+    let file_id = d.first.file_id.file_id()?;
+    let root = ctx.sema.db.parse_or_expand(d.first.file_id);
+    let first = block_element(d.first.value.to_node(&root))?;
+    let last = block_element(d.last.value.to_node(&root))?;
+    let range = TextRange::new(first.text_range().start(), last.text_range().end());
+
+    let message = if ast::Stmt::can_cast(first.kind()) {
+        "unreachable statement"
+    } else {
+        "unreachable expression"
+    };
+    let removed = match first.prev_sibling_or_token() {
+        Some(ws) if ws.kind() == SyntaxKind::WHITESPACE => range.cover(ws.text_range()),
+        _ => range,
+    };
+    let edit = TextEdit::delete(removed);
+    Some(
+        Diagnostic::new(
+            DiagnosticCode::RustcLint("unreachable_code"),
+            message,
+            FileRange { file_id: file_id.into(), range },
+        )
+        .with_main_node(InFile::new(d.first.file_id, SyntaxNodePtr::new(&first)))
+        .with_fixes(Some(vec![fix(
+            "remove_unreachable_code",
+            "Remove unreachable code",
+            SourceChange::from_text_edit(file_id, edit),
+            range,
+        )]))
+        .with_unused(true)
+        .experimental(),
+    )
+}
+
+/// The statement or tail expression of a block that `node` belongs to.
+fn block_element(node: SyntaxNode) -> Option<SyntaxNode> {
+    node.ancestors().find(|it| it.parent().is_some_and(|it| ast::StmtList::can_cast(it.kind())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{
+        check_diagnostics_with_config, check_fix_with_config, test_config_enabling,
+    };
+
+    #[track_caller]
+    fn check_diagnostics(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
+        check_diagnostics_with_config(test_config_enabling("unreachable_code"), ra_fixture);
+    }
+
+    #[track_caller]
+    fn check_fix(
+        #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
+        #[rust_analyzer::rust_fixture] ra_fixture_after: &str,
+    ) {
+        check_fix_with_config(
+            test_config_enabling("unreachable_code"),
+            ra_fixture_before,
+            ra_fixture_after,
+        );
+    }
+
+    #[test]
+    fn after_return() {
+        check_diagnostics(
+            r#"
+fn f() -> u32 {
+    return 0;
+    let x = 1; x + 1
+  //^^^^^^^^^^^^^^^^ 💡 warn: unreachable statement
+}
+
+fn g(c: bool) {
+    match c {
+        true => return,
+        false => return,
+    }
+    g(c);
+  //^^^^^ 💡 warn: unreachable statement
+}
+
+fn h() -> u32 {
+    return 0;
+    1
+  //^ 💡 warn: unreachable expression
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn after_never_typed_expressions() {
+        check_diagnostics(
+            r#"
+//- minicore: panic
+fn never() -> ! {
+    loop {}
+}
+
+fn f() {
+    never();
+    f();
+  //^^^^ 💡 warn: unreachable statement
+}
+
+fn g() {
+    loop {}
+    g();
+  //^^^^ 💡 warn: unreachable statement
+}
+
+fn h() {
+    loop {
+        break;
+        h();
+      //^^^^ 💡 warn: unreachable statement
+    }
+    let _x = never();
+    h();
+  //^^^^ 💡 warn: unreachable statement
+}
+
+fn i() {
+    panic!("");
+    i();
+  //^^^^ 💡 warn: unreachable statement
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn reachable() {
+        check_diagnostics(
+            r#"
+fn f(c: bool) -> u32 {
+    if c {
+        return 0;
+    }
+    loop {
+        if c {
+            break;
+        }
+    }
+    let _g = |c: bool| {
+        if c {
+            return;
+        }
+        let _ = c;
+    };
+    1
+}
+
+#[allow(unreachable_code)]
+fn h() {
+    return;
+    h();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn remove_unreachable_code() {
+        check_fix(
+            r#"
+fn f() -> u32 {
+    return 0;
+    let x$0 = 1;
+    x + 1
+}
+"#,
+            r#"
+fn f() -> u32 {
+    return 0;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn items_are_kept() {
+        check_diagnostics(
+            r#"
+fn f() -> u32 {
+    return 0;
+    let x = 1;
+  //^^^^^^^^^^ 💡 warn: unreachable statement
+    fn g() -> u32 { 1 }
+    struct S;
+    x + g()
+  //^^^^^^^ 💡 warn: unreachable expression
+}
+"#,
+        );
+        check_fix(
+            r#"
+fn f() -> u32 {
+    return 0;
+    let x = 1;
+    fn g() -> u32 { 1 }
+    x + g$0()
+}
+"#,
+            r#"
+fn f() -> u32 {
+    return 0;
+    let x = 1;
+    fn g() -> u32 { 1 }
+}
+"#,
+        );
+    }
+}
@@ -284,12 +284,9 @@ fn method_fix(
 #[cfg(test)]
 mod tests {
 
-    use crate::{
-        tests::{
-            check_diagnostics, check_diagnostics_with_config, check_diagnostics_with_disabled,
-            check_fix, check_no_fix,
-        },
-        DiagnosticsConfig,
+    use crate::tests::{
+        check_diagnostics, check_diagnostics_with_config, check_diagnostics_with_disabled,
+        check_fix, check_no_fix, test_config,
     };
 
     #[test]
@@ -372,7 +369,7 @@ fn foo() {
 
     #[test]
     fn no_diagnostic_for_missing_name() {
-        let mut config = DiagnosticsConfig::test_sample();
+        let mut config = test_config();
         config.disabled.insert("syntax-error".to_owned());
         check_diagnostics_with_config(config, "fn foo() { (). }");
     }
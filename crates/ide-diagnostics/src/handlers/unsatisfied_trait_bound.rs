@@ -0,0 +1,335 @@
+use either::Either;
+use hir::{HasSource, HirDisplay, TraitRef};
+use ide_db::{assists::Assist, source_change::SourceChange, text_edit::TextEdit};
+use syntax::{
+    ast::{self, edit::IndentLevel, HasAttrs, HasName, HasTypeBounds},
+    AstNode, SyntaxKind, TextRange,
+};
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+/// The standard library traits that `#[derive]` can implement.
+const DERIVABLE_TRAITS: &[&str] =
+    &["Clone", "Copy", "Debug", "Default", "Eq", "Hash", "Ord", "PartialEq", "PartialOrd"];
+
+// Diagnostic: unsatisfied-trait-bound
+//
+// This diagnostic is triggered when a function, tuple struct or enum variant is called with
+// types that don't implement the traits its where clauses require. If the bound doesn't hold
+// because an impl that would apply requires another bound, the chain of such bounds is shown.
+//
+// The bounds of operators (e.g. a missing `Add` impl for `a + b`) and of struct literals are not
+// checked yet.
+pub(crate) fn unsatisfied_trait_bound(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UnsatisfiedTraitBound,
+) -> Diagnostic {
+    let db = ctx.sema.db;
+    let innermost = d.causes.last().unwrap_or(&d.bound);
+    let mut message = format!(
+        "the trait bound `{}: {}` is not satisfied",
+        innermost.self_ty().display(db, ctx.edition),
+        innermost.display(db, ctx.edition)
+    );
+    // Each trait ref is required for the one before it to hold.
+    for required in std::iter::once(&d.bound).chain(&d.causes).rev().skip(1) {
+        message.push_str(&format!(
+            "; required for `{}` to implement `{}`",
+            required.self_ty().display(db, ctx.edition),
+            required.display(db, ctx.edition)
+        ));
+    }
+    if let Some(name) = d.required_by.name(db) {
+        message.push_str(&format!("; required by a bound in `{}`", name.display(db, ctx.edition)));
+    }
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0277"),
+        message,
+        d.expr.map(Into::into),
+    )
+    .experimental()
+    .with_fixes(fixes(ctx, d, innermost))
+}
+
+fn fixes(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UnsatisfiedTraitBound,
+    innermost: &TraitRef,
+) -> Option<Vec<Assist>> {
+    let range = ctx.sema.diagnostics_display_range(d.expr.map(Into::into)).range;
+    let fix = derive_fix(ctx, innermost, range).or_else(|| add_bound_fix(ctx, innermost, range))?;
+    Some(vec![fix])
+}
+
+fn derive_fix(ctx: &DiagnosticsContext<'_>, bound: &TraitRef, range: TextRange) -> Option<Assist> {
+    let db = ctx.sema.db;
+    let trait_ = bound.trait_();
+    let trait_name = trait_.name(db).display(db, ctx.edition).to_string();
+    let is_derivable = trait_.module(db).krate().origin(db).is_lang()
+        && DERIVABLE_TRAITS.contains(&trait_name.as_str());
+    let adt = bound.self_ty().as_adt()?;
+    if !is_derivable || !adt.module(db).krate().origin(db).is_local() {
+        return None;
+    }
+    let source = adt.source(db)?;
+    let file_id = source.file_id.file_id()?;
+    let adt = source.value;
+
+    let existing_derive = adt.attrs().find_map(|attr| {
+        if attr.simple_name()? != "derive" {
+            return None;
+        }
+        let token_tree = attr.token_tree()?;
+        token_tree.r_paren_token().map(|r_paren| (token_tree, r_paren))
+    });
+    let edit = match existing_derive {
+        Some((token_tree, r_paren)) => {
+            let is_empty = token_tree.syntax().children_with_tokens().count() == 2;
+            let insert = if is_empty { trait_name.clone() } else { format!(", {trait_name}") };
+            TextEdit::insert(r_paren.text_range().start(), insert)
+        }
+        None => {
+            // Keep the new attribute after doc comments and the other attributes.
+            let item_start = adt.syntax().children_with_tokens().find(|it| {
+                !matches!(
+                    it.kind(),
+                    SyntaxKind::ATTR | SyntaxKind::COMMENT | SyntaxKind::WHITESPACE
+                )
+            })?;
+            let indent = IndentLevel::from_node(adt.syntax());
+            TextEdit::insert(
+                item_start.text_range().start(),
+                format!("#[derive({trait_name})]\n{indent}"),
+            )
+        }
+    };
+    Some(fix(
+        "derive_trait",
+        &format!("Derive `{trait_name}`"),
+        SourceChange::from_text_edit(file_id, edit),
+        range,
+    ))
+}
+
+fn add_bound_fix(
+    ctx: &DiagnosticsContext<'_>,
+    bound: &TraitRef,
+    range: TextRange,
+) -> Option<Assist> {
+    let db = ctx.sema.db;
+    let type_param = bound.self_ty().as_type_param(db)?;
+    if type_param.is_implicit(db) || !type_param.module(db).krate().origin(db).is_local() {
+        return None;
+    }
+    let source = type_param.merge().source(db)?;
+    let file_id = source.file_id.file_id()?;
+    let Either::Left(ast::TypeOrConstParam::Type(param)) = source.value else {
+        return None;
+    };
+
+    let bound_text = bound.display(db, ctx.edition).to_string();
+    let edit = match param.type_bound_list() {
+        Some(bounds) => {
+            TextEdit::insert(bounds.syntax().text_range().end(), format!(" + {bound_text}"))
+        }
+        None => {
+            TextEdit::insert(param.name()?.syntax().text_range().end(), format!(": {bound_text}"))
+        }
+    };
+    Some(fix(
+        "add_trait_bound",
+        &format!("Add `{}: {bound_text}` bound", type_param.name(db).display(db, ctx.edition)),
+        SourceChange::from_text_edit(file_id, edit),
+        range,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn unsatisfied_bound_of_call() {
+        check_diagnostics(
+            r#"
+//- minicore: clone, builtin_impls
+struct S;
+fn needs_clone<T: Clone>(_: T) {}
+
+fn f() {
+    needs_clone(S);
+  //^^^^^^^^^^^^^^ 💡 error: the trait bound `S: Clone` is not satisfied; required by a bound in `needs_clone`
+    needs_clone(0u32);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn unsatisfied_bound_of_method_call() {
+        check_diagnostics(
+            r#"
+trait Tr {}
+struct S;
+struct Foo;
+impl Foo {
+    fn method<T>(&self, _: T) where T: Tr {}
+}
+
+fn f() {
+    Foo.method(S);
+  //^^^^^^^^^^^^^ error: the trait bound `S: Tr` is not satisfied; required by a bound in `method`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn explains_the_chain_of_impls() {
+        check_diagnostics(
+            r#"
+trait Tr {}
+struct Wrapper<T>(T);
+impl<T: Tr> Tr for Wrapper<T> {}
+struct S;
+fn needs_tr<T: Tr>(_: T) {}
+
+fn f() {
+    needs_tr(Wrapper(Wrapper(S)));
+  //^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ error: the trait bound `S: Tr` is not satisfied; required for `Wrapper<S>` to implement `Tr`; required for `Wrapper<Wrapper<S>>` to implement `Tr`; required by a bound in `needs_tr`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn bounds_failing_after_inference() {
+        check_diagnostics(
+            r#"
+trait Tr {}
+impl Tr for (u8, u8) {}
+fn needs_tr<T: Tr>(_: T) {}
+
+fn f() {
+    let x;
+    needs_tr((x, 0u32));
+  //^^^^^^^^^^^^^^^^^^^ error: the trait bound `(u8, u32): Tr` is not satisfied; required by a bound in `needs_tr`
+    let y;
+    needs_tr((y, 0u8));
+    x = 0u8;
+    y = 0u8;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn undecided_bounds_are_not_reported() {
+        check_diagnostics(
+            r#"
+trait Tr {}
+fn needs_tr<T: Tr>(_: T) {}
+
+fn f<U: Tr>(u: U) {
+    needs_tr(u);
+    needs_tr(unknown);
+           //^^^^^^^ error: no such value in this scope
+    needs_tr(loop {});
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn derive_trait() {
+        check_fix(
+            r#"
+//- minicore: clone, derive
+/// Docs.
+struct S;
+fn needs_clone<T: Clone>(_: T) {}
+
+fn f() {
+    needs_clone$0(S);
+}
+"#,
+            r#"
+/// Docs.
+#[derive(Clone)]
+struct S;
+fn needs_clone<T: Clone>(_: T) {}
+
+fn f() {
+    needs_clone(S);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn derive_trait_in_existing_derive() {
+        check_fix(
+            r#"
+//- minicore: clone, copy, derive
+#[derive(Copy)]
+struct S;
+fn needs_clone<T: Clone>(_: T) {}
+
+fn f() {
+    needs_clone$0(S);
+}
+"#,
+            r#"
+#[derive(Copy, Clone)]
+struct S;
+fn needs_clone<T: Clone>(_: T) {}
+
+fn f() {
+    needs_clone(S);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_trait_bound() {
+        check_fix(
+            r#"
+trait Tr {}
+fn needs_tr<T: Tr>(_: T) {}
+
+fn f<U>(u: U) {
+    needs_tr$0(u);
+}
+"#,
+            r#"
+trait Tr {}
+fn needs_tr<T: Tr>(_: T) {}
+
+fn f<U: Tr>(u: U) {
+    needs_tr(u);
+}
+"#,
+        );
+        check_fix(
+            r#"
+//- minicore: clone
+trait Tr {}
+fn needs_tr<T: Tr>(_: T) {}
+
+fn f<U: Clone>(u: U) {
+    needs_tr$0(u);
+}
+"#,
+            r#"
+trait Tr {}
+fn needs_tr<T: Tr>(_: T) {}
+
+fn f<U: Clone + Tr>(u: U) {
+    needs_tr(u);
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use hir::{Crate, InFile};
+use ide_db::{
+    imports::unused_imports::{leaf_use_trees, UnusedImportsFinder},
+    source_change::{SourceChange, SourceChangeBuilder},
+    EditionedFileId, FileRange, FxHashMap, Severity,
+};
+use syntax::{
+    ast::{self, HasVisibility},
+    AstNode, SyntaxNodePtr,
+};
+
+use crate::{fix, handle_lints, unresolved_fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unused-imports
+//
+// This diagnostic is triggered when nothing imported by a `use` item is used in the module
+// containing it.
+pub(crate) fn unused_imports(
+    ctx: &DiagnosticsContext<'_>,
+    acc: &mut Vec<Diagnostic>,
+    file_id: EditionedFileId,
+    krate: Crate,
+) {
+    let mut finder = UnusedImportsFinder::new(&ctx.sema);
+    for use_tree in unused_use_trees(ctx, &mut finder, file_id) {
+        let range = use_tree.syntax().text_range();
+
+        let mut builder = SourceChangeBuilder::new(file_id);
+        builder.make_mut(use_tree.clone()).remove_recursive();
+        let remove_one =
+            fix("remove_unused_import", "Remove unused import", builder.finish(), range);
+
+        let mut remove_all = unresolved_fix(
+            "remove_all_unused_imports",
+            "Remove all unused imports in crate",
+            range,
+        );
+        if ctx.resolve.should_resolve(&remove_all.id) {
+            remove_all.source_change = Some(remove_all_in_crate(ctx, krate));
+        }
+
+        acc.push(diagnostic(file_id, &use_tree).with_fixes(Some(vec![remove_one, remove_all])));
+    }
+}
+
+fn diagnostic(file_id: EditionedFileId, use_tree: &ast::UseTree) -> Diagnostic {
+    Diagnostic::new(
+        DiagnosticCode::RustcLint("unused_imports"),
+        format!("unused import: `{}`", use_tree.syntax().text()),
+        FileRange { file_id: file_id.into(), range: use_tree.syntax().text_range() },
+    )
+    .with_main_node(InFile::new(file_id.into(), SyntaxNodePtr::new(use_tree.syntax())))
+    .with_unused(true)
+    .experimental()
+}
+
+/// Returns the unused use trees of the private `use` items in `file_id`.
+fn unused_use_trees(
+    ctx: &DiagnosticsContext<'_>,
+    finder: &mut UnusedImportsFinder<'_, '_>,
+    file_id: EditionedFileId,
+) -> Vec<ast::UseTree> {
+    ctx.sema
+        .parse(file_id)
+        .syntax()
+        .descendants()
+        .filter_map(ast::Use::cast)
+        // Re-exports may be used from anywhere, which we don't search.
+        .filter(|it| it.visibility().is_none())
+        .flat_map(|it| leaf_use_trees(&it))
+        .filter(|it| finder.is_unused(it))
+        .collect()
+}
+
+/// Removes the unused imports in all files of `krate`, except for those where `unused_imports`
+/// is allowed.
+fn remove_all_in_crate(ctx: &DiagnosticsContext<'_>, krate: Crate) -> SourceChange {
+    let db = ctx.sema.db;
+    let mut finder = UnusedImportsFinder::new(&ctx.sema);
+    let mut files = krate
+        .modules(db)
+        .into_iter()
+        .filter_map(|module| module.as_source_file_id(db))
+        .collect::<Vec<_>>();
+    files.sort_unstable_by_key(|it| it.file_id());
+    files.dedup();
+
+    let mut builder = None::<SourceChangeBuilder>;
+    let mut lint_cache = FxHashMap::default();
+    for file_id in files {
+        let use_trees = unused_use_trees(ctx, &mut finder, file_id);
+        if use_trees.is_empty() {
+            continue;
+        }
+        let mut diagnostics =
+            use_trees.iter().map(|it| diagnostic(file_id, it)).collect::<Vec<_>>();
+        let mut lints = use_trees
+            .iter()
+            .zip(&mut diagnostics)
+            .map(|(it, diag)| (InFile::new(file_id.into(), it.syntax().clone()), diag))
+            .collect::<Vec<_>>();
+        handle_lints(&ctx.sema, &mut lint_cache, &mut lints, &mut Vec::new(), file_id.edition());
+
+        let builder = match &mut builder {
+            Some(builder) => {
+                builder.edit_file(file_id);
+                builder
+            }
+            None => builder.insert(SourceChangeBuilder::new(file_id)),
+        };
+        let use_trees = use_trees
+            .into_iter()
+            .zip(&diagnostics)
+            .filter(|(_, diag)| diag.severity != Severity::Allow)
+            .map(|(it, _)| builder.make_mut(it))
+            .collect::<Vec<_>>();
+        for use_tree in use_trees {
+            use_tree.remove_recursive();
+        }
+    }
+    builder.map(SourceChangeBuilder::finish).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{
+        check_diagnostics_with_config, check_fix_with_config, check_fixes_with_config,
+        test_config_enabling,
+    };
+
+    #[track_caller]
+    fn check_diagnostics(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
+        check_diagnostics_with_config(test_config_enabling("unused_imports"), ra_fixture);
+    }
+
+    #[track_caller]
+    fn check_fix(
+        #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
+        #[rust_analyzer::rust_fixture] ra_fixture_after: &str,
+    ) {
+        check_fix_with_config(
+            test_config_enabling("unused_imports"),
+            ra_fixture_before,
+            ra_fixture_after,
+        );
+    }
+
+    #[track_caller]
+    fn check_fixes(
+        #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
+        ra_fixtures_after: Vec<&str>,
+    ) {
+        check_fixes_with_config(
+            test_config_enabling("unused_imports"),
+            ra_fixture_before,
+            ra_fixtures_after,
+        );
+    }
+
+    #[test]
+    fn unused_imports() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub struct Used;
+    pub struct Unused;
+    pub fn unused_fn() {}
+    pub mod glob {
+        pub struct Unused;
+    }
+}
+
+use foo::Used;
+use foo::Unused;
+  //^^^^^^^^^^^ 💡 warn: unused import: `foo::Unused`
+use foo::{glob::*, unused_fn};
+        //^^^^^^^ 💡 warn: unused import: `glob::*`
+                 //^^^^^^^^^ 💡 warn: unused import: `unused_fn`
+pub use foo::Unused as Reexported;
+#[allow(unused_imports)]
+use foo::unused_fn as allowed;
+
+fn f(_: Used) {
+    use foo::Used;
+      //^^^^^^^^^ 💡 warn: unused import: `foo::Used`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn trait_used_for_methods() {
+        check_diagnostics(
+            r#"
+mod foo {
+    pub trait Tr {
+        fn method(&self) {}
+    }
+    impl Tr for () {}
+    pub trait Unused {}
+}
+
+use foo::Tr as _;
+use foo::Unused as _;
+  //^^^^^^^^^^^^^^^^ 💡 warn: unused import: `foo::Unused as _`
+
+fn f() {
+    ().method();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn name_in_several_namespaces() {
+        check_diagnostics(
+            r#"
+//- minicore: derive, hash
+use core::hash::Hash;
+
+#[derive(Hash)]
+struct S;
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_2_used() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, addr_of
+use core::ptr::{addr_of, addr_of_mut};
+
+static mut STATIC_MUT: i32 = 0;
+
+fn main() {
+    let _x = addr_of!(STATIC_MUT);
+    let _x = addr_of_mut!(STATIC_MUT);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn used_in_macro_rules() {
+        check_diagnostics(
+            r#"
+//- /lib.rs crate:lib deps:core
+use core::{concat, panic};
+
+macro_rules! m {
+    () => {
+        panic!(concat!(""));
+    };
+}
+
+fn f() {
+    m!();
+}
+//- /core.rs crate:core
+#[macro_export]
+#[rustc_builtin_macro]
+macro_rules! concat { () => {} }
+#[macro_export]
+macro_rules! panic { ($($tt:tt)*) => {} }
+"#,
+        );
+    }
+
+    #[test]
+    fn remove_unused_import() {
+        check_fix(
+            r#"
+mod foo {
+    pub struct A;
+    pub struct B;
+}
+use foo::{A, B$0};
+
+fn f(_: A) {}
+"#,
+            r#"
+mod foo {
+    pub struct A;
+    pub struct B;
+}
+use foo::A;
+
+fn f(_: A) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn remove_all_unused_imports_in_crate() {
+        check_fixes(
+            r#"
+mod foo {
+    pub struct A;
+    pub struct B;
+}
+mod bar {
+    use crate::foo::A;
+    #[allow(unused_imports)]
+    use crate::foo::B;
+}
+use foo::{A, B$0};
+"#,
+            vec![
+                r#"
+mod foo {
+    pub struct A;
+    pub struct B;
+}
+mod bar {
+    use crate::foo::A;
+    #[allow(unused_imports)]
+    use crate::foo::B;
+}
+use foo::A;
+"#,
+                r#"
+mod foo {
+    pub struct A;
+    pub struct B;
+}
+mod bar {
+    #[allow(unused_imports)]
+    use crate::foo::B;
+}
+"#,
+            ],
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use hir::{db::ExpandDatabase, Adt, ModuleDef, MustUsePath};
+use ide_db::{
+    assists::Assist, famous_defs::FamousDefs, source_change::SourceChange, text_edit::TextEdit,
+};
+use syntax::{ast, match_ast, AstNode};
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unused-must-use
+//
+// This diagnostic is triggered when a value that must be used is discarded, like an unhandled
+// `Result`, a future that is never awaited or the return value of a `#[must_use]` function.
+pub(crate) fn unused_must_use(ctx: &DiagnosticsContext<'_>, d: &hir::UnusedMustUse) -> Diagnostic {
+    let message = message(ctx, &d.path, String::new(), String::new());
+    let message = match &d.reason {
+        Some(reason) => format!("{message}: {reason}"),
+        None => message,
+    };
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcLint("unused_must_use"),
+        message,
+        d.expr.map(Into::into),
+    )
+    .with_fixes(fixes(ctx, d))
+}
+
+/// Describes the unused value like rustc, e.g. "unused pinned boxed `Future` trait object".
+fn message(ctx: &DiagnosticsContext<'_>, path: &MustUsePath, pre: String, post: String) -> String {
+    let db = ctx.sema.db;
+    match path {
+        MustUsePath::Def(ModuleDef::Function(it)) => format!(
+            "unused return value of `{}`{post} that must be used",
+            it.name(db).display(db, ctx.edition)
+        ),
+        MustUsePath::Def(it) => format!(
+            "unused {pre}`{}`{post} that must be used",
+            it.name(db).map(|it| it.display(db, ctx.edition).to_string()).unwrap_or_default()
+        ),
+        MustUsePath::Opaque(it) => format!(
+            "unused {pre}implementer of `{}`{post} that must be used",
+            it.name(db).display(db, ctx.edition)
+        ),
+        MustUsePath::TraitObject(it) => format!(
+            "unused {pre}`{}` trait object{post} that must be used",
+            it.name(db).display(db, ctx.edition)
+        ),
+        MustUsePath::Boxed(it) => message(ctx, it, format!("{pre}boxed "), post),
+        MustUsePath::Pinned(it) => message(ctx, it, format!("{pre}pinned "), post),
+        MustUsePath::Array(it) => message(ctx, it, format!("{pre}array of "), post),
+        MustUsePath::TupleElement(idx, it) => {
+            message(ctx, it, pre, format!("{post} in tuple element {idx}"))
+        }
+    }
+}
+
+fn fixes(ctx: &DiagnosticsContext<'_>, d: &hir::UnusedMustUse) -> Option<Vec<Assist>> {
+    let file_id = d.expr.file_id.file_id()?;
+    let db = ctx.sema.db;
+    let root = db.parse_or_expand(d.expr.file_id);
+    let expr = d.expr.value.to_node(&root);
+    let ty = ctx.sema.type_of_expr(&expr)?.original;
+    let range = expr.syntax().text_range();
+    let postfix = |suffix: &str| {
+        let mut edit = TextEdit::builder();
+        if is_postfix_operand(&expr) {
+            edit.insert(range.end(), suffix.to_owned());
+        } else {
+            edit.insert(range.start(), "(".to_owned());
+            edit.insert(range.end(), format!("){suffix}"));
+        }
+        SourceChange::from_text_edit(file_id, edit.finish())
+    };
+
+    let famous_defs = FamousDefs(&ctx.sema, ctx.sema.scope(expr.syntax())?.krate());
+    let mut fixes = Vec::new();
+    let is_future =
+        famous_defs.core_future_Future().is_some_and(|future| ty.impls_trait(db, future, &[]));
+    if is_future && is_in_async_context(&expr) {
+        fixes.push(fix("add_await", "Add `.await`", postfix(".await"), range));
+    }
+    let ret_ty = enclosing_fn(&expr).and_then(|it| ctx.sema.to_def(&it)).and_then(|func| {
+        if func.is_async(db) {
+            func.async_ret_type(db)
+        } else {
+            Some(func.ret_type(db))
+        }
+    });
+    if let (Some(Adt::Enum(adt)), Some(ret_ty)) = (ty.as_adt(), ret_ty) {
+        let try_enums = [famous_defs.core_result_Result(), famous_defs.core_option_Option()];
+        if try_enums.contains(&Some(adt)) && ret_ty.as_adt() == Some(Adt::Enum(adt)) {
+            fixes.push(fix("add_question_mark", "Add `?`", postfix("?"), range));
+        }
+    }
+    fixes.push(fix(
+        "bind_to_let_underscore",
+        "Bind to `let _`",
+        SourceChange::from_text_edit(
+            file_id,
+            TextEdit::insert(range.start(), "let _ = ".to_owned()),
+        ),
+        range,
+    ));
+    Some(fixes)
+}
+
+/// Whether `.await` and `?` can be applied to `expr` without parentheses.
+fn is_postfix_operand(expr: &ast::Expr) -> bool {
+    matches!(
+        expr,
+        ast::Expr::AwaitExpr(_)
+            | ast::Expr::CallExpr(_)
+            | ast::Expr::FieldExpr(_)
+            | ast::Expr::IndexExpr(_)
+            | ast::Expr::Literal(_)
+            | ast::Expr::MacroExpr(_)
+            | ast::Expr::MethodCallExpr(_)
+            | ast::Expr::ParenExpr(_)
+            | ast::Expr::PathExpr(_)
+            | ast::Expr::TryExpr(_)
+    )
+}
+
+fn is_in_async_context(expr: &ast::Expr) -> bool {
+    expr.syntax()
+        .ancestors()
+        .find_map(|it| {
+            match_ast! {
+                match it {
+                    ast::Fn(it) => Some(it.async_token().is_some()),
+                    ast::ClosureExpr(it) => Some(it.async_token().is_some()),
+                    ast::BlockExpr(it) => it.async_token().map(|_| true),
+                    _ => None,
+                }
+            }
+        })
+        .unwrap_or(false)
+}
+
+/// The function `?` returns from when used on `expr`, if it isn't in a closure or a block that
+/// `?` would return from instead.
+fn enclosing_fn(expr: &ast::Expr) -> Option<ast::Fn> {
+    expr.syntax()
+        .ancestors()
+        .find_map(|it| {
+            match_ast! {
+                match it {
+                    ast::Fn(it) => Some(Some(it)),
+                    ast::ClosureExpr(_) => Some(None),
+                    ast::BlockExpr(it) => {
+                        (it.async_token().is_some() || it.try_token().is_some()).then_some(None)
+                    },
+                    _ => None,
+                }
+            }
+        })
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{
+        check_diagnostics_with_config, check_fixes_with_config, test_config_enabling,
+    };
+
+    #[track_caller]
+    fn check_diagnostics(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
+        check_diagnostics_with_config(test_config_enabling("unused_must_use"), ra_fixture);
+    }
+
+    #[track_caller]
+    fn check_fixes(
+        #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
+        ra_fixtures_after: Vec<&str>,
+    ) {
+        check_fixes_with_config(
+            test_config_enabling("unused_must_use"),
+            ra_fixture_before,
+            ra_fixtures_after,
+        );
+    }
+
+    #[test]
+    fn unused_must_use() {
+        check_diagnostics(
+            r#"
+//- minicore: result, future, iterators
+#[must_use]
+fn must_use() -> u32 { 0 }
+#[must_use = "reason"]
+struct MustUse;
+fn result() -> Result<(), ()> { Ok(()) }
+async fn future() {}
+
+fn f() {
+    must_use();
+  //^^^^^^^^^^ 💡 warn: unused return value of `must_use` that must be used
+    MustUse;
+  //^^^^^^^ 💡 warn: unused `MustUse` that must be used: reason
+    result();
+  //^^^^^^^^ 💡 warn: unused `Result` that must be used: this `Result` may be an `Err` variant, which should be handled
+    future();
+  //^^^^^^^^ 💡 warn: unused implementer of `Future` that must be used: futures do nothing unless you `.await` or poll them
+    core::iter::repeat(0).take(1);
+  //^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 warn: unused `Take` that must be used: iterators are lazy and do nothing unless consumed
+    let _ = must_use();
+    let _x = result();
+    #[allow(unused_must_use)]
+    result();
+}
+
+fn g() -> u32 {
+    must_use()
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn function_and_type() {
+        check_diagnostics(
+            r#"
+#[must_use]
+struct MustUse;
+#[must_use = "reason"]
+fn must_use() -> MustUse { MustUse }
+
+fn f() {
+    must_use();
+  //^^^^^^^^^^ 💡 warn: unused `MustUse` that must be used
+  //^^^^^^^^^^ 💡 warn: unused return value of `must_use` that must be used: reason
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn wrapped_types() {
+        check_diagnostics(
+            r#"
+//- minicore: future, pin, result
+#[lang = "owned_box"]
+struct Box<T: ?Sized>(*mut T);
+
+fn boxed() -> Box<Result<(), ()>> { loop {} }
+fn pinned() -> core::pin::Pin<Box<dyn core::future::Future<Output = ()>>> { loop {} }
+fn tuple() -> (u32, Result<(), ()>) { loop {} }
+fn array() -> [Result<(), ()>; 2] { loop {} }
+fn empty_array() -> [Result<(), ()>; 0] { loop {} }
+
+fn f() {
+    boxed();
+  //^^^^^^^ 💡 warn: unused boxed `Result` that must be used: this `Result` may be an `Err` variant, which should be handled
+    pinned();
+  //^^^^^^^^ 💡 warn: unused pinned boxed `Future` trait object that must be used: futures do nothing unless you `.await` or poll them
+    tuple();
+  //^^^^^^^ 💡 warn: unused `Result` in tuple element 1 that must be used: this `Result` may be an `Err` variant, which should be handled
+    array();
+  //^^^^^^^ 💡 warn: unused array of `Result` that must be used: this `Result` may be an `Err` variant, which should be handled
+    empty_array();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_await() {
+        check_fixes(
+            r#"
+//- minicore: future
+async fn future() {}
+
+async fn f() {
+    future$0();
+}
+"#,
+            vec![
+                r#"
+async fn future() {}
+
+async fn f() {
+    future().await;
+}
+"#,
+                r#"
+async fn future() {}
+
+async fn f() {
+    let _ = future();
+}
+"#,
+            ],
+        );
+    }
+
+    #[test]
+    fn add_question_mark() {
+        check_fixes(
+            r#"
+//- minicore: result
+fn result() -> Result<(), ()> { Ok(()) }
+
+fn f() -> Result<(), ()> {
+    result$0();
+    Ok(())
+}
+"#,
+            vec![
+                r#"
+fn result() -> Result<(), ()> { Ok(()) }
+
+fn f() -> Result<(), ()> {
+    result()?;
+    Ok(())
+}
+"#,
+                r#"
+fn result() -> Result<(), ()> { Ok(()) }
+
+fn f() -> Result<(), ()> {
+    let _ = result();
+    Ok(())
+}
+"#,
+            ],
+        );
+    }
+
+    #[test]
+    fn add_await_and_question_mark_with_parentheses() {
+        check_fixes(
+            r#"
+//- minicore: future, result
+async fn result() -> Result<(), ()> { Ok(()) }
+
+async fn f(r: Result<(), ()>) -> Result<(), ()> {
+    result().await;
+    if true { r } else { r$0 };
+    Ok(())
+}
+"#,
+            vec![
+                r#"
+async fn result() -> Result<(), ()> { Ok(()) }
+
+async fn f(r: Result<(), ()>) -> Result<(), ()> {
+    result().await;
+    (if true { r } else { r })?;
+    Ok(())
+}
+"#,
+            ],
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use hir::PathResolution;
+use syntax::{ast, AstNode, SyntaxNodePtr};
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: use-after-move
+//
+// This diagnostic is triggered when a local variable is used after its value has been moved out.
+pub(crate) fn use_after_move(ctx: &DiagnosticsContext<'_>, d: &hir::UseAfterMove) -> Diagnostic {
+    // MIR only knows the span of the whole statement or call the use happens in, narrow it down
+    // to the path that actually refers to the local.
+    let root = ctx.sema.parse_or_expand(d.span.file_id);
+    let node = d.span.value.to_node(&root);
+    let node = match node.ancestors().find_map(ast::LetStmt::cast) {
+        Some(let_stmt) if ast::Pat::can_cast(node.kind()) => let_stmt.syntax().clone(),
+        _ => node,
+    };
+    let span = node
+        .descendants()
+        .filter_map(ast::PathExpr::cast)
+        .find(|it| {
+            it.path().and_then(|path| ctx.sema.resolve_path(&path))
+                == Some(PathResolution::Local(d.local))
+        })
+        .map_or(d.span, |it| d.span.with_value(SyntaxNodePtr::new(it.syntax())));
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0382"),
+        format!(
+            "use of moved value: `{}`",
+            d.local.name(ctx.sema.db).display(ctx.sema.db, ctx.edition)
+        ),
+        span,
+    )
+    .experimental() // we don't track partial moves and drop elaboration precisely yet
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn use_after_move() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct X;
+fn consume(_: X) {}
+fn main() {
+    let a = X;
+    consume(a);
+    consume(a);
+          //^ error: use of moved value: `a`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn move_into_binding() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct X;
+fn main() {
+    let a = X;
+    let b = a;
+    let c = a;
+          //^ error: use of moved value: `a`
+    _ = (b, c);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn reinitialized() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct X;
+fn consume(_: X) {}
+fn main() {
+    let mut a = X;
+    consume(a);
+    a = X;
+    consume(a);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn moved_in_one_branch() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct X;
+fn consume(_: X) {}
+fn f(c: bool) {
+    let a = X;
+    if c {
+        consume(a);
+    }
+    consume(a);
+          //^ error: use of moved value: `a`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn moved_in_previous_iteration() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct X;
+fn consume(_: X) {}
+fn f() {
+    let a = X;
+    loop {
+        consume(a);
+              //^ error: use of moved value: `a`
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn moved_in_different_branches() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct X;
+fn consume(_: X) {}
+fn f(c: bool) {
+    let a = X;
+    if c {
+        consume(a);
+    } else {
+        consume(a);
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn copy_types() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, derive
+#[derive(Clone, Copy)]
+struct X;
+fn consume<T>(_: T) {}
+fn f(r: &i32) {
+    let a = X;
+    consume(a);
+    consume(a);
+    let b = 5;
+    consume(b);
+    consume(b);
+    consume(r);
+    consume(r);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn match_guard() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, option
+struct X;
+fn consume(_: X) {}
+fn f(o: Option<X>, c: bool) {
+    match o {
+        Some(x) if c => consume(x),
+        Some(x) => consume(x),
+        None => {}
+    }
+}
+"#,
+        );
+    }
+}
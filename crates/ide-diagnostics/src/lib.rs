@@ -26,6 +26,7 @@
 mod handlers {
     pub(crate) mod await_outside_of_async;
     pub(crate) mod break_outside_of_loop;
+    pub(crate) mod conflicting_borrows;
     pub(crate) mod expected_function;
     pub(crate) mod generic_args_prohibited;
     pub(crate) mod inactive_code;
@@ -33,6 +34,7 @@ mod handlers {
     pub(crate) mod incorrect_case;
     pub(crate) mod invalid_cast;
     pub(crate) mod invalid_derive_target;
+    pub(crate) mod lifetime_errors;
     pub(crate) mod macro_error;
     pub(crate) mod malformed_derive;
     pub(crate) mod mismatched_arg_count;
@@ -56,6 +58,7 @@ mod handlers {
     pub(crate) mod typed_hole;
     pub(crate) mod undeclared_label;
     pub(crate) mod unimplemented_builtin_macro;
+    pub(crate) mod unreachable_code;
     pub(crate) mod unreachable_label;
     pub(crate) mod unresolved_assoc_item;
     pub(crate) mod unresolved_extern_crate;
@@ -65,12 +68,18 @@ mod handlers {
     pub(crate) mod unresolved_macro_call;
     pub(crate) mod unresolved_method;
     pub(crate) mod unresolved_module;
+    pub(crate) mod unsatisfied_trait_bound;
+    pub(crate) mod unused_must_use;
     pub(crate) mod unused_variables;
+    pub(crate) mod use_after_move;
 
     // The handlers below are unusual, the implement the diagnostics as well.
+    pub(crate) mod cargo_toml;
+    pub(crate) mod dead_code;
     pub(crate) mod field_shorthand;
     pub(crate) mod json_is_not_rust;
     pub(crate) mod unlinked_file;
+    pub(crate) mod unused_imports;
     pub(crate) mod useless_braces;
 }
 
@@ -84,6 +93,7 @@ use hir::{db::ExpandDatabase, diagnostics::AnyDiagnostic, Crate, HirFileId, InFi
 use ide_db::{
     assists::{Assist, AssistId, AssistKind, AssistResolveStrategy},
     base_db::{ReleaseChannel, SourceDatabase},
+    cargo_toml,
     generated::lints::{Lint, LintGroup, CLIPPY_LINT_GROUPS, DEFAULT_LINTS, DEFAULT_LINT_GROUPS},
     imports::insert_use::InsertUseConfig,
     label::Label,
@@ -309,7 +319,7 @@ pub fn syntax_diagnostics(
 ) -> Vec<Diagnostic> {
     let _p = tracing::info_span!("syntax_diagnostics").entered();
 
-    if config.disabled.contains("syntax-error") {
+    if config.disabled.contains("syntax-error") || cargo_toml::is_cargo_toml(db, file_id) {
         return Vec::new();
     }
 
@@ -343,6 +353,12 @@ pub fn semantic_diagnostics(
     file_id: FileId,
 ) -> Vec<Diagnostic> {
     let _p = tracing::info_span!("semantic_diagnostics").entered();
+    if cargo_toml::is_cargo_toml(db, file_id) {
+        let mut res = Vec::new();
+        handlers::cargo_toml::cargo_toml(db, &mut res, file_id);
+        res.retain(|d| !config.disabled.contains(d.code.as_str()));
+        return res;
+    }
     let sema = Semantics::new(db);
     let file_id = sema
         .attach_first_edition(file_id)
@@ -387,88 +403,49 @@ pub fn semantic_diagnostics(
         None => handlers::unlinked_file::unlinked_file(&ctx, &mut res, file_id.file_id()),
     }
 
-    for diag in diags {
-        let d = match diag {
-            AnyDiagnostic::AwaitOutsideOfAsync(d) => handlers::await_outside_of_async::await_outside_of_async(&ctx, &d),
-            AnyDiagnostic::CastToUnsized(d) => handlers::invalid_cast::cast_to_unsized(&ctx, &d),
-            AnyDiagnostic::ExpectedFunction(d) => handlers::expected_function::expected_function(&ctx, &d),
-            AnyDiagnostic::InactiveCode(d) => match handlers::inactive_code::inactive_code(&ctx, &d) {
-                Some(it) => it,
-                None => continue,
-            }
-            AnyDiagnostic::IncoherentImpl(d) => handlers::incoherent_impl::incoherent_impl(&ctx, &d),
-            AnyDiagnostic::IncorrectCase(d) => handlers::incorrect_case::incorrect_case(&ctx, &d),
-            AnyDiagnostic::InvalidCast(d) => handlers::invalid_cast::invalid_cast(&ctx, &d),
-            AnyDiagnostic::InvalidDeriveTarget(d) => handlers::invalid_derive_target::invalid_derive_target(&ctx, &d),
-            AnyDiagnostic::MacroDefError(d) => handlers::macro_error::macro_def_error(&ctx, &d),
-            AnyDiagnostic::MacroError(d) => handlers::macro_error::macro_error(&ctx, &d),
-            AnyDiagnostic::MacroExpansionParseError(d) => {
-                // FIXME: Point to the correct error span here, not just the macro-call name
-                res.extend(d.errors.iter().take(16).map(|err| {
-                    {
-                        Diagnostic::new(
-                            DiagnosticCode::SyntaxError,
-                            format!("Syntax Error in Expansion: {err}"),
-                            ctx.resolve_precise_location(&d.node.clone(), d.precise_location),
-                        )
-                    }
-                    .experimental()
-                }));
-                continue;
-            },
-            AnyDiagnostic::MalformedDerive(d) => handlers::malformed_derive::malformed_derive(&ctx, &d),
-            AnyDiagnostic::MismatchedArgCount(d) => handlers::mismatched_arg_count::mismatched_arg_count(&ctx, &d),
-            AnyDiagnostic::MissingFields(d) => handlers::missing_fields::missing_fields(&ctx, &d),
-            AnyDiagnostic::MissingMatchArms(d) => handlers::missing_match_arms::missing_match_arms(&ctx, &d),
-            AnyDiagnostic::MissingUnsafe(d) => handlers::missing_unsafe::missing_unsafe(&ctx, &d),
-            AnyDiagnostic::MovedOutOfRef(d) => handlers::moved_out_of_ref::moved_out_of_ref(&ctx, &d),
-            AnyDiagnostic::NeedMut(d) => match handlers::mutability_errors::need_mut(&ctx, &d) {
-                Some(it) => it,
-                None => continue,
-            },
-            AnyDiagnostic::NonExhaustiveLet(d) => handlers::non_exhaustive_let::non_exhaustive_let(&ctx, &d),
-            AnyDiagnostic::NoSuchField(d) => handlers::no_such_field::no_such_field(&ctx, &d),
-            AnyDiagnostic::PrivateAssocItem(d) => handlers::private_assoc_item::private_assoc_item(&ctx, &d),
-            AnyDiagnostic::PrivateField(d) => handlers::private_field::private_field(&ctx, &d),
-            AnyDiagnostic::ReplaceFilterMapNextWithFindMap(d) => handlers::replace_filter_map_next_with_find_map::replace_filter_map_next_with_find_map(&ctx, &d),
-            AnyDiagnostic::TraitImplIncorrectSafety(d) => handlers::trait_impl_incorrect_safety::trait_impl_incorrect_safety(&ctx, &d),
-            AnyDiagnostic::TraitImplMissingAssocItems(d) => handlers::trait_impl_missing_assoc_item::trait_impl_missing_assoc_item(&ctx, &d),
-            AnyDiagnostic::TraitImplRedundantAssocItems(d) => handlers::trait_impl_redundant_assoc_item::trait_impl_redundant_assoc_item(&ctx, &d),
-            AnyDiagnostic::TraitImplOrphan(d) => handlers::trait_impl_orphan::trait_impl_orphan(&ctx, &d),
-            AnyDiagnostic::TypedHole(d) => handlers::typed_hole::typed_hole(&ctx, &d),
-            AnyDiagnostic::TypeMismatch(d) => handlers::type_mismatch::type_mismatch(&ctx, &d),
-            AnyDiagnostic::UndeclaredLabel(d) => handlers::undeclared_label::undeclared_label(&ctx, &d),
-            AnyDiagnostic::UnimplementedBuiltinMacro(d) => handlers::unimplemented_builtin_macro::unimplemented_builtin_macro(&ctx, &d),
-            AnyDiagnostic::UnreachableLabel(d) => handlers::unreachable_label::unreachable_label(&ctx, &d),
-            AnyDiagnostic::UnresolvedAssocItem(d) => handlers::unresolved_assoc_item::unresolved_assoc_item(&ctx, &d),
-            AnyDiagnostic::UnresolvedExternCrate(d) => handlers::unresolved_extern_crate::unresolved_extern_crate(&ctx, &d),
-            AnyDiagnostic::UnresolvedField(d) => handlers::unresolved_field::unresolved_field(&ctx, &d),
-            AnyDiagnostic::UnresolvedIdent(d) => handlers::unresolved_ident::unresolved_ident(&ctx, &d),
-            AnyDiagnostic::UnresolvedImport(d) => handlers::unresolved_import::unresolved_import(&ctx, &d),
-            AnyDiagnostic::UnresolvedMacroCall(d) => handlers::unresolved_macro_call::unresolved_macro_call(&ctx, &d),
-            AnyDiagnostic::UnresolvedMethodCall(d) => handlers::unresolved_method::unresolved_method(&ctx, &d),
-            AnyDiagnostic::UnresolvedModule(d) => handlers::unresolved_module::unresolved_module(&ctx, &d),
-            AnyDiagnostic::UnusedMut(d) => match handlers::mutability_errors::unused_mut(&ctx, &d) {
-                Some(it) => it,
-                None => continue,
-            },
-            AnyDiagnostic::UnusedVariable(d) => match handlers::unused_variables::unused_variables(&ctx, &d) {
-                Some(it) => it,
-                None => continue,
-            },
-            AnyDiagnostic::BreakOutsideOfLoop(d) => handlers::break_outside_of_loop::break_outside_of_loop(&ctx, &d),
-            AnyDiagnostic::MismatchedTupleStructPatArgCount(d) => handlers::mismatched_arg_count::mismatched_tuple_struct_pat_arg_count(&ctx, &d),
-            AnyDiagnostic::RemoveTrailingReturn(d) => match handlers::remove_trailing_return::remove_trailing_return(&ctx, &d) {
-                Some(it) => it,
-                None => continue,
-            },
-            AnyDiagnostic::RemoveUnnecessaryElse(d) => match handlers::remove_unnecessary_else::remove_unnecessary_else(&ctx, &d) {
-                Some(it) => it,
-                None => continue,
-            },
-            AnyDiagnostic::GenericArgsProhibited(d) => handlers::generic_args_prohibited::generic_args_prohibited(&ctx, &d)
+    handle_any_diagnostics(&ctx, diags, &mut res);
+
+    // These search for the usages of every item, which is expensive, so skip them when disabled.
+    if let Some(m) = module {
+        let enabled = |code: &str| {
+            !(ctx.config.disabled.contains(code) || ctx.config.disable_experimental)
+                && db.parse_errors(file_id).as_deref().is_none_or(|es| es.len() < 16)
         };
-        res.push(d)
+        if enabled("unused_imports") {
+            handlers::unused_imports::unused_imports(&ctx, &mut res, file_id, m.krate());
+        }
+        if enabled("dead_code") {
+            handlers::dead_code::dead_code(&ctx, &mut res, file_id);
+        }
+    }
+
+    // Report errors in code that is cfg'd out here, but active in a cfg variant of the crate.
+    let variants = match module {
+        Some(m) if db.parse_errors(file_id).as_deref().is_none_or(|es| es.len() < 16) => {
+            m.krate().cfg_variants(db)
+        }
+        _ => Vec::new(),
+    };
+    for variant in variants {
+        let sema = Semantics::new_for_crate(db, variant);
+        let Some(m) = sema.file_to_module_def(file_id).filter(|m| m.krate() == variant) else {
+            continue;
+        };
+        let mut diags = Vec::new();
+        m.diagnostics(db, &mut diags, config.style_lints);
+        // Code that is inactive in the variant is checked by the crate itself.
+        diags.retain(|diag| !matches!(diag, AnyDiagnostic::InactiveCode(_)));
+
+        let mut variant_res = Vec::new();
+        handle_any_diagnostics(&DiagnosticsContext { sema, ..ctx }, diags, &mut variant_res);
+        for diag in variant_res {
+            let is_duplicate = res.iter().any(|it| {
+                it.code == diag.code && it.range == diag.range && it.message == diag.message
+            });
+            if !is_duplicate {
+                res.push(diag);
+            }
+        }
     }
 
     res.retain(|d| {
@@ -515,6 +492,107 @@ pub fn semantic_diagnostics(
     res
 }
 
+fn handle_any_diagnostics(
+    ctx: &DiagnosticsContext<'_>,
+    diags: Vec<AnyDiagnostic>,
+    res: &mut Vec<Diagnostic>,
+) {
+    for diag in diags {
+        let d = match diag {
+            AnyDiagnostic::AwaitOutsideOfAsync(d) => handlers::await_outside_of_async::await_outside_of_async(ctx, &d),
+            AnyDiagnostic::CastToUnsized(d) => handlers::invalid_cast::cast_to_unsized(ctx, &d),
+            AnyDiagnostic::ExpectedFunction(d) => handlers::expected_function::expected_function(ctx, &d),
+            AnyDiagnostic::InactiveCode(d) => match handlers::inactive_code::inactive_code(ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            }
+            AnyDiagnostic::IncoherentImpl(d) => handlers::incoherent_impl::incoherent_impl(ctx, &d),
+            AnyDiagnostic::IncorrectCase(d) => handlers::incorrect_case::incorrect_case(ctx, &d),
+            AnyDiagnostic::InvalidCast(d) => handlers::invalid_cast::invalid_cast(ctx, &d),
+            AnyDiagnostic::InvalidDeriveTarget(d) => handlers::invalid_derive_target::invalid_derive_target(ctx, &d),
+            AnyDiagnostic::MacroDefError(d) => handlers::macro_error::macro_def_error(ctx, &d),
+            AnyDiagnostic::MacroError(d) => handlers::macro_error::macro_error(ctx, &d),
+            AnyDiagnostic::MacroExpansionParseError(d) => {
+                // FIXME: Point to the correct error span here, not just the macro-call name
+                res.extend(d.errors.iter().take(16).map(|err| {
+                    {
+                        Diagnostic::new(
+                            DiagnosticCode::SyntaxError,
+                            format!("Syntax Error in Expansion: {err}"),
+                            ctx.resolve_precise_location(&d.node.clone(), d.precise_location),
+                        )
+                    }
+                    .experimental()
+                }));
+                continue;
+            },
+            AnyDiagnostic::MalformedDerive(d) => handlers::malformed_derive::malformed_derive(ctx, &d),
+            AnyDiagnostic::MismatchedArgCount(d) => handlers::mismatched_arg_count::mismatched_arg_count(ctx, &d),
+            AnyDiagnostic::MissingFields(d) => handlers::missing_fields::missing_fields(ctx, &d),
+            AnyDiagnostic::MissingMatchArms(d) => handlers::missing_match_arms::missing_match_arms(ctx, &d),
+            AnyDiagnostic::MissingUnsafe(d) => handlers::missing_unsafe::missing_unsafe(ctx, &d),
+            AnyDiagnostic::MovedOutOfRef(d) => handlers::moved_out_of_ref::moved_out_of_ref(ctx, &d),
+            AnyDiagnostic::NeedMut(d) => match handlers::mutability_errors::need_mut(ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
+            AnyDiagnostic::NonExhaustiveLet(d) => handlers::non_exhaustive_let::non_exhaustive_let(ctx, &d),
+            AnyDiagnostic::NoSuchField(d) => handlers::no_such_field::no_such_field(ctx, &d),
+            AnyDiagnostic::PrivateAssocItem(d) => handlers::private_assoc_item::private_assoc_item(ctx, &d),
+            AnyDiagnostic::PrivateField(d) => handlers::private_field::private_field(ctx, &d),
+            AnyDiagnostic::ReplaceFilterMapNextWithFindMap(d) => handlers::replace_filter_map_next_with_find_map::replace_filter_map_next_with_find_map(ctx, &d),
+            AnyDiagnostic::TraitImplIncorrectSafety(d) => handlers::trait_impl_incorrect_safety::trait_impl_incorrect_safety(ctx, &d),
+            AnyDiagnostic::TraitImplMissingAssocItems(d) => handlers::trait_impl_missing_assoc_item::trait_impl_missing_assoc_item(ctx, &d),
+            AnyDiagnostic::TraitImplRedundantAssocItems(d) => handlers::trait_impl_redundant_assoc_item::trait_impl_redundant_assoc_item(ctx, &d),
+            AnyDiagnostic::TraitImplOrphan(d) => handlers::trait_impl_orphan::trait_impl_orphan(ctx, &d),
+            AnyDiagnostic::TypedHole(d) => handlers::typed_hole::typed_hole(ctx, &d),
+            AnyDiagnostic::TypeMismatch(d) => handlers::type_mismatch::type_mismatch(ctx, &d),
+            AnyDiagnostic::UndeclaredLabel(d) => handlers::undeclared_label::undeclared_label(ctx, &d),
+            AnyDiagnostic::UnimplementedBuiltinMacro(d) => handlers::unimplemented_builtin_macro::unimplemented_builtin_macro(ctx, &d),
+            AnyDiagnostic::UnreachableCode(d) => match handlers::unreachable_code::unreachable_code(ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
+            AnyDiagnostic::UnreachableLabel(d) => handlers::unreachable_label::unreachable_label(ctx, &d),
+            AnyDiagnostic::UnresolvedAssocItem(d) => handlers::unresolved_assoc_item::unresolved_assoc_item(ctx, &d),
+            AnyDiagnostic::UnresolvedExternCrate(d) => handlers::unresolved_extern_crate::unresolved_extern_crate(ctx, &d),
+            AnyDiagnostic::UnresolvedField(d) => handlers::unresolved_field::unresolved_field(ctx, &d),
+            AnyDiagnostic::UnresolvedIdent(d) => handlers::unresolved_ident::unresolved_ident(ctx, &d),
+            AnyDiagnostic::UnresolvedImport(d) => handlers::unresolved_import::unresolved_import(ctx, &d),
+            AnyDiagnostic::UnresolvedMacroCall(d) => handlers::unresolved_macro_call::unresolved_macro_call(ctx, &d),
+            AnyDiagnostic::UnresolvedMethodCall(d) => handlers::unresolved_method::unresolved_method(ctx, &d),
+            AnyDiagnostic::UnresolvedModule(d) => handlers::unresolved_module::unresolved_module(ctx, &d),
+            AnyDiagnostic::UnsatisfiedTraitBound(d) => handlers::unsatisfied_trait_bound::unsatisfied_trait_bound(ctx, &d),
+            AnyDiagnostic::UnusedMut(d) => match handlers::mutability_errors::unused_mut(ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
+            AnyDiagnostic::UnusedMustUse(d) => handlers::unused_must_use::unused_must_use(ctx, &d),
+            AnyDiagnostic::UnusedVariable(d) => match handlers::unused_variables::unused_variables(ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
+            AnyDiagnostic::UseAfterMove(d) => handlers::use_after_move::use_after_move(ctx, &d),
+            AnyDiagnostic::ConflictingBorrow(d) => handlers::conflicting_borrows::conflicting_borrows(ctx, &d),
+            AnyDiagnostic::DoesNotLiveLongEnough(d) => handlers::lifetime_errors::does_not_live_long_enough(ctx, &d),
+            AnyDiagnostic::ReturnsLocalReference(d) => handlers::lifetime_errors::returns_local_reference(ctx, &d),
+            AnyDiagnostic::LifetimeMayNotLiveLongEnough(d) => handlers::lifetime_errors::lifetime_may_not_live_long_enough(ctx, &d),
+            AnyDiagnostic::BreakOutsideOfLoop(d) => handlers::break_outside_of_loop::break_outside_of_loop(ctx, &d),
+            AnyDiagnostic::MismatchedTupleStructPatArgCount(d) => handlers::mismatched_arg_count::mismatched_tuple_struct_pat_arg_count(ctx, &d),
+            AnyDiagnostic::RemoveTrailingReturn(d) => match handlers::remove_trailing_return::remove_trailing_return(ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
+            AnyDiagnostic::RemoveUnnecessaryElse(d) => match handlers::remove_unnecessary_else::remove_unnecessary_else(ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
+            AnyDiagnostic::GenericArgsProhibited(d) => handlers::generic_args_prohibited::generic_args_prohibited(ctx, &d)
+        };
+        res.push(d)
+    }
+}
+
 /// Request both syntax and semantic diagnostics for the given [`FileId`].
 pub fn full_diagnostics(
     db: &RootDatabase,
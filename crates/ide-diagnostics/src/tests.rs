@@ -12,6 +12,25 @@ use test_utils::{assert_eq_text, extract_annotations, MiniCore};
 
 use crate::{DiagnosticsConfig, ExprFillDefaultMode, Severity};
 
+/// Diagnostics that most fixtures would trigger, which are only checked by their own tests.
+const NOISY_DIAGNOSTICS: &[&str] =
+    &["dead_code", "unreachable_code", "unused_imports", "unused_must_use"];
+
+/// The configuration used by the checks below, which disables [`NOISY_DIAGNOSTICS`].
+pub(crate) fn test_config() -> DiagnosticsConfig {
+    let mut config = DiagnosticsConfig::test_sample();
+    config.disabled.extend(NOISY_DIAGNOSTICS.iter().map(|&it| it.to_owned()));
+    config
+}
+
+/// The configuration used by the tests of one of [`NOISY_DIAGNOSTICS`], which enables only that
+/// diagnostic.
+pub(crate) fn test_config_enabling(code: &str) -> DiagnosticsConfig {
+    let mut config = test_config();
+    config.disabled.remove(code);
+    config
+}
+
 /// Takes a multi-file input fixture with annotated cursor positions,
 /// and checks that:
 ///  * a diagnostic is produced
@@ -38,13 +57,24 @@ pub(crate) fn check_fixes(
     }
 }
 
+#[track_caller]
+pub(crate) fn check_fixes_with_config(
+    config: DiagnosticsConfig,
+    #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
+    ra_fixtures_after: Vec<&str>,
+) {
+    for (i, ra_fixture_after) in ra_fixtures_after.iter().enumerate() {
+        check_nth_fix_with_config(config.clone(), i, ra_fixture_before, ra_fixture_after)
+    }
+}
+
 #[track_caller]
 fn check_nth_fix(
     nth: usize,
     #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
     #[rust_analyzer::rust_fixture] ra_fixture_after: &str,
 ) {
-    let mut config = DiagnosticsConfig::test_sample();
+    let mut config = test_config();
     config.expr_fill_default = ExprFillDefaultMode::Default;
     check_nth_fix_with_config(config, nth, ra_fixture_before, ra_fixture_after)
 }
@@ -55,12 +85,21 @@ pub(crate) fn check_fix_with_disabled(
     #[rust_analyzer::rust_fixture] ra_fixture_after: &str,
     disabled: impl Iterator<Item = String>,
 ) {
-    let mut config = DiagnosticsConfig::test_sample();
+    let mut config = test_config();
     config.expr_fill_default = ExprFillDefaultMode::Default;
     config.disabled.extend(disabled);
     check_nth_fix_with_config(config, 0, ra_fixture_before, ra_fixture_after)
 }
 
+#[track_caller]
+pub(crate) fn check_fix_with_config(
+    config: DiagnosticsConfig,
+    #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
+    #[rust_analyzer::rust_fixture] ra_fixture_after: &str,
+) {
+    check_nth_fix_with_config(config, 0, ra_fixture_before, ra_fixture_after)
+}
+
 #[track_caller]
 fn check_nth_fix_with_config(
     config: DiagnosticsConfig,
@@ -122,7 +161,7 @@ pub(crate) fn check_has_fix(
     let after = trim_indent(ra_fixture_after);
 
     let (db, file_position) = RootDatabase::with_position(ra_fixture_before);
-    let mut conf = DiagnosticsConfig::test_sample();
+    let mut conf = test_config();
     conf.expr_fill_default = ExprFillDefaultMode::Default;
     let fix = super::full_diagnostics(
         &db,
@@ -168,7 +207,7 @@ pub(crate) fn check_has_single_fix(
     let after = trim_indent(ra_fixture_after);
 
     let (db, file_position) = RootDatabase::with_position(ra_fixture_before);
-    let mut conf = DiagnosticsConfig::test_sample();
+    let mut conf = test_config();
     conf.expr_fill_default = ExprFillDefaultMode::Default;
     let mut n_fixes = 0;
     let fix = super::full_diagnostics(
@@ -214,7 +253,7 @@ pub(crate) fn check_no_fix(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
     let (db, file_position) = RootDatabase::with_position(ra_fixture);
     let diagnostic = super::full_diagnostics(
         &db,
-        &DiagnosticsConfig::test_sample(),
+        &test_config(),
         &AssistResolveStrategy::All,
         file_position.file_id.into(),
     )
@@ -225,7 +264,7 @@ pub(crate) fn check_no_fix(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
 
 #[track_caller]
 pub(crate) fn check_diagnostics(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
-    let mut config = DiagnosticsConfig::test_sample();
+    let mut config = test_config();
     config.disabled.insert("inactive-code".to_owned());
     check_diagnostics_with_config(config, ra_fixture)
 }
@@ -235,7 +274,7 @@ pub(crate) fn check_diagnostics_with_disabled(
     #[rust_analyzer::rust_fixture] ra_fixture: &str,
     disabled: &[&str],
 ) {
-    let mut config = DiagnosticsConfig::test_sample();
+    let mut config = test_config();
     config.disabled.extend(disabled.iter().map(|&s| s.to_owned()));
     check_diagnostics_with_config(config, ra_fixture)
 }
@@ -330,6 +369,32 @@ fn test_disabled_diagnostics() {
     assert!(!diagnostics.is_empty());
 }
 
+#[test]
+fn cfg_variant_diagnostics() {
+    check_diagnostics(
+        r#"
+//- /lib.rs crate:lib cfg:unix cfg_variant:windows
+#[cfg(unix)]
+fn sys_fn() -> u32 { 0 }
+#[cfg(windows)]
+fn sys_fn() -> u32 { "" }
+                   //^^ error: expected u32, found &str
+
+fn common() -> u32 {
+    let x: u32 = "";
+               //^^ error: expected u32, found &str
+    sys_fn() + x
+}
+
+#[cfg(windows)]
+fn only_windows() {
+    unix_only();
+  //^^^^^^^^^ error: no such value in this scope
+}
+"#,
+    );
+}
+
 #[test]
 fn minicore_smoke_test() {
     if test_utils::skip_slow_tests() {
@@ -338,7 +403,7 @@ fn minicore_smoke_test() {
 
     fn check(minicore: MiniCore) {
         let source = minicore.source_code();
-        let mut config = DiagnosticsConfig::test_sample();
+        let mut config = test_config();
         // This should be ignored since we conditionally remove code which creates single item use with braces
         config.disabled.insert("unused_braces".to_owned());
         config.disabled.insert("unused_variables".to_owned());
@@ -9,6 +9,8 @@
 // The syntax for a structural search replace command is `<search_pattern> ==>> <replace_pattern>`.
 // A `$<name>` placeholder in the search pattern will match any AST node and `$<name>` will reference it in the replacement.
 // Within a macro call, a placeholder will match up until whatever token follows the placeholder.
+// Code passed to macro calls, such as the elements of `vec![...]` or the arguments of `format!`, is
+// matched after expansion, provided that it was written in the macro call.
 //
 // All paths in both the search pattern and the replacement template must resolve in the context
 // in which this command is invoked. Paths in the search pattern will then match the code if they
@@ -37,6 +39,12 @@
 // |---------------|------------------------|
 // | kind(literal) | Is a literal (e.g. `42` or `"forty two"`) |
 // | not(a)        | Negates the constraint `a` |
+// | Type          | Has a type that unifies with `Type`, where `_` matches any type (e.g. `Vec<_>`) |
+// | impl Trait    | Has a type that implements `Trait` |
+//
+// For example `${v: Vec<_>}.len()` only matches calls of `len` on vectors and
+// `${x: impl Display}.to_string()` only matches calls of `to_string` on types that implement
+// `Display`.
 //
 // Available via the command `rust-analyzer.ssr`.
 //
@@ -81,8 +89,11 @@ pub use crate::{errors::SsrError, from_comment::ssr_from_comment, matching::Matc
 use crate::{errors::bail, matching::MatchFailureReason};
 use hir::{FileRange, Semantics};
 use ide_db::text_edit::TextEdit;
-use ide_db::{base_db::SourceDatabase, EditionedFileId, FileId, FxHashMap, RootDatabase};
+use ide_db::{
+    base_db::SourceDatabase, EditionedFileId, FileId, FxHashMap, FxHashSet, RootDatabase,
+};
 use resolving::ResolvedRule;
+use std::sync::Arc;
 use syntax::{ast, AstNode, SyntaxNode, TextRange};
 
 // A structured search replace rule. Create by calling `parse` on a str.
@@ -166,6 +177,22 @@ impl<'db> MatchFinder<'db> {
         Ok(())
     }
 
+    /// Like `add_rule`, but the rule only matches code within `files`.
+    pub fn add_rule_in_files(
+        &mut self,
+        rule: SsrRule,
+        files: FxHashSet<FileId>,
+    ) -> Result<(), SsrError> {
+        let files = Arc::new(files);
+        for parsed_rule in rule.parsed_rules {
+            let mut resolved =
+                ResolvedRule::new(parsed_rule, &self.resolution_scope, self.rules.len())?;
+            resolved.files = Some(files.clone());
+            self.rules.push(resolved);
+        }
+        Ok(())
+    }
+
     /// Finds matches for all added rules and returns edits for all found matches.
     pub fn edits(&self) -> FxHashMap<FileId, TextEdit> {
         let mut matches_by_file = FxHashMap::default();
@@ -3,7 +3,7 @@
 
 use crate::{
     parsing::{Constraint, NodeKind, Placeholder, Var},
-    resolving::{ResolvedPattern, ResolvedRule, UfcsCallInfo},
+    resolving::{ConstraintType, ResolvedPattern, ResolvedRule, UfcsCallInfo},
     SsrMatches,
 };
 use hir::{FileRange, ImportPathConfig, Semantics};
@@ -176,7 +176,10 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         // Handle placeholders.
         if let Some(placeholder) = self.get_placeholder_for_node(pattern) {
             for constraint in &placeholder.constraints {
-                self.check_constraint(constraint, code)?;
+                // Type constraints need type inference, so we defer them to the second phase.
+                if matches!(phase, Phase::Second(_)) || !constraint.needs_types() {
+                    self.check_constraint(constraint, code)?;
+                }
             }
             if let Phase::Second(matches_out) = phase {
                 let original_range = self
@@ -317,7 +320,6 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
         Ok(())
     }
 
-    #[allow(clippy::only_used_in_recursion)]
     fn check_constraint(
         &self,
         constraint: &Constraint,
@@ -332,10 +334,45 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
                     fail_match!("Constraint {:?} failed for '{}'", constraint, code.text());
                 }
             }
+            Constraint::Type(ty) => {
+                let Some(constraint_type) = self.rule.pattern.constraint_types.get(ty.syntax())
+                else {
+                    fail_match!("Type constraint `{}` wasn't resolved", ty);
+                };
+                let code_type = self.type_of(code).ok_or_else(|| {
+                    match_error!("Couldn't determine the type of '{}'", code.text())
+                })?;
+                let db = self.sema.db;
+                let satisfied = match constraint_type {
+                    ConstraintType::UnifiesWith(ty) => code_type.could_unify_with(db, ty),
+                    ConstraintType::Implements(traits) => {
+                        traits.iter().all(|(trait_, args)| code_type.impls_trait(db, *trait_, args))
+                    }
+                };
+                if !satisfied {
+                    use hir::HirDisplay;
+                    fail_match!(
+                        "Type `{}` of '{}' doesn't satisfy the constraint `{}`",
+                        code_type.display(db, Edition::CURRENT),
+                        code.text(),
+                        ty
+                    );
+                }
+            }
         }
         Ok(())
     }
 
+    fn type_of(&self, code: &SyntaxNode) -> Option<hir::Type> {
+        if let Some(expr) = ast::Expr::cast(code.clone()) {
+            return Some(self.sema.type_of_expr(&expr)?.original);
+        }
+        if let Some(pat) = ast::Pat::cast(code.clone()) {
+            return Some(self.sema.type_of_pat(&pat)?.original);
+        }
+        None
+    }
+
     /// Paths are matched based on whether they refer to the same thing, even if they're written
     /// differently.
     fn attempt_match_path(
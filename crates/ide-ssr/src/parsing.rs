@@ -6,7 +6,7 @@
 //! e.g. expressions, type references etc.
 use ide_db::{FxHashMap, FxHashSet};
 use std::{fmt::Display, str::FromStr};
-use syntax::{SmolStr, SyntaxKind, SyntaxNode, T};
+use syntax::{ast, AstNode, SmolStr, SyntaxKind, SyntaxNode, T};
 
 use crate::errors::bail;
 use crate::{fragments, SsrError, SsrPattern, SsrRule};
@@ -47,6 +47,9 @@ pub(crate) struct Var(pub(crate) String);
 pub(crate) enum Constraint {
     Kind(NodeKind),
     Not(Box<Constraint>),
+    /// The type of the matched code unifies with the given type, or implements the given traits if
+    /// it's an `impl Trait` type.
+    Type(ast::Type),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -303,13 +306,15 @@ fn parse_placeholder(tokens: &mut std::vec::IntoIter<Token>) -> Result<Placehold
 }
 
 fn parse_constraint(tokens: &mut std::vec::IntoIter<Token>) -> Result<Constraint, SsrError> {
+    skip_whitespace(tokens);
     let constraint_type = tokens
-        .next()
-        .ok_or_else(|| SsrError::new("Found end of placeholder while looking for a constraint"))?
-        .text
-        .to_string();
-    match constraint_type.as_str() {
-        "kind" => {
+        .as_slice()
+        .first()
+        .ok_or_else(|| SsrError::new("Found end of placeholder while looking for a constraint"))?;
+    let is_call = tokens.as_slice().get(1).is_some_and(|it| it.kind == T!['(']);
+    match constraint_type.text.as_str() {
+        "kind" if is_call => {
+            tokens.next();
             expect_token(tokens, "(")?;
             let t = tokens.next().ok_or_else(|| {
                 SsrError::new("Unexpected end of constraint while looking for kind")
@@ -320,13 +325,52 @@ fn parse_constraint(tokens: &mut std::vec::IntoIter<Token>) -> Result<Constraint
             expect_token(tokens, ")")?;
             Ok(Constraint::Kind(NodeKind::from(&t.text)?))
         }
-        "not" => {
+        "not" if is_call => {
+            tokens.next();
             expect_token(tokens, "(")?;
             let sub = parse_constraint(tokens)?;
             expect_token(tokens, ")")?;
             Ok(Constraint::Not(Box::new(sub)))
         }
-        x => bail!("Unsupported constraint type '{}'", x),
+        _ => parse_type_constraint(tokens),
+    }
+}
+
+/// Parses a type such as `Vec<i32>` or `impl Display` up until the `:`, `)` or `}` that ends the
+/// constraint.
+fn parse_type_constraint(tokens: &mut std::vec::IntoIter<Token>) -> Result<Constraint, SsrError> {
+    let mut text = String::new();
+    let mut depth = 0usize;
+    let mut prev = None;
+    while let Some(token) = tokens.as_slice().first() {
+        let next = tokens.as_slice().get(1).map(|it| it.kind);
+        match token.kind {
+            T!['('] | T!['['] | T!['{'] | T![<] => depth += 1,
+            // `->` in function pointer and `Fn` types.
+            T![>] if prev == Some(T![-]) => {}
+            T![')'] | T![']'] | T!['}'] | T![>] if depth == 0 => break,
+            T![')'] | T![']'] | T!['}'] | T![>] => depth -= 1,
+            // A single `:` separates constraints, `::` separates path segments.
+            T![:] if depth == 0 && prev != Some(T![:]) && next != Some(T![:]) => break,
+            _ => {}
+        }
+        prev = Some(token.kind);
+        text.push_str(&token.text);
+        tokens.next();
+    }
+    if text.trim().is_empty() {
+        bail!("Expected a constraint, found end of placeholder");
+    }
+    let ty = fragments::ty(&text)
+        .ok()
+        .and_then(ast::Type::cast)
+        .ok_or_else(|| SsrError(format!("Unsupported constraint type '{}'", text.trim())))?;
+    Ok(Constraint::Type(ty))
+}
+
+fn skip_whitespace(tokens: &mut std::vec::IntoIter<Token>) {
+    while tokens.as_slice().first().is_some_and(|it| it.kind == SyntaxKind::WHITESPACE) {
+        tokens.next();
     }
 }
 
@@ -340,6 +384,17 @@ fn expect_token(tokens: &mut std::vec::IntoIter<Token>, expected: &str) -> Resul
     bail!("Expected {} found end of stream", expected);
 }
 
+impl Constraint {
+    /// Returns whether checking this constraint requires type inference of the matched code.
+    pub(crate) fn needs_types(&self) -> bool {
+        match self {
+            Constraint::Kind(_) => false,
+            Constraint::Not(sub) => sub.needs_types(),
+            Constraint::Type(_) => true,
+        }
+    }
+}
+
 impl NodeKind {
     fn from(name: &SmolStr) -> Result<NodeKind, SsrError> {
         Ok(match name.as_str() {
@@ -1,14 +1,19 @@
 //! This module is responsible for resolving paths within rules.
 
+use std::sync::Arc;
+
 use hir::AsAssocItem;
-use ide_db::FxHashMap;
+use ide_db::{FileId, FxHashMap, FxHashSet};
 use parsing::Placeholder;
 use syntax::{
     ast::{self, HasGenericArgs},
     SmolStr, SyntaxKind, SyntaxNode, SyntaxToken,
 };
 
-use crate::{errors::error, parsing, SsrError};
+use crate::{
+    errors::{bail, error},
+    parsing, SsrError,
+};
 
 pub(crate) struct ResolutionScope<'db> {
     scope: hir::SemanticsScope<'db>,
@@ -19,6 +24,8 @@ pub(crate) struct ResolvedRule {
     pub(crate) pattern: ResolvedPattern,
     pub(crate) template: Option<ResolvedPattern>,
     pub(crate) index: usize,
+    /// If set, the rule only matches code within these files.
+    pub(crate) files: Option<Arc<FxHashSet<FileId>>>,
 }
 
 pub(crate) struct ResolvedPattern {
@@ -27,6 +34,8 @@ pub(crate) struct ResolvedPattern {
     // Paths in `node` that we've resolved.
     pub(crate) resolved_paths: FxHashMap<SyntaxNode, ResolvedPath>,
     pub(crate) ufcs_function_calls: FxHashMap<SyntaxNode, UfcsCallInfo>,
+    // Types of the type constraints of placeholders, keyed by the syntax of the constraint.
+    pub(crate) constraint_types: FxHashMap<SyntaxNode, ConstraintType>,
     pub(crate) contains_self: bool,
 }
 
@@ -36,6 +45,13 @@ pub(crate) struct ResolvedPath {
     pub(crate) depth: u32,
 }
 
+pub(crate) enum ConstraintType {
+    /// A type that the type of the matched code has to unify with.
+    UnifiesWith(hir::Type),
+    /// Traits and their generic arguments that the type of the matched code has to implement.
+    Implements(Vec<(hir::Trait, Vec<hir::Type>)>),
+}
+
 pub(crate) struct UfcsCallInfo {
     pub(crate) call_expr: ast::CallExpr,
     pub(crate) function: hir::Function,
@@ -58,9 +74,15 @@ impl ResolvedRule {
             pattern: resolver.resolve_pattern_tree(rule.pattern)?,
             template: resolved_template,
             index,
+            files: None,
         })
     }
 
+    /// Returns whether the rule may match code within `file_id`.
+    pub(crate) fn applies_to(&self, file_id: FileId) -> bool {
+        self.files.as_ref().is_none_or(|files| files.contains(&file_id))
+    }
+
     pub(crate) fn get_placeholder(&self, token: &SyntaxToken) -> Option<&Placeholder> {
         if token.kind() != SyntaxKind::IDENT {
             return None;
@@ -102,6 +124,12 @@ impl Resolver<'_, '_> {
                 None
             })
             .collect();
+        let mut constraint_types = FxHashMap::default();
+        for placeholder in self.placeholders_by_stand_in.values() {
+            for constraint in &placeholder.constraints {
+                self.resolve_constraint(constraint, &mut constraint_types)?;
+            }
+        }
         let contains_self =
             pattern.descendants_with_tokens().any(|node_or_token| match node_or_token {
                 SyntaxElement::Token(t) => t.kind() == T![self],
@@ -112,10 +140,77 @@ impl Resolver<'_, '_> {
             resolved_paths,
             placeholders_by_stand_in: self.placeholders_by_stand_in.clone(),
             ufcs_function_calls,
+            constraint_types,
             contains_self,
         })
     }
 
+    fn resolve_constraint(
+        &self,
+        constraint: &parsing::Constraint,
+        constraint_types: &mut FxHashMap<SyntaxNode, ConstraintType>,
+    ) -> Result<(), SsrError> {
+        use syntax::ast::AstNode;
+        match constraint {
+            parsing::Constraint::Kind(_) => {}
+            parsing::Constraint::Not(sub) => self.resolve_constraint(sub, constraint_types)?,
+            parsing::Constraint::Type(ast::Type::ImplTraitType(impl_trait)) => {
+                let mut traits = Vec::new();
+                for bound in impl_trait.type_bound_list().into_iter().flat_map(|it| it.bounds()) {
+                    let Some(ast::Type::PathType(path_type)) = bound.ty() else {
+                        // Lifetime bounds don't restrict which code matches.
+                        continue;
+                    };
+                    let path = path_type.path().ok_or_else(|| error!("Invalid trait bound"))?;
+                    let trait_ = match self.resolution_scope.resolve_path(&path) {
+                        Some(hir::PathResolution::Def(hir::ModuleDef::Trait(trait_))) => trait_,
+                        _ => bail!("Failed to resolve trait `{}`", path.syntax().text()),
+                    };
+                    let mut args = Vec::new();
+                    let generic_args =
+                        path.segment().and_then(|it| it.generic_arg_list()).into_iter();
+                    for arg in generic_args.flat_map(|it| it.generic_args()) {
+                        match arg {
+                            ast::GenericArg::TypeArg(arg) => {
+                                args.push(self.resolve_type(&arg.ty().ok_or_else(|| {
+                                    error!("Invalid trait bound `{}`", path.syntax().text())
+                                })?)?);
+                            }
+                            ast::GenericArg::LifetimeArg(_) => {}
+                            _ => bail!(
+                                "Unsupported generic argument `{}` in trait bound",
+                                arg.syntax().text()
+                            ),
+                        }
+                    }
+                    traits.push((trait_, args));
+                }
+                constraint_types
+                    .insert(impl_trait.syntax().clone(), ConstraintType::Implements(traits));
+            }
+            parsing::Constraint::Type(ty) => {
+                constraint_types.insert(
+                    ty.syntax().clone(),
+                    ConstraintType::UnifiesWith(self.resolve_type(ty)?),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_type(&self, ty: &ast::Type) -> Result<hir::Type, SsrError> {
+        use syntax::ast::AstNode;
+        // Lowering doesn't report unresolved paths, so check them first. They would otherwise
+        // unify with everything.
+        for path_type in ty.syntax().descendants().filter_map(ast::PathType::cast) {
+            let path = path_type.path().ok_or_else(|| error!("Invalid type `{}`", ty))?;
+            if self.resolution_scope.resolve_path(&path).is_none() {
+                bail!("Failed to resolve path `{}`", path.syntax().text());
+            }
+        }
+        Ok(self.resolution_scope.scope.speculative_resolve_type(ty))
+    }
+
     fn resolve(
         &self,
         node: SyntaxNode,
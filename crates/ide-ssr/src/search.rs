@@ -147,6 +147,9 @@ impl MatchFinder<'_> {
 
     fn slow_scan(&self, rule: &ResolvedRule, matches_out: &mut Vec<Match>) {
         self.search_files_do(|file_id| {
+            if !rule.applies_to(file_id) {
+                return;
+            }
             let file = self.sema.parse_guess_edition(file_id);
             let code = file.syntax();
             self.slow_scan_node(code, rule, &None, matches_out);
@@ -193,8 +196,12 @@ impl MatchFinder<'_> {
                 if let Some(tt) = macro_call.token_tree() {
                     // When matching within a macro expansion, we only want to allow matches of
                     // nodes that originated entirely from within the token tree of the macro call.
-                    // i.e. we don't want to match something that came from the macro itself.
-                    if let Some(range) = self.sema.original_range_opt(tt.syntax()) {
+                    // i.e. we don't want to match something that came from the macro itself. The
+                    // token tree of a macro call within another expansion (e.g. `format_args!`
+                    // within `format!`) generally can't be mapped back as a whole, in which case
+                    // we keep restricting matches to the outer macro call.
+                    let range = self.sema.original_range_opt(tt.syntax()).or(*restrict_range);
+                    if let Some(range) = range {
                         self.slow_scan_node(&expanded, rule, &Some(range), matches_out);
                     }
                 }
@@ -217,7 +224,9 @@ impl MatchFinder<'_> {
             return;
         }
         if let Ok(m) = matching::get_match(false, rule, code, restrict_range, &self.sema) {
-            matches_out.push(m);
+            if rule.applies_to(m.range.file_id.file_id()) {
+                matches_out.push(m);
+            }
         }
     }
 
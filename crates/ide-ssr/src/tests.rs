@@ -55,6 +55,18 @@ fn parser_invalid_template() {
     );
 }
 
+#[test]
+fn parser_invalid_constraint() {
+    assert_eq!(
+        parse_error_text("${a:}.len() ==>> 1"),
+        "Parse error: Expected a constraint, found end of placeholder"
+    );
+    assert_eq!(
+        parse_error_text("${a: i32 i32}.len() ==>> 1"),
+        "Parse error: Unsupported constraint type 'i32 i32'"
+    );
+}
+
 #[test]
 fn parser_undefined_placeholder_in_replacement() {
     assert_eq!(
@@ -558,6 +570,66 @@ fn literal_constraint() {
     assert_matches("Some(${a:not(kind(literal))})", code, &["Some(x1)", "Some(40 + 2)"]);
 }
 
+#[test]
+fn type_constraint() {
+    let code = r#"
+        struct Foo;
+        struct Bar<T>(T);
+        impl Foo { fn len(&self) -> usize { 0 } }
+        impl<T> Bar<T> { fn len(&self) -> usize { 0 } }
+        fn f1(foo: Foo, b1: Bar<i32>, b2: Bar<u8>) {
+            foo.len();
+            b1.len();
+            b2.len();
+        }
+        "#;
+    assert_matches("${a: Foo}.len()", code, &["foo.len()"]);
+    assert_matches("${a: Bar<_>}.len()", code, &["b1.len()", "b2.len()"]);
+    assert_matches("${a:Bar<u8>}.len()", code, &["b2.len()"]);
+    assert_matches("${a:not(Foo)}.len()", code, &["b1.len()", "b2.len()"]);
+    assert_match_failure_reason(
+        "${a: Foo}.len()",
+        code,
+        "b1.len()",
+        "Type `Bar<i32>` of 'b1' doesn't satisfy the constraint `Foo`",
+    );
+}
+
+#[test]
+fn trait_bound_constraint() {
+    let code = r#"
+        trait Display {}
+        trait Into<T> {}
+        struct Foo;
+        struct Bar<T>(T);
+        impl Display for Foo {}
+        impl Into<u8> for Bar<i32> {}
+        impl Foo { fn len(&self) -> usize { 0 } }
+        impl<T> Bar<T> { fn len(&self) -> usize { 0 } }
+        fn f1(foo: Foo, b1: Bar<i32>, b2: Bar<u8>) {
+            foo.len();
+            b1.len();
+            b2.len();
+        }
+        "#;
+    assert_matches("${a: impl Display}.len()", code, &["foo.len()"]);
+    assert_matches("${a: impl Into<u8>}.len()", code, &["b1.len()"]);
+    assert_matches("${a:not(impl Display)}.len()", code, &["b1.len()", "b2.len()"]);
+}
+
+#[test]
+fn unresolved_type_constraint() {
+    let (db, position, selections) = single_file("fn f1() {}");
+    let mut match_finder = MatchFinder::in_context(
+        &db,
+        position.into(),
+        selections.into_iter().map(Into::into).collect(),
+    )
+    .unwrap();
+    let error = match_finder.add_search_pattern("${a: Foo<_>}.len()".parse().unwrap()).unwrap_err();
+    assert_eq!(error.to_string(), "Parse error: Failed to resolve path `Foo<_>`");
+}
+
 #[test]
 fn match_reordered_struct_instantiation() {
     assert_matches(
@@ -961,6 +1033,37 @@ fn replace_outside_and_within_macro_expansion() {
     )
 }
 
+#[test]
+fn replace_within_nested_macro_expansion() {
+    assert_ssr_transform(
+        "${s: Foo}.len() ==>> $s.size()",
+        r#"
+            #[rustc_builtin_macro]
+            macro_rules! format_args { ($fmt:expr, $($args:tt)*) => {{}} }
+            macro_rules! format { ($($arg:tt)*) => { format_args!($($arg)*) } }
+            macro_rules! vec { ($($x:expr),* $(,)?) => { [$($x),*] } }
+            struct Foo;
+            impl Foo { fn len(&self) -> usize { 0 } fn size(&self) -> usize { 0 } }
+            fn f(a: Foo, b: &str) {
+                let v = vec![a.len(), b.len()];
+                let s = format!("{} {:?}", a.len(), vec![a.len()]);
+            }
+            "#,
+        expect![[r##"
+            #[rustc_builtin_macro]
+            macro_rules! format_args { ($fmt:expr, $($args:tt)*) => {{}} }
+            macro_rules! format { ($($arg:tt)*) => { format_args!($($arg)*) } }
+            macro_rules! vec { ($($x:expr),* $(,)?) => { [$($x),*] } }
+            struct Foo;
+            impl Foo { fn len(&self) -> usize { 0 } fn size(&self) -> usize { 0 } }
+            fn f(a: Foo, b: &str) {
+                let v = vec![a.size(), b.len()];
+                let s = format!("{} {:?}", a.size(), vec![a.size()]);
+            }
+        "##]],
+    )
+}
+
 #[test]
 fn preserves_whitespace_within_macro_expansion() {
     assert_ssr_transform(
@@ -1417,3 +1520,31 @@ fn replace_autoref_mut() {
         "#]],
     );
 }
+
+#[test]
+fn rule_restricted_to_files() {
+    use ide_db::symbol_index::SymbolsDatabase;
+    use test_fixture::{WithFixture, WORKSPACE};
+    let (mut db, files) = ide_db::RootDatabase::with_many_files(
+        r#"
+//- /lib.rs
+mod a;
+mod b;
+fn foo() {}
+fn bar() {}
+//- /a.rs
+fn f() { crate::foo(); }
+//- /b.rs
+fn f() { crate::foo(); }
+"#,
+    );
+    let mut local_roots = FxHashSet::default();
+    local_roots.insert(WORKSPACE);
+    db.set_local_roots_with_durability(Arc::new(local_roots), Durability::HIGH);
+    let mut match_finder = MatchFinder::at_first_file(&db).unwrap();
+    let rule: SsrRule = "foo() ==>> bar()".parse().unwrap();
+    let file_id = files[2].file_id();
+    match_finder.add_rule_in_files(rule, FxHashSet::from_iter([file_id])).unwrap();
+    let edits = match_finder.edits();
+    assert_eq!(edits.keys().copied().collect::<Vec<_>>(), vec![file_id]);
+}
@@ -0,0 +1,230 @@
+//! Entry point for document links.
+
+use hir::{InFile, Semantics};
+use ide_db::{
+    defs::Definition, documentation::docs_with_rangemap, FileId, FileRange, RootDatabase,
+};
+use syntax::{
+    ast::{self, AstToken, HasAttrs, IsString},
+    AstNode, NodeOrToken, SyntaxKind, TextRange,
+};
+
+use crate::{
+    doc_links::{doc_attributes, extract_definitions_from_docs, resolve_doc_path_for_def},
+    goto_definition::try_lookup_include_path,
+    TryToNav,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DocumentLink {
+    pub range: TextRange,
+    pub target: DocumentLinkTarget,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DocumentLinkTarget {
+    /// A whole file, as referenced by `#[path]` or `include_str!`.
+    File(FileId),
+    /// The definition an intra-doc link resolves to.
+    Definition(FileRange),
+}
+
+// Feature: Document Links
+//
+// Makes the following clickable in the editor:
+//
+// - the path of `#[path = "..."]` attributes on modules
+// - the file argument of `include!`, `include_str!` and `include_bytes!`, including
+//   `#[doc = include_str!("...")]`
+// - intra-doc links in documentation comments
+pub(crate) fn document_links(db: &RootDatabase, file_id: FileId) -> Vec<DocumentLink> {
+    let sema = &Semantics::new(db);
+    let file = sema.parse_guess_edition(file_id);
+    let hir_file_id = sema.hir_file_for(file.syntax());
+
+    let mut res = Vec::new();
+    for node in file.syntax().descendants() {
+        if let Some(module) = ast::Module::cast(node.clone()) {
+            if let Some(link) = module_path_link(sema, &module) {
+                res.push(link);
+            }
+        }
+
+        if let Some(call) = ast::MacroCall::cast(node.clone()) {
+            res.extend(include_link(sema, file_id, &call));
+        }
+
+        let Some((attributes, def)) = doc_attributes(sema, &node) else { continue };
+        let Some((docs, doc_mapping)) = docs_with_rangemap(db, &attributes) else { continue };
+        for (range, link, ns) in extract_definitions_from_docs(&docs) {
+            let Some(InFile { file_id, value: range }) = doc_mapping.map(range) else { continue };
+            if file_id != hir_file_id {
+                continue;
+            }
+            let Some(nav) = resolve_doc_path_for_def(db, def, &link, ns)
+                .and_then(|def: Definition| def.try_to_nav(db))
+            else {
+                continue;
+            };
+            let nav = nav.call_site;
+            res.push(DocumentLink {
+                range,
+                target: DocumentLinkTarget::Definition(FileRange {
+                    file_id: nav.file_id,
+                    range: nav.focus_or_full_range(),
+                }),
+            });
+        }
+    }
+    res.sort_by_key(|link| link.range.start());
+    res
+}
+
+fn module_path_link(
+    sema: &Semantics<'_, RootDatabase>,
+    module: &ast::Module,
+) -> Option<DocumentLink> {
+    let path_attr = module.attrs().find(|attr| attr.simple_name().as_deref() == Some("path"))?;
+    let ast::Expr::Literal(literal) = path_attr.expr()? else { return None };
+    let ast::LiteralKind::String(string) = literal.kind() else { return None };
+    let target = sema.to_def(module)?.definition_source_file_id(sema.db).file_id()?;
+    Some(DocumentLink {
+        range: string.text_range_between_quotes()?,
+        target: DocumentLinkTarget::File(target.file_id()),
+    })
+}
+
+/// Links the file argument of an `include!`-like macro call. An argument that is not a single
+/// literal, like `concat!("foo", ".txt")`, is linked as a whole.
+fn include_link(
+    sema: &Semantics<'_, RootDatabase>,
+    file_id: FileId,
+    call: &ast::MacroCall,
+) -> Option<DocumentLink> {
+    let tt = call.token_tree()?;
+    let string = tt
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|it| it.kind() == SyntaxKind::STRING)?;
+    if !sema.resolve_macro_call(call)?.is_include_like(sema.db) {
+        return None;
+    }
+    let target = sema
+        .descend_into_macros_no_opaque(string)
+        .into_iter()
+        .find_map(|it| try_lookup_include_path(sema, ast::String::cast(it)?, file_id))?;
+
+    let args =
+        tt.syntax().children_with_tokens().filter(|it| !it.kind().is_trivia()).collect::<Vec<_>>();
+    // The first and last elements are the delimiters.
+    let range = match &args[..] {
+        [_, NodeOrToken::Token(it), _] if it.kind() == SyntaxKind::STRING => {
+            ast::String::cast(it.clone())?.text_range_between_quotes()?
+        }
+        [_, it, _] => it.text_range(),
+        [_, first, .., last, _] => first.text_range().cover(last.text_range()),
+        _ => return None,
+    };
+    Some(DocumentLink { range, target: DocumentLinkTarget::File(target.file_id) })
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::fixture;
+
+    fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str, expect: Expect) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let links = analysis.document_links(file_id).unwrap();
+        let text = analysis.file_text(file_id).unwrap();
+        let actual = links
+            .into_iter()
+            .map(|link| format!("{:?} {:?}\n", &text[link.range], link.target))
+            .collect::<String>();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn module_path_attribute() {
+        check(
+            r#"
+//- /main.rs
+#[path = "other/bar.rs"]
+mod foo;
+mod baz;
+//- /other/bar.rs
+//- /baz.rs
+"#,
+            expect![[r#"
+                "other/bar.rs" File(FileId(1))
+            "#]],
+        );
+    }
+
+    #[test]
+    fn include_macros() {
+        check(
+            r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! include_str {}
+#[rustc_builtin_macro]
+macro_rules! include_bytes {}
+#[rustc_builtin_macro]
+macro_rules! concat {}
+
+fn main() {
+    let a = include_str!("foo.txt");
+    let b = include_bytes!(concat!("foo", ".txt"));
+    let c = include_str!("missing.txt");
+    let d = "foo.txt";
+    let e = concat!("foo.txt");
+}
+//- /foo.txt
+// empty
+"#,
+            expect![[r#"
+                "foo.txt" File(FileId(1))
+                "concat!(\"foo\", \".txt\")" File(FileId(1))
+            "#]],
+        );
+    }
+
+    #[test]
+    fn doc_include_str() {
+        check(
+            r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! include_str {}
+
+#[doc = include_str!("docs.md")]
+struct Item;
+//- /docs.md
+// docs
+"#,
+            expect![[r#"
+                "docs.md" File(FileId(1))
+            "#]],
+        );
+    }
+
+    #[test]
+    fn intra_doc_links() {
+        check(
+            r#"
+//- /main.rs
+/// See [`Bar`] and [baz](crate::baz), but not [`Missing`].
+struct Foo;
+struct Bar;
+fn baz() {}
+"#,
+            expect![[r#"
+                "[`Bar`]" Definition(FileRangeWrapper { file_id: FileId(0), range: 79..82 })
+                "[baz](crate::baz)" Definition(FileRangeWrapper { file_id: FileId(0), range: 87..90 })
+            "#]],
+        );
+    }
+}
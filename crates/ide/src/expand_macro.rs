@@ -1,12 +1,15 @@
+use either::Either;
 use hir::db::ExpandDatabase;
-use hir::{ExpandResult, InFile, MacroFileIdExt, Semantics};
+use hir::{ExpandResult, HasSource, InFile, MacroFileIdExt, Semantics};
 use ide_db::base_db::CrateId;
 use ide_db::{
-    helpers::pick_best_token, syntax_helpers::prettify_macro_expansion, FileId, RootDatabase,
+    helpers::pick_best_token,
+    syntax_helpers::{macro_def::macro_rules, prettify_macro_expansion},
+    FileId, RootDatabase,
 };
 use span::{Edition, SpanMap, SyntaxContextId, TextRange, TextSize};
 use stdx::format_to;
-use syntax::{ast, ted, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, T};
+use syntax::{ast, ted, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, SyntaxToken, T};
 
 use crate::FilePosition;
 
@@ -15,6 +18,33 @@ pub struct ExpandedMacro {
     pub expansion: String,
 }
 
+pub struct ExpandedMacroSteps {
+    pub name: String,
+    pub steps: Vec<MacroExpansionStep>,
+}
+
+/// The expansion after expanding one more level of macro calls.
+pub struct MacroExpansionStep {
+    pub expansion: String,
+    /// The macro calls expanded in this step.
+    pub calls: Vec<ExpandedMacroCall>,
+}
+
+pub struct ExpandedMacroCall {
+    pub name: String,
+    /// The index of the `macro_rules!` arm used for the expansion.
+    pub matched_arm: Option<u32>,
+    /// The arms of the called `macro_rules!`, empty for other kinds of macros.
+    pub arms: Vec<MacroArm>,
+    pub error: Option<String>,
+}
+
+pub struct MacroArm {
+    pub pattern: String,
+    /// Why this arm does not match the input, `None` if it does.
+    pub error: Option<String>,
+}
+
 // Feature: Expand Macro Recursively
 //
 // Shows the full macro expansion of the macro at the current caret position.
@@ -86,26 +116,98 @@ pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<
         return derive;
     }
 
-    let mut anc = tok.parent_ancestors();
+    let (name, item, kind) = find_macro_call(&sema, position.file_id, &tok)?;
     let mut span_map = SpanMap::empty();
     let mut error = String::new();
-    let (name, expanded, kind) = loop {
-        let node = anc.next()?;
+    let expanded = expand_macro_recur(
+        &sema,
+        &item,
+        &mut error,
+        &mut span_map,
+        TextSize::new(0),
+        u32::MAX,
+        &mut Vec::new(),
+    )?;
 
+    // FIXME:
+    // macro expansion may lose all white space information
+    // But we hope someday we can use ra_fmt for that
+    let mut expansion = format(db, kind, position.file_id, expanded, &span_map, krate);
+
+    if !error.is_empty() {
+        expansion.insert_str(0, &format!("Expansion had errors:{error}\n\n"));
+    }
+    Some(ExpandedMacro { name, expansion })
+}
+
+// Feature: Expand Macro Step by Step
+//
+// Shows the expansion of the macro at the current caret position one level at a time. Every step
+// lists the macro calls it expanded, which `macro_rules!` arm each of them used and why the other
+// arms did not match.
+//
+// | Editor  | Action Name |
+// |---------|-------------|
+// | VS Code | **rust-analyzer: Expand macro step by step at caret** |
+pub(crate) fn expand_macro_steps(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<ExpandedMacroSteps> {
+    // Expansions nest at most this deep, see `SourceAnalyzer::expand`.
+    const MAX_STEPS: u32 = 64;
+
+    let sema = Semantics::new(db);
+    let file = sema.parse_guess_edition(position.file_id);
+    let krate = sema.file_to_module_def(position.file_id)?.krate().into();
+
+    let tok = pick_best_token(file.syntax().token_at_offset(position.offset), |kind| match kind {
+        SyntaxKind::IDENT => 1,
+        _ => 0,
+    })?;
+    let (name, item, kind) = find_macro_call(&sema, position.file_id, &tok)?;
+
+    let mut steps = Vec::new();
+    for depth in 1..=MAX_STEPS {
+        let mut span_map = SpanMap::empty();
+        let mut calls = Vec::new();
+        let expanded = expand_macro_recur(
+            &sema,
+            &item,
+            &mut String::new(),
+            &mut span_map,
+            TextSize::new(0),
+            depth,
+            &mut calls,
+        )?;
+        if calls.is_empty() {
+            break;
+        }
+        let expansion = format(db, kind, position.file_id, expanded, &span_map, krate);
+        steps.push(MacroExpansionStep { expansion, calls });
+    }
+    Some(ExpandedMacroSteps { name, steps })
+}
+
+/// Finds the innermost attribute or function-like macro call around `tok`, returning its name and
+/// the kind of syntax it expands to.
+fn find_macro_call(
+    sema: &Semantics<'_, RootDatabase>,
+    file_id: FileId,
+    tok: &SyntaxToken,
+) -> Option<(String, ast::Item, SyntaxKind)> {
+    let db = sema.db;
+    for node in tok.parent_ancestors() {
         if let Some(item) = ast::Item::cast(node.clone()) {
             if let Some(def) = sema.resolve_attr_macro_call(&item) {
-                break (
-                    def.name(db)
-                        .display(
-                            db,
-                            sema.attach_first_edition(position.file_id)
-                                .map(|it| it.edition())
-                                .unwrap_or(Edition::CURRENT),
-                        )
-                        .to_string(),
-                    expand_macro_recur(&sema, &item, &mut error, &mut span_map, TextSize::new(0))?,
+                let edition = sema
+                    .attach_first_edition(file_id)
+                    .map(|it| it.edition())
+                    .unwrap_or(Edition::CURRENT);
+                return Some((
+                    def.name(db).display(db, edition).to_string(),
+                    item,
                     SyntaxKind::MACRO_ITEMS,
-                );
+                ));
             }
         }
         if let Some(mac) = ast::MacroCall::cast(node) {
@@ -113,37 +215,55 @@ pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<
             name.push('!');
             let syntax_kind =
                 mac.syntax().parent().map(|it| it.kind()).unwrap_or(SyntaxKind::MACRO_ITEMS);
-            break (
-                name,
-                expand_macro_recur(
-                    &sema,
-                    &ast::Item::MacroCall(mac),
-                    &mut error,
-                    &mut span_map,
-                    TextSize::new(0),
-                )?,
-                syntax_kind,
-            );
+            return Some((name, ast::Item::MacroCall(mac), syntax_kind));
         }
-    };
-
-    // FIXME:
-    // macro expansion may lose all white space information
-    // But we hope someday we can use ra_fmt for that
-    let mut expansion = format(db, kind, position.file_id, expanded, &span_map, krate);
-
-    if !error.is_empty() {
-        expansion.insert_str(0, &format!("Expansion had errors:{error}\n\n"));
     }
-    Some(ExpandedMacro { name, expansion })
+    None
+}
+
+/// Describes a macro call expanded by [`expand_macro_steps`], including how its input matched
+/// the arms of the called `macro_rules!`.
+fn describe_macro_call(
+    sema: &Semantics<'_, RootDatabase>,
+    item: &ast::Item,
+    error: Option<String>,
+) -> ExpandedMacroCall {
+    let db = sema.db;
+    let ast::Item::MacroCall(macro_call) = item else {
+        let name = sema
+            .resolve_attr_macro_call(item)
+            .map(|it| it.name(db).as_str().to_owned())
+            .unwrap_or_default();
+        return ExpandedMacroCall { name, matched_arm: None, arms: Vec::new(), error };
+    };
+    let name = macro_call.path().map(|it| format!("{it}!")).unwrap_or_default();
+    let patterns = match sema.resolve_macro_call(macro_call).and_then(|it| it.source(db)) {
+        Some(InFile { value: Either::Left(mac), .. }) => {
+            macro_rules(&mac).into_iter().map(|rule| rule.matcher.syntax().to_string()).collect()
+        }
+        _ => Vec::new(),
+    };
+    let arms = match sema.macro_call_rule_errors(macro_call) {
+        Some(errors) => patterns
+            .into_iter()
+            .zip(errors)
+            .map(|(pattern, error)| MacroArm { pattern, error })
+            .collect(),
+        None => Vec::new(),
+    };
+    ExpandedMacroCall { name, matched_arm: sema.resolve_macro_call_arm(macro_call), arms, error }
 }
 
+/// Expands `macro_call`, and the macro calls in its expansion up to `depth` levels deep in total.
+/// The calls expanded at the last level are pushed to `last_level_calls`.
 fn expand_macro_recur(
     sema: &Semantics<'_, RootDatabase>,
     macro_call: &ast::Item,
     error: &mut String,
     result_span_map: &mut SpanMap<SyntaxContextId>,
     offset_in_original_node: TextSize,
+    depth: u32,
+    last_level_calls: &mut Vec<ExpandedMacroCall>,
 ) -> Option<SyntaxNode> {
     let ExpandResult { value: expanded, err } = match macro_call {
         item @ ast::Item::MacroCall(macro_call) => {
@@ -152,6 +272,10 @@ fn expand_macro_recur(
         item => sema.expand_attr_macro(item)?,
     };
     let expanded = expanded.clone_for_update();
+    if depth == 1 {
+        let err = err.as_ref().map(|err| err.render_to_string(sema.db).to_string());
+        last_level_calls.push(describe_macro_call(sema, macro_call, err));
+    }
     if let Some(err) = err {
         format_to!(error, "\n{}", err.render_to_string(sema.db));
     }
@@ -163,7 +287,15 @@ fn expand_macro_recur(
         expanded.text_range().len(),
         &expansion_span_map,
     );
-    Some(expand(sema, expanded, error, result_span_map, u32::from(offset_in_original_node) as i32))
+    Some(expand(
+        sema,
+        expanded,
+        error,
+        result_span_map,
+        u32::from(offset_in_original_node) as i32,
+        depth - 1,
+        last_level_calls,
+    ))
 }
 
 fn expand(
@@ -172,7 +304,12 @@ fn expand(
     error: &mut String,
     result_span_map: &mut SpanMap<SyntaxContextId>,
     mut offset_in_original_node: i32,
+    depth: u32,
+    last_level_calls: &mut Vec<ExpandedMacroCall>,
 ) -> SyntaxNode {
+    if depth == 0 {
+        return expanded;
+    }
     let children = expanded.descendants().filter_map(ast::Item::cast);
     let mut replacements = Vec::new();
 
@@ -186,6 +323,8 @@ fn expand(
                 (offset_in_original_node + (u32::from(child.syntax().text_range().start()) as i32))
                     as u32,
             ),
+            depth,
+            last_level_calls,
         ) {
             offset_in_original_node = offset_in_original_node
                 + (u32::from(new_node.text_range().len()) as i32)
@@ -290,6 +429,7 @@ fn _format(
 #[cfg(test)]
 mod tests {
     use expect_test::{expect, Expect};
+    use stdx::format_to;
 
     use crate::fixture;
 
@@ -301,6 +441,31 @@ mod tests {
         expect.assert_eq(&actual);
     }
 
+    #[track_caller]
+    fn check_steps(#[rust_analyzer::rust_fixture] ra_fixture: &str, expect: Expect) {
+        let (analysis, pos) = fixture::position(ra_fixture);
+        let expansion = analysis.expand_macro_steps(pos).unwrap().unwrap();
+        let mut actual = expansion.name;
+        for (i, step) in expansion.steps.iter().enumerate() {
+            format_to!(actual, "\n--- step {} ---\n{}", i + 1, step.expansion);
+            for call in &step.calls {
+                format_to!(actual, "\n{} (matched arm: {:?})", call.name, call.matched_arm);
+                if let Some(error) = &call.error {
+                    format_to!(actual, "\n  error: {error}");
+                }
+                for (idx, arm) in call.arms.iter().enumerate() {
+                    format_to!(
+                        actual,
+                        "\n  arm {idx} {}: {}",
+                        arm.pattern,
+                        arm.error.as_deref().unwrap_or("matches")
+                    );
+                }
+            }
+        }
+        expect.assert_eq(&actual);
+    }
+
     #[test]
     fn expand_allowed_builtin_macro() {
         check(
@@ -677,4 +842,65 @@ crate::Foo;
 crate::Foo;"#]],
         );
     }
+
+    #[test]
+    fn macro_expand_steps() {
+        check_steps(
+            r#"
+macro_rules! inner {
+    ($e:expr) => { $e * 2 };
+}
+macro_rules! outer {
+    (double $e:expr) => { inner!($e) };
+    ($i:ident) => { $i };
+}
+fn main() {
+    let x = out$0er!(double 1 + 1);
+}
+"#,
+            expect![[r#"
+                outer!
+                --- step 1 ---
+                inner!((1+1))
+                outer! (matched arm: Some(0))
+                  arm 0 (double $e:expr): matches
+                  arm 1 ($i:ident): leftover tokens
+                --- step 2 ---
+                (1+1)*2
+                inner! (matched arm: Some(0))
+                  arm 0 ($e:expr): matches"#]],
+        );
+    }
+
+    #[test]
+    fn macro_expand_steps_items() {
+        check_steps(
+            r#"
+macro_rules! item {
+    ($name:ident) => { fn $name() {} };
+}
+macro_rules! items {
+    ($($name:ident),*) => { $(item!($name);)* };
+    () => {};
+}
+it$0ems!(a, b);
+"#,
+            expect![[r#"
+                items!
+                --- step 1 ---
+                item!(a);
+                item!(b);
+                items! (matched arm: Some(0))
+                  arm 0 ($($name:ident),*): matches
+                  arm 1 (): leftover tokens
+                --- step 2 ---
+                fn a(){}
+
+                fn b(){}
+                item! (matched arm: Some(0))
+                  arm 0 ($name:ident): matches
+                item! (matched arm: Some(0))
+                  arm 0 ($name:ident): matches"#]],
+        );
+    }
 }
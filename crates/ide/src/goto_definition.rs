@@ -14,6 +14,7 @@ use ide_db::{
     defs::{Definition, IdentClass},
     famous_defs::FamousDefs,
     helpers::pick_best_token,
+    syntax_helpers::macro_def,
     RootDatabase, SymbolKind,
 };
 use itertools::Itertools;
@@ -87,6 +88,10 @@ pub(crate) fn goto_definition(
         return Some(RangeInfo::new(original_token.text_range(), navs));
     }
 
+    if let Some(navs) = find_macro_metavar_binder(file_id, &original_token) {
+        return Some(RangeInfo::new(original_token.text_range(), navs));
+    }
+
     let navs = sema
         .descend_into_macros_no_opaque(original_token.clone())
         .into_iter()
@@ -202,7 +207,7 @@ fn find_definition_for_known_blanket_dual_impls(
     Some(def_to_nav(sema.db, def))
 }
 
-fn try_lookup_include_path(
+pub(crate) fn try_lookup_include_path(
     sema: &Semantics<'_, RootDatabase>,
     token: ast::String,
     file_id: FileId,
@@ -231,6 +236,29 @@ fn try_lookup_include_path(
     })
 }
 
+/// Finds the binder of a `$name` metavariable in a declarative macro definition.
+fn find_macro_metavar_binder(
+    file_id: FileId,
+    token: &SyntaxToken,
+) -> Option<Vec<NavigationTarget>> {
+    let rule = macro_def::metavar_rule(token)?;
+    let binder = macro_def::metavar_binders(&rule.matcher)
+        .into_iter()
+        .find(|binder| binder.name.text() == token.text())?;
+    let range = binder.name.text_range();
+    Some(vec![NavigationTarget {
+        file_id,
+        full_range: range,
+        name: token.text().into(),
+        alias: None,
+        focus_range: Some(range),
+        kind: None,
+        container_name: None,
+        description: binder.fragment.map(|it| it.text().to_owned()),
+        docs: None,
+    }])
+}
+
 fn try_lookup_macro_def_in_macro_use(
     sema: &Semantics<'_, RootDatabase>,
     token: SyntaxToken,
@@ -961,6 +989,27 @@ macro_rules! foo { () => { () } }
         );
     }
 
+    #[test]
+    fn goto_def_for_macro_metavariable() {
+        check(
+            r#"
+macro_rules! foo {
+    ($a:ident) => {};
+    ($($a:ident),* ; $b:expr) => { $($a)* $b$0 };
+                    //^
+}
+"#,
+        );
+        check(
+            r#"
+macro foo($name:ident) {
+         //^^^^
+    fn $name$0() {}
+}
+"#,
+        );
+    }
+
     #[test]
     fn goto_def_for_macro_defined_fn_with_arg() {
         check(
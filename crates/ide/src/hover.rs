@@ -1,3 +1,4 @@
+mod cargo_toml;
 mod render;
 
 #[cfg(test)]
@@ -124,14 +125,24 @@ pub(crate) fn hover(
     frange @ FileRange { file_id, range }: FileRange,
     config: &HoverConfig,
 ) -> Option<RangeInfo<HoverResult>> {
-    let sema = &hir::Semantics::new(db);
-    let file = sema.parse_guess_edition(file_id).syntax().clone();
-    let edition =
-        sema.attach_first_edition(file_id).map(|it| it.edition()).unwrap_or(Edition::CURRENT);
-    let mut res = if range.is_empty() {
-        hover_offset(sema, FilePosition { file_id, offset: range.start() }, file, config, edition)
+    let mut res = if ide_db::cargo_toml::is_cargo_toml(db, file_id) {
+        cargo_toml::hover(db, FilePosition { file_id, offset: range.start() })
     } else {
-        hover_ranged(sema, frange, file, config, edition)
+        let sema = &hir::Semantics::new(db);
+        let file = sema.parse_guess_edition(file_id).syntax().clone();
+        let edition =
+            sema.attach_first_edition(file_id).map(|it| it.edition()).unwrap_or(Edition::CURRENT);
+        if range.is_empty() {
+            hover_offset(
+                sema,
+                FilePosition { file_id, offset: range.start() },
+                file,
+                config,
+                edition,
+            )
+        } else {
+            hover_ranged(sema, frange, file, config, edition)
+        }
     }?;
 
     if let HoverDocFormat::PlainText = config.format {
@@ -0,0 +1,54 @@
+//! Hover for dependencies in `Cargo.toml` manifests.
+
+use ide_db::{base_db::SourceDatabase, cargo_toml, RootDatabase};
+use itertools::Itertools;
+
+use crate::{hover::HoverResult, markup::Markup, FilePosition, RangeInfo};
+
+// Feature: Cargo.toml Support
+//
+// `Cargo.toml` manifests of the workspace get a few IDE features based on the crate graph:
+//
+// - the names of crates in the crate graph are completed in dependency tables,
+// - the features of dependencies are completed in their `features` arrays and in the `[features]`
+//   table,
+// - hovering a dependency shows the version it resolved to and its features,
+// - unknown features and invalid workspace inheritance are reported as diagnostics.
+//
+// Manifests need to be valid TOML for this, except for a dependency name that is being typed.
+pub(super) fn hover(
+    db: &RootDatabase,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<RangeInfo<HoverResult>> {
+    let manifest = cargo_toml::Manifest::parse(&db.file_text(file_id))?;
+    let dep =
+        manifest.dependencies().into_iter().find(|it| it.key.range.contains_inclusive(offset))?;
+    let crates = cargo_toml::package_crates(db, file_id);
+    let krate = cargo_toml::resolve_package(db, &crates, dep.package_name())?;
+
+    let mut markup = match &db.crate_graph()[krate].version {
+        Some(version) => format!("```toml\n{} = \"{version}\"\n```", dep.package_name()),
+        None => format!("```toml\n{}\n```", dep.package_name()),
+    };
+    let enabled = cargo_toml::enabled_features(db, krate);
+    if !enabled.is_empty() {
+        markup += &format!("\n___\nenabled features: {}", format_features(&enabled));
+    }
+    let disabled = cargo_toml::declared_features(db, krate)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|it| !enabled.contains(it))
+        .collect::<Vec<_>>();
+    if !disabled.is_empty() {
+        markup += &format!("\n\nother features: {}", format_features(&disabled));
+    }
+
+    Some(RangeInfo::new(
+        dep.key.range,
+        HoverResult { markup: Markup::from(markup), actions: Vec::new() },
+    ))
+}
+
+fn format_features(features: &[hir::Symbol]) -> String {
+    features.iter().map(|it| format!("`{}`", it.as_str())).join(", ")
+}
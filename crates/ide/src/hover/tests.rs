@@ -1802,6 +1802,52 @@ fn hover_local_var_edge() {
     )
 }
 
+#[test]
+fn hover_local_var_inferred_lifetime() {
+    check(
+        r#"
+fn func<'a>(foo: &'a i32, bar: &i32) {
+    let baz$0 = foo;
+    let _ = bar;
+}
+"#,
+        expect![[r#"
+            *baz*
+
+            ```rust
+            let baz: &'a i32
+            ```
+
+            ---
+
+            size = 8, align = 8, niches = 1
+        "#]],
+    )
+}
+
+#[test]
+fn hover_local_var_borrowing_local_has_no_lifetime() {
+    check(
+        r#"
+fn func<'a>(foo: &'a i32, cond: bool) {
+    let x = 0;
+    let baz$0 = if cond { foo } else { &x };
+}
+"#,
+        expect![[r#"
+            *baz*
+
+            ```rust
+            let baz: &i32
+            ```
+
+            ---
+
+            size = 8, align = 8, niches = 1
+        "#]],
+    )
+}
+
 #[test]
 fn hover_for_param_edge() {
     check(
@@ -8252,7 +8298,7 @@ format_args!("{aaaaa$0}");
             *aaaaa*
 
             ```rust
-            let aaaaa: &str
+            let aaaaa: &'static str
             ```
         "#]],
     );
@@ -8272,7 +8318,7 @@ format_args!("{$0aaaaa}");
             *aaaaa*
 
             ```rust
-            let aaaaa: &str
+            let aaaaa: &'static str
             ```
         "#]],
     );
@@ -8292,7 +8338,7 @@ format_args!(r"{$0aaaaa}");
             *aaaaa*
 
             ```rust
-            let aaaaa: &str
+            let aaaaa: &'static str
             ```
         "#]],
     );
@@ -8317,7 +8363,7 @@ foo!(r"{$0aaaaa}");
             *aaaaa*
 
             ```rust
-            let aaaaa: &str
+            let aaaaa: &'static str
             ```
         "#]],
     );
@@ -10353,3 +10399,24 @@ macro_rules! str {
         "#]],
     );
 }
+
+#[test]
+fn hover_cargo_toml_dependency() {
+    check(
+        r#"
+//- /foo/src/lib.rs crate:foo deps:serde env:CARGO_MANIFEST_DIR=/foo
+//- /serde/src/lib.rs crate:serde@1.0.210,https://github.com/serde-rs/serde cfg:feature=derive,feature=std
+//- /foo/Cargo.toml
+[dependencies]
+ser$0de = { version = "1", features = ["derive"] }
+"#,
+        expect![[r#"
+            *serde*
+            ```toml
+            serde = "1.0.210"
+            ```
+            ___
+            enabled features: `derive`, `std`
+        "#]],
+    );
+}
@@ -0,0 +1,186 @@
+//! Entry point for inline values shown while debugging.
+
+use std::iter;
+
+use hir::{ScopeDef, Semantics};
+use ide_db::{FileId, FileRange, RootDatabase};
+use syntax::{ast, AstNode, TextRange, TextSize};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct InlineValue {
+    pub range: TextRange,
+    pub kind: InlineValueKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum InlineValueKind {
+    /// The value can be looked up by name in the current stack frame.
+    Variable { name: String },
+    /// The value has to be computed by evaluating the expression in the current stack frame.
+    Expression { expression: String },
+}
+
+// Feature: Inline Values
+//
+// While execution is stopped in a debugger, shows the values of the local bindings visible at the
+// stopped location next to their declarations.
+pub(crate) fn inline_values(
+    db: &RootDatabase,
+    FileRange { file_id, range }: FileRange,
+    stopped_at: TextSize,
+) -> Vec<InlineValue> {
+    let sema = &Semantics::new(db);
+    let file = sema.parse_guess_edition(file_id);
+    // Debuggers usually report the start of the line they stopped at, so skip to the statement
+    // that is about to run.
+    let Some(token) = file.syntax().token_at_offset(stopped_at).right_biased().and_then(|token| {
+        iter::successors(Some(token), |it| it.next_token()).find(|it| !it.kind().is_trivia())
+    }) else {
+        return Vec::new();
+    };
+    // The scope of the first expression of that statement is the one that sees all previous
+    // bindings, including those shadowing each other.
+    let stmt_expr = token
+        .parent_ancestors()
+        .take_while(|it| !ast::StmtList::can_cast(it.kind()))
+        .find(|it| {
+            ast::Stmt::can_cast(it.kind())
+                || it.parent().is_some_and(|parent| ast::StmtList::can_cast(parent.kind()))
+        })
+        .and_then(|stmt| stmt.descendants().find_map(ast::Expr::cast));
+    let scope = match stmt_expr {
+        Some(expr) => sema.scope(expr.syntax()),
+        None => token
+            .parent()
+            .and_then(|parent| sema.scope_at_offset(&parent, token.text_range().start())),
+    };
+    let Some(scope) = scope else { return Vec::new() };
+
+    let mut res = Vec::new();
+    scope.process_all_names(&mut |name, def| {
+        let ScopeDef::Local(local) = def else { return };
+        let Some(name_range) = local
+            .primary_source(db)
+            .name()
+            .and_then(|it| it.syntax().original_file_range_opt(db))
+            .filter(|(it, _)| FileId::from(it.file_id) == file_id && range.contains_range(it.range))
+            .map(|(it, _)| it.range)
+        else {
+            return;
+        };
+        let name = name.as_str().to_owned();
+        // Debug info doesn't describe `self` as an ordinary variable on every backend.
+        let kind = if local.is_self(db) {
+            InlineValueKind::Expression { expression: name }
+        } else {
+            InlineValueKind::Variable { name }
+        };
+        res.push(InlineValue { range: name_range, kind });
+    });
+    res.sort_by_key(|it| it.range.start());
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use ide_db::FileRange;
+    use syntax::TextRange;
+
+    use crate::fixture;
+
+    fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let text = analysis.file_text(position.file_id).unwrap();
+        let range = TextRange::up_to((text.len() as u32).into());
+        let values = analysis
+            .inline_values(FileRange { file_id: position.file_id, range }, position.offset)
+            .unwrap();
+        let actual = values
+            .into_iter()
+            .map(|it| format!("{:?} {:?}\n", it.range, it.kind))
+            .collect::<String>();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn locals_in_scope() {
+        check(
+            r#"
+fn foo(a: i32, (b, c): (i32, i32)) {
+    let d = a + b;
+    {
+        let inner = 1;
+    }
+    let e = d$0 + c;
+    let f = 0;
+}
+"#,
+            expect![[r#"
+                7..8 Variable { name: "a" }
+                16..17 Variable { name: "b" }
+                19..20 Variable { name: "c" }
+                45..46 Variable { name: "d" }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn shadowed_locals() {
+        check(
+            r#"
+fn foo() {
+    let x = 1;
+    let x = x + 1;
+    $0x;
+}
+"#,
+            expect![[r#"
+                34..35 Variable { name: "x" }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn self_param_and_closure() {
+        check(
+            r#"
+struct S;
+impl S {
+    fn foo(&self, a: i32) {
+        let f = |b: i32| {
+            let c = a + b;
+            $0c
+        };
+    }
+}
+"#,
+            expect![[r#"
+                31..35 Expression { expression: "self" }
+                37..38 Variable { name: "a" }
+                64..65 Variable { name: "b" }
+                90..91 Variable { name: "c" }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn locals_from_macros() {
+        check(
+            r#"
+macro_rules! bind {
+    ($name:ident) => { let $name = 0; };
+}
+fn foo() {
+    bind!(x);
+    let y = 1;
+    $0y;
+}
+"#,
+            expect![[r#"
+                84..85 Variable { name: "x" }
+                96..97 Variable { name: "y" }
+            "#]],
+        );
+    }
+}
@@ -1,10 +1,12 @@
-use hir::{ConstEvalError, DefWithBody, Semantics};
-use ide_db::{base_db::SourceRootDatabase, FilePosition, LineIndexDatabase, RootDatabase};
+use hir::{ConstEvalError, DefWithBody, Semantics, TestEvalOutcome};
+use ide_db::{base_db::SourceRootDatabase, FileId, FilePosition, LineIndexDatabase, RootDatabase};
 use span::Edition;
 use std::time::{Duration, Instant};
 use stdx::format_to;
 use syntax::{algo::ancestors_at_offset, ast, AstNode, TextRange};
 
+use crate::test_explorer;
+
 // Feature: Interpret A Function, Static Or Const.
 //
 // | Editor  | Action Name |
@@ -35,17 +37,7 @@ fn find_and_interpret(db: &RootDatabase, position: FilePosition) -> Option<(Dura
         ast::Item::Static(it) => sema.to_def(&it)?.into(),
         _ => return None,
     };
-    let span_formatter = |file_id, text_range: TextRange| {
-        let path = &db
-            .source_root(db.file_source_root(file_id))
-            .path_for_file(&file_id)
-            .map(|x| x.to_string());
-        let path = path.as_deref().unwrap_or("<unknown file>");
-        match db.line_index(file_id).try_line_col(text_range.start()) {
-            Some(line_col) => format!("file://{path}:{}:{}", line_col.line + 1, line_col.col),
-            None => format!("file://{path} range {text_range:?}"),
-        }
-    };
+    let span_formatter = |file_id, text_range| format_span(db, file_id, text_range);
     let edition = def.module(db).krate().edition(db);
     let start_time = Instant::now();
     let res = match def {
@@ -64,18 +56,113 @@ pub(crate) fn render_const_eval_error(
     e: ConstEvalError,
     edition: Edition,
 ) -> String {
-    let span_formatter = |file_id, text_range: TextRange| {
-        let path = &db
-            .source_root(db.file_source_root(file_id))
-            .path_for_file(&file_id)
-            .map(|x| x.to_string());
-        let path = path.as_deref().unwrap_or("<unknown file>");
-        match db.line_index(file_id).try_line_col(text_range.start()) {
-            Some(line_col) => format!("file://{path}:{}:{}", line_col.line + 1, line_col.col),
-            None => format!("file://{path} range {text_range:?}"),
-        }
-    };
+    let span_formatter = |file_id, text_range| format_span(db, file_id, text_range);
     let mut r = String::new();
     _ = e.pretty_print(&mut r, db, span_formatter, edition);
     r
 }
+
+/// Runs the test with the given test explorer id in the interpreter, or returns `None` if there is
+/// no such test.
+pub(crate) fn interpret_test(db: &RootDatabase, test_id: &str) -> Option<TestEvalOutcome> {
+    let test = test_explorer::find_test_by_id(db, test_id)?;
+    Some(test.eval_test(db, |file_id, text_range| format_span(db, file_id, text_range)))
+}
+
+fn format_span(db: &RootDatabase, file_id: FileId, text_range: TextRange) -> String {
+    let path = &db
+        .source_root(db.file_source_root(file_id))
+        .path_for_file(&file_id)
+        .map(|x| x.to_string());
+    let path = path.as_deref().unwrap_or("<unknown file>");
+    match db.line_index(file_id).try_line_col(text_range.start()) {
+        Some(line_col) => format!("file://{path}:{}:{}", line_col.line + 1, line_col.col),
+        None => format!("file://{path} range {text_range:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::fixture;
+
+    fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str, tests: &[&str], expect: Expect) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        let mut ids = analysis.find_test_ids(None).unwrap();
+        ids.sort();
+        let mut included = analysis.find_test_ids(Some(&["foo::tests".to_owned()])).unwrap();
+        included.sort();
+        let mut actual = format!("{ids:?}\nincluded: {included:?}\n");
+        for test in tests {
+            let outcome = analysis.interpret_test(&format!("foo::{test}")).unwrap();
+            actual += &format!("{test}: {outcome:?}\n");
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn interpret_tests() {
+        check(
+            r#"
+//- minicore: fmt, panic
+//- /lib.rs crate:foo
+extern "C" {
+    fn ffi() -> i32;
+}
+
+#[test]
+fn top_level() {}
+
+mod tests {
+    #[test]
+    fn passes() {
+        let x = 2 + 2;
+        if x != 4 {
+            panic!("math is broken");
+        }
+    }
+
+    #[test]
+    fn fails() {
+        panic!("expected failure");
+    }
+
+    #[test]
+    #[ignore]
+    fn ignored() {}
+
+    #[test]
+    fn calls_ffi() {
+        unsafe { super::ffi() };
+    }
+
+    #[test]
+    fn returns_result() -> Result<(), ()> {
+        Ok(())
+    }
+}
+"#,
+            &[
+                "top_level",
+                "tests::passes",
+                "tests::fails",
+                "tests::ignored",
+                "tests::calls_ffi",
+                "tests::returns_result",
+                "tests::missing",
+            ],
+            expect![[r#"
+                ["foo::tests::calls_ffi", "foo::tests::fails", "foo::tests::ignored", "foo::tests::passes", "foo::tests::returns_result", "foo::top_level"]
+                included: ["foo::tests::calls_ffi", "foo::tests::fails", "foo::tests::ignored", "foo::tests::passes", "foo::tests::returns_result"]
+                top_level: Some(Passed)
+                tests::passes: Some(Passed)
+                tests::fails: Some(Failed { message: "panicked:\nexpected failure" })
+                tests::ignored: Some(Ignored)
+                tests::calls_ffi: Some(Unsupported { reason: "Mir eval error:\nNotSupported(\"unknown external function ffi\")\n" })
+                tests::returns_result: Some(Unsupported { reason: "tests returning a value are not supported" })
+                tests::missing: None
+            "#]],
+        );
+    }
+}
@@ -21,6 +21,7 @@ mod navigation_target;
 mod annotations;
 mod call_hierarchy;
 mod doc_links;
+mod document_links;
 mod expand_macro;
 mod extend_selection;
 mod fetch_crates;
@@ -33,8 +34,10 @@ mod goto_type_definition;
 mod highlight_related;
 mod hover;
 mod inlay_hints;
+mod inline_values;
 mod interpret;
 mod join_lines;
+mod linked_editing;
 mod markdown_remove;
 mod matching_brace;
 mod moniker;
@@ -49,6 +52,7 @@ mod static_index;
 mod status;
 mod syntax_highlighting;
 mod test_explorer;
+mod type_hierarchy;
 mod typing;
 mod view_crate_graph;
 mod view_hir;
@@ -80,7 +84,10 @@ use crate::navigation_target::ToNav;
 pub use crate::{
     annotations::{Annotation, AnnotationConfig, AnnotationKind, AnnotationLocation},
     call_hierarchy::{CallHierarchyConfig, CallItem},
-    expand_macro::ExpandedMacro,
+    document_links::{DocumentLink, DocumentLinkTarget},
+    expand_macro::{
+        ExpandedMacro, ExpandedMacroCall, ExpandedMacroSteps, MacroArm, MacroExpansionStep,
+    },
     file_structure::{StructureNode, StructureNodeKind},
     folding_ranges::{Fold, FoldKind},
     highlight_related::{HighlightRelatedConfig, HighlightedRange},
@@ -94,6 +101,7 @@ pub use crate::{
         InlayHintPosition, InlayHintsConfig, InlayKind, InlayTooltip, LazyProperty,
         LifetimeElisionHints,
     },
+    inline_values::{InlineValue, InlineValueKind},
     join_lines::JoinLinesConfig,
     markup::Markup,
     moniker::{
@@ -115,7 +123,7 @@ pub use crate::{
     },
     test_explorer::{TestItem, TestItemKind},
 };
-pub use hir::Semantics;
+pub use hir::{Semantics, TestEvalOutcome};
 pub use ide_assists::{
     Assist, AssistConfig, AssistId, AssistKind, AssistResolveStrategy, SingleResolve,
 };
@@ -133,7 +141,8 @@ pub use ide_db::{
     search::{ReferenceCategory, SearchScope},
     source_change::{FileSystemEdit, SnippetEdit, SourceChange},
     symbol_index::Query,
-    FileId, FilePosition, FileRange, RootDatabase, Severity, SymbolKind,
+    FileId, FilePosition, FileRange, PersistKeys, PersistedCaches, RootDatabase, Severity,
+    SymbolKind,
 };
 pub use ide_diagnostics::{Diagnostic, DiagnosticCode, DiagnosticsConfig, ExprFillDefaultMode};
 pub use ide_ssr::SsrError;
@@ -293,6 +302,16 @@ impl Analysis {
         self.with_db(move |db| prime_caches::parallel_prime_caches(db, num_worker_threads, &cb))
     }
 
+    /// Computes the keys persisted caches of the current crate graph are looked up with.
+    pub fn persist_keys(&self) -> Cancellable<PersistKeys> {
+        self.with_db(prime_caches::persist_keys)
+    }
+
+    /// Collects the item trees, def maps and symbols of libraries so that they can be persisted.
+    pub fn library_caches(&self) -> Cancellable<PersistedCaches> {
+        self.with_db(prime_caches::library_caches)
+    }
+
     /// Gets the text of the source file.
     pub fn file_text(&self, file_id: FileId) -> Cancellable<Arc<str>> {
         self.with_db(|db| SourceDatabase::file_text(db, file_id))
@@ -346,6 +365,10 @@ impl Analysis {
         self.with_db(|db| interpret::interpret(db, position))
     }
 
+    pub fn interpret_test(&self, test_id: &str) -> Cancellable<Option<TestEvalOutcome>> {
+        self.with_db(|db| interpret::interpret_test(db, test_id))
+    }
+
     pub fn view_item_tree(&self, file_id: FileId) -> Cancellable<String> {
         self.with_db(|db| view_item_tree::view_item_tree(db, file_id))
     }
@@ -366,6 +389,10 @@ impl Analysis {
         self.with_db(|db| test_explorer::discover_tests_in_file(db, file_id))
     }
 
+    pub fn find_test_ids(&self, include: Option<&[String]>) -> Cancellable<Vec<String>> {
+        self.with_db(|db| test_explorer::find_test_ids(db, include))
+    }
+
     /// Renders the crate graph to GraphViz "dot" syntax.
     pub fn view_crate_graph(&self, full: bool) -> Cancellable<Result<String, String>> {
         self.with_db(|db| view_crate_graph::view_crate_graph(db, full))
@@ -379,6 +406,14 @@ impl Analysis {
         self.with_db(|db| expand_macro::expand_macro(db, position))
     }
 
+    /// Expands the macro at the given position one level at a time.
+    pub fn expand_macro_steps(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<ExpandedMacroSteps>> {
+        self.with_db(|db| expand_macro::expand_macro_steps(db, position))
+    }
+
     /// Returns an edit to remove all newlines in the range, cleaning up minor
     /// stuff like trailing commas.
     pub fn join_lines(&self, config: &JoinLinesConfig, frange: FileRange) -> Cancellable<TextEdit> {
@@ -447,6 +482,28 @@ impl Analysis {
         })
     }
 
+    /// Returns the links to other files and definitions found in the given file.
+    pub fn document_links(&self, file_id: FileId) -> Cancellable<Vec<DocumentLink>> {
+        self.with_db(|db| document_links::document_links(db, file_id))
+    }
+
+    /// Returns the values a debugger stopped at `stopped_at` can show inline in the given range.
+    pub fn inline_values(
+        &self,
+        range: FileRange,
+        stopped_at: TextSize,
+    ) -> Cancellable<Vec<InlineValue>> {
+        self.with_db(|db| inline_values::inline_values(db, range, stopped_at))
+    }
+
+    /// Returns the ranges that should be edited together with the identifier at `position`.
+    pub fn linked_editing_ranges(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<Vec<TextRange>>> {
+        self.with_db(|db| linked_editing::linked_editing_ranges(db, position))
+    }
+
     /// Returns the set of folding ranges.
     pub fn folding_ranges(&self, file_id: FileId) -> Cancellable<Vec<Fold>> {
         self.with_db(|db| {
@@ -572,6 +629,24 @@ impl Analysis {
         self.with_db(|db| call_hierarchy::outgoing_calls(db, config, position))
     }
 
+    /// Computes type hierarchy candidates for the given file position.
+    pub fn type_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy(db, position))
+    }
+
+    /// Computes the direct supertypes of the type at the given file position.
+    pub fn supertypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::supertypes(db, position))
+    }
+
+    /// Computes the direct subtypes of the type at the given file position.
+    pub fn subtypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::subtypes(db, position))
+    }
+
     /// Returns a `mod name;` declaration which created the current module.
     pub fn parent_module(&self, position: FilePosition) -> Cancellable<Vec<NavigationTarget>> {
         self.with_db(|db| parent_module::parent_module(db, position))
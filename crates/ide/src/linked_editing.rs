@@ -0,0 +1,199 @@
+//! Entry point for linked editing ranges.
+
+use hir::{GenericParam, HasSource, HirFileId, Semantics};
+use ide_db::{
+    defs::{Definition, IdentClass},
+    search::SearchScope,
+    syntax_helpers::macro_def::metavar_rule,
+    FilePosition, RootDatabase,
+};
+use itertools::Itertools;
+use span::EditionedFileId;
+use syntax::{ast, AstNode, SyntaxKind, SyntaxToken, TextRange, TextSize, T};
+
+// Feature: Linked Editing
+//
+// Edits all occurrences of an identifier at once while typing, for:
+//
+// - a metavariable within one `macro_rules!` arm
+// - a loop or block label and the `break`/`continue` expressions using it
+// - a generic lifetime within the item declaring it
+pub(crate) fn linked_editing_ranges(
+    db: &RootDatabase,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<Vec<TextRange>> {
+    let sema = &Semantics::new(db);
+    let file_id = sema
+        .attach_first_edition(file_id)
+        .unwrap_or_else(|| EditionedFileId::current_edition(file_id));
+    let file = sema.parse(file_id);
+    let token = file.syntax().token_at_offset(offset).find(|it| {
+        matches!(it.kind(), SyntaxKind::LIFETIME_IDENT | T![$]) || it.kind().is_any_identifier()
+    })?;
+
+    let ranges = match token.kind() {
+        SyntaxKind::LIFETIME_IDENT => lifetime_ranges(sema, file_id, &token)?,
+        _ => metavar_ranges(sema, &token)?,
+    };
+    (ranges.len() > 1).then_some(ranges)
+}
+
+fn lifetime_ranges(
+    sema: &Semantics<'_, RootDatabase>,
+    file_id: EditionedFileId,
+    token: &SyntaxToken,
+) -> Option<Vec<TextRange>> {
+    let (def, decl) = match *IdentClass::classify_token(sema, token)?.definitions().as_slice() {
+        [(def @ Definition::Label(label), _)] => {
+            (def, label.source(sema.db)?.map(|it| it.lifetime()))
+        }
+        [(def @ Definition::GenericParam(GenericParam::LifetimeParam(param)), _)] => {
+            (def, param.source(sema.db)?.map(|it| it.lifetime()))
+        }
+        _ => return None,
+    };
+    let decl = decl.transpose()?;
+    if decl.file_id != HirFileId::from(file_id) {
+        return None;
+    }
+    let mut usages = def.usages(sema).in_scope(&SearchScope::single_file(file_id)).all();
+    let ranges = usages
+        .references
+        .remove(&file_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|it| it.range)
+        .chain(Some(decl.value.syntax().text_range()))
+        // Leave the `'` alone, only the name is edited.
+        .map(|range| TextRange::new(range.start() + TextSize::of('\''), range.end()))
+        .sorted_by_key(|range| range.start())
+        .dedup()
+        .collect();
+    Some(ranges)
+}
+
+fn metavar_ranges(
+    sema: &Semantics<'_, RootDatabase>,
+    token: &SyntaxToken,
+) -> Option<Vec<TextRange>> {
+    let name = match token.kind() {
+        T![$] => token.next_token()?,
+        _ => token.clone(),
+    };
+    // Bail out early on tokens that can't be the name of a metavariable.
+    metavar_rule(&name)?;
+    let mac = sema.to_def(&name.parent_ancestors().find_map(ast::Macro::cast)?)?;
+    Some(mac.metavar_ranges(sema.db, name.text_range()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture;
+
+    fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
+        let (analysis, position, mut annotations) = fixture::annotations(ra_fixture);
+        let ranges = analysis.linked_editing_ranges(position).unwrap().unwrap_or_default();
+        annotations.sort_by_key(|(range, _)| range.range.start());
+        let expected = annotations.into_iter().map(|(range, _)| range.range).collect::<Vec<_>>();
+        assert_eq!(ranges, expected);
+    }
+
+    #[test]
+    fn macro_rules_metavar() {
+        check(
+            r#"
+macro_rules! m {
+    ($x$0:expr, $y:ident) => { $x + $y + $x };
+    //^
+                            //^       //^
+    ($x:expr) => { $x };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_rules_metavar_in_repetition() {
+        check(
+            r#"
+macro_rules! m {
+    ($($item:expr),*) => { [$($item$0),*] };
+      //^^^^                 //^^^^
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_rules_escaped_dollar() {
+        check(
+            r#"
+macro_rules! m {
+    ($x$0:ident) => { macro_rules! inner { ($$x:expr) => { $x }; } };
+    //^                                                 //^
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_rules_dollar_crate() {
+        check(
+            r#"
+macro_rules! m {
+    () => { $crate$0::foo() };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_2_metavar() {
+        check(
+            r#"
+macro m($e$0:expr) { $e }
+       //^        //^
+"#,
+        );
+    }
+
+    #[test]
+    fn label() {
+        check(
+            r#"
+fn f() {
+    'outer$0: loop {
+   //^^^^^
+        'inner: loop {
+            break 'outer;
+                 //^^^^^
+            continue 'inner;
+        }
+        continue 'outer;
+                //^^^^^
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn lifetime() {
+        check(
+            r#"
+struct S<'a>(&'a ());
+fn f<'a, 'b>(x: &'a$0 (), y: &'b ()) -> S<'a> where 'b: 'a {}
+    //^         //^                    //^           //^
+"#,
+        );
+    }
+
+    #[test]
+    fn static_lifetime() {
+        check(
+            r#"
+fn f(x: &'static$0 ()) -> &'static () {}
+"#,
+        );
+    }
+}
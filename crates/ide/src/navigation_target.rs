@@ -187,8 +187,8 @@ impl TryToNav for FileSymbol {
             orig_range_with_focus_r(
                 db,
                 self.loc.hir_file_id,
-                self.loc.ptr.text_range(),
-                Some(self.loc.name_ptr.text_range()),
+                self.loc.range,
+                Some(self.loc.name_range),
             )
             .map(|(FileRange { file_id, range: full_range }, focus_range)| {
                 NavigationTarget {
@@ -1,6 +1,6 @@
 use hir::{db::DefDatabase, Semantics};
 use ide_db::{
-    base_db::{CrateId, FileLoader},
+    base_db::{CrateId, FileLoader, SourceDatabase},
     FileId, FilePosition, RootDatabase,
 };
 use itertools::Itertools;
@@ -54,9 +54,11 @@ pub(crate) fn parent_module(db: &RootDatabase, position: FilePosition) -> Vec<Na
 
 /// This returns `Vec` because a module may be included from several places.
 pub(crate) fn crates_for(db: &RootDatabase, file_id: FileId) -> Vec<CrateId> {
+    let crate_graph = db.crate_graph();
     db.relevant_crates(file_id)
         .iter()
         .copied()
+        .filter(|&crate_id| crate_graph.cfg_variant_of(crate_id).is_none())
         .filter(|&crate_id| db.crate_def_map(crate_id).modules_for_file(file_id).next().is_some())
         .sorted()
         .collect()
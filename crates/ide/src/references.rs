@@ -55,58 +55,12 @@ pub(crate) fn find_all_refs(
 ) -> Option<Vec<ReferenceSearchResult>> {
     let _p = tracing::info_span!("find_all_refs").entered();
     let syntax = sema.parse_guess_edition(position.file_id).syntax().clone();
-    let make_searcher = |literal_search: bool| {
-        move |def: Definition| {
-            let mut usages =
-                def.usages(sema).set_scope(search_scope.as_ref()).include_self_refs().all();
-            if literal_search {
-                retain_adt_literal_usages(&mut usages, def, sema);
-            }
-
-            let mut references: IntMap<FileId, Vec<(TextRange, ReferenceCategory)>> = usages
-                .into_iter()
-                .map(|(file_id, refs)| {
-                    (
-                        file_id.into(),
-                        refs.into_iter()
-                            .map(|file_ref| (file_ref.range, file_ref.category))
-                            .unique()
-                            .collect(),
-                    )
-                })
-                .collect();
-            let declaration = match def {
-                Definition::Module(module) => {
-                    Some(NavigationTarget::from_module_to_decl(sema.db, module))
-                }
-                def => def.try_to_nav(sema.db),
-            }
-            .map(|nav| {
-                let (nav, extra_ref) = match nav.def_site {
-                    Some(call) => (call, Some(nav.call_site)),
-                    None => (nav.call_site, None),
-                };
-                if let Some(extra_ref) = extra_ref {
-                    references
-                        .entry(extra_ref.file_id)
-                        .or_default()
-                        .push((extra_ref.focus_or_full_range(), ReferenceCategory::empty()));
-                }
-                Declaration {
-                    is_mut: matches!(def, Definition::Local(l) if l.is_mut(sema.db)),
-                    nav,
-                }
-            });
-            ReferenceSearchResult { declaration, references }
-        }
-    };
-
     // Find references for control-flow keywords.
     if let Some(res) = handle_control_flow_keywords(sema, position) {
         return Some(vec![res]);
     }
 
-    match name_for_constructor_search(&syntax, position) {
+    let mut res = match name_for_constructor_search(&syntax, position) {
         Some(name) => {
             let def = match NameClass::classify(sema, &name)? {
                 NameClass::Definition(it) | NameClass::ConstReference(it) => it,
@@ -114,11 +68,101 @@ pub(crate) fn find_all_refs(
                     Definition::Field(field_ref)
                 }
             };
-            Some(vec![make_searcher(true)(def)])
+            vec![search_def(sema, search_scope.as_ref(), true, def)]
         }
-        None => {
-            let search = make_searcher(false);
-            Some(find_defs(sema, &syntax, position.offset)?.into_iter().map(search).collect())
+        None => find_defs(sema, &syntax, position.offset)?
+            .into_iter()
+            .map(|def| search_def(sema, search_scope.as_ref(), false, def))
+            .collect(),
+    };
+    add_cfg_variant_refs(sema, position, search_scope.as_ref(), &mut res);
+    Some(res)
+}
+
+fn search_def(
+    sema: &Semantics<'_, RootDatabase>,
+    search_scope: Option<&SearchScope>,
+    literal_search: bool,
+    def: Definition,
+) -> ReferenceSearchResult {
+    let mut usages = def.usages(sema).set_scope(search_scope).include_self_refs().all();
+    if literal_search {
+        retain_adt_literal_usages(&mut usages, def, sema);
+    }
+
+    let mut references: IntMap<FileId, Vec<(TextRange, ReferenceCategory)>> = usages
+        .into_iter()
+        .map(|(file_id, refs)| {
+            (
+                file_id.into(),
+                refs.into_iter()
+                    .map(|file_ref| (file_ref.range, file_ref.category))
+                    .unique()
+                    .collect(),
+            )
+        })
+        .collect();
+    let declaration = match def {
+        Definition::Module(module) => Some(NavigationTarget::from_module_to_decl(sema.db, module)),
+        def => def.try_to_nav(sema.db),
+    }
+    .map(|nav| {
+        let (nav, extra_ref) = match nav.def_site {
+            Some(call) => (call, Some(nav.call_site)),
+            None => (nav.call_site, None),
+        };
+        if let Some(extra_ref) = extra_ref {
+            references
+                .entry(extra_ref.file_id)
+                .or_default()
+                .push((extra_ref.focus_or_full_range(), ReferenceCategory::empty()));
+        }
+        Declaration { is_mut: matches!(def, Definition::Local(l) if l.is_mut(sema.db)), nav }
+    });
+    ReferenceSearchResult { declaration, references }
+}
+
+/// Adds the references that are only visible in cfg variants of crates, that is references in code
+/// that is cfg'd out in the active configuration.
+fn add_cfg_variant_refs(
+    sema: &Semantics<'_, RootDatabase>,
+    position: FilePosition,
+    search_scope: Option<&SearchScope>,
+    res: &mut Vec<ReferenceSearchResult>,
+) {
+    let db = sema.db;
+    // Only the variants of the crates the file belongs to can contain it.
+    let variants = sema
+        .file_to_module_defs(position.file_id)
+        .map(|module| module.krate())
+        .filter(|krate| krate.cfg_variant_of(db).is_none())
+        .flat_map(|krate| krate.cfg_variants(db))
+        .unique();
+    for variant in variants {
+        let sema = Semantics::new_for_crate(db, variant);
+        let syntax = sema.parse_guess_edition(position.file_id).syntax().clone();
+        let Some(defs) = find_defs(&sema, &syntax, position.offset) else { continue };
+        let scope = SearchScope::krate(db, variant);
+        let scope = search_scope.map_or(scope.clone(), |it| it.intersection(&scope));
+        for def in defs {
+            let variant_res = search_def(&sema, Some(&scope), false, def);
+            let declaration_range = |it: &ReferenceSearchResult| {
+                it.declaration.as_ref().map(|decl| (decl.nav.file_id, decl.nav.full_range))
+            };
+            let Some(existing) =
+                res.iter_mut().find(|it| declaration_range(it) == declaration_range(&variant_res))
+            else {
+                res.push(variant_res);
+                continue;
+            };
+            for (file_id, refs) in variant_res.references {
+                let existing_refs = existing.references.entry(file_id).or_default();
+                for reference in refs {
+                    if !existing_refs.contains(&reference) {
+                        existing_refs.push(reference);
+                    }
+                }
+            }
         }
     }
 }
@@ -2775,4 +2819,42 @@ const FOO$0: i32 = 0;
             "#]],
         );
     }
+
+    #[test]
+    fn test_find_all_refs_in_cfg_variants() {
+        check(
+            r#"
+//- /lib.rs crate:lib cfg:unix cfg_variant:windows
+pub fn helper$0() {}
+
+#[cfg(unix)]
+fn unix() { helper(); }
+
+#[cfg(windows)]
+fn windows() { helper(); }
+"#,
+            expect![[r#"
+                helper Function FileId(0) 0..18 7..13
+
+                FileId(0) 45..51
+                FileId(0) 89..95
+            "#]],
+        );
+        check(
+            r#"
+//- /lib.rs crate:lib cfg:unix cfg_variant:windows
+#[cfg(windows)]
+fn windows() {
+    let local$0 = 0;
+    local + local;
+}
+"#,
+            expect![[r#"
+                local Local FileId(0) 39..44 39..44
+
+                FileId(0) 54..59 read
+                FileId(0) 62..67 read
+            "#]],
+        );
+    }
 }
@@ -1,6 +1,6 @@
 //! Discovers tests
 
-use hir::{Crate, Module, ModuleDef, Semantics};
+use hir::{Crate, Function, Module, ModuleDef, Semantics};
 use ide_db::{
     base_db::{CrateGraph, CrateId, SourceDatabase},
     FileId, RootDatabase,
@@ -31,7 +31,7 @@ pub(crate) fn discover_test_roots(db: &RootDatabase) -> Vec<TestItem> {
     let crate_graph = db.crate_graph();
     crate_graph
         .iter()
-        .filter(|&id| crate_graph[id].origin.is_local())
+        .filter(|&id| crate_graph[id].origin.is_local() && crate_graph.cfg_variant_of(id).is_none())
         .filter_map(|id| {
             let test_id = crate_graph[id].display_name.as_ref()?.to_string();
             Some(TestItem {
@@ -195,3 +195,43 @@ pub(crate) fn discover_tests_in_crate(db: &RootDatabase, crate_id: CrateId) -> V
     r.extend(discover_tests_in_module(db, module, crate_test_id, false));
     r
 }
+
+/// Returns the ids of the test functions that are, or are nested in, one of the `include`d test
+/// items. `None` selects all tests of the local crates.
+pub(crate) fn find_test_ids(db: &RootDatabase, include: Option<&[String]>) -> Vec<String> {
+    let is_included = |id: &str| {
+        include.is_none_or(|include| {
+            include.iter().any(|it| {
+                id.strip_prefix(it.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+        })
+    };
+    discover_test_roots(db)
+        .into_iter()
+        .filter_map(|root| match root.kind {
+            TestItemKind::Crate(crate_id) => Some(crate_id),
+            _ => None,
+        })
+        .flat_map(|crate_id| discover_tests_in_crate(db, crate_id))
+        .filter(|item| matches!(item.kind, TestItemKind::Function) && is_included(&item.id))
+        .map(|item| item.id)
+        .collect()
+}
+
+/// Resolves the id of a test function, as produced by the discovery functions above.
+pub(crate) fn find_test_by_id(db: &RootDatabase, test_id: &str) -> Option<Function> {
+    let (path, fn_name) = test_id.rsplit_once("::")?;
+    let mut segments = path.split("::");
+    let crate_id = find_crate_by_id(&db.crate_graph(), segments.next()?)?;
+    let mut module = Crate::from(crate_id).root_module();
+    for segment in segments {
+        module = module
+            .children(db)
+            .find(|it| it.name(db).is_some_and(|name| name.as_str() == segment))?;
+    }
+    module.declarations(db).into_iter().find_map(|def| match def {
+        ModuleDef::Function(f) if f.is_test(db) && f.name(db).as_str() == fn_name => Some(f),
+        _ => None,
+    })
+}
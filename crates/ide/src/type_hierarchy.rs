@@ -0,0 +1,260 @@
+//! Entry point for type-hierarchy
+
+use hir::{Impl, Semantics};
+use ide_db::{
+    defs::{Definition, NameClass, NameRefClass},
+    search::FileReference,
+    FxIndexSet, RootDatabase,
+};
+use syntax::{ast, AstNode, TextSize};
+
+use crate::{FilePosition, NavigationTarget, RangeInfo, TryToNav};
+
+// Feature: Type Hierarchy
+//
+// Shows the supertraits and implementors of a trait, as well as the traits implemented by a type,
+// as a navigable tree.
+//
+// Only implementors whose self type is an ADT are listed as subtypes, blanket impls are skipped.
+pub(crate) fn type_hierarchy(
+    db: &RootDatabase,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = &Semantics::new(db);
+    let file = sema.parse_guess_edition(file_id);
+    let token = file.syntax().token_at_offset(offset).next()?;
+    let range = token.text_range();
+
+    let navs = hierarchy_defs(sema, file.syntax(), offset)
+        .filter_map(|def| def.try_to_nav(db))
+        .map(|nav| nav.call_site)
+        .collect();
+
+    Some(RangeInfo::new(range, navs))
+}
+
+pub(crate) fn supertypes(
+    db: &RootDatabase,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = &Semantics::new(db);
+    let file = sema.parse_guess_edition(file_id);
+
+    let mut res = FxIndexSet::default();
+    for def in hierarchy_defs(sema, file.syntax(), offset) {
+        match def {
+            Definition::Trait(trait_) => {
+                // `direct_supertraits` includes the trait itself.
+                res.extend(
+                    trait_
+                        .direct_supertraits(db)
+                        .into_iter()
+                        .filter(|&it| it != trait_)
+                        .map(Definition::Trait),
+                );
+            }
+            Definition::Adt(adt) => res.extend(
+                Impl::all_for_type(db, adt.ty(db))
+                    .into_iter()
+                    .filter_map(|imp| imp.trait_(db))
+                    .map(Definition::Trait),
+            ),
+            _ => (),
+        }
+    }
+
+    Some(to_navs(db, res))
+}
+
+pub(crate) fn subtypes(
+    db: &RootDatabase,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = &Semantics::new(db);
+    let file = sema.parse_guess_edition(file_id);
+
+    let mut res = FxIndexSet::default();
+    for def in hierarchy_defs(sema, file.syntax(), offset) {
+        let Definition::Trait(trait_) = def else { continue };
+
+        // Subtraits have to mention the trait somewhere in their header, so look for them
+        // among its usages instead of walking every trait in the crate graph.
+        for (_, references) in def.usages(sema).all() {
+            let references =
+                references.iter().filter_map(|FileReference { name, .. }| name.as_name_ref());
+            for name in references {
+                let subtrait = sema
+                    .ancestors_with_macros(name.syntax().clone())
+                    .find_map(ast::Trait::cast)
+                    .and_then(|it| sema.to_def(&it));
+                if let Some(subtrait) = subtrait {
+                    if subtrait != trait_ && subtrait.direct_supertraits(db).contains(&trait_) {
+                        res.insert(Definition::Trait(subtrait));
+                    }
+                }
+            }
+        }
+
+        res.extend(
+            Impl::all_for_trait(db, trait_)
+                .into_iter()
+                .filter_map(|imp| imp.self_ty(db).as_adt())
+                .map(Definition::Adt),
+        );
+    }
+
+    Some(to_navs(db, res))
+}
+
+/// Classifies the name under the cursor, keeping only definitions that can take part in a type
+/// hierarchy.
+fn hierarchy_defs<'a>(
+    sema: &'a Semantics<'_, RootDatabase>,
+    file: &syntax::SyntaxNode,
+    offset: TextSize,
+) -> impl Iterator<Item = Definition> + 'a {
+    sema.find_nodes_at_offset_with_descend(file, offset).filter_map(move |node| {
+        let def = match node {
+            ast::NameLike::NameRef(name_ref) => match NameRefClass::classify(sema, &name_ref)? {
+                NameRefClass::Definition(def, _) => def,
+                _ => return None,
+            },
+            ast::NameLike::Name(name) => match NameClass::classify(sema, &name)? {
+                NameClass::Definition(def) => def,
+                _ => return None,
+            },
+            ast::NameLike::Lifetime(_) => return None,
+        };
+        match def {
+            Definition::Trait(_) | Definition::Adt(_) => Some(def),
+            Definition::TypeAlias(alias) => alias.ty(sema.db).as_adt().map(Definition::Adt),
+            _ => None,
+        }
+    })
+}
+
+fn to_navs(db: &RootDatabase, defs: FxIndexSet<Definition>) -> Vec<NavigationTarget> {
+    defs.into_iter().filter_map(|def| def.try_to_nav(db)).map(|nav| nav.call_site).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use ide_db::FilePosition;
+    use itertools::Itertools;
+
+    use crate::fixture;
+
+    fn check_hierarchy(
+        #[rust_analyzer::rust_fixture] ra_fixture: &str,
+        expected_nav: Expect,
+        expected_supertypes: Expect,
+        expected_subtypes: Expect,
+    ) {
+        let (analysis, pos) = fixture::position(ra_fixture);
+
+        let mut navs = analysis.type_hierarchy(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        let nav = navs.pop().unwrap();
+        expected_nav.assert_eq(&nav.debug_render());
+
+        let item_pos =
+            FilePosition { file_id: nav.file_id, offset: nav.focus_or_full_range().start() };
+        let supertypes = analysis.supertypes(item_pos).unwrap().unwrap();
+        expected_supertypes.assert_eq(&supertypes.iter().map(|it| it.debug_render()).join("\n"));
+
+        let subtypes = analysis.subtypes(item_pos).unwrap().unwrap();
+        expected_subtypes.assert_eq(&subtypes.iter().map(|it| it.debug_render()).join("\n"));
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_trait() {
+        check_hierarchy(
+            r#"
+trait Service {}
+trait Layer: Service {}
+trait Stack$0: Layer {}
+trait Top: Stack {}
+struct S;
+impl Stack for S {}
+"#,
+            expect!["Stack Trait FileId(0) 41..62 47..52"],
+            expect!["Layer Trait FileId(0) 17..40 23..28"],
+            expect![[r#"
+                Top Trait FileId(0) 63..82 69..72
+                S Struct FileId(0) 83..92 90..91"#]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_trait_ref() {
+        check_hierarchy(
+            r#"
+trait Service {}
+trait Layer: Serv$0ice {}
+"#,
+            expect!["Service Trait FileId(0) 0..16 6..13"],
+            expect![[]],
+            expect!["Layer Trait FileId(0) 17..40 23..28"],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_where_clause_supertrait() {
+        check_hierarchy(
+            r#"
+trait Service$0 {}
+trait Layer where Self: Service {}
+trait Other { fn f<T: Service>(); }
+"#,
+            expect!["Service Trait FileId(0) 0..16 6..13"],
+            expect![[]],
+            expect!["Layer Trait FileId(0) 17..51 23..28"],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_adt() {
+        check_hierarchy(
+            r#"
+trait A {}
+trait B {}
+enum E$0 {}
+impl A for E {}
+impl B for E {}
+impl E {}
+"#,
+            expect!["E Enum FileId(0) 22..31 27..28"],
+            expect![[r#"
+                A Trait FileId(0) 0..10 6..7
+                B Trait FileId(0) 11..21 17..18"#]],
+            expect![[]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_dedups_generic_impls() {
+        check_hierarchy(
+            r#"
+trait T$0<X> {}
+struct S;
+impl T<u8> for S {}
+impl T<u16> for S {}
+impl<X> T<X> for X {}
+"#,
+            expect!["T Trait FileId(0) 0..13 6..7"],
+            expect![[]],
+            expect!["S Struct FileId(0) 14..23 21..22"],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_not_a_type() {
+        let (analysis, pos) = fixture::position(
+            r#"
+fn foo$0() {}
+"#,
+        );
+        assert!(analysis.type_hierarchy(pos).unwrap().unwrap().info.is_empty());
+    }
+}
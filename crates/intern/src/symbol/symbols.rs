@@ -212,6 +212,7 @@ define_symbols! {
     Err,
     exchange_malloc,
     exhaustive_patterns,
+    expected,
     export_name,
     f128,
     f16,
@@ -314,6 +315,7 @@ define_symbols! {
     module_path,
     mul_assign,
     mul,
+    must_use,
     naked_asm,
     ne,
     neg,
@@ -438,6 +440,7 @@ define_symbols! {
     shl,
     shr_assign,
     shr,
+    should_panic,
     simd,
     sized,
     skip,
@@ -491,6 +494,7 @@ define_symbols! {
     unsafe_cell,
     unsize,
     unstable,
+    used,
     usize,
     v1,
     va_list,
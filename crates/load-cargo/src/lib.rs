@@ -2,7 +2,7 @@
 //! for incorporating changes.
 // Note, don't remove any public api from this. This API is consumed by external tools
 // to run rust-analyzer as a library.
-use std::{collections::hash_map::Entry, iter, mem, path::Path, sync};
+use std::{collections::hash_map::Entry, fs, io, iter, mem, path::Path, sync};
 
 use crossbeam_channel::{unbounded, Receiver};
 use hir_expand::proc_macro::{
@@ -11,10 +11,10 @@ use hir_expand::proc_macro::{
 };
 use ide_db::{
     base_db::{CrateGraph, CrateWorkspaceData, Env, SourceRoot, SourceRootId},
-    prime_caches, ChangeWithProcMacros, FxHashMap, RootDatabase,
+    prime_caches, ChangeWithProcMacros, FxHashMap, PersistedCaches, RootDatabase,
 };
 use itertools::Itertools;
-use proc_macro_api::{MacroDylib, ProcMacroClient};
+use proc_macro_api::{MacroDylib, ProcMacroClient, ProcMacroServerOptions, ServerError};
 use project_model::{CargoConfig, PackageRoot, ProjectManifest, ProjectWorkspace};
 use span::Span;
 use vfs::{
@@ -71,10 +71,15 @@ pub fn load_workspace(
     let proc_macro_server = match &load_config.with_proc_macro_server {
         ProcMacroServerChoice::Sysroot => ws
             .find_sysroot_proc_macro_srv()
-            .and_then(|it| ProcMacroClient::spawn(&it, extra_env).map_err(Into::into))
+            .and_then(|it| {
+                ProcMacroClient::spawn(&it, extra_env, ProcMacroServerOptions::default())
+                    .map_err(Into::into)
+            })
             .map_err(|e| (e, true)),
         ProcMacroServerChoice::Explicit(path) => {
-            ProcMacroClient::spawn(path, extra_env).map_err(Into::into).map_err(|e| (e, true))
+            ProcMacroClient::spawn(path, extra_env, ProcMacroServerOptions::default())
+                .map_err(Into::into)
+                .map_err(|e| (e, true))
         }
         ProcMacroServerChoice::None => {
             Err((anyhow::format_err!("proc macro server disabled"), false))
@@ -377,6 +382,37 @@ impl SourceRootConfig {
     }
 }
 
+/// Loads the caches persisted by [`store_persisted_caches`] into `db` and returns how many entries
+/// were loaded. A missing or outdated cache is not an error, nothing is loaded in that case.
+pub fn load_persisted_caches(db: &mut RootDatabase, path: &AbsPath) -> anyhow::Result<usize> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let Some(caches) = PersistedCaches::from_bytes(&bytes) else {
+        tracing::info!("ignoring outdated persisted caches at {path}");
+        return Ok(0);
+    };
+    let len = caches.len();
+    db.use_persisted_caches(caches);
+    Ok(len)
+}
+
+/// Stores caches at `path`, so that the next session can load them with
+/// [`load_persisted_caches`]. The caches of a database's libraries are collected by
+/// [`prime_caches::library_caches`].
+pub fn store_persisted_caches(caches: &PersistedCaches, path: &AbsPath) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write to a temporary file first, so that other sessions never see a partially written cache.
+    let tmp = AbsPathBuf::assert(format!("{path}.tmp").into());
+    fs::write(&tmp, caches.to_bytes())?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
 /// Load the proc-macros for the given lib path, disabling all expanders whose names are in `ignored_macros`.
 pub fn load_proc_macro(
     server: &ProcMacroClient,
@@ -512,6 +548,9 @@ impl ProcMacroExpander for Expander {
         ) {
             Ok(Ok(subtree)) => Ok(subtree),
             Ok(Err(err)) => Err(ProcMacroExpansionError::Panic(err.0)),
+            Err(ServerError { timed_out: Some(timeout), .. }) => {
+                Err(ProcMacroExpansionError::TimedOut(timeout))
+            }
             Err(err) => Err(ProcMacroExpansionError::System(err.to_string())),
         }
     }
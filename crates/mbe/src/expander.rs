@@ -56,6 +56,16 @@ pub(crate) fn expand_rules(
     }
 }
 
+/// Matches `input` against every rule without transcribing, returning the first error of each
+/// rule, `None` for the rules that matched.
+pub(crate) fn match_rules(
+    rules: &[crate::Rule],
+    input: &tt::TopSubtree<Span>,
+    def_site_edition: Edition,
+) -> Vec<Option<ExpandError>> {
+    rules.iter().map(|rule| matcher::match_(&rule.lhs, input, def_site_edition).err).collect()
+}
+
 /// The actual algorithm for expansion is not too hard, but is pretty tricky.
 /// `Bindings` structure is the key to understanding what we are doing here.
 ///
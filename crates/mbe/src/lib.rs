@@ -29,7 +29,10 @@ use tt::DelimSpan;
 use std::fmt;
 use std::sync::Arc;
 
-use crate::parser::{MetaTemplate, MetaVarKind, Op};
+use intern::{sym, Symbol};
+use rustc_hash::FxHashMap;
+
+use crate::parser::{ConcatMetaVarExprElem, MetaTemplate, MetaVarKind, Op};
 
 pub use tt::{Delimiter, DelimiterKind, Punct};
 
@@ -106,6 +109,47 @@ impl fmt::Display for ExpandErrorKind {
     }
 }
 
+/// A problem in a macro definition that does not prevent parsing it, but breaks invocations of
+/// the affected rule.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DefinitionLint {
+    pub span: Span,
+    pub kind: DefinitionLintKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DefinitionLintKind {
+    /// A metavariable in a matcher has an unknown fragment specifier.
+    UnknownFragmentSpecifier(Symbol),
+    /// A metavariable in a transcriber is not bound by the matcher.
+    UnboundMetavariable(Symbol),
+    /// A metavariable is used in fewer repetitions than it is bound in.
+    StillRepeating(Symbol),
+}
+
+impl DefinitionLintKind {
+    /// Whether rustc rejects the definition, as opposed to merely behaving unexpectedly.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, DefinitionLintKind::UnboundMetavariable(_))
+    }
+}
+
+impl fmt::Display for DefinitionLintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionLintKind::UnknownFragmentSpecifier(name) => {
+                write!(f, "invalid fragment specifier for `${}`", name.as_str())
+            }
+            DefinitionLintKind::UnboundMetavariable(name) => {
+                write!(f, "unknown macro variable `{}`", name.as_str())
+            }
+            DefinitionLintKind::StillRepeating(name) => {
+                write!(f, "variable `{}` is still repeating at this depth", name.as_str())
+            }
+        }
+    }
+}
+
 // FIXME: Showing these errors could be nicer.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum CountError {
@@ -258,6 +302,44 @@ impl DeclarativeMacro {
     ) -> ExpandResult<(tt::TopSubtree<Span>, MatchedArmIndex)> {
         expander::expand_rules(&self.rules, tt, marker, call_site, def_site_edition)
     }
+
+    /// Matches `tt` against each rule in order, returning why each of them failed to match.
+    /// Rules that match `tt` have no error.
+    pub fn match_rules(
+        &self,
+        tt: &tt::TopSubtree<Span>,
+        def_site_edition: Edition,
+    ) -> Vec<Option<ExpandError>> {
+        expander::match_rules(&self.rules, tt, def_site_edition)
+    }
+
+    /// Returns the spans of the names of the metavariables in each rule, both where the matcher
+    /// binds them and where the transcriber uses them.
+    pub fn metavar_spans(&self) -> Vec<Vec<(Symbol, Span)>> {
+        self.rules
+            .iter()
+            .map(|Rule { lhs, rhs }| {
+                let mut acc = Vec::new();
+                collect_metavar_spans(lhs, &mut acc);
+                collect_metavar_spans(rhs, &mut acc);
+                acc
+            })
+            .collect()
+    }
+
+    /// Checks the rules for mistakes that only surface when the macro gets invoked.
+    pub fn lints(&self) -> Vec<DefinitionLint> {
+        let mut acc = Vec::new();
+        for Rule { lhs, rhs } in &self.rules {
+            let mut binders = FxHashMap::default();
+            collect_binders(lhs, 0, &mut binders, &mut acc);
+            // Transcribers defining macros legitimately contain the metavariables of the inner
+            // macro, which aren't bound by this one.
+            let defines_macro = contains_ident(rhs, &sym::macro_rules);
+            check_metavar_uses(rhs, 0, &binders, defines_macro, &mut acc);
+        }
+        acc
+    }
 }
 
 impl Rule {
@@ -308,6 +390,95 @@ fn validate(pattern: &MetaTemplate) -> Result<(), ParseError> {
     Ok(())
 }
 
+fn collect_binders(
+    pattern: &MetaTemplate,
+    depth: usize,
+    binders: &mut FxHashMap<Symbol, usize>,
+    acc: &mut Vec<DefinitionLint>,
+) {
+    for op in pattern.iter() {
+        match op {
+            Op::Var { name, kind, id } => {
+                if kind.is_none() {
+                    acc.push(DefinitionLint {
+                        span: *id,
+                        kind: DefinitionLintKind::UnknownFragmentSpecifier(name.clone()),
+                    });
+                }
+                binders.insert(name.clone(), depth);
+            }
+            Op::Subtree { tokens, .. } => collect_binders(tokens, depth, binders, acc),
+            Op::Repeat { tokens, .. } => collect_binders(tokens, depth + 1, binders, acc),
+            _ => (),
+        }
+    }
+}
+
+fn check_metavar_uses(
+    template: &MetaTemplate,
+    depth: usize,
+    binders: &FxHashMap<Symbol, usize>,
+    defines_macro: bool,
+    acc: &mut Vec<DefinitionLint>,
+) {
+    let check = |name: &Symbol, span: Span, acc: &mut Vec<DefinitionLint>| match binders.get(name) {
+        Some(&bound_depth) if bound_depth > depth => acc
+            .push(DefinitionLint { span, kind: DefinitionLintKind::StillRepeating(name.clone()) }),
+        Some(_) => (),
+        None if defines_macro => (),
+        None => acc.push(DefinitionLint {
+            span,
+            kind: DefinitionLintKind::UnboundMetavariable(name.clone()),
+        }),
+    };
+    for op in template.iter() {
+        match op {
+            Op::Var { name, id, .. } | Op::Ignore { name, id } => check(name, *id, acc),
+            Op::Concat { elements, .. } => {
+                for element in elements.iter() {
+                    if let ConcatMetaVarExprElem::Var(ident) = element {
+                        check(&ident.sym, ident.span, acc);
+                    }
+                }
+            }
+            Op::Subtree { tokens, .. } => {
+                check_metavar_uses(tokens, depth, binders, defines_macro, acc)
+            }
+            Op::Repeat { tokens, .. } => {
+                check_metavar_uses(tokens, depth + 1, binders, defines_macro, acc)
+            }
+            _ => (),
+        }
+    }
+}
+
+fn collect_metavar_spans(template: &MetaTemplate, acc: &mut Vec<(Symbol, Span)>) {
+    for op in template.iter() {
+        match op {
+            Op::Var { name, id, .. } | Op::Ignore { name, id } => acc.push((name.clone(), *id)),
+            Op::Concat { elements, .. } => {
+                for element in elements.iter() {
+                    if let ConcatMetaVarExprElem::Var(ident) = element {
+                        acc.push((ident.sym.clone(), ident.span));
+                    }
+                }
+            }
+            Op::Subtree { tokens, .. } | Op::Repeat { tokens, .. } => {
+                collect_metavar_spans(tokens, acc)
+            }
+            _ => (),
+        }
+    }
+}
+
+fn contains_ident(template: &MetaTemplate, ident: &Symbol) -> bool {
+    template.iter().any(|op| match op {
+        Op::Ident(it) => it.sym == *ident,
+        Op::Subtree { tokens, .. } | Op::Repeat { tokens, .. } => contains_ident(tokens, ident),
+        _ => false,
+    })
+}
+
 pub type ExpandResult<T> = ValueResult<T, ExpandError>;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -1,4 +1,5 @@
-//! Defines messages for cross-process message passing based on `ndjson` wire protocol
+//! Defines messages for cross-process message passing based on `ndjson` or `postcard` wire
+//! protocols
 pub(crate) mod flat;
 
 use std::io::{self, BufRead, Write};
@@ -7,7 +8,10 @@ use paths::Utf8PathBuf;
 use serde::de::DeserializeOwned;
 use serde_derive::{Deserialize, Serialize};
 
-use crate::ProcMacroKind;
+use crate::{
+    legacy_protocol::postcard::{read_postcard, write_postcard},
+    ProcMacroKind,
+};
 
 pub use self::flat::{
     deserialize_span_data_index_map, serialize_span_data_index_map, FlatTree, SpanDataIndexMap,
@@ -22,8 +26,10 @@ pub const HAS_GLOBAL_SPANS: u32 = 3;
 pub const RUST_ANALYZER_SPAN_SUPPORT: u32 = 4;
 /// Whether literals encode their kind as an additional u32 field and idents their rawness as a u32 field
 pub const EXTENDED_LEAF_DATA: u32 = 5;
+/// Whether the server can switch to [`MessageFormat::Postcard`]
+pub const POSTCARD_FORMAT: u32 = 6;
 
-pub const CURRENT_API_VERSION: u32 = EXTENDED_LEAF_DATA;
+pub const CURRENT_API_VERSION: u32 = POSTCARD_FORMAT;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
@@ -44,6 +50,16 @@ pub enum SpanMode {
     RustAnalyzer,
 }
 
+/// The encoding of the messages on the wire.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageFormat {
+    /// Newline delimited json, see [`super::json`].
+    #[default]
+    Json,
+    /// Since [`POSTCARD_FORMAT`], see [`super::postcard`].
+    Postcard,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Response {
     /// Since [`NO_VERSION_CHECK_VERSION`]
@@ -62,6 +78,11 @@ pub enum Response {
 #[serde(default)]
 pub struct ServerConfig {
     pub span_mode: SpanMode,
+    /// The format of the messages following the response to this config.
+    pub format: MessageFormat,
+    /// Reuse the expansion of an input the server has expanded before. Only correct for macros
+    /// that depend on nothing but their input and environment, so this is off by default.
+    pub cache_expansions: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -110,8 +131,7 @@ pub struct ExpandMacroData {
 
 #[derive(Copy, Clone, Default, Debug, Serialize, Deserialize)]
 pub struct ExpnGlobals {
-    #[serde(skip_serializing)]
-    #[serde(default)]
+    #[serde(skip)]
     pub serialize: bool,
     pub def_site: usize,
     pub call_site: usize,
@@ -124,7 +144,96 @@ impl ExpnGlobals {
     }
 }
 
+/// The representation of a [`Request`] in [`MessageFormat::Postcard`], which unlike json can't
+/// express flattened and optional fields.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PostcardRequest {
+    ListMacros { dylib_path: Utf8PathBuf },
+    ExpandMacro(Box<PostcardExpandMacro>),
+    ApiVersionCheck {},
+    SetConfig(ServerConfig),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostcardExpandMacro {
+    pub lib: Utf8PathBuf,
+    pub env: Vec<(String, String)>,
+    pub current_dir: Option<String>,
+    pub macro_body: FlatTree,
+    pub macro_name: String,
+    pub attributes: Option<FlatTree>,
+    pub has_global_spans: ExpnGlobals,
+    pub span_data_table: Vec<u32>,
+}
+
+impl From<Request> for PostcardRequest {
+    fn from(req: Request) -> Self {
+        match req {
+            Request::ListMacros { dylib_path } => PostcardRequest::ListMacros { dylib_path },
+            Request::ExpandMacro(task) => {
+                let ExpandMacro { lib, env, current_dir, data } = *task;
+                let ExpandMacroData {
+                    macro_body,
+                    macro_name,
+                    attributes,
+                    has_global_spans,
+                    span_data_table,
+                } = data;
+                PostcardRequest::ExpandMacro(Box::new(PostcardExpandMacro {
+                    lib,
+                    env,
+                    current_dir,
+                    macro_body,
+                    macro_name,
+                    attributes,
+                    has_global_spans,
+                    span_data_table,
+                }))
+            }
+            Request::ApiVersionCheck {} => PostcardRequest::ApiVersionCheck {},
+            Request::SetConfig(config) => PostcardRequest::SetConfig(config),
+        }
+    }
+}
+
+impl From<PostcardRequest> for Request {
+    fn from(req: PostcardRequest) -> Self {
+        match req {
+            PostcardRequest::ListMacros { dylib_path } => Request::ListMacros { dylib_path },
+            PostcardRequest::ExpandMacro(task) => {
+                let PostcardExpandMacro {
+                    lib,
+                    env,
+                    current_dir,
+                    macro_body,
+                    macro_name,
+                    attributes,
+                    has_global_spans,
+                    span_data_table,
+                } = *task;
+                Request::ExpandMacro(Box::new(ExpandMacro {
+                    lib,
+                    env,
+                    current_dir,
+                    data: ExpandMacroData {
+                        macro_body,
+                        macro_name,
+                        attributes,
+                        has_global_spans: ExpnGlobals { serialize: true, ..has_global_spans },
+                        span_data_table,
+                    },
+                }))
+            }
+            PostcardRequest::ApiVersionCheck {} => Request::ApiVersionCheck {},
+            PostcardRequest::SetConfig(config) => Request::SetConfig(config),
+        }
+    }
+}
+
 pub trait Message: serde::Serialize + DeserializeOwned {
+    /// The representation of `Self` in [`MessageFormat::Postcard`].
+    type Postcard: serde::Serialize + DeserializeOwned + From<Self> + Into<Self>;
+
     fn read<R: BufRead>(
         from_proto: ProtocolRead<R>,
         inp: &mut R,
@@ -145,10 +254,29 @@ pub trait Message: serde::Serialize + DeserializeOwned {
         let text = serde_json::to_string(&self)?;
         to_proto(out, &text)
     }
+    fn read_postcard<R: BufRead>(inp: &mut R, buf: &mut Vec<u8>) -> io::Result<Option<Self>> {
+        Ok(match read_postcard(inp, buf)? {
+            None => None,
+            Some(bytes) => Some(
+                postcard::from_bytes_cobs::<Self::Postcard>(bytes)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                    .into(),
+            ),
+        })
+    }
+    fn write_postcard<W: Write>(self, out: &mut W) -> io::Result<()> {
+        let bytes = postcard::to_allocvec_cobs(&Self::Postcard::from(self))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        write_postcard(out, &bytes)
+    }
 }
 
-impl Message for Request {}
-impl Message for Response {}
+impl Message for Request {
+    type Postcard = PostcardRequest;
+}
+impl Message for Response {
+    type Postcard = Response;
+}
 
 #[allow(type_alias_bounds)]
 type ProtocolRead<R: BufRead> =
@@ -293,4 +421,53 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_proc_macro_rpc_works_postcard() {
+        let tt = fixture_token_tree();
+        let mut span_data_table = Default::default();
+        let macro_body = FlatTree::new(tt.view(), CURRENT_API_VERSION, &mut span_data_table);
+        let task = ExpandMacro {
+            data: ExpandMacroData {
+                macro_body,
+                macro_name: "Derive".to_owned(),
+                attributes: None,
+                has_global_spans: ExpnGlobals {
+                    serialize: true,
+                    def_site: 0,
+                    call_site: 1,
+                    mixed_site: 2,
+                },
+                span_data_table: serialize_span_data_index_map(&span_data_table),
+            },
+            lib: Utf8PathBuf::from_path_buf(std::env::current_dir().unwrap()).unwrap(),
+            env: vec![("FOO".to_owned(), "bar".to_owned())],
+            current_dir: None,
+        };
+
+        let mut bytes = Vec::new();
+        // Output of an ill behaved macro preceding the message is skipped
+        bytes.extend_from_slice(b"debug output\n");
+        Request::ExpandMacro(Box::new(task)).write_postcard(&mut bytes).unwrap();
+        Request::ApiVersionCheck {}.write_postcard(&mut bytes).unwrap();
+
+        let mut inp = &bytes[..];
+        let mut buf = Vec::new();
+        let Some(Request::ExpandMacro(back)) = Request::read_postcard(&mut inp, &mut buf).unwrap()
+        else {
+            panic!("expected an expansion request");
+        };
+        assert_eq!(back.data.macro_name, "Derive");
+        assert_eq!(back.env, [("FOO".to_owned(), "bar".to_owned())]);
+        assert_eq!(back.data.has_global_spans.call_site, 1);
+        let span_data_table = deserialize_span_data_index_map(&back.data.span_data_table);
+        assert!(
+            tt == back.data.macro_body.to_subtree_resolved(CURRENT_API_VERSION, &span_data_table)
+        );
+        assert!(matches!(
+            Request::read_postcard(&mut inp, &mut buf).unwrap(),
+            Some(Request::ApiVersionCheck {})
+        ));
+        assert!(Request::read_postcard(&mut inp, &mut buf).unwrap().is_none());
+    }
 }
@@ -31,9 +31,8 @@
 //!  }
 //! ```
 //!
-//! We probably should replace most of the code here with bincode someday, but,
-//! as we don't have bincode in Cargo.toml yet, lets stick with serde_json for
-//! the time being.
+//! The same representation is used by the postcard wire format, where the
+//! arrays of numbers are encoded as compact varints.
 
 use std::collections::VecDeque;
 
@@ -0,0 +1,37 @@
+//! Protocol functions for postcard, a compact binary format.
+//!
+//! Messages are COBS encoded, so that they can be delimited by a zero byte, and are prefixed by a
+//! `0xFF` byte. As that byte never occurs in UTF-8, text ill behaved macros print to stdout can be
+//! told apart from the message following it.
+use std::io::{self, BufRead, Write};
+
+const MESSAGE_START: u8 = 0xFF;
+
+pub fn read_postcard<'a>(
+    inp: &mut impl BufRead,
+    buf: &'a mut Vec<u8>,
+) -> io::Result<Option<&'a mut [u8]>> {
+    buf.clear();
+    inp.read_until(0, buf)?;
+    if buf.is_empty() {
+        return Ok(None);
+    }
+
+    let Some(start) = buf.iter().position(|&it| it == MESSAGE_START) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "missing message start"));
+    };
+    if start != 0 {
+        // Some ill behaved macro try to use stdout for debugging
+        // We ignore it here
+        tracing::error!("proc-macro tried to print : {}", String::from_utf8_lossy(&buf[..start]));
+    }
+    Ok(Some(&mut buf[start + 1..]))
+}
+
+pub fn write_postcard(out: &mut impl Write, msg: &[u8]) -> io::Result<()> {
+    tracing::debug!("> {} bytes", msg.len());
+    out.write_all(&[MESSAGE_START])?;
+    out.write_all(msg)?;
+    out.flush()?;
+    Ok(())
+}
@@ -8,12 +8,20 @@
 pub mod legacy_protocol {
     pub mod json;
     pub mod msg;
+    pub mod postcard;
 }
 mod process;
 
 use paths::{AbsPath, AbsPathBuf};
 use span::Span;
-use std::{fmt, io, sync::Arc};
+use std::{
+    fmt, io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use crate::{
     legacy_protocol::msg::{
@@ -21,7 +29,7 @@ use crate::{
         ExpandMacroData, ExpnGlobals, FlatTree, PanicMessage, Request, Response, SpanDataIndexMap,
         HAS_GLOBAL_SPANS, RUST_ANALYZER_SPAN_SUPPORT,
     },
-    process::ProcMacroServerProcess,
+    process::{ProcMacroServerProcess, ServerCommand},
 };
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, serde_derive::Serialize, serde_derive::Deserialize)]
@@ -43,9 +51,28 @@ pub struct ProcMacroClient {
     /// That means that concurrent salsa requests may block each other when expanding proc macros,
     /// which is unfortunate, but simple and good enough for the time being.
     process: Arc<ProcMacroServerProcess>,
+    /// Whether `process` has been handed out to a dylib already, only relevant when
+    /// [`ProcMacroServerOptions::process_per_dylib`] is set.
+    process_taken: AtomicBool,
     path: AbsPathBuf,
 }
 
+/// Limits on the proc-macro server processes spawned by a [`ProcMacroClient`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcMacroServerOptions {
+    /// Kill the server if a single expansion takes longer than this. The server is restarted
+    /// when the next request comes in.
+    pub expansion_timeout: Option<Duration>,
+    /// Upper bound in bytes on the address space of each server process. Only supported on unix.
+    pub memory_limit: Option<u64>,
+    /// Load each proc-macro dylib into a separate server process, so that a misbehaving
+    /// dylib can't take down the macros of all the others.
+    pub process_per_dylib: bool,
+    /// Let the server reuse the expansions of inputs it has already expanded, see
+    /// [`legacy_protocol::msg::ServerConfig::cache_expansions`].
+    pub cache_expansions: bool,
+}
+
 pub struct MacroDylib {
     path: AbsPathBuf,
 }
@@ -58,8 +85,9 @@ impl MacroDylib {
 
 /// A handle to a specific proc-macro (a `#[proc_macro]` annotated function).
 ///
-/// It exists within the context of a specific proc-macro server -- currently
-/// we share a single expander process for all macros within a workspace.
+/// It exists within the context of a specific proc-macro server -- either the one shared by
+/// the whole workspace or, with [`ProcMacroServerOptions::process_per_dylib`], the one
+/// dedicated to its dylib.
 #[derive(Debug, Clone)]
 pub struct ProcMacro {
     process: Arc<ProcMacroServerProcess>,
@@ -82,6 +110,15 @@ impl PartialEq for ProcMacro {
 pub struct ServerError {
     pub message: String,
     pub io: Option<Arc<io::Error>>,
+    /// Set if the server got killed because the request exceeded
+    /// [`ProcMacroServerOptions::expansion_timeout`].
+    pub timed_out: Option<Duration>,
+}
+
+impl ServerError {
+    fn unexpected_response() -> ServerError {
+        ServerError { message: "unexpected response".to_owned(), io: None, timed_out: None }
+    }
 }
 
 impl fmt::Display for ServerError {
@@ -99,11 +136,16 @@ impl ProcMacroClient {
     /// Spawns an external process as the proc macro server and returns a client connected to it.
     pub fn spawn(
         process_path: &AbsPath,
-        env: impl IntoIterator<Item = (impl AsRef<std::ffi::OsStr>, impl AsRef<std::ffi::OsStr>)>
-            + Clone,
+        env: impl IntoIterator<Item = (impl AsRef<std::ffi::OsStr>, impl AsRef<std::ffi::OsStr>)>,
+        options: ProcMacroServerOptions,
     ) -> io::Result<ProcMacroClient> {
-        let process = ProcMacroServerProcess::run(process_path, env)?;
-        Ok(ProcMacroClient { process: Arc::new(process), path: process_path.to_owned() })
+        let command = ServerCommand::new(process_path, env, options);
+        let process = ProcMacroServerProcess::run(Arc::new(command))?;
+        Ok(ProcMacroClient {
+            process: Arc::new(process),
+            process_taken: AtomicBool::new(false),
+            path: process_path.to_owned(),
+        })
     }
 
     pub fn server_path(&self) -> &AbsPath {
@@ -113,21 +155,37 @@ impl ProcMacroClient {
     /// Loads a proc-macro dylib into the server process returning a list of `ProcMacro`s loaded.
     pub fn load_dylib(&self, dylib: MacroDylib) -> Result<Vec<ProcMacro>, ServerError> {
         let _p = tracing::info_span!("ProcMacroServer::load_dylib").entered();
-        let macros = self.process.find_proc_macros(&dylib.path)?;
+        let process = self.process_for_dylib()?;
+        let macros = process.find_proc_macros(&dylib.path)?;
 
         let dylib_path = Arc::new(dylib.path);
         match macros {
             Ok(macros) => Ok(macros
                 .into_iter()
                 .map(|(name, kind)| ProcMacro {
-                    process: self.process.clone(),
+                    process: process.clone(),
                     name: name.into(),
                     kind,
                     dylib_path: dylib_path.clone(),
                 })
                 .collect()),
-            Err(message) => Err(ServerError { message, io: None }),
+            Err(message) => Err(ServerError { message, io: None, timed_out: None }),
+        }
+    }
+
+    /// Returns the server process `load_dylib` should load the next dylib into.
+    fn process_for_dylib(&self) -> Result<Arc<ProcMacroServerProcess>, ServerError> {
+        let command = self.process.command();
+        if !command.options().process_per_dylib || !self.process_taken.swap(true, Ordering::Relaxed)
+        {
+            return Ok(self.process.clone());
         }
+        let process = ProcMacroServerProcess::run(command.clone()).map_err(|e| ServerError {
+            message: "failed to spawn proc-macro server for dylib".to_owned(),
+            io: Some(Arc::new(e)),
+            timed_out: None,
+        })?;
+        Ok(Arc::new(process))
     }
 
     pub fn exited(&self) -> Option<&ServerError> {
@@ -196,7 +254,7 @@ impl ProcMacro {
                     &deserialize_span_data_index_map(&resp.span_data_table),
                 )
             })),
-            _ => Err(ServerError { message: "unexpected response".to_owned(), io: None }),
+            _ => Err(ServerError::unexpected_response()),
         }
     }
 }
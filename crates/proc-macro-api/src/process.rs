@@ -1,62 +1,109 @@
 //! Handle process life-time and message passing for proc-macro client
 
 use std::{
+    ffi::OsString,
     io::{self, BufRead, BufReader, Read, Write},
     panic::AssertUnwindSafe,
     process::{Child, ChildStdin, ChildStdout, Command, Stdio},
-    sync::{Arc, Mutex, OnceLock},
+    sync::{mpsc, Arc, Mutex, OnceLock},
+    time::Duration,
 };
 
-use paths::AbsPath;
-use stdx::JodChild;
+use paths::{AbsPath, AbsPathBuf};
+use stdx::{thread::ThreadIntent, JodChild};
 
 use crate::{
     legacy_protocol::{
         json::{read_json, write_json},
         msg::{
-            Message, Request, Response, ServerConfig, SpanMode, CURRENT_API_VERSION,
-            RUST_ANALYZER_SPAN_SUPPORT,
+            Message, MessageFormat, Request, Response, ServerConfig, SpanMode, CURRENT_API_VERSION,
+            POSTCARD_FORMAT, RUST_ANALYZER_SPAN_SUPPORT,
         },
     },
-    ProcMacroKind, ServerError,
+    ProcMacroKind, ProcMacroServerOptions, ServerError,
 };
 
+/// How many times in a row a server process may die before we stop restarting it.
+const MAX_CONSECUTIVE_CRASHES: u32 = 3;
+
 #[derive(Debug)]
 pub(crate) struct ProcMacroServerProcess {
     /// The state of the proc-macro server process, the protocol is currently strictly sequential
     /// hence the lock on the state.
     state: Mutex<ProcessSrvState>,
+    command: Arc<ServerCommand>,
     version: u32,
     mode: SpanMode,
-    /// Populated when the server exits.
+    format: MessageFormat,
+    /// Populated when the server exits and can't be restarted.
     exited: OnceLock<AssertUnwindSafe<ServerError>>,
 }
 
+/// Everything needed to (re)spawn a server process.
+#[derive(Debug)]
+pub(crate) struct ServerCommand {
+    path: AbsPathBuf,
+    env: Vec<(OsString, OsString)>,
+    options: ProcMacroServerOptions,
+}
+
 #[derive(Debug)]
 struct ProcessSrvState {
     process: Process,
     stdin: ChildStdin,
     stdout: BufReader<ChildStdout>,
+    /// Set when the process was killed or crashed, the next request will start a fresh one.
+    dead: bool,
+    /// The number of times the process died since the last successful request. Timeouts are
+    /// not counted, a slow expansion says nothing about whether the next one will succeed.
+    crashes: u32,
+    /// Spawned on the first request that has a timeout, and kept across restarts.
+    watchdog: Option<Watchdog>,
+}
+
+impl ServerCommand {
+    pub(crate) fn new(
+        path: &AbsPath,
+        env: impl IntoIterator<Item = (impl AsRef<std::ffi::OsStr>, impl AsRef<std::ffi::OsStr>)>,
+        options: ProcMacroServerOptions,
+    ) -> ServerCommand {
+        ServerCommand {
+            path: path.to_owned(),
+            env: env
+                .into_iter()
+                .map(|(k, v)| (k.as_ref().to_owned(), v.as_ref().to_owned()))
+                .collect(),
+            options,
+        }
+    }
+
+    pub(crate) fn options(&self) -> &ProcMacroServerOptions {
+        &self.options
+    }
+}
+
+impl ProcessSrvState {
+    fn spawn(command: &ServerCommand) -> io::Result<ProcessSrvState> {
+        let mut process = Process::run(
+            &command.path,
+            command.env.iter().map(|(k, v)| (k, v)),
+            command.options.memory_limit,
+        )?;
+        let (stdin, stdout) = process.stdio().expect("couldn't access child stdio");
+        Ok(ProcessSrvState { process, stdin, stdout, dead: false, crashes: 0, watchdog: None })
+    }
 }
 
 impl ProcMacroServerProcess {
-    pub(crate) fn run(
-        process_path: &AbsPath,
-        env: impl IntoIterator<Item = (impl AsRef<std::ffi::OsStr>, impl AsRef<std::ffi::OsStr>)>
-            + Clone,
-    ) -> io::Result<ProcMacroServerProcess> {
-        let create_srv = || {
-            let mut process = Process::run(process_path, env.clone())?;
-            let (stdin, stdout) = process.stdio().expect("couldn't access child stdio");
-
-            io::Result::Ok(ProcMacroServerProcess {
-                state: Mutex::new(ProcessSrvState { process, stdin, stdout }),
-                version: 0,
-                mode: SpanMode::Id,
-                exited: OnceLock::new(),
-            })
+    pub(crate) fn run(command: Arc<ServerCommand>) -> io::Result<ProcMacroServerProcess> {
+        let mut srv = ProcMacroServerProcess {
+            state: Mutex::new(ProcessSrvState::spawn(&command)?),
+            command,
+            version: 0,
+            mode: SpanMode::Id,
+            format: MessageFormat::Json,
+            exited: OnceLock::new(),
         };
-        let mut srv = create_srv()?;
         tracing::info!("sending proc-macro server version check");
         match srv.version_check() {
             Ok(v) if v > CURRENT_API_VERSION => Err(io::Error::new(
@@ -69,11 +116,22 @@ impl ProcMacroServerProcess {
                 tracing::info!("Proc-macro server version: {v}");
                 srv.version = v;
                 if srv.version >= RUST_ANALYZER_SPAN_SUPPORT {
-                    if let Ok(mode) = srv.enable_rust_analyzer_spans() {
-                        srv.mode = mode;
+                    let format = if srv.version >= POSTCARD_FORMAT {
+                        MessageFormat::Postcard
+                    } else {
+                        MessageFormat::Json
+                    };
+                    if let Ok(config) = srv.set_config(ServerConfig {
+                        span_mode: SpanMode::RustAnalyzer,
+                        format,
+                        cache_expansions: srv.command.options.cache_expansions,
+                    }) {
+                        srv.mode = config.span_mode;
+                        srv.format = config.format;
                     }
                 }
                 tracing::info!("Proc-macro server span mode: {:?}", srv.mode);
+                tracing::info!("Proc-macro server message format: {:?}", srv.format);
                 Ok(srv)
             }
             Err(e) => {
@@ -85,6 +143,10 @@ impl ProcMacroServerProcess {
         }
     }
 
+    pub(crate) fn command(&self) -> &Arc<ServerCommand> {
+        &self.command
+    }
+
     pub(crate) fn exited(&self) -> Option<&ServerError> {
         self.exited.get().map(|it| &it.0)
     }
@@ -99,17 +161,18 @@ impl ProcMacroServerProcess {
 
         match response {
             Response::ApiVersionCheck(version) => Ok(version),
-            _ => Err(ServerError { message: "unexpected response".to_owned(), io: None }),
+            _ => Err(ServerError::unexpected_response()),
         }
     }
 
-    fn enable_rust_analyzer_spans(&self) -> Result<SpanMode, ServerError> {
-        let request = Request::SetConfig(ServerConfig { span_mode: SpanMode::RustAnalyzer });
+    /// Asks the server to use `config`, returning the config it actually switched to.
+    fn set_config(&self, config: ServerConfig) -> Result<ServerConfig, ServerError> {
+        let request = Request::SetConfig(config);
         let response = self.send_task(request)?;
 
         match response {
-            Response::SetConfig(ServerConfig { span_mode }) => Ok(span_mode),
-            _ => Err(ServerError { message: "unexpected response".to_owned(), io: None }),
+            Response::SetConfig(config) => Ok(config),
+            _ => Err(ServerError::unexpected_response()),
         }
     }
 
@@ -123,77 +186,212 @@ impl ProcMacroServerProcess {
 
         match response {
             Response::ListMacros(it) => Ok(it),
-            _ => Err(ServerError { message: "unexpected response".to_owned(), io: None }),
+            _ => Err(ServerError::unexpected_response()),
         }
     }
 
     pub(crate) fn send_task(&self, req: Request) -> Result<Response, ServerError> {
+        let timeout = match req {
+            Request::ExpandMacro(_) => self.command.options.expansion_timeout,
+            _ => None,
+        };
+        self.send_task_with_timeout(req, timeout)
+    }
+
+    fn send_task_with_timeout(
+        &self,
+        req: Request,
+        timeout: Option<Duration>,
+    ) -> Result<Response, ServerError> {
         if let Some(server_error) = self.exited.get() {
             return Err(server_error.0.clone());
         }
 
         let state = &mut *self.state.lock().unwrap();
-        let mut buf = String::new();
-        send_request(&mut state.stdin, &mut state.stdout, req, &mut buf)
-            .and_then(|res| {
-                res.ok_or_else(|| {
-                    let message = "proc-macro server did not respond with data".to_owned();
-                    ServerError {
-                        io: Some(Arc::new(io::Error::new(
-                            io::ErrorKind::BrokenPipe,
-                            message.clone(),
-                        ))),
-                        message,
+        if state.dead {
+            if let Err(e) = self.restart(state) {
+                return Err(self.exited.get_or_init(|| AssertUnwindSafe(e)).0.clone());
+            }
+        }
+
+        if let Some(timeout) = timeout {
+            state.watchdog.get_or_insert_with(Watchdog::spawn).arm(&state.process, timeout);
+        }
+        let res = exchange(state, req, self.format);
+        let timed_out = timeout.filter(|_| state.watchdog.as_ref().is_some_and(Watchdog::disarm));
+        if let Some(timeout) = timed_out {
+            state.dead = true;
+            tracing::warn!("proc-macro server got killed after {timeout:?}");
+            return Err(ServerError {
+                message: format!("proc macro timed out after {timeout:?}"),
+                io: None,
+                timed_out: Some(timeout),
+            });
+        }
+
+        match res {
+            Ok(res) => {
+                state.crashes = 0;
+                Ok(res)
+            }
+            Err(e) if e.io.as_ref().map(|it| it.kind()) == Some(io::ErrorKind::BrokenPipe) => {
+                // Either way the pipe is unusable now, so the next request needs a new process.
+                state.dead = true;
+                state.crashes += 1;
+                let mut child = state.process.child.lock().unwrap();
+                let server_error = match child.try_wait() {
+                    Ok(None) | Err(_) => e,
+                    Ok(Some(status)) => {
+                        let mut msg = String::new();
+                        if !status.success() {
+                            if let Some(stderr) = child.stderr.as_mut() {
+                                _ = stderr.read_to_string(&mut msg);
+                            }
+                        }
+                        let hint = match self.command.options.memory_limit {
+                            Some(limit) if !status.success() => format!(
+                                " (the server is limited to {} MiB of memory)",
+                                limit / (1024 * 1024)
+                            ),
+                            _ => String::new(),
+                        };
+                        ServerError {
+                            message: format!(
+                                "proc-macro server exited with {status}{hint}{}{msg}",
+                                if msg.is_empty() { "" } else { ": " }
+                            ),
+                            io: None,
+                            timed_out: None,
+                        }
                     }
-                })
-            })
-            .map_err(|e| {
-                if e.io.as_ref().map(|it| it.kind()) == Some(io::ErrorKind::BrokenPipe) {
-                    match state.process.child.try_wait() {
-                        Ok(None) | Err(_) => e,
-                        Ok(Some(status)) => {
-                            let mut msg = String::new();
-                            if !status.success() {
-                                if let Some(stderr) = state.process.child.stderr.as_mut() {
-                                    _ = stderr.read_to_string(&mut msg);
-                                }
+                };
+                drop(child);
+                Err(self.crashed(state, server_error))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the error of a request the process died on, giving up on the server if it died
+    /// too many times in a row.
+    fn crashed(&self, state: &ProcessSrvState, server_error: ServerError) -> ServerError {
+        if state.crashes < MAX_CONSECUTIVE_CRASHES {
+            return server_error;
+        }
+        // `AssertUnwindSafe` is fine here, we already correct initialized
+        // server_error at this point.
+        self.exited.get_or_init(|| AssertUnwindSafe(server_error)).0.clone()
+    }
+
+    /// Replaces a dead server process with a fresh one, configured like the original.
+    fn restart(&self, state: &mut ProcessSrvState) -> Result<(), ServerError> {
+        tracing::info!("restarting proc-macro server");
+        let crashes = state.crashes;
+        let watchdog = state.watchdog.take();
+        *state = ProcessSrvState::spawn(&self.command).map_err(|e| ServerError {
+            message: "failed to restart proc-macro server".to_owned(),
+            io: Some(Arc::new(e)),
+            timed_out: None,
+        })?;
+        state.crashes = crashes;
+        state.watchdog = watchdog;
+        // A fresh server always starts out talking JSON.
+        match exchange(state, Request::ApiVersionCheck {}, MessageFormat::Json)? {
+            Response::ApiVersionCheck(_) => (),
+            _ => return Err(ServerError::unexpected_response()),
+        }
+        if self.version >= RUST_ANALYZER_SPAN_SUPPORT {
+            let config = ServerConfig {
+                span_mode: self.mode,
+                format: self.format,
+                cache_expansions: self.command.options.cache_expansions,
+            };
+            match exchange(state, Request::SetConfig(config), MessageFormat::Json)? {
+                Response::SetConfig(_) => (),
+                _ => return Err(ServerError::unexpected_response()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Kills the server process if a request does not complete in time. Requests are sequential, so
+/// a single thread watches all of them.
+#[derive(Debug)]
+struct Watchdog {
+    // Declared before `_thread`, so that the thread is told to stop before it is joined.
+    requests: mpsc::Sender<WatchdogRequest>,
+    killed: mpsc::Receiver<bool>,
+    _thread: stdx::thread::JoinHandle,
+}
+
+#[derive(Debug)]
+enum WatchdogRequest {
+    Arm(Arc<Mutex<JodChild>>, Duration),
+    Disarm,
+}
+
+impl Watchdog {
+    fn spawn() -> Watchdog {
+        let (requests, request_rx) = mpsc::channel();
+        let (killed_tx, killed) = mpsc::channel();
+        let thread = stdx::thread::Builder::new(ThreadIntent::Worker)
+            .name("ProcMacroWatchdog".to_owned())
+            .spawn(move || {
+                while let Ok(request) = request_rx.recv() {
+                    let WatchdogRequest::Arm(child, timeout) = request else { continue };
+                    let was_killed = match request_rx.recv_timeout(timeout) {
+                        Ok(_) => false,
+                        Err(mpsc::RecvTimeoutError::Timeout) => {
+                            _ = child.lock().unwrap().kill();
+                            // The request fails once the process is gone, and disarms us.
+                            if request_rx.recv().is_err() {
+                                return;
                             }
-                            let server_error = ServerError {
-                                message: format!(
-                                    "proc-macro server exited with {status}{}{msg}",
-                                    if msg.is_empty() { "" } else { ": " }
-                                ),
-                                io: None,
-                            };
-                            // `AssertUnwindSafe` is fine here, we already correct initialized
-                            // server_error at this point.
-                            self.exited.get_or_init(|| AssertUnwindSafe(server_error)).0.clone()
+                            true
                         }
+                        Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                    };
+                    if killed_tx.send(was_killed).is_err() {
+                        return;
                     }
-                } else {
-                    e
                 }
             })
+            .expect("failed to spawn proc-macro watchdog thread");
+        Watchdog { requests, killed, _thread: thread }
+    }
+
+    fn arm(&self, process: &Process, timeout: Duration) {
+        _ = self.requests.send(WatchdogRequest::Arm(process.child.clone(), timeout));
+    }
+
+    /// Returns whether the process had to be killed.
+    fn disarm(&self) -> bool {
+        _ = self.requests.send(WatchdogRequest::Disarm);
+        self.killed.recv().unwrap_or(false)
     }
 }
 
 #[derive(Debug)]
 struct Process {
-    child: JodChild,
+    /// Shared with the [`Watchdog`], which kills the process while a request is blocked on it.
+    child: Arc<Mutex<JodChild>>,
 }
 
 impl Process {
     fn run(
         path: &AbsPath,
         env: impl IntoIterator<Item = (impl AsRef<std::ffi::OsStr>, impl AsRef<std::ffi::OsStr>)>,
+        memory_limit: Option<u64>,
     ) -> io::Result<Process> {
-        let child = JodChild(mk_child(path, env)?);
-        Ok(Process { child })
+        let child = JodChild(mk_child(path, env, memory_limit)?);
+        Ok(Process { child: Arc::new(Mutex::new(child)) })
     }
 
     fn stdio(&mut self) -> Option<(ChildStdin, BufReader<ChildStdout>)> {
-        let stdin = self.child.stdin.take()?;
-        let stdout = self.child.stdout.take()?;
+        let mut child = self.child.lock().unwrap();
+        let stdin = child.stdin.take()?;
+        let stdout = child.stdout.take()?;
         let read = BufReader::new(stdout);
 
         Some((stdin, read))
@@ -203,6 +401,7 @@ impl Process {
 fn mk_child(
     path: &AbsPath,
     env: impl IntoIterator<Item = (impl AsRef<std::ffi::OsStr>, impl AsRef<std::ffi::OsStr>)>,
+    memory_limit: Option<u64>,
 ) -> io::Result<Child> {
     #[allow(clippy::disallowed_methods)]
     let mut cmd = Command::new(path);
@@ -218,22 +417,171 @@ fn mk_child(
         path_var.push(std::env::var_os("PATH").unwrap_or_default());
         cmd.env("PATH", path_var);
     }
+    #[cfg(unix)]
+    if let Some(limit) = memory_limit {
+        use std::os::unix::process::CommandExt;
+        let limit = limit as libc::rlim_t;
+        // SAFETY: `setrlimit` is async-signal-safe and the closure does not allocate.
+        unsafe {
+            cmd.pre_exec(move || {
+                let rlimit = libc::rlimit { rlim_cur: limit, rlim_max: limit };
+                if libc::setrlimit(libc::RLIMIT_AS, &rlimit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    if memory_limit.is_some() {
+        tracing::warn!("proc-macro server memory limits are only supported on unix");
+    }
     cmd.spawn()
 }
 
+/// Sends `req` and waits for the response, treating a closed stdout as a broken pipe.
+fn exchange(
+    state: &mut ProcessSrvState,
+    req: Request,
+    format: MessageFormat,
+) -> Result<Response, ServerError> {
+    send_request(&mut state.stdin, &mut state.stdout, req, format)?.ok_or_else(|| {
+        let message = "proc-macro server did not respond with data".to_owned();
+        ServerError {
+            io: Some(Arc::new(io::Error::new(io::ErrorKind::BrokenPipe, message.clone()))),
+            message,
+            timed_out: None,
+        }
+    })
+}
+
 fn send_request(
     mut writer: &mut impl Write,
     mut reader: &mut impl BufRead,
     req: Request,
-    buf: &mut String,
+    format: MessageFormat,
 ) -> Result<Option<Response>, ServerError> {
-    req.write(write_json, &mut writer).map_err(|err| ServerError {
+    match format {
+        MessageFormat::Json => req.write(write_json, &mut writer),
+        MessageFormat::Postcard => req.write_postcard(&mut writer),
+    }
+    .map_err(|err| ServerError {
         message: "failed to write request".into(),
         io: Some(Arc::new(err)),
+        timed_out: None,
     })?;
-    let res = Response::read(read_json, &mut reader, buf).map_err(|err| ServerError {
+    let res = match format {
+        MessageFormat::Json => Response::read(read_json, &mut reader, &mut String::new()),
+        MessageFormat::Postcard => Response::read_postcard(&mut reader, &mut Vec::new()),
+    }
+    .map_err(|err| ServerError {
         message: "failed to read response".into(),
         io: Some(Arc::new(err)),
+        timed_out: None,
     })?;
     Ok(res)
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{iter, time::Duration};
+
+    use super::*;
+
+    const VERSION_CHECK: &str = r#"read -r request; echo '{"ApiVersionCheck":1}'"#;
+    const LIST_MACROS: &str = r#"echo '{"ListMacros":{"Ok":[]}}'"#;
+
+    /// Writes a shell script that plays the proc-macro server.
+    fn fake_server(name: &str, script: &str) -> AbsPathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path =
+            std::env::temp_dir().join(format!("proc-macro-api-test-{}-{name}", std::process::id()));
+        std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        AbsPathBuf::assert_utf8(path)
+    }
+
+    fn run(
+        path: &AbsPath,
+        env: &[(&str, &str)],
+        options: ProcMacroServerOptions,
+    ) -> ProcMacroServerProcess {
+        let command = ServerCommand::new(path, env.iter().copied(), options);
+        ProcMacroServerProcess::run(Arc::new(command)).unwrap()
+    }
+
+    fn list_macros(
+        srv: &ProcMacroServerProcess,
+        timeout: Option<Duration>,
+    ) -> Result<Response, ServerError> {
+        let request = Request::ListMacros { dylib_path: "/dylib.so".into() };
+        srv.send_task_with_timeout(request, timeout)
+    }
+
+    #[test]
+    fn requests_finishing_in_time_are_not_killed() {
+        let path = fake_server(
+            "in-time",
+            &format!("{VERSION_CHECK}\nwhile read -r request; do {LIST_MACROS}; done"),
+        );
+        let srv = run(&path, &[], ProcMacroServerOptions::default());
+        for _ in 0..MAX_CONSECUTIVE_CRASHES + 1 {
+            assert!(list_macros(&srv, Some(Duration::from_secs(10))).is_ok());
+        }
+        assert!(srv.exited().is_none());
+        _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn hanging_server_is_restarted_after_every_timeout() {
+        let path =
+            fake_server("hanging", &format!("{VERSION_CHECK}\nread -r request\nexec sleep 60"));
+        let srv = run(&path, &[], ProcMacroServerOptions::default());
+        let timeout = Duration::from_millis(100);
+        for _ in 0..MAX_CONSECUTIVE_CRASHES + 1 {
+            let error = list_macros(&srv, Some(timeout)).unwrap_err();
+            assert_eq!(error.timed_out, Some(timeout));
+            assert!(srv.exited().is_none());
+        }
+        _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn crashed_server_is_restarted() {
+        let marker = std::env::temp_dir()
+            .join(format!("proc-macro-api-test-{}-restart-marker", std::process::id()));
+        _ = std::fs::remove_file(&marker);
+        let path = fake_server(
+            "restart",
+            &format!(
+                r#"{VERSION_CHECK}
+if [ -e "$MARKER" ]; then
+    while read -r request; do {LIST_MACROS}; done
+fi
+touch "$MARKER"
+read -r request
+exit 1"#
+            ),
+        );
+        let srv =
+            run(&path, &[("MARKER", marker.to_str().unwrap())], ProcMacroServerOptions::default());
+        assert!(list_macros(&srv, None).is_err());
+        assert!(srv.exited().is_none());
+        assert!(list_macros(&srv, None).is_ok());
+        assert_eq!(srv.state.lock().unwrap().crashes, 0);
+        _ = std::fs::remove_file(path);
+        _ = std::fs::remove_file(marker);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn memory_limit_is_applied() {
+        let path = fake_server("memory-limit", "ulimit -v");
+        let limit = 512 * 1024 * 1024;
+        let child = mk_child(&path, iter::empty::<(&str, &str)>(), Some(limit)).unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), (limit / 1024).to_string());
+        _ = std::fs::remove_file(path);
+    }
+}
@@ -5,7 +5,7 @@ use proc_macro_api::legacy_protocol::{
     json::{read_json, write_json},
     msg::{
         self, deserialize_span_data_index_map, serialize_span_data_index_map, ExpandMacroData,
-        ExpnGlobals, Message, SpanMode, TokenId, CURRENT_API_VERSION,
+        ExpnGlobals, Message, MessageFormat, SpanMode, TokenId, CURRENT_API_VERSION,
     },
 };
 use proc_macro_srv::EnvSnapshot;
@@ -22,15 +22,25 @@ pub(crate) fn run() -> io::Result<()> {
     }
 
     let mut buf = String::new();
-    let mut read_request = || msg::Request::read(read_json, &mut io::stdin().lock(), &mut buf);
-    let write_response = |msg: msg::Response| msg.write(write_json, &mut io::stdout().lock());
+    let mut bytes = Vec::new();
+    let mut read_request = |format: MessageFormat| match format {
+        MessageFormat::Json => msg::Request::read(read_json, &mut io::stdin().lock(), &mut buf),
+        MessageFormat::Postcard => msg::Request::read_postcard(&mut io::stdin().lock(), &mut bytes),
+    };
+    let write_response = |msg: msg::Response, format: MessageFormat| match format {
+        MessageFormat::Json => msg.write(write_json, &mut io::stdout().lock()),
+        MessageFormat::Postcard => msg.write_postcard(&mut io::stdout().lock()),
+    };
 
     let env = EnvSnapshot::default();
-    let srv = proc_macro_srv::ProcMacroSrv::new(&env);
+    let mut srv = proc_macro_srv::ProcMacroSrv::new(&env);
 
     let mut span_mode = SpanMode::Id;
+    let mut format = MessageFormat::Json;
 
-    while let Some(req) = read_request()? {
+    while let Some(req) = read_request(format)? {
+        // The response to a config change is still sent in the previous format
+        let mut next_format = format;
         let res = match req {
             msg::Request::ListMacros { dylib_path } => {
                 msg::Response::ListMacros(srv.list_macros(&dylib_path).map(|macros| {
@@ -122,10 +132,13 @@ pub(crate) fn run() -> io::Result<()> {
             msg::Request::ApiVersionCheck {} => msg::Response::ApiVersionCheck(CURRENT_API_VERSION),
             msg::Request::SetConfig(config) => {
                 span_mode = config.span_mode;
+                next_format = config.format;
+                srv.set_expansion_cache(config.cache_expansions);
                 msg::Response::SetConfig(config)
             }
         };
-        write_response(res)?
+        write_response(res, format)?;
+        format = next_format;
     }
 
     Ok(())
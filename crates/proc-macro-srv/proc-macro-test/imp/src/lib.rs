@@ -73,6 +73,19 @@ pub fn fn_like_span_ops(args: TokenStream) -> TokenStream {
     TokenStream::from_iter(vec![first, second, third])
 }
 
+#[proc_macro]
+pub fn fn_like_env_var(args: TokenStream) -> TokenStream {
+    let value = std::env::var(args.to_string()).unwrap_or_default();
+    TokenStream::from(TokenTree::from(Literal::string(&value)))
+}
+
+#[proc_macro]
+pub fn fn_like_read_file(args: TokenStream) -> TokenStream {
+    let path = args.to_string();
+    let contents = std::fs::read_to_string(path.trim_matches('"')).unwrap();
+    TokenStream::from(TokenTree::from(Literal::string(&contents)))
+}
+
 #[proc_macro_attribute]
 pub fn attr_noop(_args: TokenStream, item: TokenStream) -> TokenStream {
     item
@@ -0,0 +1,143 @@
+//! Caches the expansions of proc-macros, so that a macro does not need to be run again on an input
+//! it has already expanded.
+//!
+//! Inputs are compared with their spans replaced by the order in which the spans first occur, so
+//! an input that merely moved within its file (e.g. because an item above it was edited) still
+//! hits the cache. The spans of the cached expansion are then mapped back to the spans of the new
+//! input.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    path::PathBuf,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use paths::Utf8PathBuf;
+use tt::{Delimiter, Ident, Leaf, Literal, Punct, Subtree, TokenTree};
+
+/// The number of expansions kept around, the oldest ones are evicted first.
+const CAPACITY: usize = 4096;
+
+/// Everything the expansion of a proc-macro depends on, with the spans of the input replaced by
+/// their index in the corresponding [`InputSpans`].
+#[derive(PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    pub(crate) lib: Utf8PathBuf,
+    pub(crate) modified_time: SystemTime,
+    pub(crate) macro_name: String,
+    pub(crate) env: Vec<(String, String)>,
+    pub(crate) current_dir: Option<PathBuf>,
+    /// The def, call and mixed site.
+    pub(crate) sites: [u32; 3],
+    pub(crate) macro_body: Box<[TokenTree<u32>]>,
+    pub(crate) attribute: Option<Box<[TokenTree<u32>]>>,
+}
+
+/// The distinct spans of an expansion's input, in the order they first occur in.
+pub(crate) struct InputSpans<S> {
+    spans: Vec<S>,
+    indices: HashMap<S, u32>,
+}
+
+impl<S> Default for InputSpans<S> {
+    fn default() -> Self {
+        InputSpans { spans: Vec::new(), indices: HashMap::new() }
+    }
+}
+
+impl<S: Copy + Eq + Hash> InputSpans<S> {
+    pub(crate) fn intern(&mut self, span: S) -> u32 {
+        *self.indices.entry(span).or_insert_with(|| {
+            self.spans.push(span);
+            self.spans.len() as u32 - 1
+        })
+    }
+
+    pub(crate) fn intern_all(&mut self, tts: &[TokenTree<S>]) -> Box<[TokenTree<u32>]> {
+        map_spans(tts, |span| Some(self.intern(span))).unwrap()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ExpansionCache {
+    expansions: HashMap<Arc<CacheKey>, Box<[TokenTree<u32>]>>,
+    order: VecDeque<Arc<CacheKey>>,
+}
+
+impl ExpansionCache {
+    pub(crate) fn get<S: Copy>(
+        &self,
+        key: &CacheKey,
+        spans: &InputSpans<S>,
+    ) -> Option<Vec<TokenTree<S>>> {
+        let expansion = self.expansions.get(key)?;
+        map_spans(expansion, |idx| spans.spans.get(idx as usize).copied()).map(Vec::from)
+    }
+
+    /// Caches `expansion`, unless it contains spans that are not part of the input, as those
+    /// could not be mapped to the spans of a later input.
+    pub(crate) fn insert<S: Copy + Eq + Hash>(
+        &mut self,
+        key: CacheKey,
+        spans: &InputSpans<S>,
+        expansion: &[TokenTree<S>],
+    ) {
+        if self.expansions.contains_key(&key) {
+            return;
+        }
+        let Some(expansion) = map_spans(expansion, |span| spans.indices.get(&span).copied()) else {
+            return;
+        };
+        if self.expansions.len() >= CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.expansions.remove(&oldest);
+            }
+        }
+        let key = Arc::new(key);
+        self.order.push_back(key.clone());
+        self.expansions.insert(key, expansion);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.expansions.len()
+    }
+}
+
+fn map_spans<S: Copy, T>(
+    tts: &[TokenTree<S>],
+    mut f: impl FnMut(S) -> Option<T>,
+) -> Option<Box<[TokenTree<T>]>> {
+    tts.iter()
+        .map(|tt| {
+            Some(match tt {
+                TokenTree::Leaf(Leaf::Literal(it)) => TokenTree::Leaf(Leaf::Literal(Literal {
+                    symbol: it.symbol.clone(),
+                    span: f(it.span)?,
+                    kind: it.kind,
+                    suffix: it.suffix.clone(),
+                })),
+                TokenTree::Leaf(Leaf::Punct(it)) => TokenTree::Leaf(Leaf::Punct(Punct {
+                    char: it.char,
+                    spacing: it.spacing,
+                    span: f(it.span)?,
+                })),
+                TokenTree::Leaf(Leaf::Ident(it)) => TokenTree::Leaf(Leaf::Ident(Ident {
+                    sym: it.sym.clone(),
+                    span: f(it.span)?,
+                    is_raw: it.is_raw,
+                })),
+                TokenTree::Subtree(it) => TokenTree::Subtree(Subtree {
+                    delimiter: Delimiter {
+                        open: f(it.delimiter.open)?,
+                        close: f(it.delimiter.close)?,
+                        kind: it.delimiter.kind,
+                    },
+                    len: it.len,
+                }),
+            })
+        })
+        .collect()
+}
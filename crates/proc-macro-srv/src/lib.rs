@@ -26,6 +26,7 @@ extern crate ra_ap_rustc_lexer as rustc_lexer;
 extern crate rustc_lexer;
 
 mod dylib;
+mod expansion_cache;
 mod proc_macros;
 mod server_impl;
 
@@ -42,7 +43,10 @@ use std::{
 use paths::{Utf8Path, Utf8PathBuf};
 use span::{Span, TokenId};
 
-use crate::server_impl::TokenStream;
+use crate::{
+    expansion_cache::{CacheKey, ExpansionCache, InputSpans},
+    server_impl::TokenStream,
+};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum ProcMacroKind {
@@ -55,12 +59,22 @@ pub const RUSTC_VERSION_STRING: &str = env!("RUSTC_VERSION");
 
 pub struct ProcMacroSrv<'env> {
     expanders: Mutex<HashMap<Utf8PathBuf, Arc<dylib::Expander>>>,
+    /// Only set if the client asked for expansions to be cached.
+    expansion_cache: Option<Mutex<ExpansionCache>>,
     env: &'env EnvSnapshot,
 }
 
 impl<'env> ProcMacroSrv<'env> {
     pub fn new(env: &'env EnvSnapshot) -> Self {
-        Self { expanders: Default::default(), env }
+        Self { expanders: Default::default(), expansion_cache: None, env }
+    }
+
+    /// Enables or disables caching of expansions. Macros that read files or talk to the network
+    /// would be served stale expansions from the cache, so it is off unless asked for.
+    pub fn set_expansion_cache(&mut self, enabled: bool) {
+        if enabled != self.expansion_cache.is_some() {
+            self.expansion_cache = enabled.then(Default::default);
+        }
     }
 }
 
@@ -83,6 +97,28 @@ impl ProcMacroSrv<'_> {
         let expander =
             self.expander(lib.as_ref()).map_err(|err| format!("failed to load macro: {err}"))?;
 
+        // Building the key copies the whole input, so only do it when the cache is enabled.
+        let cache_key = self.expansion_cache.is_some().then(|| {
+            let mut spans = InputSpans::default();
+            let key = CacheKey {
+                lib: lib.as_ref().to_owned(),
+                modified_time: expander.modified_time(),
+                macro_name: macro_name.clone(),
+                env: env.clone(),
+                current_dir: current_dir.as_ref().map(|it| it.as_ref().to_owned()),
+                sites: [spans.intern(def_site), spans.intern(call_site), spans.intern(mixed_site)],
+                macro_body: spans.intern_all(&macro_body.0),
+                attribute: attribute.as_ref().map(|it| spans.intern_all(&it.0)),
+            };
+            (key, spans)
+        });
+        if let Some((key, spans)) = &cache_key {
+            if let Some(expansion) = self.expansion_cache().and_then(|cache| cache.get(key, spans))
+            {
+                return Ok(expansion);
+            }
+        }
+
         let prev_env = EnvChange::apply(snapped_env, env, current_dir.as_ref().map(<_>::as_ref));
 
         // Note, we spawn a new thread here so that thread locals allocation don't accumulate (this
@@ -115,6 +151,11 @@ impl ProcMacroSrv<'_> {
         });
         prev_env.rollback();
 
+        if let (Ok(expansion), Some((key, spans)), Some(mut cache)) =
+            (&result, cache_key, self.expansion_cache())
+        {
+            cache.insert(key, &spans, expansion);
+        }
         result
     }
 
@@ -126,6 +167,11 @@ impl ProcMacroSrv<'_> {
         Ok(expander.list_macros())
     }
 
+    fn expansion_cache(&self) -> Option<std::sync::MutexGuard<'_, ExpansionCache>> {
+        let cache = self.expansion_cache.as_ref()?;
+        Some(cache.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
     fn expander(&self, path: &Utf8Path) -> Result<Arc<dylib::Expander>, String> {
         let expander = || {
             let expander = dylib::Expander::new(path)
@@ -153,7 +199,7 @@ impl ProcMacroSrv<'_> {
     }
 }
 
-pub trait ProcMacroSrvSpan: Copy + Send {
+pub trait ProcMacroSrvSpan: Copy + Send + Eq + std::hash::Hash {
     type Server: proc_macro::bridge::server::Server<TokenStream = TokenStream<Self>>;
     fn make_server(call_site: Self, def_site: Self, mixed_site: Self) -> Self::Server;
 }
@@ -248,6 +248,58 @@ fn test_attr_macro() {
     );
 }
 
+/// Tests that an input which merely moved within its file is expanded from the cache, with the
+/// spans of the expansion mapped to the moved input.
+#[test]
+fn test_expansion_cache() {
+    let env = crate::EnvSnapshot::default();
+    let mut srv = crate::ProcMacroSrv::new(&env);
+    srv.set_expansion_cache(true);
+
+    let first = expand_anchored(&srv, "fn_like_clone_tokens", "ident, []", 2, vec![]);
+    let moved = expand_anchored(&srv, "fn_like_clone_tokens", "ident, []", 3, vec![]);
+    assert_eq!(srv.expansion_cache().unwrap().len(), 1);
+    assert_eq!(moved, first.replace("42:2@", "42:3@"));
+
+    expand_anchored(&srv, "fn_like_clone_tokens", "other, []", 3, vec![]);
+    assert_eq!(srv.expansion_cache().unwrap().len(), 2);
+}
+
+/// Tests that a cached expansion is not reused for an input from a different environment.
+#[test]
+fn test_expansion_cache_env_change() {
+    let env = crate::EnvSnapshot::default();
+    let mut srv = crate::ProcMacroSrv::new(&env);
+    srv.set_expansion_cache(true);
+
+    let var = |value: &str| vec![("PROC_MACRO_TEST_VAR".to_owned(), value.to_owned())];
+    let first = expand_anchored(&srv, "fn_like_env_var", "PROC_MACRO_TEST_VAR", 2, var("first"));
+    let second = expand_anchored(&srv, "fn_like_env_var", "PROC_MACRO_TEST_VAR", 2, var("second"));
+    assert!(first.contains("\"first\""), "{first}");
+    assert!(second.contains("\"second\""), "{second}");
+    assert_eq!(srv.expansion_cache().unwrap().len(), 2);
+}
+
+/// Tests that expansions are not cached unless asked for, so that macros reading files see
+/// their changes.
+#[test]
+fn test_no_expansion_cache_by_default() {
+    let env = crate::EnvSnapshot::default();
+    let srv = crate::ProcMacroSrv::new(&env);
+    let path = std::env::temp_dir().join(format!("proc-macro-srv-test-{}", std::process::id()));
+    let input = format!("{:?}", path.to_str().unwrap());
+
+    std::fs::write(&path, "first").unwrap();
+    let first = expand_anchored(&srv, "fn_like_read_file", &input, 2, vec![]);
+    std::fs::write(&path, "second").unwrap();
+    let second = expand_anchored(&srv, "fn_like_read_file", &input, 2, vec![]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(first.contains("\"first\""), "{first}");
+    assert!(second.contains("\"second\""), "{second}");
+    assert!(srv.expansion_cache().is_none());
+}
+
 /// Tests that we find and classify all proc macros correctly.
 #[test]
 fn list_test_macros() {
@@ -262,6 +314,8 @@ fn list_test_macros() {
         fn_like_mk_idents [Bang]
         fn_like_span_join [Bang]
         fn_like_span_ops [Bang]
+        fn_like_env_var [Bang]
+        fn_like_read_file [Bang]
         attr_noop [Attr]
         attr_panic [Attr]
         attr_error [Attr]
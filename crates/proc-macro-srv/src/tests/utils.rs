@@ -104,6 +104,46 @@ fn assert_expand_impl(
     expect_s.assert_eq(&format!("{res:#?}"));
 }
 
+/// Expands `input` with `srv` in `env`, with all spans anchored at `ast_id`.
+pub(crate) fn expand_anchored(
+    srv: &ProcMacroSrv<'_>,
+    macro_name: &str,
+    input: &str,
+    ast_id: u32,
+    env: Vec<(String, String)>,
+) -> String {
+    let call_site = Span {
+        range: TextRange::new(0.into(), 100.into()),
+        anchor: SpanAnchor {
+            file_id: EditionedFileId::current_edition(FileId::from_raw(42)),
+            ast_id: ErasedFileAstId::from_raw(ast_id),
+        },
+        ctx: SyntaxContextId::root(span::Edition::CURRENT),
+    };
+    let input = syntax_bridge::parse_to_token_tree(
+        span::Edition::CURRENT,
+        call_site.anchor,
+        call_site.ctx,
+        input,
+    )
+    .unwrap();
+
+    let res = srv
+        .expand(
+            proc_macro_test_dylib_path(),
+            env,
+            None::<&str>,
+            macro_name.to_owned(),
+            input,
+            None,
+            call_site,
+            call_site,
+            call_site,
+        )
+        .unwrap();
+    format!("{:#?}", tt::TokenTreesView::new(&res))
+}
+
 pub(crate) fn list() -> Vec<String> {
     let dylib_path = proc_macro_test_dylib_path();
     let env = EnvSnapshot::default();
@@ -14,7 +14,7 @@ use serde_json::from_value;
 use span::Edition;
 use toolchain::Tool;
 
-use crate::{CfgOverrides, InvocationStrategy};
+use crate::{CfgOverrides, CfgVariant, InvocationStrategy};
 use crate::{ManifestPath, Sysroot};
 
 /// [`CargoWorkspace`] represents the logical structure of, well, a Cargo
@@ -95,6 +95,8 @@ pub struct CargoConfig {
     /// Extra includes to add to the VFS.
     pub extra_includes: Vec<AbsPathBuf>,
     pub cfg_overrides: CfgOverrides,
+    /// Alternate configurations to additionally analyze workspace members under.
+    pub cfg_variants: Vec<CfgVariant>,
     /// Invoke `cargo check` through the RUSTC_WRAPPER.
     pub wrap_rustc_in_build_scripts: bool,
     /// The command to run instead of `cargo check` for building build scripts.
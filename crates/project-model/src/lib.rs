@@ -66,7 +66,9 @@ pub use crate::{
     manifest_path::ManifestPath,
     project_json::{ProjectJson, ProjectJsonData},
     sysroot::Sysroot,
-    workspace::{FileLoader, PackageRoot, ProjectWorkspace, ProjectWorkspaceKind},
+    workspace::{
+        FileLoader, PackageRoot, ProjectWorkspace, ProjectWorkspaceKind, ResolvedCfgVariant,
+    },
 };
 pub use cargo_metadata::Metadata;
 
@@ -244,6 +246,18 @@ impl CfgOverrides {
     }
 }
 
+/// An alternate configuration that local crates are additionally analyzed under, so that code
+/// which is cfg'd out in the active configuration gets checked as well.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct CfgVariant {
+    /// The target tuple whose `rustc --print cfg` replaces the one of the workspace.
+    pub target: Option<String>,
+    /// The features to enable instead of the active ones.
+    pub features: Option<Vec<String>>,
+    /// Additional cfg options to enable.
+    pub cfgs: Vec<cfg::CfgAtom>,
+}
+
 fn parse_cfg(s: &str) -> Result<cfg::CfgAtom, String> {
     let res = match s.split_once('=') {
         Some((key, value)) => {
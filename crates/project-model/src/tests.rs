@@ -4,7 +4,7 @@ use base_db::{CrateGraph, ProcMacroPaths};
 use cargo_metadata::Metadata;
 use cfg::{CfgAtom, CfgDiff};
 use expect_test::{expect_file, ExpectFile};
-use intern::sym;
+use intern::{sym, Symbol};
 use paths::{AbsPath, AbsPathBuf, Utf8Path, Utf8PathBuf};
 use rustc_hash::FxHashMap;
 use serde::de::DeserializeOwned;
@@ -13,8 +13,8 @@ use triomphe::Arc;
 
 use crate::{
     sysroot::RustLibSrcWorkspace, workspace::ProjectWorkspaceKind, CargoWorkspace, CfgOverrides,
-    ManifestPath, ProjectJson, ProjectJsonData, ProjectWorkspace, RustSourceWorkspaceConfig,
-    Sysroot, WorkspaceBuildScripts,
+    CfgVariant, ManifestPath, ProjectJson, ProjectJsonData, ProjectWorkspace, ResolvedCfgVariant,
+    RustSourceWorkspaceConfig, Sysroot, WorkspaceBuildScripts,
 };
 
 fn load_cargo(file: &str) -> (CrateGraph, ProcMacroPaths) {
@@ -50,6 +50,7 @@ fn load_workspace_from_metadata(file: &str) -> ProjectWorkspace {
         target_layout: Err("target_data_layout not loaded".into()),
         extra_includes: Vec::new(),
         set_test: true,
+        cfg_variants: Vec::new(),
     }
 }
 
@@ -66,6 +67,7 @@ fn load_rust_project(file: &str) -> (CrateGraph, ProcMacroPaths) {
         cfg_overrides: Default::default(),
         extra_includes: Vec::new(),
         set_test: true,
+        cfg_variants: Vec::new(),
     };
     to_crate_graph(project_workspace, &mut Default::default())
 }
@@ -210,6 +212,66 @@ fn cargo_hello_world_project_model() {
     )
 }
 
+#[test]
+fn cargo_hello_world_project_model_with_cfg_variants() {
+    let variant =
+        CfgVariant { cfgs: vec![CfgAtom::Flag(Symbol::intern("windows"))], ..Default::default() };
+    let project_workspace = ProjectWorkspace {
+        cfg_variants: vec![ResolvedCfgVariant { variant, rustc_cfg: None }],
+        ..load_workspace_from_metadata("hello-world-metadata.json")
+    };
+    let (crate_graph, _proc_macros) = to_crate_graph(project_workspace, &mut Default::default());
+
+    let windows = CfgAtom::Flag(Symbol::intern("windows"));
+    let variants: Vec<_> =
+        crate_graph.iter().filter(|&it| crate_graph.cfg_variant_of(it).is_some()).collect();
+    // The lib, bin, example and test targets of the only workspace member
+    assert_eq!(variants.len(), 4);
+    for &variant in &variants {
+        let of = crate_graph.cfg_variant_of(variant).unwrap();
+        assert_eq!(crate_graph[variant].root_file_id, crate_graph[of].root_file_id);
+        assert!(crate_graph[variant].cfg_options.check_atom(&windows));
+        assert!(!crate_graph[of].cfg_options.check_atom(&windows));
+        // Dependencies on other workspace members point to their variants
+        for dep in &crate_graph[variant].dependencies {
+            let is_member = crate_graph[dep.crate_id].origin.is_local();
+            assert_eq!(is_member, variants.contains(&dep.crate_id), "{}", dep.name);
+        }
+    }
+}
+
+#[test]
+fn cargo_hello_world_crate_cfg_options() {
+    let project_workspace = load_workspace_from_metadata("hello-world-metadata.json");
+    let mut file_map = FxHashMap::default();
+    let (crate_graph, _proc_macros) = to_crate_graph(project_workspace.clone(), &mut file_map);
+
+    let windows = CfgAtom::Flag(Symbol::intern("windows"));
+    let mut n_checked = 0;
+    for (root, file_id) in &file_map {
+        let Some(krate) = crate_graph.iter().find(|&it| crate_graph[it].root_file_id == *file_id)
+        else {
+            continue;
+        };
+        let default = ResolvedCfgVariant { variant: CfgVariant::default(), rustc_cfg: None };
+        let Some(cfg_options) = project_workspace.crate_cfg_options(root, &default) else {
+            assert!(!crate_graph[krate].origin.is_local(), "{root}");
+            continue;
+        };
+        // The default variant reproduces the cfgs `cargo metadata` gave us
+        assert_eq!(cfg_options, *crate_graph[krate].cfg_options, "{root}");
+
+        let variant = ResolvedCfgVariant {
+            variant: CfgVariant { cfgs: vec![windows.clone()], ..Default::default() },
+            rustc_cfg: None,
+        };
+        let cfg_options = project_workspace.crate_cfg_options(root, &variant).unwrap();
+        assert!(cfg_options.check_atom(&windows));
+        n_checked += 1;
+    }
+    assert_eq!(n_checked, 4);
+}
+
 #[test]
 fn rust_project_hello_world_project_model() {
     let (crate_graph, _proc_macros) = load_rust_project("hello-world-project.json");
@@ -294,6 +356,7 @@ fn smoke_test_real_sysroot_cargo() {
         target_layout: Err("target_data_layout not loaded".into()),
         extra_includes: Vec::new(),
         set_test: true,
+        cfg_variants: Vec::new(),
     };
     project_workspace.to_crate_graph(
         &mut {
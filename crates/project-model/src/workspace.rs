@@ -25,9 +25,9 @@ use crate::{
     project_json::{Crate, CrateArrayIdx},
     sysroot::{RustLibSrcCrate, RustLibSrcWorkspace},
     toolchain_info::{rustc_cfg, target_data_layout, target_tuple, version, QueryConfig},
-    CargoConfig, CargoWorkspace, CfgOverrides, InvocationStrategy, ManifestPath, Package,
-    ProjectJson, ProjectManifest, RustSourceWorkspaceConfig, Sysroot, TargetData, TargetKind,
-    WorkspaceBuildScripts,
+    CargoConfig, CargoWorkspace, CfgOverrides, CfgVariant, InvocationStrategy, ManifestPath,
+    Package, ProjectJson, ProjectManifest, RustSourceWorkspaceConfig, Sysroot, TargetData,
+    TargetKind, WorkspaceBuildScripts,
 };
 use tracing::{debug, error, info};
 
@@ -66,6 +66,16 @@ pub struct ProjectWorkspace {
     pub extra_includes: Vec<AbsPathBuf>,
     /// Set `cfg(test)` for local crates
     pub set_test: bool,
+    /// Alternate configurations workspace members are additionally lowered with.
+    pub cfg_variants: Vec<ResolvedCfgVariant>,
+}
+
+/// A [`CfgVariant`] with the cfg options of its target resolved.
+#[derive(Clone, Debug)]
+pub struct ResolvedCfgVariant {
+    pub variant: CfgVariant,
+    /// The `rustc --print cfg` output for the variant's target, if it has one.
+    pub rustc_cfg: Option<Vec<CfgAtom>>,
 }
 
 #[derive(Clone)]
@@ -114,6 +124,7 @@ impl fmt::Debug for ProjectWorkspace {
             cfg_overrides,
             extra_includes,
             set_test,
+            cfg_variants,
         } = self;
         match kind {
             ProjectWorkspaceKind::Cargo { cargo, error: _, build_scripts, rustc } => f
@@ -131,6 +142,7 @@ impl fmt::Debug for ProjectWorkspace {
                 .field("toolchain", &toolchain)
                 .field("data_layout", &target_layout)
                 .field("set_test", set_test)
+                .field("n_cfg_variants", &cfg_variants.len())
                 .field("build_scripts", &build_scripts.error().unwrap_or("ok"))
                 .finish(),
             ProjectWorkspaceKind::Json(project) => {
@@ -214,6 +226,7 @@ impl ProjectWorkspace {
             extra_env,
             set_test,
             cfg_overrides,
+            cfg_variants,
             extra_includes,
             sysroot,
             sysroot_src,
@@ -348,6 +361,17 @@ impl ProjectWorkspace {
             });
             let cargo_config_extra_env =
                 s.spawn(|| cargo_config_env(cargo_toml, extra_env, &sysroot));
+            let cfg_variants = s.spawn(|| {
+                cfg_variants
+                    .iter()
+                    .map(|variant| ResolvedCfgVariant {
+                        variant: variant.clone(),
+                        rustc_cfg: variant.target.as_deref().map(|target| {
+                            rustc_cfg::get(toolchain_config, Some(target), extra_env)
+                        }),
+                    })
+                    .collect::<Vec<_>>()
+            });
             thread::Result::Ok((
                 toolchain.join()?,
                 rustc_cfg.join()?,
@@ -356,6 +380,7 @@ impl ProjectWorkspace {
                 loaded_sysroot.join()?,
                 cargo_metadata.join()?,
                 cargo_config_extra_env.join()?,
+                cfg_variants.join()?,
             ))
         });
 
@@ -367,6 +392,7 @@ impl ProjectWorkspace {
             loaded_sysroot,
             cargo_metadata,
             cargo_config_extra_env,
+            cfg_variants,
         ) = match join {
             Ok(it) => it,
             Err(e) => std::panic::resume_unwind(e),
@@ -396,6 +422,7 @@ impl ProjectWorkspace {
             target_layout: data_layout.map(Arc::from).map_err(|it| Arc::from(it.to_string())),
             extra_includes: extra_includes.clone(),
             set_test: *set_test,
+            cfg_variants,
         })
     }
 
@@ -452,6 +479,7 @@ impl ProjectWorkspace {
             cfg_overrides: config.cfg_overrides.clone(),
             extra_includes: config.extra_includes.clone(),
             set_test: config.set_test,
+            cfg_variants: Vec::new(),
         }
     }
 
@@ -515,6 +543,7 @@ impl ProjectWorkspace {
             cfg_overrides: config.cfg_overrides.clone(),
             extra_includes: config.extra_includes.clone(),
             set_test: config.set_test,
+            cfg_variants: Vec::new(),
         })
     }
 
@@ -818,6 +847,52 @@ impl ProjectWorkspace {
         }
     }
 
+    /// Resolves the cfg options of `variant`'s target, if it names one.
+    pub fn resolve_cfg_variant(
+        &self,
+        variant: CfgVariant,
+        extra_env: &FxHashMap<String, String>,
+    ) -> ResolvedCfgVariant {
+        let rustc_cfg = variant.target.as_deref().map(|target| {
+            let config = match &self.kind {
+                ProjectWorkspaceKind::Cargo { cargo, .. } => {
+                    QueryConfig::Cargo(&self.sysroot, cargo.manifest_path())
+                }
+                _ => QueryConfig::Rustc(&self.sysroot, self.workspace_root().as_ref()),
+            };
+            rustc_cfg::get(config, Some(target), extra_env)
+        });
+        ResolvedCfgVariant { variant, rustc_cfg }
+    }
+
+    /// Computes the cfg options the crate rooted at `crate_root` has under `variant`, without
+    /// re-running `cargo metadata`.
+    ///
+    /// Returns `None` if the crate is not a target of a cargo workspace member.
+    pub fn crate_cfg_options(
+        &self,
+        crate_root: &AbsPath,
+        variant: &ResolvedCfgVariant,
+    ) -> Option<CfgOptions> {
+        let ProjectWorkspaceKind::Cargo { cargo, build_scripts, .. } = &self.kind else {
+            return None;
+        };
+        let pkg = cargo.packages().filter(|&pkg| cargo[pkg].is_member).find(|&pkg| {
+            cargo[pkg].targets.iter().any(|&tgt| cargo[tgt].root.as_path() == crate_root)
+        })?;
+        let rustc_cfg = CfgOptions::from_iter(
+            variant.rustc_cfg.as_ref().unwrap_or(&self.rustc_cfg).iter().cloned(),
+        );
+        Some(variant_cfg_options(
+            &rustc_cfg,
+            &cargo[pkg],
+            &variant.variant,
+            build_scripts.get_output(pkg),
+            &self.cfg_overrides,
+            self.set_test,
+        ))
+    }
+
     pub fn to_crate_graph(
         &self,
         load: FileLoader<'_>,
@@ -825,7 +900,7 @@ impl ProjectWorkspace {
     ) -> (CrateGraph, ProcMacroPaths) {
         let _p = tracing::info_span!("ProjectWorkspace::to_crate_graph").entered();
 
-        let Self { kind, sysroot, cfg_overrides, rustc_cfg, .. } = self;
+        let Self { kind, sysroot, cfg_overrides, rustc_cfg, cfg_variants, .. } = self;
         let (crate_graph, proc_macros) = match kind {
             ProjectWorkspaceKind::Json(project) => project_json_to_crate_graph(
                 rustc_cfg.clone(),
@@ -844,6 +919,7 @@ impl ProjectWorkspace {
                     sysroot,
                     rustc_cfg.clone(),
                     cfg_overrides,
+                    cfg_variants,
                     build_scripts,
                     self.set_test,
                 )
@@ -857,6 +933,7 @@ impl ProjectWorkspace {
                         sysroot,
                         rustc_cfg.clone(),
                         cfg_overrides,
+                        cfg_variants,
                         build_scripts,
                         self.set_test,
                     )
@@ -1062,6 +1139,7 @@ fn cargo_to_crate_graph(
     sysroot: &Sysroot,
     rustc_cfg: Vec<CfgAtom>,
     override_cfg: &CfgOverrides,
+    cfg_variants: &[ResolvedCfgVariant],
     build_scripts: &WorkspaceBuildScripts,
     set_test: bool,
 ) -> (CrateGraph, ProcMacroPaths) {
@@ -1084,20 +1162,7 @@ fn cargo_to_crate_graph(
     for pkg in cargo.packages() {
         has_private |= cargo[pkg].metadata.rustc_private;
 
-        let cfg_options = {
-            let mut cfg_options = cfg_options.clone();
-
-            if cargo[pkg].is_local {
-                if set_test {
-                    // Add test cfg for local crates
-                    cfg_options.insert_atom(sym::test.clone());
-                }
-                cfg_options.insert_atom(sym::rust_analyzer.clone());
-            }
-
-            override_cfg.apply(&mut cfg_options, &cargo[pkg].name);
-            cfg_options
-        };
+        let cfg_options = package_cfg_options(&cfg_options, &cargo[pkg], override_cfg, set_test);
 
         let mut lib_tgt = None;
         for &tgt in cargo[pkg].targets.iter() {
@@ -1243,6 +1308,106 @@ fn cargo_to_crate_graph(
             );
         }
     }
+
+    for variant in cfg_variants {
+        let rustc_cfg = variant.rustc_cfg.as_ref().map(|it| CfgOptions::from_iter(it.clone()));
+        let rustc_cfg = rustc_cfg.as_ref().unwrap_or(&cfg_options);
+        let mut crates = Vec::new();
+        for pkg in cargo.packages().filter(|&pkg| cargo[pkg].is_member) {
+            let cfg_options = Arc::new(variant_cfg_options(
+                rustc_cfg,
+                &cargo[pkg],
+                &variant.variant,
+                build_scripts.get_output(pkg),
+                override_cfg,
+                set_test,
+            ));
+
+            crates.extend(
+                pkg_crates
+                    .get(&pkg)
+                    .into_iter()
+                    .flatten()
+                    .filter(|(_, kind)| {
+                        !matches!(
+                            kind,
+                            TargetKind::BuildScript | TargetKind::Lib { is_proc_macro: true }
+                        )
+                    })
+                    .map(|&(krate, _)| (krate, cfg_options.clone())),
+            );
+        }
+        crate_graph.add_cfg_variants(crates);
+    }
+    res
+}
+
+/// Returns the cfg options shared by all crates of `pkg`, that is without its features and build
+/// script cfgs.
+fn package_cfg_options(
+    rustc_cfg: &CfgOptions,
+    pkg: &PackageData,
+    override_cfg: &CfgOverrides,
+    set_test: bool,
+) -> CfgOptions {
+    let mut cfg_options = rustc_cfg.clone();
+
+    if pkg.is_local {
+        if set_test {
+            // Add test cfg for local crates
+            cfg_options.insert_atom(sym::test.clone());
+        }
+        cfg_options.insert_atom(sym::rust_analyzer.clone());
+    }
+
+    override_cfg.apply(&mut cfg_options, &pkg.name);
+    cfg_options
+}
+
+/// Returns the cfg options of `pkg`'s crates under `variant`, where `rustc_cfg` already accounts
+/// for the variant's target.
+fn variant_cfg_options(
+    rustc_cfg: &CfgOptions,
+    pkg: &PackageData,
+    variant: &CfgVariant,
+    build_data: Option<&BuildScriptOutput>,
+    override_cfg: &CfgOverrides,
+    set_test: bool,
+) -> CfgOptions {
+    let mut cfg_options = package_cfg_options(rustc_cfg, pkg, override_cfg, set_test);
+    let features = match &variant.features {
+        Some(features) => resolve_features(pkg, features),
+        None => pkg.active_features.clone(),
+    };
+    for feature in features {
+        cfg_options.insert_key_value(sym::feature.clone(), Symbol::intern(&feature));
+    }
+    if let Some(build_data) = build_data {
+        cfg_options.extend(build_data.cfgs.iter().cloned());
+    }
+    cfg_options.extend(variant.cfgs.iter().cloned());
+    cfg_options
+}
+
+/// Returns the given features of `pkg` along with the features they enable.
+///
+/// Unknown features as well as dependency features are ignored.
+fn resolve_features(pkg: &PackageData, features: &[String]) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut worklist: Vec<&str> = features.iter().map(String::as_str).collect();
+    while let Some(feature) = worklist.pop() {
+        let Some(enables) = pkg.features.get(feature) else { continue };
+        if res.iter().any(|it| it == feature) {
+            continue;
+        }
+        res.push(feature.to_owned());
+        worklist.extend(enables.iter().filter_map(|it| match it.split_once('/') {
+            // `dep/feature` also enables the (implicit) feature of an optional dependency
+            Some((dep, _)) => (!dep.ends_with('?')).then_some(dep),
+            None => (!it.starts_with("dep:")).then_some(it.as_str()),
+        }));
+    }
+    res.sort();
     res
 }
 
@@ -1508,6 +1673,7 @@ fn sysroot_to_crate_graph(
                     .unwrap(),
                     ..Default::default()
                 },
+                &[],
                 &WorkspaceBuildScripts::default(),
                 false,
             );
@@ -79,8 +79,9 @@ fn actual_main() -> anyhow::Result<ExitCode> {
         flags::RustAnalyzerCmd::Highlight(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::AnalysisStats(cmd) => cmd.run(verbosity)?,
         flags::RustAnalyzerCmd::Diagnostics(cmd) => cmd.run()?,
+        flags::RustAnalyzerCmd::Lint(cmd) => return cmd.run(),
         flags::RustAnalyzerCmd::UnresolvedReferences(cmd) => cmd.run()?,
-        flags::RustAnalyzerCmd::Ssr(cmd) => cmd.run()?,
+        flags::RustAnalyzerCmd::Ssr(cmd) => return cmd.run(),
         flags::RustAnalyzerCmd::Search(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Lsif(cmd) => {
             cmd.run(&mut std::io::stdout(), Some(project_model::RustLibSource::Discover))?
@@ -266,16 +267,14 @@ fn run_server() -> anyhow::Result<()> {
 
     let server_capabilities = rust_analyzer::server_capabilities(&config);
 
-    let initialize_result = lsp_types::InitializeResult {
-        capabilities: server_capabilities,
-        server_info: Some(lsp_types::ServerInfo {
+    // Not an `lsp_types::InitializeResult`, as the capabilities are JSON.
+    let initialize_result = serde_json::json!({
+        "capabilities": server_capabilities,
+        "serverInfo": lsp_types::ServerInfo {
             name: String::from("rust-analyzer"),
             version: Some(rust_analyzer::version().to_string()),
-        }),
-        offset_encoding: None,
-    };
-
-    let initialize_result = serde_json::to_value(initialize_result).unwrap();
+        },
+    });
 
     if let Err(e) = connection.initialize_finish(initialize_id, initialize_result) {
         if e.channel_is_disconnected() {
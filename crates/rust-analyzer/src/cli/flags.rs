@@ -128,6 +128,31 @@ xflags::xflags! {
             optional --proc-macro-srv path: PathBuf
         }
 
+        /// Run the native diagnostics over a workspace, exiting with a non-zero status code if any
+        /// errors are reported.
+        cmd lint {
+            /// Directory with Cargo.toml.
+            required path: PathBuf
+
+            /// Output format: `text` (default), `json` (one object per line) or `sarif`.
+            optional --format format: LintOutputFormat
+            /// Minimum severity of the reported diagnostics: `hint`, `warning` (default) or `error`.
+            optional --min-severity severity: LintSeverity
+            /// Don't report diagnostics with this code, in addition to `diagnostics.disabled`.
+            repeated --allow code: String
+            /// Report diagnostics with this code as errors.
+            repeated --deny code: String
+            /// A path to a json configuration file, in the same format as the server configuration.
+            optional --config-path config_path: PathBuf
+
+            /// Don't run build scripts or load `OUT_DIR` values by running `cargo check` before analysis.
+            optional --disable-build-scripts
+            /// Don't use expand proc macros.
+            optional --disable-proc-macros
+            /// Run the proc-macro-srv binary at the specified path.
+            optional --proc-macro-srv path: PathBuf
+        }
+
         /// Report unresolved references
         cmd unresolved-references {
             /// Directory with Cargo.toml.
@@ -144,6 +169,14 @@ xflags::xflags! {
         cmd ssr {
             /// A structured search replace rule (`$a.foo($b) ==>> bar($a, $b)`)
             repeated rule: SsrRule
+            /// Read additional rules from a file. Rules are separated by blank lines, lines starting
+            /// with `//` are comments and a `paths:` line restricts the next rule to files matching
+            /// the given comma-separated globs.
+            optional --rules path: PathBuf
+            /// Print the changes as a unified diff instead of applying them.
+            optional --dry-run
+            /// Don't apply the changes, but exit with a non-zero status code if any rule matches.
+            optional --check
         }
 
         cmd search {
@@ -198,6 +231,7 @@ pub enum RustAnalyzerCmd {
     RunTests(RunTests),
     RustcTests(RustcTests),
     Diagnostics(Diagnostics),
+    Lint(Lint),
     UnresolvedReferences(UnresolvedReferences),
     Ssr(Ssr),
     Search(Search),
@@ -270,6 +304,20 @@ pub struct Diagnostics {
     pub proc_macro_srv: Option<PathBuf>,
 }
 
+#[derive(Debug)]
+pub struct Lint {
+    pub path: PathBuf,
+
+    pub format: Option<LintOutputFormat>,
+    pub min_severity: Option<LintSeverity>,
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    pub config_path: Option<PathBuf>,
+    pub disable_build_scripts: bool,
+    pub disable_proc_macros: bool,
+    pub proc_macro_srv: Option<PathBuf>,
+}
+
 #[derive(Debug)]
 pub struct UnresolvedReferences {
     pub path: PathBuf,
@@ -282,6 +330,10 @@ pub struct UnresolvedReferences {
 #[derive(Debug)]
 pub struct Ssr {
     pub rule: Vec<SsrRule>,
+
+    pub rules: Option<PathBuf>,
+    pub dry_run: bool,
+    pub check: bool,
 }
 
 #[derive(Debug)]
@@ -353,3 +405,45 @@ impl FromStr for OutputFormat {
         }
     }
 }
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LintOutputFormat {
+    #[default]
+    Text,
+    Json,
+    Sarif,
+}
+
+impl FromStr for LintOutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(format!("unknown output format `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    Hint,
+    #[default]
+    Warning,
+    Error,
+}
+
+impl FromStr for LintSeverity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hint" => Ok(Self::Hint),
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => Err(format!("unknown severity `{s}`")),
+        }
+    }
+}
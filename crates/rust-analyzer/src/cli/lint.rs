@@ -0,0 +1,404 @@
+//! Runs the native diagnostics over all local files of a workspace and reports them in a format
+//! suitable for CI. Exits with a non-zero status code if any errors are reported.
+
+use std::{
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use ide::{AnalysisHost, AssistResolveStrategy, Diagnostic, DiagnosticCode, Severity};
+use ide_db::{base_db::SourceRootDatabase, line_index::WideEncoding, LineIndexDatabase};
+use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
+use rustc_hash::FxHashSet;
+use serde_json::json;
+use vfs::{AbsPathBuf, FileId};
+
+use crate::{
+    cli::flags::{self, LintOutputFormat, LintSeverity},
+    config::{Config, ConfigChange},
+};
+
+impl flags::Lint {
+    pub fn run(self) -> anyhow::Result<ExitCode> {
+        const STACK_SIZE: usize = 1024 * 1024 * 8;
+
+        let handle = stdx::thread::Builder::new(stdx::thread::ThreadIntent::LatencySensitive)
+            .name("BIG_STACK_THREAD".into())
+            .stack_size(STACK_SIZE)
+            .spawn(|| self.run_())
+            .unwrap();
+
+        handle.join()
+    }
+
+    fn run_(self) -> anyhow::Result<ExitCode> {
+        let root = AbsPathBuf::assert_utf8(std::env::current_dir()?.join(&self.path)).normalize();
+
+        let mut config =
+            Config::new(root.clone(), lsp_types::ClientCapabilities::default(), vec![], None);
+        if let Some(p) = &self.config_path {
+            let mut file = io::BufReader::new(std::fs::File::open(p)?);
+            let json = serde_json::from_reader(&mut file)?;
+            let mut change = ConfigChange::default();
+            change.change_client_config(json);
+
+            let error_sink;
+            (config, error_sink, _) = config.apply_change(change);
+            if !error_sink.is_empty() {
+                anyhow::bail!("{error_sink}");
+            }
+        }
+
+        let with_proc_macro_server = if let Some(p) = &self.proc_macro_srv {
+            let path = AbsPathBuf::assert_utf8(std::env::current_dir()?.join(p));
+            ProcMacroServerChoice::Explicit(path)
+        } else if self.disable_proc_macros {
+            ProcMacroServerChoice::None
+        } else {
+            ProcMacroServerChoice::Sysroot
+        };
+        let load_cargo_config = LoadCargoConfig {
+            load_out_dirs_from_check: !self.disable_build_scripts,
+            with_proc_macro_server,
+            prefill_caches: false,
+        };
+        let cargo_config = config.cargo(None);
+        let (db, vfs, _proc_macro) =
+            load_workspace_at(root.as_path().as_ref(), &cargo_config, &load_cargo_config, &|_| {})?;
+        let host = AnalysisHost::with_database(db);
+        let db = host.raw_database();
+        let analysis = host.analysis();
+
+        // Walk files instead of modules, so that files which aren't part of the module tree are
+        // reported as well.
+        let mut files: Vec<(FileId, String)> = vfs
+            .iter()
+            .filter_map(|(file_id, path)| {
+                let path = path.as_path()?;
+                if path.extension() != Some("rs") {
+                    return None;
+                }
+                if db.source_root(db.file_source_root(file_id)).is_library {
+                    return None;
+                }
+                let display = match path.strip_prefix(&root) {
+                    Some(relative) => relative.as_str().replace('\\', "/"),
+                    None => path.to_string(),
+                };
+                Some((file_id, display))
+            })
+            .collect();
+        files.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+        let min_severity = self.min_severity.unwrap_or_default();
+        let deny: FxHashSet<String> = self.deny.into_iter().collect();
+        let mut diagnostics = vec![];
+        for (file_id, path) in &files {
+            let mut diagnostics_config = config.diagnostics(Some(db.file_source_root(*file_id)));
+            diagnostics_config.enabled = true;
+            diagnostics_config.disabled.extend(self.allow.iter().cloned());
+
+            let line_index = db.line_index(*file_id);
+            let file_diagnostics = analysis.full_diagnostics(
+                &diagnostics_config,
+                AssistResolveStrategy::None,
+                *file_id,
+            )?;
+            for Diagnostic { code, message, range, severity, .. } in file_diagnostics {
+                let severity = match severity {
+                    _ if deny.contains(code.as_str()) => LintSeverity::Error,
+                    Severity::Error => LintSeverity::Error,
+                    Severity::Warning => LintSeverity::Warning,
+                    Severity::WeakWarning => LintSeverity::Hint,
+                    Severity::Allow => continue,
+                };
+                if severity < min_severity {
+                    continue;
+                }
+                let position = |offset| {
+                    let line_col = line_index.line_col(offset);
+                    let col = line_index
+                        .to_wide(WideEncoding::Utf16, line_col)
+                        .map_or(line_col.col, |it| it.col);
+                    (line_col.line + 1, col + 1)
+                };
+                diagnostics.push(LintDiagnostic {
+                    path: path.clone(),
+                    code,
+                    severity,
+                    message,
+                    start: position(range.range.start()),
+                    end: position(range.range.end()),
+                });
+            }
+        }
+
+        let stdout = &mut io::stdout().lock();
+        match self.format.unwrap_or_default() {
+            LintOutputFormat::Text => write_text(stdout, &diagnostics)?,
+            LintOutputFormat::Json => write_json(stdout, &diagnostics)?,
+            LintOutputFormat::Sarif => {
+                let root_uri = lsp_types::Url::from_directory_path(root.as_path()).ok();
+                write_sarif(stdout, &diagnostics, root_uri.as_ref().map(|it| it.as_str()))?
+            }
+        }
+        stdout.flush()?;
+
+        let has_errors = diagnostics.iter().any(|it| it.severity == LintSeverity::Error);
+        Ok(if has_errors { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+    }
+}
+
+struct LintDiagnostic {
+    /// Path of the file, relative to the workspace root if it is inside of it.
+    path: String,
+    code: DiagnosticCode,
+    severity: LintSeverity,
+    message: String,
+    /// One-based line and column, columns are counted in UTF-16 code units.
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+fn severity_name(severity: LintSeverity) -> &'static str {
+    match severity {
+        LintSeverity::Hint => "hint",
+        LintSeverity::Warning => "warning",
+        LintSeverity::Error => "error",
+    }
+}
+
+fn write_text(out: &mut impl Write, diagnostics: &[LintDiagnostic]) -> io::Result<()> {
+    for d in diagnostics {
+        let (line, col) = d.start;
+        let severity = severity_name(d.severity);
+        writeln!(out, "{}:{line}:{col}: {severity}[{}]: {}", d.path, d.code.as_str(), d.message)?;
+    }
+    let errors = diagnostics.iter().filter(|it| it.severity == LintSeverity::Error).count();
+    writeln!(out, "{} diagnostics, {errors} errors", diagnostics.len())
+}
+
+fn write_json(out: &mut impl Write, diagnostics: &[LintDiagnostic]) -> io::Result<()> {
+    for d in diagnostics {
+        let json = json!({
+            "path": d.path,
+            "code": d.code.as_str(),
+            "severity": severity_name(d.severity),
+            "message": d.message,
+            "start": { "line": d.start.0, "column": d.start.1 },
+            "end": { "line": d.end.0, "column": d.end.1 },
+        });
+        writeln!(out, "{json}")?;
+    }
+    Ok(())
+}
+
+/// Writes a SARIF 2.1.0 log with a single run. Relative paths are resolved against `root_uri`.
+fn write_sarif(
+    out: &mut impl Write,
+    diagnostics: &[LintDiagnostic],
+    root_uri: Option<&str>,
+) -> io::Result<()> {
+    let mut rules = vec![];
+    let mut seen_codes = FxHashSet::default();
+    for d in diagnostics {
+        if seen_codes.insert(d.code.as_str()) {
+            rules.push(json!({ "id": d.code.as_str(), "helpUri": d.code.url() }));
+        }
+    }
+    let results: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            let artifact_location = if std::path::Path::new(&d.path).is_absolute() {
+                let uri =
+                    lsp_types::Url::from_file_path(&d.path).map_or(d.path.clone(), String::from);
+                json!({ "uri": uri })
+            } else {
+                json!({ "uri": d.path, "uriBaseId": "SRCROOT" })
+            };
+            json!({
+                "ruleId": d.code.as_str(),
+                "level": match d.severity {
+                    LintSeverity::Hint => "note",
+                    LintSeverity::Warning => "warning",
+                    LintSeverity::Error => "error",
+                },
+                "message": { "text": d.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": artifact_location,
+                        "region": {
+                            "startLine": d.start.0,
+                            "startColumn": d.start.1,
+                            "endLine": d.end.0,
+                            "endColumn": d.end.1,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+    let mut run = json!({
+        "tool": {
+            "driver": {
+                "name": "rust-analyzer",
+                "informationUri": "https://rust-analyzer.github.io",
+                "version": crate::version::version().to_string(),
+                "rules": rules,
+            },
+        },
+        "columnKind": "utf16CodeUnits",
+        "results": results,
+    });
+    if let Some(root_uri) = root_uri {
+        run["originalUriBaseIds"] = json!({ "SRCROOT": { "uri": root_uri } });
+    }
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [run],
+    });
+    serde_json::to_writer_pretty(&mut *out, &log)?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    fn sample() -> Vec<LintDiagnostic> {
+        vec![
+            LintDiagnostic {
+                path: "src/lib.rs".to_owned(),
+                code: DiagnosticCode::RustcHardError("E0425"),
+                severity: LintSeverity::Error,
+                message: "no such value in this scope".to_owned(),
+                start: (3, 5),
+                end: (3, 8),
+            },
+            LintDiagnostic {
+                path: "src/orphan.rs".to_owned(),
+                code: DiagnosticCode::Ra("unlinked-file", Severity::WeakWarning),
+                severity: LintSeverity::Hint,
+                message: "file not included in module tree".to_owned(),
+                start: (1, 1),
+                end: (1, 1),
+            },
+        ]
+    }
+
+    fn render(write: impl Fn(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut out = vec![];
+        write(&mut out).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .replace(crate::version::version().to_string().as_str(), "$VERSION")
+    }
+
+    #[test]
+    fn text_output() {
+        expect![[r#"
+            src/lib.rs:3:5: error[E0425]: no such value in this scope
+            src/orphan.rs:1:1: hint[unlinked-file]: file not included in module tree
+            2 diagnostics, 1 errors
+        "#]]
+        .assert_eq(&render(|out| write_text(out, &sample())));
+    }
+
+    #[test]
+    fn json_output() {
+        expect![[r#"
+            {"path":"src/lib.rs","code":"E0425","severity":"error","message":"no such value in this scope","start":{"line":3,"column":5},"end":{"line":3,"column":8}}
+            {"path":"src/orphan.rs","code":"unlinked-file","severity":"hint","message":"file not included in module tree","start":{"line":1,"column":1},"end":{"line":1,"column":1}}
+        "#]]
+        .assert_eq(&render(|out| write_json(out, &sample())));
+    }
+
+    #[test]
+    fn sarif_output() {
+        expect![[r#"
+            {
+              "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+              "version": "2.1.0",
+              "runs": [
+                {
+                  "tool": {
+                    "driver": {
+                      "name": "rust-analyzer",
+                      "informationUri": "https://rust-analyzer.github.io",
+                      "version": "$VERSION",
+                      "rules": [
+                        {
+                          "id": "E0425",
+                          "helpUri": "https://doc.rust-lang.org/stable/error_codes/E0425.html"
+                        },
+                        {
+                          "id": "unlinked-file",
+                          "helpUri": "https://rust-analyzer.github.io/manual.html#unlinked-file"
+                        }
+                      ]
+                    }
+                  },
+                  "columnKind": "utf16CodeUnits",
+                  "results": [
+                    {
+                      "ruleId": "E0425",
+                      "level": "error",
+                      "message": {
+                        "text": "no such value in this scope"
+                      },
+                      "locations": [
+                        {
+                          "physicalLocation": {
+                            "artifactLocation": {
+                              "uri": "src/lib.rs",
+                              "uriBaseId": "SRCROOT"
+                            },
+                            "region": {
+                              "startLine": 3,
+                              "startColumn": 5,
+                              "endLine": 3,
+                              "endColumn": 8
+                            }
+                          }
+                        }
+                      ]
+                    },
+                    {
+                      "ruleId": "unlinked-file",
+                      "level": "note",
+                      "message": {
+                        "text": "file not included in module tree"
+                      },
+                      "locations": [
+                        {
+                          "physicalLocation": {
+                            "artifactLocation": {
+                              "uri": "src/orphan.rs",
+                              "uriBaseId": "SRCROOT"
+                            },
+                            "region": {
+                              "startLine": 1,
+                              "startColumn": 1,
+                              "endLine": 1,
+                              "endColumn": 1
+                            }
+                          }
+                        }
+                      ]
+                    }
+                  ],
+                  "originalUriBaseIds": {
+                    "SRCROOT": {
+                      "uri": "file:///ws/"
+                    }
+                  }
+                }
+              ]
+            }
+        "#]]
+        .assert_eq(&render(|out| write_sarif(out, &sample(), Some("file:///ws/"))));
+    }
+}
@@ -4,9 +4,8 @@ use std::env;
 use std::time::Instant;
 
 use ide::{
-    Analysis, AnalysisHost, FileId, FileRange, MonikerKind, MonikerResult, PackageInformation,
-    RootDatabase, StaticIndex, StaticIndexedFile, TokenId, TokenStaticData,
-    VendoredLibrariesConfig,
+    Analysis, AnalysisHost, FileId, FileRange, MonikerResult, PackageInformation, RootDatabase,
+    StaticIndex, StaticIndexedFile, TokenId, TokenStaticData, VendoredLibrariesConfig,
 };
 use ide_db::{line_index::WideEncoding, LineIndexDatabase};
 use load_cargo::{load_workspace, LoadCargoConfig, ProcMacroServerChoice};
@@ -169,16 +168,8 @@ impl LsifManager<'_, '_> {
             }));
         }
         if let Some(MonikerResult::Moniker(moniker)) = token.moniker {
-            let package_id = self.get_package_id(moniker.package_information);
-            let moniker_id = self.add_vertex(lsif::Vertex::Moniker(lsp_types::Moniker {
-                scheme: "rust-analyzer".to_owned(),
-                identifier: moniker.identifier.to_string(),
-                unique: lsp_types::UniquenessLevel::Scheme,
-                kind: Some(match moniker.kind {
-                    MonikerKind::Import => lsp_types::MonikerKind::Import,
-                    MonikerKind::Export => lsp_types::MonikerKind::Export,
-                }),
-            }));
+            let package_id = self.get_package_id(moniker.package_information.clone());
+            let moniker_id = self.add_vertex(lsif::Vertex::Moniker(to_proto::moniker(&moniker)));
             self.add_edge(lsif::Edge::PackageInformation(lsif::EdgeData {
                 in_v: package_id.into(),
                 out_v: moniker_id.into(),
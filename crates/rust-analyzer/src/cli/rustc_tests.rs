@@ -98,6 +98,7 @@ impl Tester {
             cfg_overrides: Default::default(),
             extra_includes: vec![],
             set_test: true,
+            cfg_variants: vec![],
         };
         let load_cargo_config = LoadCargoConfig {
             load_out_dirs_from_check: false,
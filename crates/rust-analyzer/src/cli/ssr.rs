@@ -1,15 +1,41 @@
 //! Applies structured search replace rules from the command line.
 
+use std::{
+    fs,
+    io::{self, Write},
+    process::ExitCode,
+};
+
 use anyhow::Context;
-use ide_db::{base_db::SourceDatabase, EditionedFileId};
-use ide_ssr::MatchFinder;
+use ide_db::{
+    base_db::SourceDatabase,
+    line_index::LineIndex,
+    text_edit::{Indel, TextEdit},
+    EditionedFileId,
+};
+use ide_ssr::{MatchFinder, SsrRule};
 use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
 use project_model::{CargoConfig, RustLibSource};
+use vfs::AbsPathBuf;
 
 use crate::cli::flags;
 
 impl flags::Ssr {
-    pub fn run(self) -> anyhow::Result<()> {
+    pub fn run(self) -> anyhow::Result<ExitCode> {
+        let root = AbsPathBuf::assert_utf8(std::env::current_dir()?);
+        let file_rules = match &self.rules {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                parse_rule_file(&text)
+                    .with_context(|| format!("failed to parse {}", path.display()))?
+            }
+            None => Vec::new(),
+        };
+        if self.rule.is_empty() && file_rules.is_empty() {
+            anyhow::bail!("no rules given");
+        }
+
         let cargo_config = CargoConfig {
             sysroot: Some(RustLibSource::Discover),
             all_targets: true,
@@ -21,26 +47,77 @@ impl flags::Ssr {
             with_proc_macro_server: ProcMacroServerChoice::Sysroot,
             prefill_caches: false,
         };
-        let (ref db, vfs, _proc_macro) = load_workspace_at(
-            &std::env::current_dir()?,
-            &cargo_config,
-            &load_cargo_config,
-            &|_| {},
-        )?;
+        let (ref db, vfs, _proc_macro) =
+            load_workspace_at(root.as_ref(), &cargo_config, &load_cargo_config, &|_| {})?;
         let mut match_finder = MatchFinder::at_first_file(db)?;
         for rule in self.rule {
             match_finder.add_rule(rule)?;
         }
-        let edits = match_finder.edits();
-        for (file_id, edit) in edits {
-            if let Some(path) = vfs.file_path(file_id).as_path() {
+        for FileRule { rule, paths } in file_rules {
+            if paths.is_empty() {
+                match_finder.add_rule(rule)?;
+                continue;
+            }
+            let files = vfs
+                .iter()
+                .filter(|(_, path)| {
+                    let Some(relative) = path.as_path().and_then(|it| it.strip_prefix(&root))
+                    else {
+                        return false;
+                    };
+                    let relative = relative.as_str().replace('\\', "/");
+                    paths.iter().any(|glob| glob_matches(glob, &relative))
+                })
+                .map(|(file_id, _)| file_id)
+                .collect();
+            match_finder.add_rule_in_files(rule, files)?;
+        }
+
+        let mut edits: Vec<_> = match_finder
+            .edits()
+            .into_iter()
+            .filter_map(|(file_id, edit)| {
+                let path = vfs.file_path(file_id).as_path()?.to_owned();
+                Some((path, file_id, edit))
+            })
+            .collect();
+        edits.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+        if !self.check && !self.dry_run {
+            for (path, file_id, edit) in edits {
                 let mut contents = db.file_text(file_id).to_string();
                 edit.apply(&mut contents);
-                std::fs::write(path, contents)
-                    .with_context(|| format!("failed to write {path}"))?;
+                fs::write(&path, contents).with_context(|| format!("failed to write {path}"))?;
             }
+            return Ok(ExitCode::SUCCESS);
         }
-        Ok(())
+
+        let stdout = &mut io::stdout().lock();
+        for (path, file_id, edit) in &edits {
+            let display = match path.strip_prefix(&root) {
+                Some(relative) => relative.as_str().replace('\\', "/"),
+                None => path.to_string(),
+            };
+            let text = db.file_text(*file_id);
+            if self.dry_run {
+                write!(stdout, "{}", unified_diff(&display, &text, edit))?;
+            } else {
+                let line_index = LineIndex::new(&text);
+                for indel in edit.iter() {
+                    let line_col = line_index.line_col(indel.delete.start());
+                    writeln!(
+                        stdout,
+                        "{display}:{}:{}: `{}` would be replaced with `{}`",
+                        line_col.line + 1,
+                        line_col.col + 1,
+                        &text[indel.delete],
+                        indel.insert
+                    )?;
+                }
+            }
+        }
+        stdout.flush()?;
+        Ok(if self.check && !edits.is_empty() { ExitCode::FAILURE } else { ExitCode::SUCCESS })
     }
 }
 
@@ -90,3 +167,294 @@ impl flags::Search {
         Ok(())
     }
 }
+
+/// A rule of a rules file, along with the globs of the files it's restricted to.
+struct FileRule {
+    rule: SsrRule,
+    paths: Vec<String>,
+}
+
+/// Parses a rules file. Rules may span multiple lines and are separated by blank lines. Lines
+/// starting with `//` are comments and a `paths:` line restricts the following rule to the files
+/// matching any of the given comma-separated globs.
+fn parse_rule_file(text: &str) -> anyhow::Result<Vec<FileRule>> {
+    let mut rules = Vec::new();
+    let mut paths = Vec::new();
+    let mut paths_line = None;
+    let mut rule_text = String::new();
+    let mut rule_line = 0;
+    let mut flush = |rule_text: &mut String, rule_line, paths: &mut Vec<String>| {
+        if rule_text.trim().is_empty() {
+            return Ok(());
+        }
+        let rule =
+            rule_text.parse().with_context(|| format!("invalid rule on line {rule_line}"))?;
+        rules.push(FileRule { rule, paths: std::mem::take(paths) });
+        rule_text.clear();
+        anyhow::Ok(())
+    };
+    for (idx, line) in text.lines().enumerate() {
+        let line_number = idx + 1;
+        let trimmed = line.trim();
+        if trimmed.starts_with("//") {
+            continue;
+        }
+        if trimmed.is_empty() {
+            flush(&mut rule_text, rule_line, &mut paths)?;
+        } else if let Some(globs) = trimmed.strip_prefix("paths:") {
+            flush(&mut rule_text, rule_line, &mut paths)?;
+            if paths_line.is_some() && !paths.is_empty() {
+                anyhow::bail!("`paths:` on line {line_number} follows another `paths:` line");
+            }
+            paths = globs
+                .split(',')
+                .map(|it| it.trim().trim_start_matches("./").to_owned())
+                .filter(|it| !it.is_empty())
+                .collect();
+            if paths.is_empty() {
+                anyhow::bail!("`paths:` on line {line_number} doesn't contain any globs");
+            }
+            paths_line = Some(line_number);
+        } else {
+            if rule_text.is_empty() {
+                rule_line = line_number;
+            } else {
+                rule_text.push('\n');
+            }
+            rule_text.push_str(line);
+        }
+    }
+    flush(&mut rule_text, rule_line, &mut paths)?;
+    if !paths.is_empty() {
+        anyhow::bail!("`paths:` on line {} isn't followed by a rule", paths_line.unwrap_or(0));
+    }
+    Ok(rules)
+}
+
+/// Returns whether the `/`-separated `path` or one of its parent directories matches `glob`. `*`
+/// and `?` match any number of characters or a single character within a path component, `**`
+/// matches any number of path components.
+fn glob_matches(glob: &str, path: &str) -> bool {
+    let glob: Vec<_> = glob.trim_end_matches('/').split('/').collect();
+    let path: Vec<_> = path.split('/').collect();
+    (1..=path.len()).any(|len| components_match(&glob, &path[..len]))
+}
+
+fn components_match(glob: &[&str], path: &[&str]) -> bool {
+    match glob.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| components_match(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((component, path)) => {
+                component_matches(first.as_bytes(), component.as_bytes())
+                    && components_match(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+fn component_matches(glob: &[u8], component: &[u8]) -> bool {
+    match glob.split_first() {
+        None => component.is_empty(),
+        Some((b'*', rest)) => {
+            (0..=component.len()).any(|skip| component_matches(rest, &component[skip..]))
+        }
+        Some((&c, rest)) => match component.split_first() {
+            Some((&d, component)) => (c == b'?' || c == d) && component_matches(rest, component),
+            None => false,
+        },
+    }
+}
+
+/// Renders the changes that `edit` makes to `text` as a unified diff with three lines of context.
+fn unified_diff(path: &str, text: &str, edit: &TextEdit) -> String {
+    const CONTEXT: usize = 3;
+
+    let mut lines: Vec<&str> = text.split_inclusive('\n').collect();
+    if lines.is_empty() {
+        lines.push("");
+    }
+    let line_starts: Vec<usize> = lines
+        .iter()
+        .scan(0, |start, line| {
+            let it = *start;
+            *start += line.len();
+            Some(it)
+        })
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+
+    // Group the changes into runs of changed lines, as `(first line, last line, new text)`.
+    let mut groups: Vec<(usize, usize, Vec<&Indel>)> = Vec::new();
+    for indel in edit.iter() {
+        let start = usize::from(indel.delete.start());
+        let end = usize::from(indel.delete.end());
+        let (first, last) = (line_of(start), line_of(end.saturating_sub(1).max(start)));
+        match groups.last_mut() {
+            Some((_, prev_last, indels)) if first <= *prev_last => {
+                *prev_last = last.max(*prev_last);
+                indels.push(indel);
+            }
+            _ => groups.push((first, last, vec![indel])),
+        }
+    }
+    let changes: Vec<_> = groups
+        .into_iter()
+        .map(|(first, last, indels)| {
+            let mut new_text = String::new();
+            let mut offset = line_starts[first];
+            for indel in indels {
+                new_text.push_str(&text[offset..usize::from(indel.delete.start())]);
+                new_text.push_str(&indel.insert);
+                offset = usize::from(indel.delete.end());
+            }
+            new_text.push_str(&text[offset..line_starts[last] + lines[last].len()]);
+            (first, last, new_text)
+        })
+        .collect();
+
+    let mut res = format!("--- a/{path}\n+++ b/{path}\n");
+    let mut delta = 0isize;
+    let mut idx = 0;
+    while idx < changes.len() {
+        // Changes that are close to each other share a hunk.
+        let mut hunk_end = idx + 1;
+        while hunk_end < changes.len()
+            && changes[hunk_end].0 - changes[hunk_end - 1].1 <= 2 * CONTEXT + 1
+        {
+            hunk_end += 1;
+        }
+        let hunk = &changes[idx..hunk_end];
+        let start = hunk[0].0.saturating_sub(CONTEXT);
+        let end = (hunk[hunk.len() - 1].1 + CONTEXT).min(lines.len() - 1);
+        let mut body = String::new();
+        let (mut old_len, mut new_len) = (0, 0);
+        let mut line = start;
+        for (first, last, new_text) in hunk {
+            for context in &lines[line..*first] {
+                push_line(&mut body, ' ', context);
+            }
+            for old in &lines[*first..=*last] {
+                push_line(&mut body, '-', old);
+            }
+            for new in new_text.split_inclusive('\n') {
+                push_line(&mut body, '+', new);
+                new_len += 1;
+            }
+            old_len += last - first + 1;
+            line = last + 1;
+        }
+        for context in &lines[line..=end] {
+            push_line(&mut body, ' ', context);
+        }
+        let context_len = end - start + 1 - old_len;
+        let (old_len, new_len) = (old_len + context_len, new_len + context_len);
+        let old_start = start + 1;
+        let new_start = (old_start as isize + delta) as usize;
+        delta += new_len as isize - old_len as isize;
+        res.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_len),
+            hunk_range(new_start, new_len)
+        ));
+        res.push_str(&body);
+        idx = hunk_end;
+    }
+    res
+}
+
+fn push_line(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// Formats the range of a hunk, where an empty range refers to the line before it.
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start - 1),
+        1 => format!("{start}"),
+        _ => format!("{start},{len}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use ide_db::text_edit::TextEditBuilder;
+    use syntax::{TextRange, TextSize};
+
+    use super::*;
+
+    #[test]
+    fn rule_file() {
+        let rules = parse_rule_file(
+            r#"
+// Deprecated in 2.0.
+old::f($a) ==>> new::f($a)
+
+paths: src/legacy/**, tests/*.rs
+${v: Vec<_>}.len()
+    ==>> count(&$v)
+"#,
+        )
+        .unwrap();
+        let paths: Vec<_> = rules.iter().map(|it| &it.paths).collect();
+        assert_eq!(paths, [&vec![], &vec!["src/legacy/**".to_owned(), "tests/*.rs".to_owned()]]);
+
+        let error = parse_rule_file("foo($a) ==>> bar($b)").err().unwrap();
+        assert_eq!(
+            format!("{error:#}"),
+            "invalid rule on line 1: Parse error: Replacement contains undefined placeholders: $b"
+        );
+        let error = parse_rule_file("foo($a) ==>> bar($a)\n\npaths: src/**").err().unwrap();
+        assert_eq!(error.to_string(), "`paths:` on line 3 isn't followed by a rule");
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches("src/legacy", "src/legacy/mod.rs"));
+        assert!(glob_matches("src/legacy/**", "src/legacy/a/b.rs"));
+        assert!(glob_matches("src/**/*.rs", "src/lib.rs"));
+        assert!(glob_matches("tests/*_test.rs", "tests/foo_test.rs"));
+        assert!(glob_matches("crates/?/src", "crates/a/src/lib.rs"));
+        assert!(!glob_matches("tests/*.rs", "tests/data/foo.rs"));
+        assert!(!glob_matches("src/legacy", "src/legacy_mod.rs"));
+        assert!(!glob_matches("crates/?/src", "crates/ab/src/lib.rs"));
+    }
+
+    #[test]
+    fn diff() {
+        let text = "fn main() {\n    foo(1);\n    a();\n    b();\n    c();\n    d();\n    e();\n    f();\n    g();\n    h();\n    foo(2);\n}";
+        let mut builder = TextEditBuilder::default();
+        for (idx, _) in text.match_indices("foo") {
+            let start = TextSize::from(idx as u32);
+            builder.replace(TextRange::at(start, TextSize::of("foo")), "bar".to_owned());
+        }
+        builder.insert(TextSize::of(text), "\n".to_owned());
+        expect![[r#"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -1,5 +1,5 @@
+             fn main() {
+            -    foo(1);
+            +    bar(1);
+                 a();
+                 b();
+                 c();
+            @@ -8,5 +8,5 @@
+                 f();
+                 g();
+                 h();
+            -    foo(2);
+            +    bar(2);
+            -}
+            \ No newline at end of file
+            +}
+        "#]]
+        .assert_eq(&unified_diff("src/main.rs", text, &builder.finish()));
+    }
+}
@@ -3,7 +3,14 @@
 //! Of particular interest is the `feature_flags` hash map: while other fields
 //! configure the server itself, feature flags are passed into analysis, and
 //! tweak things like automatic insertion of `()` in completions.
-use std::{env, fmt, iter, ops::Not, sync::OnceLock};
+use std::{
+    env, fmt,
+    hash::{Hash, Hasher},
+    iter,
+    ops::Not,
+    sync::OnceLock,
+    time::Duration,
+};
 
 use cfg::{CfgAtom, CfgDiff};
 use hir::Symbol;
@@ -20,11 +27,12 @@ use ide_db::{
 };
 use itertools::Itertools;
 use paths::{Utf8Path, Utf8PathBuf};
+use proc_macro_api::ProcMacroServerOptions;
 use project_model::{
-    CargoConfig, CargoFeatures, ProjectJson, ProjectJsonData, ProjectJsonFromCommand,
+    CargoConfig, CargoFeatures, CfgVariant, ProjectJson, ProjectJsonData, ProjectJsonFromCommand,
     ProjectManifest, RustLibSource,
 };
-use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 use semver::Version;
 use serde::{
     de::{DeserializeOwned, Error},
@@ -79,6 +87,10 @@ config_data! {
         cachePriming_enable: bool = true,
         /// How many worker threads to handle priming caches. The default `0` means to pick automatically.
         cachePriming_numThreads: NumThreads = NumThreads::Physical,
+        /// Store the item trees, def maps and symbol indices of library crates on disk after priming
+        /// caches and reuse them on the next start, so that the dependencies don't have to be
+        /// analyzed again.
+        cachePriming_persist: bool = false,
 
         /// Custom completion snippets.
         completion_snippets_custom: FxHashMap<String, SnippetDef> = Config::completion_snippets_default(),
@@ -213,7 +225,9 @@ config_data! {
         /// Whether to hide inlay type hints for constructors.
         inlayHints_typeHints_hideNamedConstructor: bool            = false,
 
-        /// Enables the experimental support for interpreting tests.
+        /// Enables the experimental support for interpreting tests. Tests run from the test
+        /// explorer are then interpreted first, and only the ones that can't be interpreted are
+        /// run with cargo.
         interpret_tests: bool = false,
 
         /// Join lines merges consecutive declaration and initialization of an assignment.
@@ -275,8 +289,23 @@ config_data! {
 
         /// Expand attribute macros. Requires `#rust-analyzer.procMacro.enable#` to be set.
         procMacro_attributes_enable: bool = true,
+        /// Let the proc-macro server reuse the expansion of an input it has already expanded.
+        /// Macros that read files, the network or anything else besides their input and
+        /// environment (e.g. `sqlx` or `askama`) may produce stale expansions with this.
+        procMacro_cacheExpansions: bool            = false,
         /// Enable support for procedural macros, implies `#rust-analyzer.cargo.buildScripts.enable#`.
         procMacro_enable: bool                     = true,
+        /// Abort a single proc-macro expansion after this many seconds, restarting the
+        /// proc-macro server that ran it. Without `#rust-analyzer.procMacro.processPerDylib#`
+        /// this also restarts the server of every other proc-macro dylib. `null` disables the
+        /// timeout.
+        procMacro_expansionTimeout: Option<u16>     = Some(5),
+        /// Maximum amount of memory in MiB each proc-macro server process may use. Only
+        /// supported on Unix.
+        procMacro_memoryLimit: Option<usize>       = None,
+        /// Load each proc-macro dylib into its own proc-macro server process, so that a crashing
+        /// or hanging dylib does not take down the proc-macros of the others.
+        procMacro_processPerDylib: bool            = true,
         /// Internal config, path to proc-macro server executable.
         procMacro_server: Option<Utf8PathBuf>          = None,
 
@@ -586,6 +615,15 @@ config_data! {
         /// Use `RUSTC_WRAPPER=rust-analyzer` when running build scripts to
         /// avoid checking unnecessary things.
         cargo_buildScripts_useRustcWrapper: bool = true,
+        /// Alternate configurations to additionally analyze workspace members under, so that
+        /// diagnostics and references in code that is cfg'd out in the active configuration are
+        /// reported as well.
+        ///
+        /// Each entry may set a `target` tuple, the `features` to enable instead of the active
+        /// ones and additional `cfgs`, for example
+        /// `{ "target": "x86_64-pc-windows-msvc", "features": ["tokio"], "cfgs": ["loom"] }`.
+        /// Every entry adds a copy of each workspace member to the analysis.
+        cargo_cfgVariants: Vec<CfgVariantDef> = vec![],
         /// List of cfg options to enable with the given values.
         cargo_cfgs: Vec<String> = {
             vec!["debug_assertions".into(), "miri".into()]
@@ -1571,6 +1609,10 @@ impl Config {
             term_search_borrowck: self.assist_termSearch_borrowcheck(source_root).to_owned(),
         }
     }
+    pub fn test_explorer_interpret(&self) -> bool {
+        self.interpret_tests().to_owned()
+    }
+
     pub fn expand_proc_attr_macros(&self) -> bool {
         self.procMacro_enable().to_owned() && self.procMacro_attributes_enable().to_owned()
     }
@@ -1849,6 +1891,17 @@ impl Config {
         self.cachePriming_enable().to_owned()
     }
 
+    /// The file the caches of the workspace's libraries are persisted in, if enabled.
+    pub fn persisted_caches_path(&self) -> Option<AbsPathBuf> {
+        if !self.cachePriming_persist() {
+            return None;
+        }
+        let mut hasher = FxHasher::default();
+        (crate::version::version().to_string(), self.root_path()).hash(&mut hasher);
+        let path = dirs::cache_dir()?.join("rust-analyzer").join("caches");
+        Some(AbsPathBuf::assert_utf8(path.join(format!("{:016x}", hasher.finish()))))
+    }
+
     pub fn publish_diagnostics(&self, source_root: Option<SourceRootId>) -> bool {
         self.diagnostics_enable(source_root).to_owned()
     }
@@ -1906,6 +1959,17 @@ impl Config {
         self.procMacro_enable().to_owned()
     }
 
+    pub fn proc_macro_server_options(&self) -> ProcMacroServerOptions {
+        ProcMacroServerOptions {
+            expansion_timeout: self
+                .procMacro_expansionTimeout()
+                .map(|secs| Duration::from_secs(secs.into())),
+            memory_limit: self.procMacro_memoryLimit().map(|mib| mib as u64 * 1024 * 1024),
+            process_per_dylib: *self.procMacro_processPerDylib(),
+            cache_expansions: *self.procMacro_cacheExpansions(),
+        }
+    }
+
     pub fn files(&self) -> FilesConfig {
         FilesConfig {
             watcher: match self.files_watcher() {
@@ -1976,29 +2040,18 @@ impl Config {
             rustc_source,
             extra_includes,
             cfg_overrides: project_model::CfgOverrides {
-                global: CfgDiff::new(
-                    self.cargo_cfgs(source_root)
-                        .iter()
-                        // parse any cfg setting formatted as key=value or just key (without value)
-                        .filter_map(|s| {
-                            let mut sp = s.splitn(2, "=");
-                            let key = sp.next();
-                            let val = sp.next();
-                            key.map(|key| (key, val))
-                        })
-                        .map(|(key, val)| match val {
-                            Some(val) => CfgAtom::KeyValue {
-                                key: Symbol::intern(key),
-                                value: Symbol::intern(val),
-                            },
-                            None => CfgAtom::Flag(Symbol::intern(key)),
-                        })
-                        .collect(),
-                    vec![],
-                )
-                .unwrap(),
+                global: CfgDiff::new(parse_cfgs(self.cargo_cfgs(source_root)), vec![]).unwrap(),
                 selective: Default::default(),
             },
+            cfg_variants: self
+                .cargo_cfgVariants(source_root)
+                .iter()
+                .map(|variant| CfgVariant {
+                    target: variant.target.clone(),
+                    features: variant.features.clone(),
+                    cfgs: parse_cfgs(&variant.cfgs),
+                })
+                .collect(),
             wrap_rustc_in_build_scripts: *self.cargo_buildScripts_useRustcWrapper(source_root),
             invocation_strategy: match self.cargo_buildScripts_invocationStrategy(source_root) {
                 InvocationStrategy::Once => project_model::InvocationStrategy::Once,
@@ -2471,6 +2524,18 @@ enum ManifestOrProjectJson {
     },
 }
 
+/// Parses cfg settings formatted as `key=value` or just `key` (without value).
+pub(crate) fn parse_cfgs(cfgs: &[String]) -> Vec<CfgAtom> {
+    cfgs.iter()
+        .map(|s| match s.split_once('=') {
+            Some((key, val)) => {
+                CfgAtom::KeyValue { key: Symbol::intern(key), value: Symbol::intern(val) }
+            }
+            None => CfgAtom::Flag(Symbol::intern(s)),
+        })
+        .collect()
+}
+
 fn deserialize_abs_pathbuf<'de, D>(de: D) -> std::result::Result<AbsPathBuf, D::Error>
 where
     D: serde::de::Deserializer<'de>,
@@ -2529,6 +2594,17 @@ pub(crate) enum CallableCompletionDef {
     None,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CfgVariantDef {
+    #[serde(default)]
+    target: Option<String>,
+    #[serde(default)]
+    features: Option<Vec<String>>,
+    #[serde(default)]
+    cfgs: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 enum CargoFeaturesDef {
@@ -3553,6 +3629,25 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
                 }
             ]
         },
+        "Vec<CfgVariantDef>" => set! {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "target": {
+                        "type": "string"
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" }
+                    },
+                    "cfgs": {
+                        "type": "array",
+                        "items": { "type": "string" }
+                    },
+                }
+            }
+        },
         "Option<MaxSubstitutionLength>" => set! {
             "anyOf": [
                 {
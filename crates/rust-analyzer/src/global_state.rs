@@ -18,7 +18,9 @@ use parking_lot::{
     RwLockWriteGuard,
 };
 use proc_macro_api::ProcMacroClient;
-use project_model::{ManifestPath, ProjectWorkspace, ProjectWorkspaceKind, WorkspaceBuildScripts};
+use project_model::{
+    ManifestPath, ProjectWorkspace, ProjectWorkspaceKind, ResolvedCfgVariant, WorkspaceBuildScripts,
+};
 use rustc_hash::{FxHashMap, FxHashSet};
 use tracing::{span, trace, Level};
 use triomphe::Arc;
@@ -109,6 +111,9 @@ pub(crate) struct GlobalState {
     pub(crate) test_run_sender: Sender<CargoTestMessage>,
     pub(crate) test_run_receiver: Receiver<CargoTestMessage>,
     pub(crate) test_run_remaining_jobs: usize,
+    /// Identifies the latest test run, so that results of interpreted tests from earlier runs can
+    /// be told apart.
+    pub(crate) test_run_generation: usize,
 
     // Project loading
     pub(crate) discover_handle: Option<discover::DiscoverHandle>,
@@ -154,6 +159,10 @@ pub(crate) struct GlobalState {
     pub(crate) workspaces: Arc<Vec<ProjectWorkspace>>,
     pub(crate) crate_graph_file_dependencies: FxHashSet<vfs::VfsPath>,
     pub(crate) detached_files: FxHashSet<ManifestPath>,
+    /// Cfg overrides set via `rust-analyzer/setActiveCfg`, keyed by crate root.
+    pub(crate) active_cfgs: FxHashMap<AbsPathBuf, ResolvedCfgVariant>,
+    /// Whether `active_cfgs` changed the crate graph since the last turn of the main loop.
+    pub(crate) active_cfgs_changed: bool,
 
     // op queues
     pub(crate) fetch_workspaces_queue: OpQueue<FetchWorkspaceRequest, FetchWorkspaceResponse>,
@@ -161,6 +170,10 @@ pub(crate) struct GlobalState {
     pub(crate) fetch_proc_macros_queue: OpQueue<Vec<ProcMacroPaths>, bool>,
     pub(crate) prime_caches_queue: OpQueue,
     pub(crate) discover_workspace_queue: OpQueue,
+    /// Whether the caches of the libraries have been persisted in this session.
+    pub(crate) persisted_caches_stored: bool,
+    /// Bumped whenever library files change, to discard keys computed before the change.
+    pub(crate) persist_keys_generation: usize,
 
     /// A deferred task queue.
     ///
@@ -221,6 +234,12 @@ impl GlobalState {
         if let Some(capacities) = config.lru_query_capacities_config() {
             analysis_host.update_lru_capacities(capacities);
         }
+        if let Some(path) = config.persisted_caches_path() {
+            match load_cargo::load_persisted_caches(analysis_host.raw_database_mut(), &path) {
+                Ok(len) => tracing::info!("loaded {len} persisted cache entries from {path}"),
+                Err(e) => tracing::warn!("failed to load persisted caches from {path}: {e}"),
+            }
+        }
         let (flycheck_sender, flycheck_receiver) = unbounded();
         let (test_run_sender, test_run_receiver) = unbounded();
 
@@ -260,6 +279,7 @@ impl GlobalState {
             test_run_sender,
             test_run_receiver,
             test_run_remaining_jobs: 0,
+            test_run_generation: 0,
 
             discover_handle: None,
             discover_sender,
@@ -275,12 +295,16 @@ impl GlobalState {
             workspaces: Arc::from(Vec::new()),
             crate_graph_file_dependencies: FxHashSet::default(),
             detached_files: FxHashSet::default(),
+            active_cfgs: FxHashMap::default(),
+            active_cfgs_changed: false,
             fetch_workspaces_queue: OpQueue::default(),
             fetch_build_data_queue: OpQueue::default(),
             fetch_proc_macros_queue: OpQueue::default(),
 
             prime_caches_queue: OpQueue::default(),
             discover_workspace_queue: OpQueue::default(),
+            persisted_caches_stored: false,
+            persist_keys_generation: 0,
 
             deferred_task_queue: task_queue,
         };
@@ -299,7 +323,7 @@ impl GlobalState {
         let mut modified_ratoml_files: FxHashMap<FileId, (ChangeKind, vfs::VfsPath)> =
             FxHashMap::default();
 
-        let (change, modified_rust_files, workspace_structure_change) = {
+        let (change, changed_files, modified_rust_files, workspace_structure_change) = {
             let mut change = ChangeWithProcMacros::new();
             let mut guard = self.vfs.write();
             let changed_files = guard.0.take_changes();
@@ -372,6 +396,7 @@ impl GlobalState {
                 // this allows delaying the re-acquisition of the write lock
                 bytes.push((file.file_id, text));
             }
+            let changed_files = bytes.iter().map(|&(file_id, _)| file_id).collect::<Vec<_>>();
             let (vfs, line_endings_map) = &mut *RwLockUpgradableReadGuard::upgrade(guard);
             bytes.into_iter().for_each(|(file_id, text)| {
                 let text = match text {
@@ -387,11 +412,23 @@ impl GlobalState {
                 let roots = self.source_root_config.partition(vfs);
                 change.set_roots(roots);
             }
-            (change, modified_rust_files, workspace_structure_change)
+            (change, changed_files, modified_rust_files, workspace_structure_change)
         };
 
         let _p = span!(Level::INFO, "GlobalState::process_changes/apply_change").entered();
         self.analysis_host.apply_change(change);
+
+        // The keys of persisted caches fingerprint the contents of the libraries.
+        let db = self.analysis_host.raw_database();
+        if changed_files
+            .iter()
+            .any(|&file_id| db.source_root(db.file_source_root(file_id)).is_library)
+        {
+            self.persist_keys_generation += 1;
+            if db.persist_keys_are_current() {
+                self.analysis_host.raw_database_mut().bind_persist_keys(Default::default());
+            }
+        }
         if !modified_ratoml_files.is_empty()
             || !self.config.same_source_root_parent_map(&self.local_roots_parent_map)
         {
@@ -8,10 +8,11 @@ use anyhow::Context;
 use base64::{prelude::BASE64_STANDARD, Engine};
 use ide::{
     AnnotationConfig, AssistKind, AssistResolveStrategy, Cancellable, CompletionFieldsToResolve,
-    FilePosition, FileRange, HoverAction, HoverGotoTypeData, InlayFieldsToResolve, Query,
-    RangeInfo, ReferenceCategory, Runnable, RunnableKind, SingleResolve, SourceChange, TextEdit,
+    FilePosition, FileRange, HoverAction, HoverGotoTypeData, InlayFieldsToResolve, MonikerResult,
+    Query, RangeInfo, ReferenceCategory, Runnable, RunnableKind, SingleResolve, SourceChange,
+    TestEvalOutcome, TextEdit,
 };
-use ide_db::{FxHashMap, SymbolKind};
+use ide_db::{FxHashMap, FxHashSet, SymbolKind};
 use itertools::Itertools;
 use lsp_server::ErrorCode;
 use lsp_types::{
@@ -21,10 +22,15 @@ use lsp_types::{
     InlayHintParams, Location, LocationLink, Position, PrepareRenameResponse, Range, RenameParams,
     ResourceOp, ResourceOperationKind, SemanticTokensDeltaParams, SemanticTokensFullDeltaResult,
     SemanticTokensParams, SemanticTokensRangeParams, SemanticTokensRangeResult,
-    SemanticTokensResult, SymbolInformation, SymbolTag, TextDocumentIdentifier, Url, WorkspaceEdit,
+    SemanticTokensResult, SymbolInformation, SymbolTag, TextDocumentIdentifier, TypeHierarchyItem,
+    TypeHierarchyPrepareParams, TypeHierarchySubtypesParams, TypeHierarchySupertypesParams, Url,
+    WorkspaceEdit,
 };
 use paths::Utf8PathBuf;
-use project_model::{CargoWorkspace, ManifestPath, ProjectWorkspaceKind, TargetKind};
+use project_model::{
+    CargoWorkspace, CfgVariant, ManifestPath, ProjectWorkspace, ProjectWorkspaceKind,
+    ResolvedCfgVariant, TargetKind,
+};
 use serde_json::json;
 use stdx::{format_to, never};
 use syntax::{TextRange, TextSize};
@@ -33,7 +39,7 @@ use vfs::{AbsPath, AbsPathBuf, FileId, VfsPath};
 
 use crate::{
     completion_item_hash,
-    config::{Config, RustfmtConfig, WorkspaceSymbolConfig},
+    config::{self, Config, RustfmtConfig, WorkspaceSymbolConfig},
     diagnostics::convert_diagnostic,
     global_state::{FetchWorkspaceRequest, GlobalState, GlobalStateSnapshot},
     hack_recover_crate_name,
@@ -51,6 +57,7 @@ use crate::{
         self, CrateInfoResult, ExternalDocsPair, ExternalDocsResponse, FetchDependencyListParams,
         FetchDependencyListResult, PositionOrRange, ViewCrateGraphParams, WorkspaceSymbolParams,
     },
+    main_loop::{InterpretedTestsProgress, Task},
     target_spec::{CargoTargetSpec, TargetSpec},
     test_runner::{CargoTestHandle, TestTarget},
     try_default,
@@ -73,6 +80,86 @@ pub(crate) fn handle_proc_macros_rebuild(state: &mut GlobalState, _: ()) -> anyh
     Ok(())
 }
 
+pub(crate) fn handle_set_active_cfg(
+    state: &mut GlobalState,
+    params: lsp_ext::SetActiveCfgParams,
+) -> anyhow::Result<()> {
+    let _p = tracing::info_span!("handle_set_active_cfg").entered();
+
+    let crate_roots = {
+        let snap = state.snapshot();
+        let file_id = try_default!(from_proto::file_id(&snap, &params.text_document.uri)?);
+        snap.analysis
+            .crates_for(file_id)?
+            .into_iter()
+            .map(|krate| snap.analysis.crate_root(krate))
+            .collect::<Cancellable<Vec<_>>>()?
+            .into_iter()
+            .filter_map(|file_id| {
+                snap.file_id_to_file_path(file_id).as_path().map(AbsPath::to_path_buf)
+            })
+            .unique()
+            .collect::<Vec<_>>()
+    };
+
+    let variant = CfgVariant {
+        features: params.features,
+        target: params.target,
+        cfgs: config::parse_cfgs(&params.cfgs),
+    };
+    let unresolved = ResolvedCfgVariant { variant: variant.clone(), rustc_cfg: None };
+    // The crate roots, grouped by the index of the workspace they belong to.
+    let mut roots_by_ws = Vec::new();
+    let mut seen = FxHashSet::default();
+    for (idx, ws) in state.workspaces.iter().enumerate() {
+        let roots = crate_roots
+            .iter()
+            .filter(|&root| {
+                !seen.contains(root) && ws.crate_cfg_options(root, &unresolved).is_some()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if !roots.is_empty() {
+            seen.extend(roots.iter().cloned());
+            roots_by_ws.push((idx, roots));
+        }
+    }
+    if roots_by_ws.is_empty() {
+        return Err(invalid_params_error(
+            "document does not belong to a member of a cargo workspace".to_owned(),
+        )
+        .into());
+    }
+
+    let workspaces = state.workspaces.clone();
+    let extra_env = state.config.extra_env(None).clone();
+    if variant.target.is_none() {
+        // There is nothing to query without a target.
+        let cfgs = resolve_active_cfgs(&workspaces, roots_by_ws, variant, &extra_env);
+        state.set_active_cfgs(cfgs);
+        return Ok(());
+    }
+    // Querying the cfgs of the target runs rustc, which mustn't block the main loop.
+    state.task_pool.handle.spawn(stdx::thread::ThreadIntent::Worker, move || {
+        Task::ActiveCfgsResolved(resolve_active_cfgs(&workspaces, roots_by_ws, variant, &extra_env))
+    });
+    Ok(())
+}
+
+fn resolve_active_cfgs(
+    workspaces: &[ProjectWorkspace],
+    roots_by_ws: Vec<(usize, Vec<AbsPathBuf>)>,
+    variant: CfgVariant,
+    extra_env: &FxHashMap<String, String>,
+) -> FxHashMap<AbsPathBuf, ResolvedCfgVariant> {
+    let mut cfgs = FxHashMap::default();
+    for (idx, roots) in roots_by_ws {
+        let resolved = workspaces[idx].resolve_cfg_variant(variant.clone(), extra_env);
+        cfgs.extend(roots.into_iter().map(|root| (root, resolved.clone())));
+    }
+    cfgs
+}
+
 pub(crate) fn handle_analyzer_status(
     snap: GlobalStateSnapshot,
     params: lsp_ext::AnalyzerStatusParams,
@@ -217,10 +304,81 @@ pub(crate) fn handle_run_test(
     if let Some(_session) = state.test_run_session.take() {
         state.send_notification::<lsp_ext::EndRunTest>(());
     }
+    state.test_run_generation += 1;
+    if state.config.test_explorer_interpret() {
+        interpret_tests(state, params);
+        return Ok(());
+    }
+    run_tests_with_cargo(state, params.include)
+}
+
+/// Runs the tests in the interpreter on a worker thread, reporting the ones that can't be
+/// interpreted back to the main loop to be run by cargo instead.
+fn interpret_tests(state: &mut GlobalState, params: lsp_ext::RunTestParams) {
+    let analysis = state.snapshot().analysis;
+    let generation = state.test_run_generation;
+    state.test_run_session = Some(Vec::new());
+    state.task_pool.handle.spawn_with_sender(stdx::thread::ThreadIntent::Worker, move |sender| {
+        let send = |progress| {
+            sender.send(Task::InterpretedTests { generation, progress }).unwrap();
+        };
+        let is_excluded = |test_id: &str| {
+            params.exclude.iter().flatten().any(|it| {
+                test_id
+                    .strip_prefix(it.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+        };
+        let Ok(test_ids) = analysis.find_test_ids(params.include.as_deref()) else {
+            send(InterpretedTestsProgress::Cancelled);
+            return;
+        };
+        let mut fallback = Vec::new();
+        for test_id in test_ids {
+            if is_excluded(&test_id) {
+                continue;
+            }
+            send(InterpretedTestsProgress::Test {
+                test_id: test_id.clone(),
+                state: lsp_ext::TestState::Started,
+            });
+            let state = match analysis.interpret_test(&test_id) {
+                Ok(Some(TestEvalOutcome::Passed)) => lsp_ext::TestState::Passed,
+                Ok(Some(TestEvalOutcome::Failed { message })) => {
+                    lsp_ext::TestState::Failed { message }
+                }
+                Ok(Some(TestEvalOutcome::Ignored)) => lsp_ext::TestState::Skipped,
+                Ok(Some(TestEvalOutcome::Unsupported { reason })) => {
+                    send(InterpretedTestsProgress::Output(format!(
+                        "{test_id} can't be interpreted, running it with cargo:\n{reason}\n"
+                    )));
+                    fallback.push(test_id);
+                    continue;
+                }
+                Ok(None) => {
+                    fallback.push(test_id);
+                    continue;
+                }
+                Err(_) => {
+                    send(InterpretedTestsProgress::Cancelled);
+                    return;
+                }
+            };
+            send(InterpretedTestsProgress::Test { test_id, state });
+        }
+        send(InterpretedTestsProgress::Finished { fallback });
+    });
+}
+
+/// Runs the tests with `cargo test`, `None` meaning all tests of all workspaces.
+pub(crate) fn run_tests_with_cargo(
+    state: &mut GlobalState,
+    include: Option<Vec<String>>,
+) -> anyhow::Result<()> {
     // We detect the lowest common ancestor of all included tests, and
     // run it. We ignore excluded tests for now, the client will handle
     // it for us.
-    let lca = match params.include {
+    let lca = match include {
         Some(tests) => tests
             .into_iter()
             .reduce(|x, y| {
@@ -257,7 +415,8 @@ pub(crate) fn handle_run_test(
             };
 
             let handle = CargoTestHandle::new(
-                test_path,
+                test_path.as_slice(),
+                false,
                 state.config.cargo_test_options(None),
                 cargo.workspace_root(),
                 test_target,
@@ -266,6 +425,52 @@ pub(crate) fn handle_run_test(
             handles.push(handle);
         }
     }
+    if handles.is_empty() {
+        state.send_notification::<lsp_ext::EndRunTest>(());
+        return Ok(());
+    }
+    // Each process send finished signal twice, once for stdout and once for stderr
+    state.test_run_remaining_jobs = 2 * handles.len();
+    state.test_run_session = Some(handles);
+    Ok(())
+}
+
+/// Runs exactly the tests with the given ids with `cargo test`, one process per package.
+pub(crate) fn run_exact_tests_with_cargo(
+    state: &mut GlobalState,
+    test_ids: Vec<String>,
+) -> anyhow::Result<()> {
+    let mut tests_by_crate: FxHashMap<&str, Vec<&str>> = FxHashMap::default();
+    for test_id in &test_ids {
+        if let Some((namespace_root, path)) = test_id.split_once("::") {
+            tests_by_crate.entry(namespace_root).or_default().push(path);
+        }
+    }
+    let mut handles = vec![];
+    for (namespace_root, paths) in tests_by_crate {
+        let package = state.workspaces.iter().find_map(|ws| match &ws.kind {
+            ProjectWorkspaceKind::Cargo { cargo, .. } => {
+                Some((cargo, find_package_name(namespace_root, cargo)?))
+            }
+            _ => None,
+        });
+        let Some((cargo, package_name)) = package else {
+            continue;
+        };
+        let handle = CargoTestHandle::new(
+            &paths,
+            true,
+            state.config.cargo_test_options(None),
+            cargo.workspace_root(),
+            TestTarget::Package(package_name),
+            state.test_run_sender.clone(),
+        )?;
+        handles.push(handle);
+    }
+    if handles.is_empty() {
+        state.send_notification::<lsp_ext::EndRunTest>(());
+        return Ok(());
+    }
     // Each process send finished signal twice, once for stdout and once for stderr
     state.test_run_remaining_jobs = 2 * handles.len();
     state.test_run_session = Some(handles);
@@ -325,6 +530,42 @@ pub(crate) fn handle_expand_macro(
     Ok(res.map(|it| lsp_ext::ExpandedMacro { name: it.name, expansion: it.expansion }))
 }
 
+pub(crate) fn handle_expand_macro_step(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::ExpandMacroParams,
+) -> anyhow::Result<Option<lsp_ext::ExpandedMacroSteps>> {
+    let _p = tracing::info_span!("handle_expand_macro_step").entered();
+    let file_id = try_default!(from_proto::file_id(&snap, &params.text_document.uri)?);
+    let line_index = snap.file_line_index(file_id)?;
+    let offset = from_proto::offset(&line_index, params.position)?;
+
+    let res = snap.analysis.expand_macro_steps(FilePosition { file_id, offset })?;
+    Ok(res.map(|it| lsp_ext::ExpandedMacroSteps {
+        name: it.name,
+        steps: it
+            .steps
+            .into_iter()
+            .map(|step| lsp_ext::MacroExpansionStep {
+                expansion: step.expansion,
+                calls: step
+                    .calls
+                    .into_iter()
+                    .map(|call| lsp_ext::ExpandedMacroCall {
+                        name: call.name,
+                        matched_arm: call.matched_arm,
+                        arms: call
+                            .arms
+                            .into_iter()
+                            .map(|arm| lsp_ext::MacroArm { pattern: arm.pattern, error: arm.error })
+                            .collect(),
+                        error: call.error,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }))
+}
+
 pub(crate) fn handle_selection_range(
     snap: GlobalStateSnapshot,
     params: lsp_types::SelectionRangeParams,
@@ -1209,6 +1450,75 @@ pub(crate) fn handle_folding_range(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_document_link(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentLinkParams,
+) -> anyhow::Result<Option<Vec<lsp_types::DocumentLink>>> {
+    let _p = tracing::info_span!("handle_document_link").entered();
+    let file_id = try_default!(from_proto::file_id(&snap, &params.text_document.uri)?);
+    let line_index = snap.file_line_index(file_id)?;
+    let res = snap
+        .analysis
+        .document_links(file_id)?
+        .into_iter()
+        .map(|it| to_proto::document_link(&snap, &line_index, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_inline_value(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::InlineValueParams,
+) -> anyhow::Result<Option<Vec<lsp_types::InlineValue>>> {
+    let _p = tracing::info_span!("handle_inline_value").entered();
+    let frange = try_default!(from_proto::file_range(&snap, &params.text_document, params.range)?);
+    let line_index = snap.file_line_index(frange.file_id)?;
+    let stopped_at = from_proto::offset(&line_index, params.context.stopped_location.start)?;
+    let res = snap
+        .analysis
+        .inline_values(frange, stopped_at)?
+        .into_iter()
+        .map(|it| to_proto::inline_value(&line_index, it))
+        .collect();
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_linked_editing_range(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::LinkedEditingRangeParams,
+) -> anyhow::Result<Option<lsp_types::LinkedEditingRanges>> {
+    let _p = tracing::info_span!("handle_linked_editing_range").entered();
+    let position =
+        try_default!(from_proto::file_position(&snap, params.text_document_position_params)?);
+    let Some(ranges) = snap.analysis.linked_editing_ranges(position)? else {
+        return Ok(None);
+    };
+    let line_index = snap.file_line_index(position.file_id)?;
+    let ranges = ranges.into_iter().map(|it| to_proto::range(&line_index, it)).collect();
+    Ok(Some(lsp_types::LinkedEditingRanges { ranges, word_pattern: None }))
+}
+
+pub(crate) fn handle_moniker(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::MonikerParams,
+) -> anyhow::Result<Option<Vec<lsp_types::Moniker>>> {
+    let _p = tracing::info_span!("handle_moniker").entered();
+    let position =
+        try_default!(from_proto::file_position(&snap, params.text_document_position_params)?);
+    let Some(RangeInfo { info: monikers, .. }) = snap.analysis.moniker(position)? else {
+        return Ok(None);
+    };
+    // Locals have no identifier that is stable across documents.
+    let res = monikers
+        .iter()
+        .filter_map(|it| match it {
+            MonikerResult::Moniker(moniker) => Some(to_proto::moniker(moniker)),
+            MonikerResult::Local { .. } => None,
+        })
+        .collect();
+    Ok(Some(res))
+}
+
 pub(crate) fn handle_signature_help(
     snap: GlobalStateSnapshot,
     params: lsp_types::SignatureHelpParams,
@@ -1852,6 +2162,76 @@ pub(crate) fn handle_call_hierarchy_outgoing(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_type_hierarchy_prepare(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchyPrepareParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = tracing::info_span!("handle_type_hierarchy_prepare").entered();
+    let position =
+        try_default!(from_proto::file_position(&snap, params.text_document_position_params)?);
+
+    let nav_info = match snap.analysis.type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let RangeInfo { range: _, info: navs } = nav_info;
+    let res = navs
+        .into_iter()
+        .filter_map(|it| to_proto::type_hierarchy_item(&snap, it).transpose())
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_supertypes(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchySupertypesParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = tracing::info_span!("handle_type_hierarchy_supertypes").entered();
+    let item = params.item;
+
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = try_default!(from_proto::file_range(&snap, &doc, item.selection_range)?);
+    let fpos = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
+
+    let navs = match snap.analysis.supertypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let res = navs
+        .into_iter()
+        .filter_map(|it| to_proto::type_hierarchy_item(&snap, it).transpose())
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_subtypes(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchySubtypesParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = tracing::info_span!("handle_type_hierarchy_subtypes").entered();
+    let item = params.item;
+
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = try_default!(from_proto::file_range(&snap, &doc, item.selection_range)?);
+    let fpos = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
+
+    let navs = match snap.analysis.subtypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let res = navs
+        .into_iter()
+        .filter_map(|it| to_proto::type_hierarchy_item(&snap, it).transpose())
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
 pub(crate) fn handle_semantic_tokens_full(
     snap: GlobalStateSnapshot,
     params: SemanticTokensParams,
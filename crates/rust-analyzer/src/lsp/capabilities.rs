@@ -4,15 +4,16 @@ use ide_db::{line_index::WideEncoding, FxHashSet};
 use lsp_types::{
     CallHierarchyServerCapability, CodeActionKind, CodeActionOptions, CodeActionProviderCapability,
     CodeLensOptions, CompletionOptions, CompletionOptionsCompletionItem, DeclarationCapability,
-    DocumentOnTypeFormattingOptions, FileOperationFilter, FileOperationPattern,
-    FileOperationPatternKind, FileOperationRegistrationOptions, FoldingRangeProviderCapability,
-    HoverProviderCapability, ImplementationProviderCapability, InlayHintOptions,
-    InlayHintServerCapabilities, OneOf, PositionEncodingKind, RenameOptions, SaveOptions,
-    SelectionRangeProviderCapability, SemanticTokensFullOptions, SemanticTokensLegend,
-    SemanticTokensOptions, ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextDocumentSyncOptions, TypeDefinitionProviderCapability,
-    WorkDoneProgressOptions, WorkspaceFileOperationsServerCapabilities,
-    WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
+    DocumentLinkOptions, DocumentOnTypeFormattingOptions, FileOperationFilter,
+    FileOperationPattern, FileOperationPatternKind, FileOperationRegistrationOptions,
+    FoldingRangeProviderCapability, HoverProviderCapability, ImplementationProviderCapability,
+    InlayHintOptions, InlayHintServerCapabilities, LinkedEditingRangeServerCapabilities, OneOf,
+    PositionEncodingKind, RenameOptions, SaveOptions, SelectionRangeProviderCapability,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions, ServerCapabilities,
+    SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions, TypeDefinitionProviderCapability, WorkDoneProgressOptions,
+    WorkspaceFileOperationsServerCapabilities, WorkspaceFoldersServerCapabilities,
+    WorkspaceServerCapabilities,
 };
 use serde_json::json;
 
@@ -22,8 +23,9 @@ use crate::{
     lsp::{ext, semantic_tokens},
 };
 
-pub fn server_capabilities(config: &Config) -> ServerCapabilities {
-    ServerCapabilities {
+/// Returns JSON, as `lsp_types::ServerCapabilities` has no field for some of the capabilities.
+pub fn server_capabilities(config: &Config) -> serde_json::Value {
+    let capabilities = ServerCapabilities {
         position_encoding: match config.caps().negotiated_encoding() {
             PositionEncoding::Utf8 => Some(PositionEncodingKind::UTF8),
             PositionEncoding::Wide(wide) => match wide {
@@ -89,8 +91,11 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             prepare_provider: Some(true),
             work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
         })),
-        linked_editing_range_provider: None,
-        document_link_provider: None,
+        linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(true)),
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: Some(false),
+            work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+        }),
         color_provider: None,
         execute_command_provider: None,
         workspace: Some(WorkspaceServerCapabilities {
@@ -140,14 +145,14 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             }
             .into(),
         ),
-        moniker_provider: None,
+        moniker_provider: Some(OneOf::Left(true)),
         inlay_hint_provider: Some(OneOf::Right(InlayHintServerCapabilities::Options(
             InlayHintOptions {
                 work_done_progress_options: Default::default(),
                 resolve_provider: Some(config.caps().inlay_hints_resolve_provider()),
             },
         ))),
-        inline_value_provider: None,
+        inline_value_provider: Some(OneOf::Left(true)),
         experimental: Some(json!({
             "externalDocs": true,
             "hoverRange": true,
@@ -173,7 +178,14 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             },
         )),
         inline_completion_provider: None,
-    }
+    };
+    let mut capabilities = serde_json::to_value(capabilities).unwrap();
+    // FIXME: `lsp_types::ServerCapabilities` has no `type_hierarchy_provider` field yet, put it
+    // next to `call_hierarchy_provider` once it has.
+    if config.caps().type_hierarchy() {
+        capabilities["typeHierarchyProvider"] = json!(true);
+    }
+    capabilities
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -406,6 +418,10 @@ impl ClientCapabilities {
         .unwrap_or_default()
     }
 
+    pub fn type_hierarchy(&self) -> bool {
+        (|| -> _ { self.0.text_document.as_ref()?.type_hierarchy.as_ref() })().is_some()
+    }
+
     pub fn text_document_diagnostic(&self) -> bool {
         (|| -> _ { self.0.text_document.as_ref()?.diagnostic.as_ref() })().is_some()
     }
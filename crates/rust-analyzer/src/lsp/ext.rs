@@ -45,6 +45,17 @@ pub struct InternalTestingFetchConfigParams {
     pub text_document: Option<TextDocumentIdentifier>,
     pub config: InternalTestingFetchConfigOption,
 }
+
+/// `textDocument/inlineValue`, `lsp_types` declares it as returning a single value instead of a
+/// list.
+pub enum InlineValueRequest {}
+
+impl Request for InlineValueRequest {
+    type Params = lsp_types::InlineValueParams;
+    type Result = Option<Vec<lsp_types::InlineValue>>;
+    const METHOD: &'static str = "textDocument/inlineValue";
+}
+
 pub enum AnalyzerStatus {}
 
 impl Request for AnalyzerStatus {
@@ -108,6 +119,24 @@ impl Request for RebuildProcMacros {
     const METHOD: &'static str = "rust-analyzer/rebuildProcMacros";
 }
 
+pub enum SetActiveCfg {}
+
+impl Request for SetActiveCfg {
+    type Params = SetActiveCfgParams;
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/setActiveCfg";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SetActiveCfgParams {
+    pub text_document: TextDocumentIdentifier,
+    pub features: Option<Vec<String>>,
+    pub target: Option<String>,
+    #[serde(default)]
+    pub cfgs: Vec<String>,
+}
+
 pub enum ViewSyntaxTree {}
 
 impl Request for ViewSyntaxTree {
@@ -315,6 +344,44 @@ pub struct ExpandedMacro {
     pub expansion: String,
 }
 
+pub enum ExpandMacroStep {}
+
+impl Request for ExpandMacroStep {
+    type Params = ExpandMacroParams;
+    type Result = Option<ExpandedMacroSteps>;
+    const METHOD: &'static str = "rust-analyzer/expandMacroStep";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandedMacroSteps {
+    pub name: String,
+    pub steps: Vec<MacroExpansionStep>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroExpansionStep {
+    pub expansion: String,
+    pub calls: Vec<ExpandedMacroCall>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandedMacroCall {
+    pub name: String,
+    pub matched_arm: Option<u32>,
+    pub arms: Vec<MacroArm>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroArm {
+    pub pattern: String,
+    pub error: Option<String>,
+}
+
 pub enum ViewRecursiveMemoryLayout {}
 
 impl Request for ViewRecursiveMemoryLayout {
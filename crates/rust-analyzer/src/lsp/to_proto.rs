@@ -9,11 +9,13 @@ use std::{
 use base64::{prelude::BASE64_STANDARD, Engine};
 use ide::{
     Annotation, AnnotationKind, Assist, AssistKind, Cancellable, CompletionFieldsToResolve,
-    CompletionItem, CompletionItemKind, CompletionRelevance, Documentation, FileId, FileRange,
-    FileSystemEdit, Fold, FoldKind, Highlight, HlMod, HlOperator, HlPunct, HlRange, HlTag, Indel,
-    InlayFieldsToResolve, InlayHint, InlayHintLabel, InlayHintLabelPart, InlayKind, LazyProperty,
-    Markup, NavigationTarget, ReferenceCategory, RenameError, Runnable, Severity, SignatureHelp,
-    SnippetEdit, SourceChange, StructureNodeKind, SymbolKind, TextEdit, TextRange, TextSize,
+    CompletionItem, CompletionItemKind, CompletionRelevance, DocumentLink, DocumentLinkTarget,
+    Documentation, FileId, FileRange, FileSystemEdit, Fold, FoldKind, Highlight, HlMod, HlOperator,
+    HlPunct, HlRange, HlTag, Indel, InlayFieldsToResolve, InlayHint, InlayHintLabel,
+    InlayHintLabelPart, InlayKind, InlineValue, InlineValueKind, LazyProperty, Markup, Moniker,
+    MonikerKind, NavigationTarget, ReferenceCategory, RenameError, Runnable, Severity,
+    SignatureHelp, SnippetEdit, SourceChange, StructureNodeKind, SymbolKind, TextEdit, TextRange,
+    TextSize,
 };
 use ide_db::{assists, rust_doc::format_docs, FxHasher};
 use itertools::Itertools;
@@ -135,6 +137,7 @@ pub(crate) fn completion_item_kind(
         CompletionItemKind::Snippet => lsp_types::CompletionItemKind::SNIPPET,
         CompletionItemKind::UnresolvedReference => lsp_types::CompletionItemKind::REFERENCE,
         CompletionItemKind::Expression => lsp_types::CompletionItemKind::SNIPPET,
+        CompletionItemKind::Feature => lsp_types::CompletionItemKind::PROPERTY,
         CompletionItemKind::SymbolKind(symbol) => match symbol {
             SymbolKind::Attribute => lsp_types::CompletionItemKind::FUNCTION,
             SymbolKind::Method => lsp_types::CompletionItemKind::METHOD,
@@ -878,6 +881,59 @@ fn semantic_token_type_and_modifiers(
     (ty, mods)
 }
 
+pub(crate) fn document_link(
+    snap: &GlobalStateSnapshot,
+    line_index: &LineIndex,
+    link: DocumentLink,
+) -> Cancellable<lsp_types::DocumentLink> {
+    let target = match link.target {
+        DocumentLinkTarget::File(file_id) => url(snap, file_id),
+        DocumentLinkTarget::Definition(frange) => {
+            let mut url = url(snap, frange.file_id);
+            let position = position(&snap.file_line_index(frange.file_id)?, frange.range.start());
+            // Editors understand this fragment as a one-based `line,column` position.
+            url.set_fragment(Some(&format!("L{},{}", position.line + 1, position.character + 1)));
+            url
+        }
+    };
+    Ok(lsp_types::DocumentLink {
+        range: range(line_index, link.range),
+        target: Some(target),
+        tooltip: None,
+        data: None,
+    })
+}
+
+pub(crate) fn inline_value(line_index: &LineIndex, value: InlineValue) -> lsp_types::InlineValue {
+    let range = range(line_index, value.range);
+    match value.kind {
+        InlineValueKind::Variable { name } => {
+            lsp_types::InlineValue::VariableLookup(lsp_types::InlineValueVariableLookup {
+                range,
+                variable_name: Some(name),
+                case_sensitive_lookup: true,
+            })
+        }
+        InlineValueKind::Expression { expression } => {
+            lsp_types::InlineValue::EvaluatableExpression(
+                lsp_types::InlineValueEvaluatableExpression { range, expression: Some(expression) },
+            )
+        }
+    }
+}
+
+pub(crate) fn moniker(moniker: &Moniker) -> lsp_types::Moniker {
+    lsp_types::Moniker {
+        scheme: "rust-analyzer".to_owned(),
+        identifier: moniker.identifier.to_string(),
+        unique: lsp_types::UniquenessLevel::Scheme,
+        kind: Some(match moniker.kind {
+            MonikerKind::Import => lsp_types::MonikerKind::Import,
+            MonikerKind::Export => lsp_types::MonikerKind::Export,
+        }),
+    }
+}
+
 pub(crate) fn folding_range(
     text: &str,
     line_index: &LineIndex,
@@ -1427,6 +1483,27 @@ pub(crate) fn call_hierarchy_item(
     })
 }
 
+/// Returns `None` for targets of an unknown kind, which a type hierarchy item can't represent.
+pub(crate) fn type_hierarchy_item(
+    snap: &GlobalStateSnapshot,
+    target: NavigationTarget,
+) -> Cancellable<Option<lsp_types::TypeHierarchyItem>> {
+    let Some(kind) = target.kind.map(symbol_kind) else { return Ok(None) };
+    let name = target.name.to_string();
+    let detail = target.description.clone();
+    let (uri, range, selection_range) = location_info(snap, target)?;
+    Ok(Some(lsp_types::TypeHierarchyItem {
+        name,
+        kind,
+        tags: None,
+        detail,
+        uri,
+        range,
+        selection_range,
+        data: None,
+    }))
+}
+
 pub(crate) fn code_action_kind(kind: AssistKind) -> lsp_types::CodeActionKind {
     match kind {
         AssistKind::None | AssistKind::Generate => lsp_types::CodeActionKind::EMPTY,
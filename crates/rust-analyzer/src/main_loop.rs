@@ -2,7 +2,7 @@
 //! requests/replies and notifications back to the client.
 
 use std::{
-    fmt,
+    fmt, mem,
     ops::Div as _,
     panic::AssertUnwindSafe,
     time::{Duration, Instant},
@@ -10,9 +10,13 @@ use std::{
 
 use always_assert::always;
 use crossbeam_channel::{select, Receiver};
-use ide_db::base_db::{SourceDatabase, SourceRootDatabase, VfsPath};
+use ide_db::{
+    base_db::{SourceDatabase, SourceRootDatabase, VfsPath},
+    FxHashMap,
+};
 use lsp_server::{Connection, Notification, Request};
 use lsp_types::{notification::Notification as _, TextDocumentIdentifier};
+use project_model::ResolvedCfgVariant;
 use stdx::thread::ThreadIntent;
 use tracing::{error, span, Level};
 use vfs::{loader::LoadingProgress, AbsPathBuf, FileId};
@@ -108,7 +112,19 @@ pub(crate) enum Task {
     Retry(lsp_server::Request),
     Diagnostics(DiagnosticsTaskKind),
     DiscoverTest(lsp_ext::DiscoverTestResults),
+    InterpretedTests {
+        generation: usize,
+        progress: InterpretedTestsProgress,
+    },
+    /// The cfgs requested with `rust-analyzer/setActiveCfg`, for the crates rooted at the paths.
+    ActiveCfgsResolved(FxHashMap<AbsPathBuf, ResolvedCfgVariant>),
     PrimeCaches(PrimeCachesProgress),
+    /// The keys of the persisted caches, computed before priming caches. `None` if the
+    /// computation was cancelled.
+    PersistKeys {
+        generation: usize,
+        keys: Option<ide::PersistKeys>,
+    },
     FetchWorkspace(ProjectWorkspaceProgress),
     FetchBuildData(BuildDataProgress),
     LoadProcMacros(ProcMacroProgress),
@@ -122,6 +138,21 @@ pub(crate) enum DiscoverProjectParam {
     Path(AbsPathBuf),
 }
 
+#[derive(Debug)]
+pub(crate) enum InterpretedTestsProgress {
+    Test {
+        test_id: String,
+        state: lsp_ext::TestState,
+    },
+    Output(String),
+    /// All tests were interpreted, except for `fallback`, which has to be run with cargo.
+    Finished {
+        fallback: Vec<String>,
+    },
+    /// The analysis changed while the tests were interpreted.
+    Cancelled,
+}
+
 #[derive(Debug)]
 pub(crate) enum PrimeCachesProgress {
     Begin,
@@ -369,6 +400,8 @@ impl GlobalState {
                             if cancelled {
                                 self.prime_caches_queue
                                     .request_op("restart after cancellation".to_owned(), ());
+                            } else if !self.persisted_caches_stored {
+                                self.store_persisted_caches();
                             }
                         }
                     };
@@ -419,7 +452,8 @@ impl GlobalState {
             if let Some(cause) = self.wants_to_switch.take() {
                 self.switch_workspaces(cause);
             }
-            (self.process_changes(), self.mem_docs.take_changes())
+            let active_cfgs_changed = mem::take(&mut self.active_cfgs_changed);
+            (self.process_changes() || active_cfgs_changed, self.mem_docs.take_changes())
         } else {
             (false, false)
         };
@@ -526,6 +560,19 @@ impl GlobalState {
 
     fn prime_caches(&mut self, cause: String) {
         tracing::debug!(%cause, "will prime caches");
+        if self.config.persisted_caches_path().is_some()
+            && !self.analysis_host.raw_database().persist_keys_are_current()
+        {
+            // Persisted def maps and symbols are only used once the keys of the crate graph are
+            // bound, so compute them first. Priming restarts when they arrive.
+            let generation = self.persist_keys_generation;
+            let analysis = self.snapshot().analysis;
+            self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, move |sender| {
+                let keys = analysis.persist_keys().ok();
+                sender.send(Task::PersistKeys { generation, keys }).unwrap();
+            });
+            return;
+        }
         let num_worker_threads = self.config.prime_caches_num_threads();
 
         self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, {
@@ -543,6 +590,20 @@ impl GlobalState {
         });
     }
 
+    fn store_persisted_caches(&mut self) {
+        let Some(path) = self.config.persisted_caches_path() else { return };
+        self.persisted_caches_stored = true;
+        let analysis = self.snapshot().analysis;
+        self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, move |_| {
+            // If the database changes in the meantime, the next session stores the caches instead.
+            let Ok(caches) = analysis.library_caches() else { return };
+            match load_cargo::store_persisted_caches(&caches, &path) {
+                Ok(()) => tracing::info!("persisted {} cache entries at {path}", caches.len()),
+                Err(e) => tracing::warn!("failed to persist caches at {path}: {e}"),
+            }
+        });
+    }
+
     fn update_diagnostics(&mut self) {
         let db = self.analysis_host.raw_database();
         let generation = self.diagnostics.next_generation();
@@ -732,6 +793,13 @@ impl GlobalState {
                 }
                 PrimeCachesProgress::End { .. } => prime_caches_progress.push(progress),
             },
+            Task::PersistKeys { generation, keys } => {
+                if let Some(keys) = keys.filter(|_| generation == self.persist_keys_generation) {
+                    self.analysis_host.raw_database_mut().bind_persist_keys(keys);
+                }
+                self.prime_caches_queue.op_completed(());
+                self.prime_caches_queue.request_op("computed persist keys".to_owned(), ());
+            }
             Task::FetchWorkspace(progress) => {
                 let (state, msg) = match progress {
                     ProjectWorkspaceProgress::Begin => (Progress::Begin, None),
@@ -817,6 +885,45 @@ impl GlobalState {
             Task::DiscoverTest(tests) => {
                 self.send_notification::<lsp_ext::DiscoveredTests>(tests);
             }
+            Task::ActiveCfgsResolved(cfgs) => self.set_active_cfgs(cfgs),
+            Task::InterpretedTests { generation, progress } => {
+                // Drop the results of aborted or superseded runs.
+                if generation != self.test_run_generation || self.test_run_session.is_none() {
+                    return;
+                }
+                match progress {
+                    InterpretedTestsProgress::Test { test_id, state } => self
+                        .send_notification::<lsp_ext::ChangeTestState>(
+                        lsp_ext::ChangeTestStateParams { test_id, state },
+                    ),
+                    InterpretedTestsProgress::Output(text) => {
+                        self.send_notification::<lsp_ext::AppendOutputToRunTest>(text)
+                    }
+                    InterpretedTestsProgress::Finished { fallback } if !fallback.is_empty() => {
+                        if let Err(e) =
+                            crate::handlers::request::run_exact_tests_with_cargo(self, fallback)
+                        {
+                            self.test_run_session = None;
+                            self.send_notification::<lsp_ext::EndRunTest>(());
+                            self.show_and_log_error(
+                                "failed to run tests with cargo".to_owned(),
+                                Some(e.to_string()),
+                            );
+                        }
+                    }
+                    InterpretedTestsProgress::Finished { .. } => {
+                        self.test_run_session = None;
+                        self.send_notification::<lsp_ext::EndRunTest>(());
+                    }
+                    InterpretedTestsProgress::Cancelled => {
+                        self.test_run_session = None;
+                        self.send_notification::<lsp_ext::AppendOutputToRunTest>(
+                            "The test run was cancelled because the code changed.\n".to_owned(),
+                        );
+                        self.send_notification::<lsp_ext::EndRunTest>(());
+                    }
+                }
+            }
         }
     }
 
@@ -1100,6 +1207,7 @@ impl GlobalState {
             // because they mutate GlobalState:
             .on_sync_mut::<lsp_ext::ReloadWorkspace>(handlers::handle_workspace_reload)
             .on_sync_mut::<lsp_ext::RebuildProcMacros>(handlers::handle_proc_macros_rebuild)
+            .on_sync_mut::<lsp_ext::SetActiveCfg>(handlers::handle_set_active_cfg)
             .on_sync_mut::<lsp_ext::MemoryUsage>(handlers::handle_memory_usage)
             .on_sync_mut::<lsp_ext::RunTest>(handlers::handle_run_test)
             // Request handlers which are related to the user typing
@@ -1137,6 +1245,7 @@ impl GlobalState {
             })
             .on::<RETRY, lsp_request::DocumentSymbolRequest>(handlers::handle_document_symbol)
             .on::<RETRY, lsp_request::FoldingRangeRequest>(handlers::handle_folding_range)
+            .on::<RETRY, lsp_request::DocumentLinkRequest>(handlers::handle_document_link)
             .on::<NO_RETRY, lsp_request::SignatureHelpRequest>(handlers::handle_signature_help)
             .on::<RETRY, lsp_request::WillRenameFiles>(handlers::handle_will_rename_files)
             .on::<NO_RETRY, lsp_request::GotoDefinition>(handlers::handle_goto_definition)
@@ -1144,6 +1253,7 @@ impl GlobalState {
             .on::<NO_RETRY, lsp_request::GotoImplementation>(handlers::handle_goto_implementation)
             .on::<NO_RETRY, lsp_request::GotoTypeDefinition>(handlers::handle_goto_type_definition)
             .on::<NO_RETRY, lsp_request::InlayHintRequest>(handlers::handle_inlay_hints)
+            .on::<NO_RETRY, lsp_ext::InlineValueRequest>(handlers::handle_inline_value)
             .on_identity::<NO_RETRY, lsp_request::InlayHintResolveRequest, _>(handlers::handle_inlay_hints_resolve)
             .on::<NO_RETRY, lsp_request::CodeLensRequest>(handlers::handle_code_lens)
             .on_identity::<NO_RETRY, lsp_request::CodeLensResolve, _>(handlers::handle_code_lens_resolve)
@@ -1151,9 +1261,14 @@ impl GlobalState {
             .on::<NO_RETRY, lsp_request::Rename>(handlers::handle_rename)
             .on::<NO_RETRY, lsp_request::References>(handlers::handle_references)
             .on::<NO_RETRY, lsp_request::DocumentHighlightRequest>(handlers::handle_document_highlight)
+            .on::<NO_RETRY, lsp_request::MonikerRequest>(handlers::handle_moniker)
+            .on::<NO_RETRY, lsp_request::LinkedEditingRange>(handlers::handle_linked_editing_range)
             .on::<NO_RETRY, lsp_request::CallHierarchyPrepare>(handlers::handle_call_hierarchy_prepare)
             .on::<NO_RETRY, lsp_request::CallHierarchyIncomingCalls>(handlers::handle_call_hierarchy_incoming)
             .on::<NO_RETRY, lsp_request::CallHierarchyOutgoingCalls>(handlers::handle_call_hierarchy_outgoing)
+            .on::<NO_RETRY, lsp_request::TypeHierarchyPrepare>(handlers::handle_type_hierarchy_prepare)
+            .on::<NO_RETRY, lsp_request::TypeHierarchySupertypes>(handlers::handle_type_hierarchy_supertypes)
+            .on::<NO_RETRY, lsp_request::TypeHierarchySubtypes>(handlers::handle_type_hierarchy_subtypes)
             // All other request handlers (lsp extension)
             .on::<RETRY, lsp_ext::FetchDependencyList>(handlers::fetch_dependency_list)
             .on::<RETRY, lsp_ext::AnalyzerStatus>(handlers::handle_analyzer_status)
@@ -1169,6 +1284,7 @@ impl GlobalState {
             .on::<NO_RETRY, lsp_ext::ViewMir>(handlers::handle_view_mir)
             .on::<NO_RETRY, lsp_ext::InterpretFunction>(handlers::handle_interpret_function)
             .on::<NO_RETRY, lsp_ext::ExpandMacro>(handlers::handle_expand_macro)
+            .on::<NO_RETRY, lsp_ext::ExpandMacroStep>(handlers::handle_expand_macro_step)
             .on::<NO_RETRY, lsp_ext::ParentModule>(handlers::handle_parent_module)
             .on::<NO_RETRY, lsp_ext::Runnables>(handlers::handle_runnables)
             .on::<NO_RETRY, lsp_ext::RelatedTests>(handlers::handle_related_tests)
@@ -18,14 +18,19 @@ use std::{iter, mem};
 use hir::{db::DefDatabase, ChangeWithProcMacros, ProcMacros, ProcMacrosBuilder};
 use ide::CrateId;
 use ide_db::{
-    base_db::{ra_salsa::Durability, CrateGraph, CrateWorkspaceData, ProcMacroPaths},
-    FxHashMap,
+    base_db::{
+        ra_salsa::Durability, CrateGraph, CrateWorkspaceData, ProcMacroPaths, SourceDatabase,
+    },
+    FxHashMap, FxHashSet,
 };
 use itertools::Itertools;
 use load_cargo::{load_proc_macro, ProjectFolders};
 use lsp_types::FileSystemWatcher;
 use proc_macro_api::ProcMacroClient;
-use project_model::{ManifestPath, ProjectWorkspace, ProjectWorkspaceKind, WorkspaceBuildScripts};
+use project_model::{
+    CfgVariant, ManifestPath, ProjectWorkspace, ProjectWorkspaceKind, ResolvedCfgVariant,
+    WorkspaceBuildScripts,
+};
 use stdx::{format_to, thread::ThreadIntent};
 use triomphe::Arc;
 use vfs::{AbsPath, AbsPathBuf, ChangeKind};
@@ -648,14 +653,15 @@ impl GlobalState {
                 };
                 info!("Using proc-macro server at {path}");
 
-                ProcMacroClient::spawn(&path, &env).map_err(|err| {
-                    tracing::error!(
-                        "Failed to run proc-macro server from path {path}, error: {err:?}",
-                    );
-                    anyhow::format_err!(
-                        "Failed to run proc-macro server from path {path}, error: {err:?}",
-                    )
-                })
+                ProcMacroClient::spawn(&path, &env, self.config.proc_macro_server_options())
+                    .map_err(|err| {
+                        tracing::error!(
+                            "Failed to run proc-macro server from path {path}, error: {err:?}",
+                        );
+                        anyhow::format_err!(
+                            "Failed to run proc-macro server from path {path}, error: {err:?}",
+                        )
+                    })
             }))
         }
 
@@ -700,7 +706,7 @@ impl GlobalState {
             })
             .collect();
 
-        let (crate_graph, proc_macro_paths, ws_data) = {
+        let (mut crate_graph, proc_macro_paths, ws_data) = {
             // Create crate graph from all the workspaces
             let vfs = &self.vfs.read().0;
             let load = |path: &AbsPath| {
@@ -713,6 +719,7 @@ impl GlobalState {
 
             ws_to_crate_graph(&self.workspaces, self.config.extra_env(None), load)
         };
+        self.apply_active_cfgs(&mut crate_graph, &self.active_cfgs);
         let mut change = ChangeWithProcMacros::new();
         if self.config.expand_proc_macros() {
             change.set_proc_macros(
@@ -744,6 +751,72 @@ impl GlobalState {
         self.reload_flycheck();
     }
 
+    /// Switches the crates rooted at the paths in `cfgs` to the respective variant.
+    pub(crate) fn set_active_cfgs(&mut self, cfgs: FxHashMap<AbsPathBuf, ResolvedCfgVariant>) {
+        let mut crate_graph = CrateGraph::clone(&self.analysis_host.raw_database().crate_graph());
+        let updated = self.apply_active_cfgs(&mut crate_graph, &cfgs);
+        for (root, resolved) in cfgs {
+            if !updated.contains(&root) {
+                // The workspace may have been reloaded while the cfgs of a target were queried.
+                self.show_and_log_error(
+                    format!("failed to switch the cfgs of the crate rooted at {root}"),
+                    Some("the crate is no longer part of a workspace".to_owned()),
+                );
+                continue;
+            }
+            if resolved.variant == CfgVariant::default() {
+                self.active_cfgs.remove(&root);
+            } else {
+                self.active_cfgs.insert(root, resolved);
+            }
+        }
+        let mut change = ChangeWithProcMacros::new();
+        change.source_change.set_crate_graph(crate_graph);
+        self.analysis_host.apply_change(change);
+        self.active_cfgs_changed = true;
+    }
+
+    /// Replaces the cfg options of the crates rooted at the paths in `cfgs` with the ones they
+    /// have under the respective variant.
+    ///
+    /// The cfg variants of those crates are left untouched. Returns the paths a crate was updated
+    /// for.
+    pub(crate) fn apply_active_cfgs(
+        &self,
+        crate_graph: &mut CrateGraph,
+        cfgs: &FxHashMap<AbsPathBuf, ResolvedCfgVariant>,
+    ) -> FxHashSet<AbsPathBuf> {
+        let mut updated = FxHashSet::default();
+        if cfgs.is_empty() {
+            return updated;
+        }
+        let mut new_cfgs = FxHashMap::default();
+        {
+            let vfs = &self.vfs.read().0;
+            for krate in crate_graph.iter() {
+                if crate_graph.cfg_variant_of(krate).is_some() {
+                    continue;
+                }
+                let Some(root) = vfs.file_path(crate_graph[krate].root_file_id).as_path() else {
+                    continue;
+                };
+                let Some(variant) = cfgs.get(root) else { continue };
+                if let Some(cfg_options) =
+                    self.workspaces.iter().find_map(|ws| ws.crate_cfg_options(root, variant))
+                {
+                    new_cfgs.insert(krate, Arc::new(cfg_options));
+                    updated.insert(root.to_path_buf());
+                }
+            }
+        }
+        for (krate, data) in crate_graph.iter_mut() {
+            if let Some(cfg_options) = new_cfgs.remove(&krate) {
+                data.cfg_options = cfg_options;
+            }
+        }
+        updated
+    }
+
     pub(super) fn fetch_workspace_error(&self) -> Result<(), String> {
         let mut buf = String::new();
 
@@ -65,6 +65,8 @@ pub(crate) struct CargoTestHandle {
 // cargo test --workspace --no-fail-fast -- -Z unstable-options --format=json
 // or
 // cargo test --package my-package --no-fail-fast -- module::func -Z unstable-options --format=json
+// or
+// cargo test --package my-package --no-fail-fast -- module::a module::b --exact -Z unstable-options --format=json
 
 #[derive(Debug)]
 pub(crate) enum TestTarget {
@@ -73,8 +75,11 @@ pub(crate) enum TestTarget {
 }
 
 impl CargoTestHandle {
+    /// Runs the tests matching any of `filters`, which must match the full test path if `exact`
+    /// is set.
     pub(crate) fn new(
-        path: Option<&str>,
+        filters: &[&str],
+        exact: bool,
         options: CargoOptions,
         root: &AbsPath,
         test_target: TestTarget,
@@ -100,8 +105,9 @@ impl CargoTestHandle {
         cmd.arg(root.join("Cargo.toml"));
         options.apply_on_command(&mut cmd);
         cmd.arg("--");
-        if let Some(path) = path {
-            cmd.arg(path);
+        cmd.args(filters);
+        if exact {
+            cmd.arg("--exact");
         }
         cmd.args(["-Z", "unstable-options"]);
         cmd.arg("--format=json");
@@ -30,7 +30,9 @@ use lsp_types::{
     PartialResultParams, Position, Range, RenameFilesParams, TextDocumentItem,
     TextDocumentPositionParams, WorkDoneProgressParams,
 };
-use rust_analyzer::lsp::ext::{OnEnter, Runnables, RunnablesParams};
+use rust_analyzer::lsp::ext::{
+    OnEnter, Runnables, RunnablesParams, SetActiveCfg, SetActiveCfgParams,
+};
 use serde_json::json;
 use stdx::format_to_acc;
 
@@ -1266,6 +1268,61 @@ pub fn foo(_input: TokenStream) -> TokenStream {
     .assert_eq(value);
 }
 
+#[test]
+fn set_active_cfg() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+[features]
+extra = []
+
+//- /src/lib.rs
+#[cfg(feature = "extra")]
+pub fn gated() {}
+pub fn f() { gated(); }
+"#,
+    )
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let hover_gated = || {
+        server.send_request::<HoverRequest>(HoverParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                server.doc_id("src/lib.rs"),
+                Position::new(2, 14),
+            ),
+            work_done_progress_params: Default::default(),
+        })
+    };
+    let set_active_cfg = |features: Option<Vec<String>>| {
+        server.request::<SetActiveCfg>(
+            SetActiveCfgParams {
+                text_document: server.doc_id("src/lib.rs"),
+                features,
+                target: None,
+                cfgs: Vec::new(),
+            },
+            json!(null),
+        )
+    };
+
+    assert!(hover_gated().is_null());
+
+    set_active_cfg(Some(vec!["extra".to_owned()]));
+    assert!(hover_gated().to_string().contains("pub fn gated()"));
+
+    set_active_cfg(None);
+    assert!(hover_gated().is_null());
+}
+
 #[test]
 fn test_will_rename_files_same_level() {
     if skip_slow_tests() {
@@ -84,6 +84,12 @@ impl<N: AstIdNode> FileAstId<N> {
     pub fn erase(self) -> ErasedFileAstId {
         self.raw
     }
+
+    /// Reconstructs a `FileAstId` from an erased one, for deserialization purposes. The caller has
+    /// to make sure that the id actually points to an `N`.
+    pub fn from_erased_unchecked(raw: ErasedFileAstId) -> Self {
+        FileAstId { raw, covariant: PhantomData }
+    }
 }
 
 pub trait AstIdNode: AstNode {}
@@ -1,5 +1,5 @@
 //! A set of high-level utility fixture methods to use in tests.
-use std::{iter, mem, str::FromStr, sync};
+use std::{iter, mem, str::FromStr, sync, time::Duration};
 
 use base_db::{
     CrateDisplayName, CrateGraph, CrateId, CrateName, CrateOrigin, CrateWorkspaceData, Dependency,
@@ -148,6 +148,7 @@ impl ChangeFixture {
         let mut crate_graph = CrateGraph::default();
         let mut crates = FxHashMap::default();
         let mut crate_deps = Vec::new();
+        let mut cfg_variants = Vec::new();
         let mut default_crate_root: Option<FileId> = None;
         let mut default_cfg = CfgOptions::default();
         let mut default_env = Env::from_iter([(
@@ -216,6 +217,9 @@ impl ChangeFixture {
                 );
                 let prev = crates.insert(crate_name.clone(), crate_id);
                 assert!(prev.is_none(), "multiple crates with same name: {crate_name}");
+                if let Some(cfg_variant) = meta.cfg_variant {
+                    cfg_variants.push((crate_id, From::from(cfg_variant)));
+                }
                 for dep in meta.deps {
                     let prelude = match &meta.extern_prelude {
                         Some(v) => v.contains(&dep),
@@ -348,6 +352,8 @@ impl ChangeFixture {
             }
         }
 
+        crate_graph.add_cfg_variants(cfg_variants);
+
         let root = match current_source_root_kind {
             SourceRootKind::Local => SourceRoot::new_local(mem::take(&mut file_set)),
             SourceRootKind::Library => SourceRoot::new_library(mem::take(&mut file_set)),
@@ -526,6 +532,21 @@ pub fn disallow_cfg(_attr: TokenStream, input: TokenStream) -> TokenStream {
                 disabled: false,
             },
         ),
+        (
+            r#"
+#[proc_macro]
+pub fn timeout(_input: TokenStream) -> TokenStream {
+    loop {}
+}
+"#
+            .into(),
+            ProcMacro {
+                name: Symbol::intern("timeout"),
+                kind: ProcMacroKind::Bang,
+                expander: sync::Arc::new(TimeoutProcMacroExpander),
+                disabled: false,
+            },
+        ),
     ])
 }
 
@@ -562,6 +583,7 @@ struct FileMeta {
     deps: Vec<String>,
     extern_prelude: Option<Vec<String>>,
     cfg: CfgOptions,
+    cfg_variant: Option<CfgOptions>,
     edition: Edition,
     env: Env,
     introduce_new_source_root: Option<SourceRootKind>,
@@ -569,14 +591,19 @@ struct FileMeta {
 
 impl FileMeta {
     fn from_fixture(f: Fixture, current_source_root_kind: SourceRootKind) -> Self {
-        let mut cfg = CfgOptions::default();
-        for (k, v) in f.cfgs {
-            if let Some(v) = v {
-                cfg.insert_key_value(Symbol::intern(&k), Symbol::intern(&v));
-            } else {
-                cfg.insert_atom(Symbol::intern(&k));
+        let to_cfg_options = |cfgs: Vec<(String, Option<String>)>| {
+            let mut cfg = CfgOptions::default();
+            for (k, v) in cfgs {
+                if let Some(v) = v {
+                    cfg.insert_key_value(Symbol::intern(&k), Symbol::intern(&v));
+                } else {
+                    cfg.insert_atom(Symbol::intern(&k));
+                }
             }
-        }
+            cfg
+        };
+        let cfg = to_cfg_options(f.cfgs);
+        let cfg_variant = f.cfg_variant.map(to_cfg_options);
 
         let introduce_new_source_root = f.introduce_new_source_root.map(|kind| match &*kind {
             "local" => SourceRootKind::Local,
@@ -593,6 +620,7 @@ impl FileMeta {
             extern_prelude: f.extern_prelude,
             deps,
             cfg,
+            cfg_variant,
             edition: f.edition.map_or(Edition::CURRENT, |v| Edition::from_str(&v).unwrap()),
             env: f.env.into_iter().collect(),
             introduce_new_source_root,
@@ -907,3 +935,21 @@ impl ProcMacroExpander for DisallowCfgProcMacroExpander {
         Ok(subtree.clone())
     }
 }
+
+// Behaves like a proc-macro that got killed by the server's expansion timeout.
+#[derive(Debug)]
+struct TimeoutProcMacroExpander;
+impl ProcMacroExpander for TimeoutProcMacroExpander {
+    fn expand(
+        &self,
+        _: &TopSubtree,
+        _: Option<&TopSubtree>,
+        _: &Env,
+        _: Span,
+        _: Span,
+        _: Span,
+        _: Option<String>,
+    ) -> Result<TopSubtree, ProcMacroExpansionError> {
+        Err(ProcMacroExpansionError::TimedOut(Duration::from_secs(5)))
+    }
+}
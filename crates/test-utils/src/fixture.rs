@@ -92,6 +92,11 @@ pub struct Fixture {
     ///
     /// Syntax: `cfg:test,dbg=false,opt_level=2`
     pub cfgs: Vec<(String, Option<String>)>,
+    /// Adds a cfg variant of this crate, a copy of it analyzed with the given configuration
+    /// options instead. This must be used with `crate` meta.
+    ///
+    /// Syntax: `cfg_variant:windows,feature=tokio`
+    pub cfg_variant: Option<Vec<(String, Option<String>)>>,
     /// Specifies the edition of this crate. This must be used with `crate` meta. If
     /// this is not specified, ([`base_db::input::Edition::CURRENT`]) will be used.
     /// This must be used with `crate` meta.
@@ -250,6 +255,7 @@ impl FixtureWithProjectMeta {
         let mut extern_prelude = None;
         let mut edition = None;
         let mut cfgs = Vec::new();
+        let mut cfg_variant = None;
         let mut env = FxHashMap::default();
         let mut introduce_new_source_root = None;
         let mut library = false;
@@ -273,14 +279,8 @@ impl FixtureWithProjectMeta {
                     }
                 }
                 "edition" => edition = Some(value.to_owned()),
-                "cfg" => {
-                    for entry in value.split(',') {
-                        match entry.split_once('=') {
-                            Some((k, v)) => cfgs.push((k.to_owned(), Some(v.to_owned()))),
-                            None => cfgs.push((entry.to_owned(), None)),
-                        }
-                    }
-                }
+                "cfg" => cfgs.extend(parse_cfgs(value)),
+                "cfg_variant" => cfg_variant = Some(parse_cfgs(value).collect()),
                 "env" => {
                     for key in value.split(',') {
                         if let Some((k, v)) = key.split_once('=') {
@@ -307,6 +307,7 @@ impl FixtureWithProjectMeta {
             deps,
             extern_prelude,
             cfgs,
+            cfg_variant,
             edition,
             env,
             introduce_new_source_root,
@@ -315,6 +316,13 @@ impl FixtureWithProjectMeta {
     }
 }
 
+fn parse_cfgs(value: &str) -> impl Iterator<Item = (String, Option<String>)> + '_ {
+    value.split(',').map(|entry| match entry.split_once('=') {
+        Some((k, v)) => (k.to_owned(), Some(v.to_owned())),
+        None => (entry.to_owned(), None),
+    })
+}
+
 impl MiniCore {
     const RAW_SOURCE: &'static str = include_str!("./minicore.rs");
 
@@ -490,7 +498,7 @@ fn parse_fixture_gets_full_meta() {
 //- toolchain: nightly
 //- proc_macros: identity
 //- minicore: coerce_unsized
-//- /lib.rs crate:foo deps:bar,baz cfg:foo=a,bar=b,atom env:OUTDIR=path/to,OTHER=foo
+//- /lib.rs crate:foo deps:bar,baz cfg:foo=a,bar=b,atom cfg_variant:windows env:OUTDIR=path/to,OTHER=foo
 mod m;
 "#,
     );
@@ -504,5 +512,6 @@ mod m;
 
     assert_eq!("foo", meta.krate.as_ref().unwrap());
     assert_eq!("/lib.rs", meta.path);
+    assert_eq!(Some(vec![("windows".to_owned(), None)]), meta.cfg_variant);
     assert_eq!(2, meta.env.len());
 }
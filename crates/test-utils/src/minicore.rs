@@ -1336,6 +1336,7 @@ pub mod option {
 
 // region:result
 pub mod result {
+    #[must_use = "this `Result` may be an `Err` variant, which should be handled"]
     pub enum Result<T, E> {
         #[lang = "Ok"]
         Ok(T),
@@ -1383,6 +1384,7 @@ pub mod future {
 
     #[doc(notable_trait)]
     #[lang = "future_trait"]
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
     pub trait Future {
         #[lang = "future_output"]
         type Output;
@@ -1423,6 +1425,7 @@ pub mod task {
 pub mod iter {
     // region:iterators
     mod adapters {
+        #[must_use = "iterators are lazy and do nothing unless consumed"]
         pub struct Take<I> {
             iter: I,
             n: usize,
@@ -1438,6 +1441,7 @@ pub mod iter {
             }
         }
 
+        #[must_use = "iterators are lazy and do nothing unless consumed"]
         pub struct FilterMap<I, F> {
             iter: I,
             f: F,
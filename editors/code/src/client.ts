@@ -19,7 +19,10 @@ export async function createClient(
     unlinkedFiles: vscode.Uri[],
 ): Promise<lc.LanguageClient> {
     const clientOptions: lc.LanguageClientOptions = {
-        documentSelector: [{ scheme: "file", language: "rust" }],
+        documentSelector: [
+            { scheme: "file", language: "rust" },
+            { scheme: "file", language: "toml", pattern: "**/Cargo.toml" },
+        ],
         initializationOptions,
         diagnosticCollectionName: "rustc",
         traceOutputChannel,
@@ -851,6 +851,70 @@ export function expandMacro(ctx: CtxInit): Cmd {
     };
 }
 
+// Opens a virtual file showing each step of the macro expansion, along with the
+// `macro_rules!` arms that did and did not match.
+export function expandMacroStep(ctx: CtxInit): Cmd {
+    function codeFormat(expanded: ra.ExpandedMacroSteps): string {
+        let result = `// Step-by-step expansion of ${expanded.name} macro\n`;
+        result += "// " + "=".repeat(result.length - 3);
+        for (const [i, step] of expanded.steps.entries()) {
+            result += `\n\n// Step ${i + 1}\n`;
+            for (const call of step.calls) {
+                result += `// ${call.name}\n`;
+                if (call.error) result += `//   error: ${call.error}\n`;
+                for (const [idx, arm] of call.arms.entries()) {
+                    const status =
+                        idx === call.matchedArm ? "used" : (arm.error ?? "matches, not used");
+                    result += `//   arm ${idx}: ${arm.pattern.replace(/\s+/g, " ")} => ${status}\n`;
+                }
+            }
+            result += "\n" + step.expansion;
+        }
+
+        return result;
+    }
+
+    const tdcp = new (class implements vscode.TextDocumentContentProvider {
+        uri = vscode.Uri.parse("rust-analyzer-expand-macro-step://expandMacroStep/[EXPANSION].rs");
+        eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+        async provideTextDocumentContent(_uri: vscode.Uri): Promise<string> {
+            const editor = vscode.window.activeTextEditor;
+            if (!editor) return "";
+            const client = ctx.client;
+
+            const position = editor.selection.active;
+
+            const expanded = await client.sendRequest(ra.expandMacroStep, {
+                textDocument: client.code2ProtocolConverter.asTextDocumentIdentifier(
+                    editor.document,
+                ),
+                position,
+            });
+
+            if (expanded == null) return "Not available";
+
+            return codeFormat(expanded);
+        }
+
+        get onDidChange(): vscode.Event<vscode.Uri> {
+            return this.eventEmitter.event;
+        }
+    })();
+
+    ctx.pushExtCleanup(
+        vscode.workspace.registerTextDocumentContentProvider(
+            "rust-analyzer-expand-macro-step",
+            tdcp,
+        ),
+    );
+
+    return async () => {
+        const document = await vscode.workspace.openTextDocument(tdcp.uri);
+        tdcp.eventEmitter.fire(tdcp.uri);
+        return vscode.window.showTextDocument(document, vscode.ViewColumn.Two, true);
+    };
+}
+
 export function reloadWorkspace(ctx: CtxInit): Cmd {
     return async () => ctx.client.sendRequest(ra.reloadWorkspace);
 }
@@ -859,6 +923,35 @@ export function rebuildProcMacros(ctx: CtxInit): Cmd {
     return async () => ctx.client.sendRequest(ra.rebuildProcMacros);
 }
 
+export function setActiveCfg(ctx: CtxInit): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        if (!editor) return;
+        const client = ctx.client;
+
+        const features = await vscode.window.showInputBox({
+            title: "Active features",
+            prompt: "Comma-separated features to enable, leave empty for the configured ones",
+        });
+        if (features === undefined) return;
+        const target = await vscode.window.showInputBox({
+            title: "Active target",
+            prompt: "Target triple to analyze the crate for, leave empty for the configured one",
+        });
+        if (target === undefined) return;
+
+        const list = features
+            .split(",")
+            .map((it) => it.trim())
+            .filter((it) => it !== "");
+        await client.sendRequest(ra.setActiveCfg, {
+            textDocument: client.code2ProtocolConverter.asTextDocumentIdentifier(editor.document),
+            features: list.length > 0 ? list : undefined,
+            target: target.trim() !== "" ? target.trim() : undefined,
+        });
+    };
+}
+
 async function showReferencesImpl(
     client: LanguageClient | undefined,
     uri: string,
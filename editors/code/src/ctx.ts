@@ -543,6 +543,8 @@ export class Ctx implements RustAnalyzerExtensionApi {
                 "\n\n" +
                 '[$(symbol-property) Rebuild Build Dependencies](command:rust-analyzer.rebuildProcMacros "Rebuild build scripts and proc-macros")' +
                 "\n\n" +
+                '[$(settings) Set Active Features](command:rust-analyzer.setActiveCfg "Switch the features and target of the current crate")' +
+                "\n\n" +
                 '[$(stop-circle) Stop server](command:rust-analyzer.stopServer "Stop the server")' +
                 "\n\n" +
                 '[$(debug-restart) Restart server](command:rust-analyzer.restartServer "Restart the server")',
@@ -34,6 +34,11 @@ export const clearFlycheck = new lc.NotificationType0("rust-analyzer/clearFlyche
 export const expandMacro = new lc.RequestType<ExpandMacroParams, ExpandedMacro | null, void>(
     "rust-analyzer/expandMacro",
 );
+export const expandMacroStep = new lc.RequestType<
+    ExpandMacroParams,
+    ExpandedMacroSteps | null,
+    void
+>("rust-analyzer/expandMacroStep");
 export const memoryUsage = new lc.RequestType0<string, void>("rust-analyzer/memoryUsage");
 export const openServerLogs = new lc.NotificationType0("rust-analyzer/openServerLogs");
 export const relatedTests = new lc.RequestType<lc.TextDocumentPositionParams, TestInfo[], void>(
@@ -41,6 +46,9 @@ export const relatedTests = new lc.RequestType<lc.TextDocumentPositionParams, Te
 );
 export const reloadWorkspace = new lc.RequestType0<null, void>("rust-analyzer/reloadWorkspace");
 export const rebuildProcMacros = new lc.RequestType0<null, void>("rust-analyzer/rebuildProcMacros");
+export const setActiveCfg = new lc.RequestType<SetActiveCfgParams, void, void>(
+    "rust-analyzer/setActiveCfg",
+);
 
 export const runFlycheck = new lc.NotificationType<{
     textDocument: lc.TextDocumentIdentifier | null;
@@ -147,6 +155,12 @@ export const fetchDependencyGraph = new lc.RequestType<
     void
 >("rust-analyzer/fetchDependencyGraph");
 
+export type SetActiveCfgParams = {
+    textDocument: lc.TextDocumentIdentifier;
+    features?: string[];
+    target?: string;
+    cfgs?: string[];
+};
 export type ExpandMacroParams = {
     textDocument: lc.TextDocumentIdentifier;
     position: lc.Position;
@@ -155,6 +169,24 @@ export type ExpandedMacro = {
     name: string;
     expansion: string;
 };
+export type ExpandedMacroSteps = {
+    name: string;
+    steps: MacroExpansionStep[];
+};
+export type MacroExpansionStep = {
+    expansion: string;
+    calls: ExpandedMacroCall[];
+};
+export type ExpandedMacroCall = {
+    name: string;
+    matchedArm: number | null;
+    arms: MacroArm[];
+    error: string | null;
+};
+export type MacroArm = {
+    pattern: string;
+    error: string | null;
+};
 export type TestInfo = { runnable: Runnable };
 export type SyntaxTreeParams = {
     textDocument: lc.TextDocumentIdentifier;